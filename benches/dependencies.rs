@@ -0,0 +1,125 @@
+//! Queries with thousands of dependencies, like the `crate_def_map` query of rust-analyzer,
+//! which reads the item tree of every file of a crate.
+//!
+//! Besides the time to verify such queries, this can report the memory their memos take,
+//! which is dominated by the list of dependencies: run `cargo bench --bench dependencies -- --nocapture`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use codspeed_criterion_compat::{criterion_group, criterion_main, BatchSize, Criterion};
+use salsa::{Database, Setter};
+
+/// Counts the bytes currently allocated, to measure the memory taken by memos.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[salsa::input]
+struct File {
+    text: usize,
+}
+
+#[salsa::input]
+struct Crate {
+    #[return_ref]
+    files: Vec<File>,
+}
+
+#[salsa::tracked]
+fn item_tree(db: &dyn Database, file: File) -> usize {
+    file.text(db)
+}
+
+/// Reads the item tree of every file, in order.
+#[salsa::tracked]
+fn crate_def_map(db: &dyn Database, krate: Crate) -> usize {
+    krate
+        .files(db)
+        .iter()
+        .map(|&file| item_tree(db, file))
+        .sum()
+}
+
+/// Reads the item trees of the files in the order their items are resolved:
+/// each file along with some files it imports from.
+#[salsa::tracked]
+fn resolve_imports(db: &dyn Database, krate: Crate) -> usize {
+    let files = krate.files(db);
+    (0..files.len())
+        .flat_map(|i| [i, i * 31 % files.len(), i / 2])
+        .map(|i| item_tree(db, files[i]) + files[i].text(db))
+        .sum()
+}
+
+const FILES: usize = 10_000;
+
+fn setup() -> (salsa::DatabaseImpl, Crate) {
+    let db = salsa::DatabaseImpl::new();
+    let files = (0..FILES).map(|i| File::new(&db, i)).collect();
+    let krate = Crate::new(&db, files);
+    for &file in krate.files(&db) {
+        item_tree(&db, file);
+    }
+    (db, krate)
+}
+
+/// Reports the bytes retained by the memo of `query`, excluding those of its dependencies.
+///
+/// Like test output, the report is only shown when the benchmark is run with `--nocapture`.
+fn report_memory(name: &str, query: fn(&dyn Database, Crate) -> usize, edges: usize) {
+    if !std::env::args().any(|arg| arg == "--nocapture") {
+        return;
+    }
+
+    let (db, krate) = setup();
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    query(&db, krate);
+    let retained = ALLOCATED.load(Ordering::Relaxed) - before;
+    eprintln!(
+        "{name}: {retained} bytes retained for {edges} dependencies ({:.1} bytes each)",
+        retained as f64 / edges as f64
+    );
+}
+
+fn many_dependencies(criterion: &mut Criterion) {
+    report_memory("crate_def_map", crate_def_map, FILES + 1);
+    report_memory("resolve_imports", resolve_imports, 2 * FILES + 1);
+
+    criterion.bench_function("verify_many_dependencies", |b| {
+        b.iter_batched_ref(
+            || {
+                let (mut db, krate) = setup();
+                crate_def_map(&db, krate);
+                resolve_imports(&db, krate);
+                // An unrelated change, so that the queries are verified rather than re-executed.
+                let other = File::new(&db, 0);
+                other.set_text(&mut db).to(1);
+                (db, krate)
+            },
+            |(db, krate)| {
+                crate_def_map(db, *krate);
+                resolve_imports(db, *krate);
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+criterion_group!(benches, many_dependencies);
+criterion_main!(benches);
@@ -15,7 +15,6 @@
 mod macro_if;
 mod maybe_backdate;
 mod maybe_clone;
-mod maybe_default;
 mod setup_accumulator_impl;
 mod setup_input_struct;
 mod setup_interned_struct;
@@ -28,6 +28,12 @@ macro_rules! setup_accumulator_impl {
                 })
             }
 
+            impl $zalsa::JarGroup for $Struct {
+                fn register(registry: &$zalsa::Registry<'_>) {
+                    registry.add_jar(&<$zalsa_struct::JarImpl<$Struct>>::default());
+                }
+            }
+
             impl $zalsa::Accumulator for $Struct {
                 const DEBUG_NAME: &'static str = stringify!($Struct);
 
@@ -35,9 +35,27 @@ macro_rules! setup_input_struct {
         // Fields that are required (have no default value). Each item is the fields name and type.
         required_fields: [$($required_field_id:ident $required_field_ty:ty),*],
 
+        // Initial value of each field in the builder: its default value, or the argument of
+        // `new` and `builder` of the same name for required fields.
+        field_defaults: [$($field_default:expr),*],
+
         // Names for the field durability methods on the builder (typically `foo_durability`)
         field_durability_ids: [$($field_durability_id:ident),*],
 
+        // Default durability for each field (`salsa::Durability::LOW` unless given with `#[durability(..)]`)
+        field_durabilities: [$($field_durability:path),*],
+
+        // Fields marked `#[index]`: field index, visibility and name of the lookup method
+        // (typically `find_by_foo`), and field type.
+        index_fields: [$($index_field_index:tt $index_find_vis:vis $index_find_id:ident $index_field_ty:ty),*],
+
+        // Whether each field is `#[lazy]` (`true` or `false`).
+        field_lazy: [$($field_lazy:tt),*],
+
+        // Fields marked `#[lazy]`: field index, visibility and name of the method registering
+        // its loader (typically `register_foo_loader`), and field type.
+        lazy_fields: [$($lazy_field_index:tt $lazy_register_vis:vis $lazy_register_id:ident $lazy_field_ty:ty),*],
+
         // Number of fields
         num_fields: $N:literal,
 
@@ -47,6 +65,18 @@ macro_rules! setup_input_struct {
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, generate a `debug(db)` method.
+        generate_debug_with_db: $generate_debug_with_db:tt,
+
+        // If true, generate a `salsa::Transfer` impl.
+        generate_transfer_impl: $generate_transfer_impl:tt,
+
+        // If true, the fields can be set within `Database::speculate`.
+        generate_speculate_impl: $generate_speculate_impl:tt,
+
+        // If true, the fields are fingerprinted for `Database::export_state_summary`.
+        generate_fingerprint_impl: $generate_fingerprint_impl:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -82,6 +112,43 @@ macro_rules! setup_input_struct {
 
                 /// A array of [`StampedValue<()>`](`StampedValue`) tuples, one per each of the value fields.
                 type Stamps = $zalsa::Array<$zalsa::Stamp, $N>;
+
+                const INDEXED_FIELDS: &'static [usize] = &[$($index_field_index),*];
+
+                #[allow(unused_variables)]
+                fn hash_field(fields: &Self::Fields, field_index: usize) -> u64 {
+                    match field_index {
+                        $($index_field_index => $zalsa::hash(&fields.$index_field_index),)*
+                        _ => unreachable!("field {field_index} is not indexed"),
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn clone_fields(fields: &Self::Fields) -> Option<Self::Fields> {
+                    $zalsa::macro_if! { if $generate_speculate_impl {
+                        Some(Clone::clone(fields))
+                    } else {
+                        None
+                    }}
+                }
+
+                #[allow(unused_variables)]
+                fn fork_fields(fields: &Self::Fields) -> Option<Self::Fields> {
+                    use $zalsa_struct::fork_helper::Fallback as _;
+                    Some(($($zalsa_struct::fork_helper::Dispatch::<$field_ty>::maybe_clone(&fields.$field_index)?,)*))
+                }
+
+                #[allow(unused_variables)]
+                fn fingerprint_field(fields: &Self::Fields, field_index: usize) -> Option<u128> {
+                    $zalsa::macro_if! { if $generate_fingerprint_impl {
+                        match field_index {
+                            $($field_index => Some($zalsa::fingerprint_of(&fields.$field_index)),)*
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    }}
+                }
             }
 
             impl $Configuration {
@@ -134,10 +201,33 @@ macro_rules! setup_input_struct {
                 }
             }
 
+            impl $zalsa::JarGroup for $Struct {
+                fn register(registry: &$zalsa::Registry<'_>) {
+                    registry.add_jar(&<$zalsa_struct::JarImpl<$Configuration>>::default());
+                }
+            }
+
             impl $zalsa::SalsaStructInDb for $Struct {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
+            }
+
+            $zalsa::macro_if! { $generate_transfer_impl =>
+                impl $zalsa::Transfer for $Struct {
+                    type Output<'db> = Self;
+
+                    fn transfer<'db>(&self, from: &dyn $zalsa::Database, to: &'db dyn $zalsa::Database) -> Self {
+                        let ingredient = $Configuration::ingredient(from);
+                        builder::new_builder($(
+                            $zalsa::Transfer::transfer(&ingredient.field(from, *self, $field_index).$field_index, from, to)
+                        ),*).new(to)
+                    }
+                }
             }
 
             impl $Struct {
@@ -150,9 +240,32 @@ macro_rules! setup_input_struct {
                     Self::builder($($required_field_id,)*).new(db)
                 }
 
+                $zalsa::if_fallible_v1! {
+                    /// Like [`Self::$new_fn`], but returns a [`salsa::Error`] rather than unwinding.
+                    pub fn try_new<$Db>(db: &$Db, $($required_field_id: $required_field_ty),*) -> Result<Self, salsa::Error>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        salsa::Error::catch(std::panic::AssertUnwindSafe(|| Self::$new_fn(db, $($required_field_id),*)))
+                    }
+                }
+
                 pub fn builder($($required_field_id: $required_field_ty),*) -> <Self as $zalsa_struct::HasBuilder>::Builder
                 {
-                    builder::new_builder($($zalsa::maybe_default!($field_option, $field_ty, $field_id,)),*)
+                    builder::new_builder($($field_default),*)
+                }
+
+                /// Creates a builder with every field set to `Default::default()`.
+                ///
+                /// Meant for tests: fields can be overridden selectively with the builder's setters.
+                /// Only available if all field types implement `Default`.
+                pub fn test_builder() -> <Self as $zalsa_struct::HasBuilder>::Builder
+                where
+                    // The higher-ranked bound defers the check to the call site.
+                    $(for<'__salsa_test> $field_ty: Default),*
+                {
+                    builder::new_builder($(<$field_ty as Default>::default()),*)
                 }
 
                 $(
@@ -161,15 +274,26 @@ macro_rules! setup_input_struct {
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
                         $Db: ?Sized + $zalsa::Database,
                     {
-                        let fields = $Configuration::ingredient(db.as_dyn_database()).field(
-                            db.as_dyn_database(),
-                            self,
-                            $field_index,
-                        );
+                        let field = $zalsa::macro_if! {
+                            if $field_lazy {
+                                $Configuration::ingredient(db.as_dyn_database()).lazy_field(
+                                    db.as_dyn_database(),
+                                    self,
+                                    $field_index,
+                                    |fields| &fields.$field_index,
+                                )
+                            } else {
+                                &$Configuration::ingredient(db.as_dyn_database()).field(
+                                    db.as_dyn_database(),
+                                    self,
+                                    $field_index,
+                                ).$field_index
+                            }
+                        };
                         $zalsa::maybe_clone!(
                             $field_option,
                             $field_ty,
-                            &fields.$field_index,
+                            field,
                         )
                     }
                 )*
@@ -181,17 +305,97 @@ macro_rules! setup_input_struct {
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
                         $Db: ?Sized + $zalsa::Database,
                     {
+                        $zalsa::assert_no_executing_query(db.as_dyn_database(), &|| {
+                            format!("call `{}::{}`", stringify!($Struct), stringify!($field_setter_id))
+                        });
                         let (ingredient, revision) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
                         $zalsa::input::SetterImpl::new(
                             revision,
                             self,
                             $field_index,
                             ingredient,
-                            |fields, f| std::mem::replace(&mut fields.$field_index, f),
+                            |fields| &mut fields.$field_index,
+                        )
+                    }
+                )*
+
+                $(
+                    /// Registers the function loading this field when it is read before being set,
+                    /// replacing any previously registered one. It returns the value along with the
+                    /// durability to record it with; thereafter, the field is set like any other.
+                    ///
+                    /// Reads made by the loader are recorded as dependencies of the query reading the field.
+                    $lazy_register_vis fn $lazy_register_id<$Db>(
+                        db: &mut $Db,
+                        loader: impl Fn(&dyn $zalsa::Database, Self) -> ($lazy_field_ty, salsa::Durability) + Send + Sync + 'static,
+                    )
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let (ingredient, _) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
+                        ingredient.register_loader($lazy_field_index, Box::new(loader));
+                    }
+                )*
+
+                /// Retires this input: its fields are dropped and the tracked functions that read
+                /// them are invalidated. Its id may later be reused for a new input.
+                ///
+                /// Reading or setting a field of a retired input panics. Once its id was reused,
+                /// though, the handle refers to the new input: handles must not be kept after
+                /// retiring the input.
+                pub fn retire<$Db>(self, db: &mut $Db)
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    $zalsa_struct::IngredientImpl::<$Configuration>::retire(db.as_dyn_database_mut(), self)
+                }
+
+                $(
+                    /// Returns an input whose field equals `value`, if any, looking it up
+                    /// in an index maintained by salsa.
+                    ///
+                    /// This reports an untracked read, since inputs with this value may be
+                    /// created or changed later.
+                    $index_find_vis fn $index_find_id<$Db>(db: &$Db, value: &$index_field_ty) -> Option<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db.as_dyn_database()).find(
+                            db.as_dyn_database(),
+                            $index_field_index,
+                            value,
+                            |fields| &fields.$index_field_index,
                         )
                     }
                 )*
 
+                /// Iterates over all inputs of this type created so far, in no particular order.
+                ///
+                /// This does not record a dependency: a tracked function calling it is not
+                /// re-executed when further inputs are created. Use `all_tracked` for that.
+                pub fn all<'db, $Db>(db: &'db $Db) -> impl Iterator<Item = Self> + 'db
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    $Configuration::ingredient(db.as_dyn_database()).all(db.as_dyn_database())
+                }
+
+                /// Like `all`, but reports an untracked read, so that a tracked function
+                /// calling it is re-executed in every new revision and sees the inputs
+                /// created since.
+                pub fn all_tracked<'db, $Db>(db: &'db $Db) -> impl Iterator<Item = Self> + 'db
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    db.report_untracked_read();
+                    Self::all(db)
+                }
+
                 $zalsa::macro_if! { $is_singleton =>
                     pub fn try_get<$Db>(db: &$Db) -> Option<Self>
                     where
@@ -209,14 +413,46 @@ macro_rules! setup_input_struct {
                     {
                         Self::try_get(db).unwrap()
                     }
+
+                    /// Creates the singleton with the given fields or, if it already exists,
+                    /// sets its fields to them. Unlike `new`, this may be called repeatedly.
+                    pub fn set<$Db>(db: &mut $Db, $($field_id: $field_ty),*) -> Self
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        match Self::try_get(db) {
+                            Some(this) => {
+                                $(
+                                    salsa::Setter::to(this.$field_setter_id(db), $field_id);
+                                )*
+                                this
+                            }
+                            None => builder::new_builder($($field_id),*).new(db),
+                        }
+                    }
+                }
+
+                $zalsa::macro_if! { $generate_debug_with_db =>
+                    /// Formats this struct with the values of its fields, read from `db`,
+                    /// including those of nested salsa structs.
+                    pub fn debug<'__salsa_debug, $Db>(self, db: &'__salsa_debug $Db) -> $zalsa::DebugWithDb<'__salsa_debug, Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $zalsa::DebugWithDb::new(self, db.as_dyn_database(), Self::default_debug_fmt)
+                    }
                 }
 
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
-                        let fields = $Configuration::ingredient(db).leak_fields(db, this);
                         let mut f = f.debug_struct(stringify!($Struct));
                         let f = f.field("[salsa id]", &$zalsa::AsId::as_id(&this));
+                        let Some(fields) = $Configuration::ingredient(db).leak_fields(db, this) else {
+                            return f.field("[retired]", &true).finish();
+                        };
                         $(
                             let f = f.field(stringify!($field_id), &fields.$field_index);
                         )*
@@ -245,8 +481,8 @@ macro_rules! setup_input_struct {
                 {
                     let current_revision = $zalsa::current_revision(db);
                     let ingredient = $Configuration::ingredient(db.as_dyn_database());
-                    let (fields, stamps) = builder::builder_into_inner(self, current_revision);
-                    ingredient.new_input(db.as_dyn_database(), fields, stamps)
+                    let (fields, stamps, unset) = builder::builder_into_inner(self, current_revision);
+                    ingredient.new_input(db.as_dyn_database(), fields, stamps, &unset)
                 }
             }
 
@@ -261,16 +497,17 @@ macro_rules! setup_input_struct {
                 pub(super) fn new_builder($($field_id: $field_ty),*) -> $Builder {
                     $Builder {
                         fields: ($($field_id,)*),
-                        durabilities: [salsa::Durability::default(); $N],
+                        durabilities: [$($field_durability),*],
+                        unset: [$($field_lazy),*],
                     }
                 }
 
-                pub(super) fn builder_into_inner(builder: $Builder, revision: $zalsa::Revision) -> (($($field_ty,)*), $zalsa::Array<$zalsa::Stamp, $N>) {
+                pub(super) fn builder_into_inner(builder: $Builder, revision: $zalsa::Revision) -> (($($field_ty,)*), $zalsa::Array<$zalsa::Stamp, $N>, [bool; $N]) {
                     let stamps = $zalsa::Array::new([
                         $($zalsa::stamp(revision, builder.durabilities[$field_index])),*
                     ]);
 
-                    (builder.fields, stamps)
+                    (builder.fields, stamps, builder.unset)
                 }
 
                 #[must_use]
@@ -280,6 +517,10 @@ macro_rules! setup_input_struct {
 
                     /// The durabilities per field.
                     durabilities: [salsa::Durability; $N],
+
+                    /// Whether each field is `#[lazy]` and was not given a value,
+                    /// so that it is loaded when first read.
+                    unset: [bool; $N],
                 }
 
                 impl $Builder {
@@ -291,15 +532,16 @@ macro_rules! setup_input_struct {
                         self
                     }
 
-                    $($zalsa::maybe_default_tt! { $field_option =>
+                    $(
                         /// Sets the value of the field `$field_id`.
                         #[must_use]
                         pub fn $field_id(mut self, value: $field_ty) -> Self
                         {
                             self.fields.$field_index = value;
+                            self.unset[$field_index] = false;
                             self
                         }
-                    })*
+                    )*
 
                     $(
                         /// Sets the durability for the field `$field_id`.
@@ -28,6 +28,9 @@ macro_rules! setup_interned_struct {
         // the salsa ID
         id: $Id:path,
 
+        // the `BuildHasher` of the interner
+        hasher: $Hasher:ty,
+
         // the lifetime used in the desugared interned struct.
         // if the `db_lt_arg`, is present, this is `db_lt_arg`, but otherwise,
         // it is `'static`.
@@ -60,6 +63,12 @@ macro_rules! setup_interned_struct {
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, generate a `debug(db)` method.
+        generate_debug_with_db: $generate_debug_with_db:tt,
+
+        // If true, generate a `salsa::Transfer` impl.
+        generate_transfer_impl: $generate_transfer_impl:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -122,6 +131,7 @@ macro_rules! setup_interned_struct {
                 const DEBUG_NAME: &'static str = stringify!($Struct);
                 type Fields<'a> = $StructDataIdent<'a>;
                 type Struct<'db> = $Struct< $($db_lt_arg)? >;
+                type Hasher = $Hasher;
                 fn struct_from_id<'db>(id: salsa::Id) -> Self::Struct<'db> {
                     use salsa::plumbing::FromId;
                     $Struct(<$Id>::from_id(id), std::marker::PhantomData)
@@ -170,10 +180,20 @@ macro_rules! setup_interned_struct {
                 }
             }
 
+            impl $zalsa::JarGroup for $StructWithStatic {
+                fn register(registry: &$zalsa::Registry<'_>) {
+                    registry.add_jar(&<$zalsa_struct::JarImpl<$Configuration>>::default());
+                }
+            }
+
             impl< $($db_lt_arg)? > $zalsa::SalsaStructInDb for $Struct< $($db_lt_arg)? > {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
             }
 
             unsafe impl< $($db_lt_arg)? > $zalsa::Update for $Struct< $($db_lt_arg)? > {
@@ -187,6 +207,22 @@ macro_rules! setup_interned_struct {
                 }
             }
 
+            $zalsa::macro_if! { $generate_transfer_impl =>
+                impl< $($db_lt_arg)? > $zalsa::Transfer for $Struct< $($db_lt_arg)? > {
+                    type Output<'__salsa_to> = <$Configuration as $zalsa_struct::Configuration>::Struct<'__salsa_to>;
+
+                    fn transfer<'__salsa_to>(
+                        &self,
+                        from: &dyn $zalsa::Database,
+                        to: &'__salsa_to dyn $zalsa::Database,
+                    ) -> Self::Output<'__salsa_to> {
+                        let fields = $Configuration::ingredient(from).data(from, $zalsa::AsId::as_id(self));
+                        let fields = $zalsa::Transfer::transfer(fields, from, to);
+                        $Struct::$new_fn(to, $(fields.$field_index),*)
+                    }
+                }
+            }
+
             impl<$db_lt> $Struct< $($db_lt_arg)? >  {
                 pub fn $new_fn<$Db, $($indexed_ty: $zalsa::interned::Lookup<$field_ty> + std::hash::Hash,)*>(db: &$db_lt $Db,  $($field_id: $indexed_ty),*) -> Self
                 where
@@ -201,6 +237,20 @@ macro_rules! setup_interned_struct {
                         StructKey::<$db_lt>($($field_id,)* std::marker::PhantomData::default()), |_, data| ($($zalsa::interned::Lookup::into_owned(data.$field_index),)*))
                 }
 
+                $zalsa::if_fallible_v1! {
+                    /// Like [`Self::$new_fn`], but returns a [`salsa::Error`] rather than unwinding.
+                    pub fn try_new<$Db, $($indexed_ty: $zalsa::interned::Lookup<$field_ty> + std::hash::Hash,)*>(db: &$db_lt $Db,  $($field_id: $indexed_ty),*) -> Result<Self, salsa::Error>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                        $(
+                            $field_ty: $zalsa::interned::HashEqLike<$indexed_ty>,
+                        )*
+                    {
+                        salsa::Error::catch(std::panic::AssertUnwindSafe(|| Self::$new_fn(db, $($field_id),*)))
+                    }
+                }
+
                 $(
                     $field_getter_vis fn $field_getter_id<$Db>(self, db: &'db $Db) -> $zalsa::maybe_cloned_ty!($field_option, 'db, $field_ty)
                     where
@@ -216,6 +266,43 @@ macro_rules! setup_interned_struct {
                     }
                 )*
 
+                /// Iterates over all values of this struct interned in the database so far,
+                /// in no particular order.
+                ///
+                /// This does not record a dependency: a tracked function calling it is not
+                /// re-executed when further values are interned. Use `all_tracked` for that.
+                pub fn all<$Db>(db: &$db_lt $Db) -> impl Iterator<Item = Self> + $db_lt
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    $Configuration::ingredient(db).all(db.as_dyn_database())
+                }
+
+                /// Like `all`, but reports an untracked read, so that a tracked function
+                /// calling it is re-executed in every new revision and sees the values
+                /// interned since.
+                pub fn all_tracked<$Db>(db: &$db_lt $Db) -> impl Iterator<Item = Self> + $db_lt
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    db.report_untracked_read();
+                    Self::all(db)
+                }
+
+                $zalsa::macro_if! { $generate_debug_with_db =>
+                    /// Formats this struct with the values of its fields, read from `db`,
+                    /// including those of nested salsa structs.
+                    pub fn debug<'__salsa_debug, $Db>(self, db: &'__salsa_debug $Db) -> $zalsa::DebugWithDb<'__salsa_debug, Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $zalsa::DebugWithDb::new(self, db.as_dyn_database(), Self::default_debug_fmt)
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
@@ -46,12 +46,69 @@ macro_rules! setup_tracked_fn {
         // If true, don't backdate the value when the new value compares equal to the old value.
         no_eq: $no_eq:tt,
 
+        // If true, compare the old and new value with `eq_with` rather than `Eq` when backdating.
+        has_eq_with: $has_eq_with:tt,
+
+        // Path to the function comparing values, if any.
+        eq_with: ($($eq_with:tt)*),
+
+        // If true, update old values with `update_with` rather than the `Update` impl.
+        has_update_with: $has_update_with:tt,
+
+        // Path to the unsafe function updating values, if any.
+        update_with: ($($update_with:tt)*),
+
+        // If true, look up the value in the memo cache backend before executing.
+        has_cache_key: $has_cache_key:tt,
+
+        // Path to the function computing the fingerprint of the arguments, if any.
+        cache_key: ($($cache_key:tt)*),
+
+        // If true, outputs that compare equal share a single allocation.
+        intern_output: $intern_output:tt,
+
+        // If true, check that the key belongs to the database before fetching.
+        validate_args: $validate_args:tt,
+
+        // If true, check for cancellation after each read.
+        auto_cancel_check: $auto_cancel_check:tt,
+
+        // If true, emit an event when the value is backdated after re-executing.
+        firewall: $firewall:tt,
+
+        // If true, share the memo with the threads blocked on the query while it was computed.
+        broadcast: $broadcast:tt,
+
+        // If true, keep the previous value if re-executing the function panics.
+        keep_stale_on_panic: $keep_stale_on_panic:tt,
+
+        // If true, memoize panics and raise them again until the inputs change.
+        memoize_panics: $memoize_panics:tt,
+
+        // If true, compare values by their fingerprint, which is kept along with the memo.
+        fingerprint: $fingerprint:tt,
+
+        // If true, the function has type parameters; each instance has its own ingredient.
+        is_generic: $is_generic:tt,
+
+        // Names of the type parameters of the function.
+        generic_params: [$($generic_param:ident),*],
+
+        // Where-clause predicates bounding the type parameters, each followed by a comma.
+        generic_bounds: [$($generic_bounds:tt)*],
+
         // If true, the input needs an interner (because it has >1 argument).
         needs_interner: $needs_interner:tt,
 
         // LRU capacity (a literal, maybe 0)
         lru: $lru:tt,
 
+        // Number of revisions after which memos are re-executed (a literal, 0 if not volatile)
+        volatile_ttl: $volatile_ttl:tt,
+
+        // Number of previous values retained for each key (a literal, 0 if none)
+        history: $history:tt,
+
         // True if we `return_ref` flag was given to the function
         return_ref: $return_ref:tt,
 
@@ -70,7 +127,7 @@ macro_rules! setup_tracked_fn {
         // Suppress this clippy lint because we sometimes require `'db` where the ordinary Rust rules would not.
         #[allow(clippy::needless_lifetimes)]
         $(#[$attr])*
-        $vis fn $fn_name<$db_lt>(
+        $vis fn $fn_name<$db_lt, $($generic_param),*>(
             $db: &$db_lt dyn $Db,
             $($input_id: $input_ty,)*
         ) -> salsa::plumbing::macro_if! {
@@ -79,13 +136,22 @@ macro_rules! setup_tracked_fn {
             } else {
                 $output_ty
             }
-        } {
+        }
+        where
+            $($generic_bounds)*
+        {
             use salsa::plumbing as $zalsa;
 
-            struct $Configuration;
+            struct $Configuration<$($generic_param),*>(std::marker::PhantomData<fn() -> ($($generic_param,)*)>);
 
-            static $FN_CACHE: $zalsa::IngredientCache<$zalsa::function::IngredientImpl<$Configuration>> =
-                $zalsa::IngredientCache::new();
+            $zalsa::macro_if! {
+                if $is_generic {
+                    static $FN_CACHE: $zalsa::GenericIngredientCache = $zalsa::GenericIngredientCache::new();
+                } else {
+                    static $FN_CACHE: $zalsa::IngredientCache<$zalsa::function::IngredientImpl<$Configuration>> =
+                        $zalsa::IngredientCache::new();
+                }
+            }
 
             $zalsa::macro_if! {
                 if $needs_interner {
@@ -111,6 +177,8 @@ macro_rules! setup_tracked_fn {
 
                         type Struct<$db_lt> = $InternedData<$db_lt>;
 
+                        type Hasher = $zalsa::interned::DefaultHasher;
+
                         fn struct_from_id<$db_lt>(
                             id: salsa::Id,
                         ) -> Self::Struct<$db_lt> {
@@ -126,26 +194,35 @@ macro_rules! setup_tracked_fn {
                 }
             }
 
-            impl $Configuration {
-                fn fn_ingredient(db: &dyn $Db) -> &$zalsa::function::IngredientImpl<$Configuration> {
+            impl<$($generic_param),*> $Configuration<$($generic_param),*>
+            where
+                $($generic_bounds)*
+            {
+                const JAR: Self = Self(std::marker::PhantomData);
+
+                fn fn_ingredient(db: &dyn $Db) -> &$zalsa::function::IngredientImpl<Self> {
                     $FN_CACHE.get_or_create(db.as_dyn_database(), || {
                         <dyn $Db as $Db>::zalsa_db(db);
-                        db.zalsa().add_or_lookup_jar_by_type(&$Configuration)
+                        <$InternedData<'_> as $zalsa::SalsaStructInDb>::register_ingredients(db.as_dyn_database());
+                        db.zalsa().add_or_lookup_jar_by_type(&Self::JAR)
                     })
                 }
 
                 $zalsa::macro_if! { $needs_interner =>
                     fn intern_ingredient(
                         db: &dyn $Db,
-                    ) -> &$zalsa::interned::IngredientImpl<$Configuration> {
+                    ) -> &$zalsa::interned::IngredientImpl<Self> {
                         $INTERN_CACHE.get_or_create(db.as_dyn_database(), || {
-                            db.zalsa().add_or_lookup_jar_by_type(&$Configuration).successor(0)
+                            db.zalsa().add_or_lookup_jar_by_type(&Self::JAR).successor(0)
                         })
                     }
                 }
             }
 
-            impl $zalsa::function::Configuration for $Configuration {
+            impl<$($generic_param),*> $zalsa::function::Configuration for $Configuration<$($generic_param),*>
+            where
+                $($generic_bounds)*
+            {
                 const DEBUG_NAME: &'static str = stringify!($fn_name);
 
                 type DbView = dyn $Db;
@@ -156,17 +233,130 @@ macro_rules! setup_tracked_fn {
 
                 type Output<$db_lt> = $output_ty;
 
+                type OutputInterner = $zalsa::macro_if! {
+                    if $intern_output {
+                        <Self::Output<'static> as $zalsa::function::InternOutput>::Interner
+                    } else {
+                        ()
+                    }
+                };
+
                 const CYCLE_STRATEGY: $zalsa::CycleRecoveryStrategy = $zalsa::CycleRecoveryStrategy::$cycle_recovery_strategy;
 
+                const AUTO_CANCEL_CHECK: bool = $auto_cancel_check;
+
+                const FIREWALL: bool = $firewall;
+
+                const BROADCAST: bool = $broadcast;
+
+                const KEEP_STALE_ON_PANIC: bool = $keep_stale_on_panic;
+
+                const MEMOIZE_PANICS: bool = $memoize_panics;
+
+                const VOLATILE_TTL: usize = $volatile_ttl;
+
+                const HISTORY: usize = $history;
+
+                const NO_EQ: bool = $no_eq;
+
                 fn should_backdate_value(
                     old_value: &Self::Output<'_>,
                     new_value: &Self::Output<'_>,
                 ) -> bool {
                     $zalsa::macro_if! {
                         if $no_eq {
+                            // `no_eq`: the output is never compared, so every re-execution
+                            // is a change that its readers observe.
                             false
                         } else {
-                            $zalsa::should_backdate_value(old_value, new_value)
+                            $zalsa::macro_if! {
+                                if $has_eq_with {
+                                    $($eq_with)*(old_value, new_value)
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $fingerprint {
+                                            $zalsa::fingerprint_of(old_value) == $zalsa::fingerprint_of(new_value)
+                                        } else {
+                                            $zalsa::should_backdate_value(old_value, new_value)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                fn fingerprint(value: &Self::Output<'_>) -> Option<u128> {
+                    $zalsa::macro_if! {
+                        if $fingerprint {
+                            Some($zalsa::fingerprint_of(value))
+                        } else {
+                            let _ = value;
+                            None
+                        }
+                    }
+                }
+
+                unsafe fn maybe_update<$db_lt>(
+                    old_pointer: *mut Self::Output<$db_lt>,
+                    new_value: Self::Output<$db_lt>,
+                ) -> bool {
+                    $zalsa::macro_if! {
+                        if $has_update_with {
+                            // SAFETY: `unsafe(update_with)` promises the `Update` contract.
+                            unsafe { $($update_with)*(old_pointer, new_value) }
+                        } else {
+                            // SAFETY: Forwarded from the caller.
+                            unsafe { <Self::Output<$db_lt> as $zalsa::Update>::maybe_update(old_pointer, new_value) }
+                        }
+                    }
+                }
+
+                fn cache_fingerprint<$db_lt>(
+                    $db: &$db_lt Self::DbView,
+                    ($($input_id),*): ($($input_ty),*),
+                ) -> Option<u128> {
+                    $zalsa::macro_if! {
+                        if $has_cache_key {
+                            Some($($cache_key)*($db, $($input_id),*))
+                        } else {
+                            let _ = ($db, $($input_id),*);
+                            None
+                        }
+                    }
+                }
+
+                fn encode_cached(value: &Self::Output<'_>) -> Vec<u8> {
+                    $zalsa::macro_if! {
+                        if $has_cache_key {
+                            $zalsa::CacheValue::encode(value)
+                        } else {
+                            let _ = value;
+                            unreachable!("`{}` has no `cache_key`", Self::DEBUG_NAME)
+                        }
+                    }
+                }
+
+                fn decode_cached<$db_lt>(bytes: &[u8]) -> Option<Self::Output<$db_lt>> {
+                    $zalsa::macro_if! {
+                        if $has_cache_key {
+                            $zalsa::CacheValue::decode(bytes)
+                        } else {
+                            let _ = bytes;
+                            unreachable!("`{}` has no `cache_key`", Self::DEBUG_NAME)
+                        }
+                    }
+                }
+
+                fn intern_output<$db_lt>(
+                    outputs: &Self::OutputInterner,
+                    value: Self::Output<$db_lt>,
+                ) -> Self::Output<$db_lt> {
+                    $zalsa::macro_if! {
+                        if $intern_output {
+                            $zalsa::function::intern_output(outputs, value)
+                        } else {
+                            value
                         }
                     }
                 }
@@ -174,7 +364,7 @@ macro_rules! setup_tracked_fn {
                 fn execute<$db_lt>($db: &$db_lt Self::DbView, ($($input_id),*): ($($input_ty),*)) -> Self::Output<$db_lt> {
                     $($inner_fn)*
 
-                    $inner($db, $($input_id),*)
+                    $inner::<$($generic_param),*>($db, $($input_id),*)
                 }
 
                 fn recover_from_cycle<$db_lt>(
@@ -188,42 +378,50 @@ macro_rules! setup_tracked_fn {
                 fn id_to_input<$db_lt>(db: &$db_lt Self::DbView, key: salsa::Id) -> Self::Input<$db_lt> {
                     $zalsa::macro_if! {
                         if $needs_interner {
-                            $Configuration::intern_ingredient(db).data(db.as_dyn_database(), key).clone()
+                            Self::intern_ingredient(db).data(db.as_dyn_database(), key).clone()
                         } else {
-                            $zalsa::FromId::from_id(key)
+                            $zalsa::FromIdWithDb::from_id(key, db.as_dyn_database())
                         }
                     }
                 }
             }
 
-            impl $zalsa::Jar for $Configuration {
+            impl<$($generic_param),*> $zalsa::Jar for $Configuration<$($generic_param),*>
+            where
+                $($generic_bounds)*
+            {
                 fn create_ingredients(
                     &self,
                     aux: &dyn $zalsa::JarAux,
                     first_index: $zalsa::IngredientIndex,
                 ) -> Vec<Box<dyn $zalsa::Ingredient>> {
-                    let struct_index = $zalsa::macro_if! {
+                    let struct_indices = $zalsa::macro_if! {
                         if $needs_interner {
-                            first_index.successor(0)
+                            vec![first_index.successor(0)]
                         } else {
-                            <$InternedData as $zalsa::SalsaStructInDb>::lookup_ingredient_index(aux)
-                                .expect(
-                                    "Salsa struct is passed as an argument of a tracked function, but its ingredient hasn't been added!"
-                                )
+                            <$InternedData as $zalsa::SalsaStructInDb>::lookup_ingredient_indices(aux)
                         }
                     };
+                    assert!(
+                        !struct_indices.is_empty(),
+                        "Salsa struct is passed as an argument of a tracked function, but its ingredient hasn't been added!"
+                    );
 
-                    let fn_ingredient = <$zalsa::function::IngredientImpl<$Configuration>>::new(
-                        struct_index,
+                    let fn_ingredient = <$zalsa::function::IngredientImpl<Self>>::new(
+                        &struct_indices,
                         first_index,
                         aux,
                     );
-                    fn_ingredient.set_capacity($lru);
+                    $zalsa::macro_if! {
+                        if0 $lru { } else {
+                            fn_ingredient.set_capacity($lru);
+                        }
+                    }
                     $zalsa::macro_if! {
                         if $needs_interner {
                             vec![
                                 Box::new(fn_ingredient),
-                                Box::new(<$zalsa::interned::IngredientImpl<$Configuration>>::new(
+                                Box::new(<$zalsa::interned::IngredientImpl<Self>>::new(
                                     first_index.successor(0)
                                 )),
                             ]
@@ -238,34 +436,205 @@ macro_rules! setup_tracked_fn {
                 fn salsa_struct_type_id(&self) -> Option<core::any::TypeId> {
                     None
                 }
+
+                fn clone_jar(&self) -> Box<dyn $zalsa::Jar + Send + Sync> {
+                    Box::new(Self::JAR)
+                }
+            }
+
+            // Each instance of a generic function is registered on first use.
+            $zalsa::macro_if! {
+                if $is_generic { } else {
+                    $zalsa::macro_if! {
+                        if $needs_interner {
+                            #[allow(non_local_definitions)]
+                            impl $zalsa::JarGroup for $fn_name {
+                                fn register(registry: &$zalsa::Registry<'_>) {
+                                    registry.add_jar(&$Configuration::JAR);
+                                }
+                            }
+                        } else {
+                            #[allow(non_local_definitions)]
+                            impl $zalsa::JarGroup for $fn_name
+                            where
+                                // The higher-ranked bound defers the check to the use site.
+                                for<'__salsa_register> $InternedData<'static>: $zalsa::JarGroup,
+                            {
+                                fn register(registry: &$zalsa::Registry<'_>) {
+                                    // The jar of the function looks up the ingredient of its salsa struct.
+                                    registry.add::<$InternedData<'static>>();
+                                    registry.add_jar(&$Configuration::JAR);
+                                }
+                            }
+                        }
+                    }
+                }
             }
 
             #[allow(non_local_definitions)]
             impl $fn_name {
-                pub fn accumulated<$db_lt, A: salsa::Accumulator>(
+                pub fn accumulated<$db_lt, $($generic_param,)* A: salsa::Accumulator>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) -> Vec<A>
+                where
+                    $($generic_bounds)*
+                {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    $Configuration::<$($generic_param),*>::fn_ingredient($db).accumulated_by::<A>($db, key)
+                }
+
+                /// Like `accumulated`, but also returns the query that accumulated each value
+                /// and the revision in which it did.
+                pub fn accumulated_with_provenance<$db_lt, $($generic_param,)* A: salsa::Accumulator>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) -> Vec<salsa::AccumulatedWithProvenance<A>>
+                where
+                    $($generic_bounds)*
+                {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    $Configuration::<$($generic_param),*>::fn_ingredient($db).accumulated_with_provenance_by::<A>($db, key)
+                }
+
+                /// Lists the direct dependencies recorded when this function was last executed
+                /// for the given arguments, e.g. to find out why it was re-executed.
+                pub fn dependencies<$db_lt, $($generic_param),*>(
                     $db: &$db_lt dyn $Db,
                     $($input_id: $input_ty,)*
-                ) -> Vec<A> {
+                ) -> Vec<salsa::DependencyInfo>
+                where
+                    $($generic_bounds)*
+                {
                     use salsa::plumbing as $zalsa;
                     let key = $zalsa::macro_if! {
                         if $needs_interner {
-                            $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
                         } else {
                             $zalsa::AsId::as_id(&($($input_id),*))
                         }
                     };
 
-                    $Configuration::fn_ingredient($db).accumulated_by::<A>($db, key)
+                    $Configuration::<$($generic_param),*>::fn_ingredient($db).dependencies($db.as_dyn_database(), key)
+                }
+
+                /// Returns a reference into the memoized value for the given arguments, e.g. a
+                /// `&[T]` for a `Vec<T>` or a `&str` for a `String`, rather than cloning it.
+                ///
+                /// The reference remains valid for as long as the database is borrowed.
+                #[allow(dead_code)]
+                pub fn get_ref<$db_lt, $($generic_param),*>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) -> &$db_lt <$output_ty as std::ops::Deref>::Target
+                where
+                    $($generic_bounds)*
+                    // The higher-ranked bound defers the check to the use site.
+                    for<'__salsa_deref> $output_ty: std::ops::Deref,
+                {
+                    use salsa::plumbing as $zalsa;
+                    $zalsa::attach($db, || {
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            } else {
+                                $zalsa::AsId::as_id(&($($input_id),*))
+                            }
+                        };
+
+                        std::ops::Deref::deref($Configuration::<$($generic_param),*>::fn_ingredient($db).fetch($db, key))
+                    })
+                }
+
+                $zalsa::if_fallible_v1! {
+                    /// Like calling this function, but returns a [`salsa::Error`] rather than unwinding,
+                    /// e.g. if the query was cancelled or its argument was retired or deleted.
+                    #[allow(dead_code)]
+                    pub fn try_fetch<$db_lt, $($generic_param),*>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                    ) -> Result<
+                        salsa::plumbing::macro_if! {
+                            if $return_ref {
+                                &$db_lt $output_ty
+                            } else {
+                                $output_ty
+                            }
+                        },
+                        salsa::Error,
+                    >
+                    where
+                        $($generic_bounds)*
+                    {
+                        use salsa::plumbing as $zalsa;
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                None
+                            } else {
+                                Some($zalsa::AsId::as_id(&($($input_id),*)))
+                            }
+                        };
+
+                        $zalsa::try_fetch($db.as_dyn_database(), key, || $fn_name::<$($generic_param),*>($db, $($input_id),*))
+                    }
+                }
+
+                /// Forces this function to be re-executed for the given arguments when it is next
+                /// called, e.g. because it read some state external to salsa that changed.
+                ///
+                /// This starts a new revision. Functions that depend on the result are only
+                /// re-executed if it changes.
+                pub fn invalidate<$db_lt, $($generic_param),*>(
+                    $db: &$db_lt mut dyn $Db,
+                    $($input_id: $input_ty,)*
+                )
+                where
+                    $($generic_bounds)*
+                {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    let index = $zalsa::Ingredient::ingredient_index($Configuration::<$($generic_param),*>::fn_ingredient($db));
+                    <$zalsa::function::IngredientImpl<$Configuration<$($generic_param),*>>>::invalidate(
+                        $db.as_dyn_database_mut(),
+                        index,
+                        key,
+                    )
                 }
 
                 $zalsa::macro_if! { $is_specifiable =>
-                    pub fn specify<$db_lt>(
+                    pub fn specify<$db_lt, $($generic_param),*>(
                         $db: &$db_lt dyn $Db,
                         $($input_id: $input_ty,)*
                         value: $output_ty,
-                    ) {
+                    )
+                    where
+                        $($generic_bounds)*
+                    {
                         let key = $zalsa::AsId::as_id(&($($input_id),*));
-                        $Configuration::fn_ingredient($db).specify_and_record(
+                        $Configuration::<$($generic_param),*>::fn_ingredient($db).specify_and_record(
                             $db,
                             key,
                             value,
@@ -273,10 +642,65 @@ macro_rules! setup_tracked_fn {
                     }
                 }
 
+                $zalsa::macro_if! { if0 $history { } else {
+                    /// Compares the value of this function for the given arguments in `old_revision`
+                    /// with its value in `new_revision`, among the values retained with `history`.
+                    #[allow(dead_code)]
+                    pub fn diff<$db_lt, $($generic_param),*>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                        old_revision: salsa::Revision,
+                        new_revision: salsa::Revision,
+                    ) -> salsa::OutputDiff<$db_lt, $output_ty>
+                    where
+                        $($generic_bounds)*
+                    {
+                        use salsa::plumbing as $zalsa;
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            } else {
+                                $zalsa::AsId::as_id(&($($input_id),*))
+                            }
+                        };
+
+                        $Configuration::<$($generic_param),*>::fn_ingredient($db).diff($db.zalsa(), key, old_revision, new_revision)
+                    }
+                } }
+
+                $zalsa::macro_if! { $fingerprint =>
+                    /// Returns the fingerprint of the value of this function for the given arguments,
+                    /// e.g. to compute the `cache_key` of another function.
+                    #[allow(dead_code)]
+                    pub fn fingerprint<$db_lt, $($generic_param),*>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                    ) -> u128
+                    where
+                        $($generic_bounds)*
+                    {
+                        use salsa::plumbing as $zalsa;
+                        $zalsa::attach($db, || {
+                            let key = $zalsa::macro_if! {
+                                if $needs_interner {
+                                    $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                                } else {
+                                    $zalsa::AsId::as_id(&($($input_id),*))
+                                }
+                            };
+
+                            $Configuration::<$($generic_param),*>::fn_ingredient($db).fetch_fingerprint($db, key)
+                        })
+                    }
+                }
+
                 $zalsa::macro_if! { if0 $lru { } else {
                     #[allow(dead_code)]
-                    fn set_lru_capacity(db: &dyn $Db, value: usize) {
-                        $Configuration::fn_ingredient(db).set_capacity(value);
+                    fn set_lru_capacity<$($generic_param),*>(db: &dyn $Db, value: usize)
+                    where
+                        $($generic_bounds)*
+                    {
+                        $Configuration::<$($generic_param),*>::fn_ingredient(db).set_capacity(value);
                     }
                 } }
             }
@@ -285,11 +709,18 @@ macro_rules! setup_tracked_fn {
                 let result = $zalsa::macro_if! {
                     if $needs_interner {
                         {
-                            let key = $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data);
-                            $Configuration::fn_ingredient($db).fetch($db, key)
+                            let key = $Configuration::<$($generic_param),*>::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data);
+                            $Configuration::<$($generic_param),*>::fn_ingredient($db).fetch($db, key)
                         }
                     } else {
-                        $Configuration::fn_ingredient($db).fetch($db, $zalsa::AsId::as_id(&($($input_id),*)))
+                        {
+                            let key = $zalsa::AsId::as_id(&($($input_id),*));
+                            let fn_ingredient = $Configuration::<$($generic_param),*>::fn_ingredient($db);
+                            $zalsa::macro_if! { $validate_args =>
+                                fn_ingredient.validate_key($db.as_dyn_database(), key);
+                            }
+                            fn_ingredient.fetch($db, key)
+                        }
                     }
                 };
 
@@ -32,6 +32,13 @@ macro_rules! setup_tracked_struct {
         // Field types, may reference `db_lt`.
         field_tys: [$($field_ty:ty),*],
 
+        // Fields that are required (have no default value). Each item is the fields name and type.
+        required_fields: [$($required_field_id:ident $required_field_ty:ty),*],
+
+        // Initial value of each field in the builder: its default value, or the argument of
+        // `new` and `builder` of the same name for required fields.
+        field_defaults: [$($field_default:expr),*],
+
         // Tracked field types.
         tracked_tys: [$($tracked_ty:ty),*],
 
@@ -50,6 +57,9 @@ macro_rules! setup_tracked_struct {
         // Absolute indices of any untracked fields.
         absolute_untracked_indices: [$($absolute_untracked_index:tt),*],
 
+        // Indices of any untracked fields, relative to only untracked fields on this struct.
+        relative_untracked_indices: [$($relative_untracked_index:tt),*],
+
         // A set of "field options" for each field.
         //
         // Each field option is a tuple `(maybe_clone, maybe_backdate)` where:
@@ -70,9 +80,19 @@ macro_rules! setup_tracked_struct {
         // Number of fields.
         num_fields: $N:literal,
 
+        // If true, the identity of the struct is computed by `identity_fn`
+        // from references to the untracked fields.
+        custom_identity: $custom_identity:tt,
+
+        // Path to the function computing the identity, if any.
+        identity_fn: ($($identity_fn:tt)*),
+
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, generate a `debug(db)` method.
+        generate_debug_with_db: $generate_debug_with_db:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -80,6 +100,7 @@ macro_rules! setup_tracked_struct {
             $zalsa:ident,
             $zalsa_struct:ident,
             $Configuration:ident,
+            $Builder:ident,
             $CACHE:ident,
             $Db:ident,
             $NonNull:ident,
@@ -114,6 +135,8 @@ macro_rules! setup_tracked_struct {
                     $($absolute_tracked_index,)*
                 ];
 
+                const CUSTOM_IDENTITY: bool = $custom_identity;
+
                 type Fields<$db_lt> = ($($field_ty,)*);
 
                 type Revisions = $zalsa::Array<$Revision, $N>;
@@ -129,7 +152,13 @@ macro_rules! setup_tracked_struct {
                 }
 
                 fn untracked_fields(fields: &Self::Fields<'_>) -> impl std::hash::Hash {
-                    ( $( &fields.$absolute_untracked_index ),* )
+                    $zalsa::macro_if! {
+                        if $custom_identity {
+                            $($identity_fn)*($( &fields.$absolute_untracked_index ),*)
+                        } else {
+                            ( $( &fields.$absolute_untracked_index ),* )
+                        }
+                    }
                 }
 
                 fn new_revisions(current_revision: $Revision) -> Self::Revisions {
@@ -182,10 +211,20 @@ macro_rules! setup_tracked_struct {
                 }
             }
 
+            impl $zalsa::JarGroup for $Configuration {
+                fn register(registry: &$zalsa::Registry<'_>) {
+                    registry.add_jar(&<$zalsa_struct::JarImpl<$Configuration>>::default());
+                }
+            }
+
             impl $zalsa::SalsaStructInDb for $Struct<'_> {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
             }
 
             impl $zalsa::TrackedStructInDb for $Struct<'_> {
@@ -218,15 +257,27 @@ macro_rules! setup_tracked_struct {
             }
 
             impl<$db_lt> $Struct<$db_lt> {
-                pub fn $new_fn<$Db>(db: &$db_lt $Db, $($field_id: $field_ty),*) -> Self
+                pub fn $new_fn<$Db>(db: &$db_lt $Db, $($required_field_id: $required_field_ty),*) -> Self
                 where
                     // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
                     $Db: ?Sized + $zalsa::Database,
                 {
-                    $Configuration::ingredient(db.as_dyn_database()).new_struct(
-                        db.as_dyn_database(),
-                        ($($field_id,)*)
-                    )
+                    Self::builder($($required_field_id,)*).new(db)
+                }
+
+                $zalsa::if_fallible_v1! {
+                    /// Like [`Self::$new_fn`], but returns a [`salsa::Error`] rather than unwinding.
+                    pub fn try_new<$Db>(db: &$db_lt $Db, $($required_field_id: $required_field_ty),*) -> Result<Self, salsa::Error>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        salsa::Error::catch(std::panic::AssertUnwindSafe(|| Self::$new_fn(db, $($required_field_id),*)))
+                    }
+                }
+
+                pub fn builder($($required_field_id: $required_field_ty),*) -> builder::$Builder<$db_lt> {
+                    builder::new_builder($($field_default),*)
                 }
 
                 $(
@@ -252,7 +303,7 @@ macro_rules! setup_tracked_struct {
                         $Db: ?Sized + $zalsa::Database,
                     {
                         let db = db.as_dyn_database();
-                        let fields = $Configuration::ingredient(db).untracked_field(db, self);
+                        let fields = $Configuration::ingredient(db).untracked_field(db, self, $absolute_untracked_index, $relative_untracked_index);
                         $crate::maybe_clone!(
                             $untracked_option,
                             $untracked_ty,
@@ -261,6 +312,18 @@ macro_rules! setup_tracked_struct {
                     }
                 )*
 
+                $zalsa::macro_if! { $generate_debug_with_db =>
+                    /// Formats this struct with the values of its fields, read from `db`,
+                    /// including those of nested salsa structs.
+                    pub fn debug<'__salsa_debug, $Db>(self, db: &'__salsa_debug $Db) -> $zalsa::DebugWithDb<'__salsa_debug, Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $zalsa::DebugWithDb::new(self, db.as_dyn_database(), Self::default_debug_fmt)
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
@@ -278,6 +341,60 @@ macro_rules! setup_tracked_struct {
                     })
                 }
             }
+
+            // Implement `new` here instead of inside the builder module
+            // because $Configuration can't be named in `builder`.
+            impl<$db_lt> builder::$Builder<$db_lt> {
+                /// Creates the new tracked struct with the set values.
+                #[must_use]
+                pub fn new<$Db>(self, db: &$db_lt $Db) -> $Struct<$db_lt>
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    $Configuration::ingredient(db.as_dyn_database()).new_struct(
+                        db.as_dyn_database(),
+                        builder::builder_into_inner(self),
+                    )
+                }
+            }
+
+            mod builder {
+                use super::*;
+
+                // These are standalone functions instead of methods on `Builder` to prevent
+                // that the enclosing module can call them.
+                pub(super) fn new_builder<$db_lt>($($field_id: $field_ty),*) -> $Builder<$db_lt> {
+                    $Builder {
+                        fields: ($($field_id,)*),
+                        phantom: std::marker::PhantomData,
+                    }
+                }
+
+                pub(super) fn builder_into_inner<$db_lt>(builder: $Builder<$db_lt>) -> ($($field_ty,)*) {
+                    builder.fields
+                }
+
+                #[must_use]
+                pub struct $Builder<$db_lt> {
+                    /// The field values.
+                    fields: ($($field_ty,)*),
+
+                    phantom: std::marker::PhantomData<&$db_lt ()>,
+                }
+
+                impl<$db_lt> $Builder<$db_lt> {
+                    $(
+                        /// Sets the value of the field `$field_id`.
+                        #[must_use]
+                        pub fn $field_id(mut self, value: $field_ty) -> Self
+                        {
+                            self.fields.$field_index = value;
+                            self
+                        }
+                    )*
+                }
+            }
         };
     };
 }
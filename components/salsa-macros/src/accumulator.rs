@@ -34,7 +34,25 @@ impl AllowedOptions for Accumulator {
     const RETURN_REF: bool = false;
     const SPECIFY: bool = false;
     const NO_EQ: bool = false;
+
+    const INTERN_OUTPUT: bool = false;
+
+    const VALIDATE_ARGS: bool = false;
+
+    const AUTO_CANCEL_CHECK: bool = false;
+
+    const FIREWALL: bool = false;
+
+    const BROADCAST: bool = false;
+
+    const KEEP_STALE_ON_PANIC: bool = false;
+
+    const MEMOIZE_PANICS: bool = false;
+
+    const FINGERPRINT: bool = false;
     const NO_DEBUG: bool = true;
+
+    const DEBUG_WITH_DB: bool = false;
     const NO_CLONE: bool = true;
     const NO_LIFETIME: bool = false;
     const SINGLETON: bool = false;
@@ -42,8 +60,32 @@ impl AllowedOptions for Accumulator {
     const DB: bool = false;
     const RECOVERY_FN: bool = false;
     const LRU: bool = false;
+
+    const HISTORY: bool = false;
     const CONSTRUCTOR_NAME: bool = false;
+
+    const GETTER_PREFIX: bool = false;
+
+    const SETTER_PREFIX: bool = false;
     const ID: bool = false;
+
+    const IDENTITY: bool = false;
+
+    const EQ_WITH: bool = false;
+
+    const UPDATE_WITH: bool = false;
+
+    const CACHE_KEY: bool = false;
+
+    const HASHER: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const TRANSFER: bool = false;
+
+    const SPECULATE: bool = false;
+
+    const LAZY: bool = false;
 }
 
 struct StructMacro {
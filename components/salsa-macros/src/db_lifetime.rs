@@ -14,17 +14,6 @@ pub(crate) fn default_db_lifetime(span: Span) -> syn::Lifetime {
     }
 }
 
-/// Require that either there are no generics or exactly one lifetime parameter.
-pub(crate) fn require_optional_db_lifetime(generics: &syn::Generics) -> syn::Result<()> {
-    if generics.params.is_empty() {
-        return Ok(());
-    }
-
-    require_db_lifetime(generics)?;
-
-    Ok(())
-}
-
 /// Require that either there is exactly one lifetime parameter.
 pub(crate) fn require_db_lifetime(generics: &syn::Generics) -> syn::Result<()> {
     if generics.params.is_empty() {
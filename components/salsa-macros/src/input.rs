@@ -40,8 +40,26 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const NO_EQ: bool = false;
 
+    const INTERN_OUTPUT: bool = false;
+
+    const VALIDATE_ARGS: bool = false;
+
+    const AUTO_CANCEL_CHECK: bool = false;
+
+    const FIREWALL: bool = false;
+
+    const BROADCAST: bool = false;
+
+    const KEEP_STALE_ON_PANIC: bool = false;
+
+    const MEMOIZE_PANICS: bool = false;
+
+    const FINGERPRINT: bool = true;
+
     const NO_DEBUG: bool = true;
 
+    const DEBUG_WITH_DB: bool = true;
+
     const NO_LIFETIME: bool = false;
 
     const NO_CLONE: bool = false;
@@ -56,9 +74,33 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const LRU: bool = false;
 
+    const HISTORY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
+    const GETTER_PREFIX: bool = true;
+
+    const SETTER_PREFIX: bool = true;
+
     const ID: bool = false;
+
+    const IDENTITY: bool = false;
+
+    const EQ_WITH: bool = false;
+
+    const UPDATE_WITH: bool = false;
+
+    const CACHE_KEY: bool = false;
+
+    const HASHER: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const TRANSFER: bool = true;
+
+    const SPECULATE: bool = true;
+
+    const LAZY: bool = true;
 }
 
 impl SalsaStructAllowedOptions for InputStruct {
@@ -71,6 +113,10 @@ impl SalsaStructAllowedOptions for InputStruct {
     const ELIDABLE_LIFETIME: bool = false;
 
     const ALLOW_DEFAULT: bool = true;
+
+    const ALLOW_DURABILITY: bool = true;
+
+    const ALLOW_INDEX: bool = true;
 }
 
 struct Macro {
@@ -95,11 +141,20 @@ impl Macro {
         let field_getter_ids = salsa_struct.field_getter_ids();
         let field_setter_ids = salsa_struct.field_setter_ids();
         let required_fields = salsa_struct.required_fields();
+        let field_defaults = salsa_struct.field_defaults();
         let field_options = salsa_struct.field_options();
         let field_tys = salsa_struct.field_tys();
         let field_durability_ids = salsa_struct.field_durability_ids();
+        let field_durabilities = salsa_struct.field_durabilities();
+        let index_fields = salsa_struct.index_fields();
+        let field_lazy = salsa_struct.field_lazy();
+        let lazy_fields = salsa_struct.lazy_fields();
         let is_singleton = self.args.singleton.is_some();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let generate_debug_with_db = salsa_struct.generate_debug_with_db();
+        let generate_transfer_impl = salsa_struct.generate_transfer_impl();
+        let generate_speculate_impl = salsa_struct.generate_speculate_impl();
+        let generate_fingerprint_impl = salsa_struct.generate_fingerprint_impl();
 
         let zalsa = self.hygiene.ident("zalsa");
         let zalsa_struct = self.hygiene.ident("zalsa_struct");
@@ -123,10 +178,19 @@ impl Macro {
                     field_tys: [#(#field_tys),*],
                     field_indices: [#(#field_indices),*],
                     required_fields: [#(#required_fields),*],
+                    field_defaults: [#(#field_defaults),*],
                     field_durability_ids: [#(#field_durability_ids),*],
+                    field_durabilities: [#(#field_durabilities),*],
+                    index_fields: [#(#index_fields),*],
+                    field_lazy: [#(#field_lazy),*],
+                    lazy_fields: [#(#lazy_fields),*],
                     num_fields: #num_fields,
                     is_singleton: #is_singleton,
                     generate_debug_impl: #generate_debug_impl,
+                    generate_debug_with_db: #generate_debug_with_db,
+                    generate_transfer_impl: #generate_transfer_impl,
+                    generate_speculate_impl: #generate_speculate_impl,
+                    generate_fingerprint_impl: #generate_fingerprint_impl,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
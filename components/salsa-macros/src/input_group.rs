@@ -0,0 +1,95 @@
+use proc_macro2::TokenStream;
+
+/// Generates a salsa input mirroring the fields of a plain struct, along with methods
+/// that create it from a value of the struct and that set the fields that changed.
+///
+/// The `#[durability(..)]` and `#[return_ref]` attributes of the fields are forwarded
+/// to the fields of the input.
+pub(crate) fn input_group_derive(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(InputGroup)` only supports `struct`",
+        ));
+    };
+    if let Some(param) = input.generics.params.first() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`derive(InputGroup)` does not support generic parameters",
+        ));
+    }
+    let syn::Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(InputGroup)` requires named fields",
+        ));
+    };
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let input_ident = format_ident!("{}Input", ident);
+    let input_doc = format!(
+        "Salsa input mirroring the fields of [`{ident}`], generated by `derive(InputGroup)`."
+    );
+
+    let field_ids = fields
+        .named
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let field_tys = fields
+        .named
+        .iter()
+        .map(|field| &field.ty)
+        .collect::<Vec<_>>();
+    let field_setters = field_ids
+        .iter()
+        .map(|id| format_ident!("set_{}", id))
+        .collect::<Vec<_>>();
+    let input_fields = fields.named.iter().map(|field| {
+        let attrs = field.attrs.iter().filter(|attr| {
+            attr.path().is_ident("durability") || attr.path().is_ident("return_ref")
+        });
+        let field_vis = &field.vis;
+        let field_id = &field.ident;
+        let field_ty = &field.ty;
+        quote! {
+            #(#attrs)*
+            #field_vis #field_id: #field_ty
+        }
+    });
+
+    Ok(quote! {
+        #[doc = #input_doc]
+        #[salsa::input]
+        #vis struct #input_ident {
+            #(#input_fields,)*
+        }
+
+        impl #input_ident {
+            /// Creates the input from the fields of `value`.
+            #[allow(dead_code)]
+            #vis fn new_from<Db>(db: &Db, value: &#ident) -> Self
+            where
+                Db: ?Sized + salsa::Database,
+            {
+                Self::new(db, #(Clone::clone(&value.#field_ids)),*)
+            }
+
+            /// Sets the fields of the input that differ from those of `value`,
+            /// keeping their durability. The readers of the other fields stay valid.
+            #[allow(dead_code)]
+            #vis fn sync_from<Db>(self, db: &mut Db, value: &#ident)
+            where
+                Db: ?Sized + salsa::Database,
+            {
+                #(
+                    let current = self.#field_ids(&*db);
+                    if std::borrow::Borrow::<#field_tys>::borrow(&current) != &value.#field_ids {
+                        salsa::Setter::to(self.#field_setters(db), Clone::clone(&value.#field_ids));
+                    }
+                )*
+            }
+        }
+    })
+}
@@ -41,8 +41,26 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const NO_EQ: bool = false;
 
+    const INTERN_OUTPUT: bool = false;
+
+    const VALIDATE_ARGS: bool = false;
+
+    const AUTO_CANCEL_CHECK: bool = false;
+
+    const FIREWALL: bool = false;
+
+    const BROADCAST: bool = false;
+
+    const KEEP_STALE_ON_PANIC: bool = false;
+
+    const MEMOIZE_PANICS: bool = false;
+
+    const FINGERPRINT: bool = false;
+
     const NO_DEBUG: bool = true;
 
+    const DEBUG_WITH_DB: bool = true;
+
     const NO_LIFETIME: bool = true;
 
     const NO_CLONE: bool = false;
@@ -57,9 +75,33 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const LRU: bool = false;
 
+    const HISTORY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
+    const GETTER_PREFIX: bool = true;
+
+    const SETTER_PREFIX: bool = false;
+
     const ID: bool = true;
+
+    const IDENTITY: bool = false;
+
+    const EQ_WITH: bool = false;
+
+    const UPDATE_WITH: bool = false;
+
+    const CACHE_KEY: bool = false;
+
+    const HASHER: bool = true;
+
+    const VOLATILE: bool = false;
+
+    const TRANSFER: bool = true;
+
+    const SPECULATE: bool = false;
+
+    const LAZY: bool = false;
 }
 
 impl SalsaStructAllowedOptions for InternedStruct {
@@ -72,6 +114,10 @@ impl SalsaStructAllowedOptions for InternedStruct {
     const ELIDABLE_LIFETIME: bool = true;
 
     const ALLOW_DEFAULT: bool = false;
+
+    const ALLOW_DURABILITY: bool = false;
+
+    const ALLOW_INDEX: bool = false;
 }
 
 struct Macro {
@@ -100,8 +146,14 @@ impl Macro {
         let field_tys = salsa_struct.field_tys();
         let field_indexed_tys = salsa_struct.field_indexed_tys();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let generate_debug_with_db = salsa_struct.generate_debug_with_db();
+        let generate_transfer_impl = salsa_struct.generate_transfer_impl();
         let has_lifetime = salsa_struct.generate_lifetime();
         let id = salsa_struct.id();
+        let hasher = match &self.args.hasher {
+            Some(hasher) => quote!(#hasher),
+            None => quote!(salsa::plumbing::interned::DefaultHasher),
+        };
 
         let (db_lt_arg, cfg, interior_lt) = if has_lifetime {
             (
@@ -137,6 +189,7 @@ impl Macro {
                     db_lt: #db_lt,
                     db_lt_arg: #db_lt_arg,
                     id: #id,
+                    hasher: #hasher,
                     interior_lt: #interior_lt,
                     new_fn: #new_fn,
                     field_options: [#(#field_options),*],
@@ -147,6 +200,8 @@ impl Macro {
                     field_indexed_tys: [#(#field_indexed_tys),*],
                     num_fields: #num_fields,
                     generate_debug_impl: #generate_debug_impl,
+                    generate_debug_with_db: #generate_debug_with_db,
+                    generate_transfer_impl: #generate_transfer_impl,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
@@ -41,9 +41,11 @@ mod debug;
 mod fn_util;
 mod hygiene;
 mod input;
+mod input_group;
 mod interned;
 mod options;
 mod salsa_struct;
+mod supertype;
 mod tracked;
 mod tracked_fn;
 mod tracked_impl;
@@ -85,6 +87,24 @@ pub fn update(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(Supertype)]
+pub fn supertype(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as syn::DeriveInput);
+    match supertype::supertype_derive(item) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => token_stream_with_error(input, error),
+    }
+}
+
+#[proc_macro_derive(InputGroup, attributes(durability, return_ref))]
+pub fn input_group(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as syn::DeriveInput);
+    match input_group::input_group_derive(item) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => token_stream_with_error(input, error),
+    }
+}
+
 pub(crate) fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
     tokens.extend(TokenStream::from(error.into_compile_error()));
     tokens
@@ -20,11 +20,66 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `no_eq` identifier.
     pub no_eq: Option<syn::Ident>,
 
+    /// The `intern_output` option is used to signal that outputs that compare equal
+    /// should share a single allocation.
+    ///
+    /// If this is `Some`, the value is the `intern_output` identifier.
+    pub intern_output: Option<syn::Ident>,
+
+    /// The `validate_args` option is used to signal that a tracked function
+    /// checks that its key belongs to the database before executing.
+    ///
+    /// If this is `Some`, the value is the `validate_args` identifier.
+    pub validate_args: Option<syn::Ident>,
+
+    /// The `auto_cancel_check` option is used to signal that a tracked function
+    /// checks for cancellation after each of its reads.
+    ///
+    /// If this is `Some`, the value is the `auto_cancel_check` identifier.
+    pub auto_cancel_check: Option<syn::Ident>,
+
+    /// The `firewall` option is used to signal that a tracked function always
+    /// compares its outputs, so that changes of its inputs stop there if possible.
+    ///
+    /// If this is `Some`, the value is the `firewall` identifier.
+    pub firewall: Option<syn::Ident>,
+
+    /// The `broadcast` option is used to signal that a tracked function shares its
+    /// memo with the threads that blocked on it while it was computed.
+    ///
+    /// If this is `Some`, the value is the `broadcast` identifier.
+    pub broadcast: Option<syn::Ident>,
+
+    /// The `keep_stale_on_panic` option is used to signal that a tracked function keeps
+    /// its previous value, rather than propagating the panic, if its re-execution panics.
+    ///
+    /// If this is `Some`, the value is the `keep_stale_on_panic` identifier.
+    pub keep_stale_on_panic: Option<syn::Ident>,
+
+    /// The `memoize_panics` option is used to signal that a panic of a tracked function
+    /// is memoized, and raised again without re-executing it until its inputs change.
+    ///
+    /// If this is `Some`, the value is the `memoize_panics` identifier.
+    pub memoize_panics: Option<syn::Ident>,
+
+    /// The `fingerprint` option is used to signal that the outputs of a tracked function
+    /// are compared by their [`Fingerprint`](`salsa::Fingerprint`), which is kept along
+    /// with the memoized value. On inputs, it signals that the fields are fingerprinted
+    /// for `Database::export_state_summary`.
+    ///
+    /// If this is `Some`, the value is the `fingerprint` identifier.
+    pub fingerprint: Option<syn::Ident>,
+
     /// Signal we should not generate a `Debug` impl.
     ///
     /// If this is `Some`, the value is the `no_debug` identifier.
     pub no_debug: Option<syn::Ident>,
 
+    /// Signal we should generate a `debug(db)` method printing the fields of the struct.
+    ///
+    /// If this is `Some`, the value is the `debug_with_db` identifier.
+    pub debug_with_db: Option<syn::Ident>,
+
     /// Signal we should not include the `'db` lifetime.
     ///
     /// If this is `Some`, the value is the `no_lifetime` identifier.
@@ -51,6 +106,7 @@ pub(crate) struct Options<A: AllowedOptions> {
     pub db_path: Option<syn::Path>,
 
     /// The `recovery_fn = <path>` option is used to indicate the recovery function.
+    /// `cycle_fallback = <path>` is accepted as another name for it.
     ///
     /// If this is `Some`, the value is the `<path>`.
     pub recovery_fn: Option<syn::Path>,
@@ -66,18 +122,88 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<usize>`.
     pub lru: Option<usize>,
 
+    /// The `history = <usize>` option is used to retain the previous values of a tracked function.
+    ///
+    /// If this is `Some`, the value is the `<usize>`.
+    pub history: Option<usize>,
+
     /// The `constructor = <ident>` option lets the user specify the name of
     /// the constructor of a salsa struct.
     ///
     /// If this is `Some`, the value is the `<ident>`.
     pub constructor_name: Option<syn::Ident>,
 
+    /// The `getter_prefix = "<prefix>"` option prepends `<prefix>` to the names of the
+    /// field getters of a salsa struct.
+    ///
+    /// If this is `Some`, the value is the `"<prefix>"` literal.
+    pub getter_prefix: Option<syn::LitStr>,
+
+    /// The `setter_prefix = "<prefix>"` option replaces the `set_` prefix of the names of
+    /// the field setters of an input.
+    ///
+    /// If this is `Some`, the value is the `"<prefix>"` literal.
+    pub setter_prefix: Option<syn::LitStr>,
+
     /// The `id = <path>` option is used to set a custom ID for interrned structs.
     ///
     /// The ID must implement `salsa::plumbing::AsId` and `salsa::plumbing::FromId`.
     /// If this is `Some`, the value is the `<ident>`.
     pub id: Option<syn::Path>,
 
+    /// The `identity = <path>` option is used to compute the identity of tracked structs
+    /// from their untracked fields with a custom function.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub identity: Option<syn::Path>,
+
+    /// The `eq_with = <path>` option is used to compare the outputs of tracked functions
+    /// for backdating with a custom function rather than `Eq`.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub eq_with: Option<syn::Path>,
+
+    /// The `unsafe(update_with = <path>)` option is used to update the old outputs of tracked
+    /// functions with a custom unsafe function rather than the `Update` impl, e.g. for
+    /// outputs like `Box<dyn Trait>`.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub update_with: Option<syn::Path>,
+
+    /// The `cache_key = <path>` option is used to look up the values of tracked functions
+    /// in the [memo cache backend](`salsa::MemoCacheBackend`) before executing them,
+    /// keyed by the fingerprint computed by the function `<path>`.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub cache_key: Option<syn::Path>,
+
+    /// The `hasher = <type>` option is used to set the `BuildHasher` used by the interner
+    /// of interned structs.
+    ///
+    /// If this is `Some`, the value is the `<type>`.
+    pub hasher: Option<syn::Type>,
+
+    /// The `volatile(ttl_revisions = <usize>)` option is used to re-execute a tracked function
+    /// once its memoized value is `<usize>` revisions old, even if its inputs did not change.
+    ///
+    /// If this is `Some`, the value is the `<usize>`.
+    pub volatile_ttl: Option<usize>,
+
+    /// Signal we should generate a `salsa::Transfer` impl.
+    ///
+    /// If this is `Some`, the value is the `transfer` identifier.
+    pub transfer: Option<syn::Ident>,
+
+    /// Signal that the fields of an input can be set within `Database::speculate`.
+    ///
+    /// If this is `Some`, the value is the `speculate` identifier.
+    pub speculate: Option<syn::Ident>,
+
+    /// Signal that the `#[lazy]` fields of an input are loaded on first read.
+    ///
+    /// If this is `Some`, the value is the `lazy` identifier.
+    pub lazy: Option<syn::Ident>,
+
     /// Remember the `A` parameter, which plays no role after parsing.
     phantom: PhantomData<A>,
 }
@@ -88,17 +214,38 @@ impl<A: AllowedOptions> Default for Options<A> {
             return_ref: Default::default(),
             specify: Default::default(),
             no_eq: Default::default(),
+            intern_output: Default::default(),
+            validate_args: Default::default(),
+            auto_cancel_check: Default::default(),
+            firewall: Default::default(),
+            broadcast: Default::default(),
+            keep_stale_on_panic: Default::default(),
+            memoize_panics: Default::default(),
+            fingerprint: Default::default(),
             no_debug: Default::default(),
+            debug_with_db: Default::default(),
             no_lifetime: Default::default(),
             no_clone: Default::default(),
             db_path: Default::default(),
             recovery_fn: Default::default(),
             data: Default::default(),
             constructor_name: Default::default(),
+            getter_prefix: Default::default(),
+            setter_prefix: Default::default(),
             phantom: Default::default(),
             lru: Default::default(),
+            history: Default::default(),
             singleton: Default::default(),
             id: Default::default(),
+            identity: Default::default(),
+            eq_with: Default::default(),
+            update_with: Default::default(),
+            cache_key: Default::default(),
+            hasher: Default::default(),
+            volatile_ttl: Default::default(),
+            transfer: Default::default(),
+            speculate: Default::default(),
+            lazy: Default::default(),
         }
     }
 }
@@ -108,7 +255,16 @@ pub(crate) trait AllowedOptions {
     const RETURN_REF: bool;
     const SPECIFY: bool;
     const NO_EQ: bool;
+    const INTERN_OUTPUT: bool;
+    const VALIDATE_ARGS: bool;
+    const AUTO_CANCEL_CHECK: bool;
+    const FIREWALL: bool;
+    const BROADCAST: bool;
+    const KEEP_STALE_ON_PANIC: bool;
+    const MEMOIZE_PANICS: bool;
+    const FINGERPRINT: bool;
     const NO_DEBUG: bool;
+    const DEBUG_WITH_DB: bool;
     const NO_LIFETIME: bool;
     const NO_CLONE: bool;
     const SINGLETON: bool;
@@ -116,8 +272,20 @@ pub(crate) trait AllowedOptions {
     const DB: bool;
     const RECOVERY_FN: bool;
     const LRU: bool;
+    const HISTORY: bool;
     const CONSTRUCTOR_NAME: bool;
+    const GETTER_PREFIX: bool;
+    const SETTER_PREFIX: bool;
     const ID: bool;
+    const IDENTITY: bool;
+    const EQ_WITH: bool;
+    const UPDATE_WITH: bool;
+    const CACHE_KEY: bool;
+    const HASHER: bool;
+    const VOLATILE: bool;
+    const TRANSFER: bool;
+    const SPECULATE: bool;
+    const LAZY: bool;
 }
 
 type Equals = syn::Token![=];
@@ -154,6 +322,122 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`no_eq` option not allowed here",
                     ));
                 }
+            } else if ident == "intern_output" {
+                if A::INTERN_OUTPUT {
+                    if let Some(old) = std::mem::replace(&mut options.intern_output, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `intern_output` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`intern_output` option not allowed here",
+                    ));
+                }
+            } else if ident == "validate_args" {
+                if A::VALIDATE_ARGS {
+                    if let Some(old) = std::mem::replace(&mut options.validate_args, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `validate_args` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`validate_args` option not allowed here",
+                    ));
+                }
+            } else if ident == "auto_cancel_check" {
+                if A::AUTO_CANCEL_CHECK {
+                    if let Some(old) =
+                        std::mem::replace(&mut options.auto_cancel_check, Some(ident))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `auto_cancel_check` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`auto_cancel_check` option not allowed here",
+                    ));
+                }
+            } else if ident == "keep_stale_on_panic" {
+                if A::KEEP_STALE_ON_PANIC {
+                    if let Some(old) =
+                        std::mem::replace(&mut options.keep_stale_on_panic, Some(ident))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `keep_stale_on_panic` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`keep_stale_on_panic` option not allowed here",
+                    ));
+                }
+            } else if ident == "memoize_panics" {
+                if A::MEMOIZE_PANICS {
+                    if let Some(old) = std::mem::replace(&mut options.memoize_panics, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `memoize_panics` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`memoize_panics` option not allowed here",
+                    ));
+                }
+            } else if ident == "fingerprint" {
+                if A::FINGERPRINT {
+                    if let Some(old) = std::mem::replace(&mut options.fingerprint, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `fingerprint` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`fingerprint` option not allowed here",
+                    ));
+                }
+            } else if ident == "firewall" {
+                if A::FIREWALL {
+                    if let Some(old) = std::mem::replace(&mut options.firewall, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `firewall` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`firewall` option not allowed here",
+                    ));
+                }
+            } else if ident == "broadcast" {
+                if A::BROADCAST {
+                    if let Some(old) = std::mem::replace(&mut options.broadcast, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `broadcast` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`broadcast` option not allowed here",
+                    ));
+                }
             } else if ident == "no_debug" {
                 if A::NO_DEBUG {
                     if let Some(old) = std::mem::replace(&mut options.no_debug, Some(ident)) {
@@ -168,6 +452,59 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`no_debug` option not allowed here",
                     ));
                 }
+            } else if ident == "debug_with_db" {
+                if A::DEBUG_WITH_DB {
+                    if let Some(old) = std::mem::replace(&mut options.debug_with_db, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `debug_with_db` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`debug_with_db` option not allowed here",
+                    ));
+                }
+            } else if ident == "transfer" {
+                if A::TRANSFER {
+                    if let Some(old) = std::mem::replace(&mut options.transfer, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `transfer` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`transfer` option not allowed here",
+                    ));
+                }
+            } else if ident == "speculate" {
+                if A::SPECULATE {
+                    if let Some(old) = std::mem::replace(&mut options.speculate, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `speculate` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`speculate` option not allowed here",
+                    ));
+                }
+            } else if ident == "lazy" {
+                if A::LAZY {
+                    if let Some(old) = std::mem::replace(&mut options.lazy, Some(ident)) {
+                        return Err(syn::Error::new(old.span(), "option `lazy` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`lazy` option not allowed here",
+                    ));
+                }
             } else if ident == "no_lifetime" {
                 if A::NO_LIFETIME {
                     if let Some(old) = std::mem::replace(&mut options.no_lifetime, Some(ident)) {
@@ -237,20 +574,20 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`db` option not allowed here",
                     ));
                 }
-            } else if ident == "recovery_fn" {
+            } else if ident == "recovery_fn" || ident == "cycle_fallback" {
                 if A::RECOVERY_FN {
                     let _eq = Equals::parse(input)?;
                     let path = syn::Path::parse(input)?;
                     if let Some(old) = std::mem::replace(&mut options.recovery_fn, Some(path)) {
                         return Err(syn::Error::new(
                             old.span(),
-                            "option `recovery_fn` provided twice",
+                            "option `recovery_fn` (or `cycle_fallback`) provided twice",
                         ));
                     }
                 } else {
                     return Err(syn::Error::new(
                         ident.span(),
-                        "`recovery_fn` option not allowed here",
+                        format!("`{ident}` option not allowed here"),
                     ));
                 }
             } else if ident == "data" {
@@ -280,6 +617,29 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`lru` option not allowed here",
                     ));
                 }
+            } else if ident == "history" {
+                if A::HISTORY {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::LitInt::parse(input)?;
+                    let value = lit.base10_parse::<usize>()?;
+                    if value == 0 {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "`history` must retain at least one value",
+                        ));
+                    }
+                    if let Some(old) = std::mem::replace(&mut options.history, Some(value)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `history` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`history` option not allowed here",
+                    ));
+                }
             } else if ident == "constructor" {
                 if A::CONSTRUCTOR_NAME {
                     let _eq = Equals::parse(input)?;
@@ -297,6 +657,38 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`constructor` option not allowed here",
                     ));
                 }
+            } else if ident == "getter_prefix" {
+                if A::GETTER_PREFIX {
+                    let _eq = Equals::parse(input)?;
+                    let lit: syn::LitStr = input.parse()?;
+                    if let Some(old) = std::mem::replace(&mut options.getter_prefix, Some(lit)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `getter_prefix` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`getter_prefix` option not allowed here",
+                    ));
+                }
+            } else if ident == "setter_prefix" {
+                if A::SETTER_PREFIX {
+                    let _eq = Equals::parse(input)?;
+                    let lit: syn::LitStr = input.parse()?;
+                    if let Some(old) = std::mem::replace(&mut options.setter_prefix, Some(lit)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `setter_prefix` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`setter_prefix` option not allowed here",
+                    ));
+                }
             } else if ident == "id" {
                 if A::ID {
                     let _eq = Equals::parse(input)?;
@@ -308,6 +700,121 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`id` option not allowed here",
                     ));
                 }
+            } else if ident == "identity" {
+                if A::IDENTITY {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.identity, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `identity` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`identity` option not allowed here",
+                    ));
+                }
+            } else if ident == "eq_with" {
+                if A::EQ_WITH {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.eq_with, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `eq_with` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`eq_with` option not allowed here",
+                    ));
+                }
+            } else if ident == "unsafe" {
+                if A::UPDATE_WITH {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let key = syn::Ident::parse(&content)?;
+                    if key != "update_with" {
+                        return Err(syn::Error::new(key.span(), "expected `update_with`"));
+                    }
+                    let _eq = Equals::parse(&content)?;
+                    let path = syn::Path::parse(&content)?;
+                    if let Some(old) = std::mem::replace(&mut options.update_with, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `update_with` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`unsafe(update_with)` option not allowed here",
+                    ));
+                }
+            } else if ident == "cache_key" {
+                if A::CACHE_KEY {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.cache_key, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `cache_key` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cache_key` option not allowed here",
+                    ));
+                }
+            } else if ident == "hasher" {
+                if A::HASHER {
+                    let _eq = Equals::parse(input)?;
+                    let ty = syn::Type::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.hasher, Some(ty)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `hasher` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`hasher` option not allowed here",
+                    ));
+                }
+            } else if ident == "volatile" {
+                if A::VOLATILE {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let key = syn::Ident::parse(&content)?;
+                    if key != "ttl_revisions" {
+                        return Err(syn::Error::new(key.span(), "expected `ttl_revisions`"));
+                    }
+                    let _eq = Equals::parse(&content)?;
+                    let lit = syn::LitInt::parse(&content)?;
+                    let value = lit.base10_parse::<usize>()?;
+                    if value == 0 {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "`ttl_revisions` must be at least 1",
+                        ));
+                    }
+                    if options.volatile_ttl.replace(value).is_some() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "option `volatile` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`volatile` option not allowed here",
+                    ));
+                }
             } else {
                 return Err(syn::Error::new(
                     ident.span(),
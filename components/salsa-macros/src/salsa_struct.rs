@@ -29,7 +29,8 @@ use crate::{
     db_lifetime,
     options::{AllowedOptions, Options},
 };
-use proc_macro2::{Ident, Literal, Span, TokenStream};
+use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
+use quote::ToTokens;
 
 pub(crate) struct SalsaStruct<'s, A: SalsaStructAllowedOptions> {
     struct_item: &'s syn::ItemStruct,
@@ -52,6 +53,12 @@ pub(crate) trait SalsaStructAllowedOptions: AllowedOptions {
 
     /// Are `#[default]` fields allowed?
     const ALLOW_DEFAULT: bool;
+
+    /// Are `#[durability(..)]` fields allowed?
+    const ALLOW_DURABILITY: bool;
+
+    /// Are `#[index]` fields allowed?
+    const ALLOW_INDEX: bool;
 }
 
 pub(crate) struct SalsaField<'s> {
@@ -59,8 +66,12 @@ pub(crate) struct SalsaField<'s> {
 
     pub(crate) has_tracked_attr: bool,
     pub(crate) has_default_attr: bool,
+    default_expr: Option<syn::Expr>,
     pub(crate) has_ref_attr: bool,
     pub(crate) has_no_eq_attr: bool,
+    pub(crate) has_index_attr: bool,
+    pub(crate) has_lazy_attr: bool,
+    durability: Option<syn::Ident>,
     get_name: syn::Ident,
     set_name: syn::Ident,
 }
@@ -70,14 +81,24 @@ const BANNED_FIELD_NAMES: &[&str] = &["from", "new"];
 #[allow(clippy::type_complexity)]
 pub(crate) const FIELD_OPTION_ATTRIBUTES: &[(&str, fn(&syn::Attribute, &mut SalsaField))] = &[
     ("tracked", |_, ef| ef.has_tracked_attr = true),
-    ("default", |_, ef| ef.has_default_attr = true),
+    ("default", |attr, ef| {
+        ef.has_default_attr = true;
+        if let syn::Meta::List(_) = attr.meta {
+            ef.default_expr = Some(attr.parse_args().unwrap());
+        }
+    }),
     ("return_ref", |_, ef| ef.has_ref_attr = true),
     ("no_eq", |_, ef| ef.has_no_eq_attr = true),
+    ("index", |_, ef| ef.has_index_attr = true),
+    ("lazy", |_, ef| ef.has_lazy_attr = true),
     ("get", |attr, ef| {
-        ef.get_name = attr.parse_args().unwrap();
+        ef.get_name = attr.parse_args_with(parse_method_name).unwrap();
     }),
     ("set", |attr, ef| {
-        ef.set_name = attr.parse_args().unwrap();
+        ef.set_name = attr.parse_args_with(parse_method_name).unwrap();
+    }),
+    ("durability", |attr, ef| {
+        ef.durability = Some(attr.parse_args().unwrap());
     }),
 ];
 
@@ -96,7 +117,7 @@ where
         let fields = n
             .named
             .iter()
-            .map(SalsaField::new)
+            .map(|field| SalsaField::new(field, args))
             .collect::<syn::Result<_>>()?;
 
         let this = Self {
@@ -107,6 +128,10 @@ where
 
         this.maybe_disallow_tracked_fields()?;
         this.maybe_disallow_default_fields()?;
+        this.check_durability_fields()?;
+        this.maybe_disallow_index_fields()?;
+        this.check_lazy_fields()?;
+        this.check_no_lifetime_fields()?;
 
         this.check_generics()?;
 
@@ -179,6 +204,135 @@ where
         Ok(())
     }
 
+    /// Check the `#[durability(..)]` attributes on the fields of this struct.
+    ///
+    /// They are only allowed if `A::ALLOW_DURABILITY` is true,
+    /// and must name one of the durability levels (`low`, `medium`, `high`).
+    fn check_durability_fields(&self) -> syn::Result<()> {
+        for ef in &self.fields {
+            let Some(durability) = &ef.durability else {
+                continue;
+            };
+
+            if !A::ALLOW_DURABILITY {
+                return Err(syn::Error::new_spanned(
+                    ef.field,
+                    format!(
+                        "`#[durability]` cannot be used with `#[salsa::{}]`",
+                        A::KIND
+                    ),
+                ));
+            }
+
+            if !["low", "medium", "high"].contains(&durability.to_string().as_str()) {
+                return Err(syn::Error::new_spanned(
+                    durability,
+                    "expected one of `low`, `medium`, or `high`",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disallow `#[index]` attributes on the fields of this struct.
+    ///
+    /// If an `#[index]` field is found, return an error.
+    fn maybe_disallow_index_fields(&self) -> syn::Result<()> {
+        if A::ALLOW_INDEX {
+            return Ok(());
+        }
+
+        for ef in &self.fields {
+            if ef.has_index_attr {
+                return Err(syn::Error::new_spanned(
+                    ef.field,
+                    format!("`#[index]` cannot be used with `#[salsa::{}]`", A::KIND),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the `#[lazy]` attributes on the fields of this struct.
+    ///
+    /// They require the `lazy` option, which in turn requires at least one `#[lazy]` field.
+    /// A lazy field is not given to the constructor, so it cannot have a `#[default]` and
+    /// cannot be indexed before it is loaded.
+    fn check_lazy_fields(&self) -> syn::Result<()> {
+        let Some(lazy) = &self.args.lazy else {
+            if let Some(ef) = self.fields.iter().find(|ef| ef.has_lazy_attr) {
+                return Err(syn::Error::new_spanned(
+                    ef.field,
+                    format!(
+                        "`#[lazy]` requires the `lazy` option, as in `#[salsa::{}(lazy)]`",
+                        A::KIND
+                    ),
+                ));
+            }
+            return Ok(());
+        };
+
+        if !self.fields.iter().any(|ef| ef.has_lazy_attr) {
+            return Err(syn::Error::new_spanned(
+                lazy,
+                "`lazy` requires at least one field marked `#[lazy]`",
+            ));
+        }
+
+        for (option, name) in [
+            (&self.args.singleton, "singleton"),
+            (&self.args.transfer, "transfer"),
+            (&self.args.speculate, "speculate"),
+            (&self.args.fingerprint, "fingerprint"),
+        ] {
+            if let Some(option) = option {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    format!("`{name}` cannot be combined with `lazy`"),
+                ));
+            }
+        }
+
+        for ef in &self.fields {
+            if ef.has_lazy_attr && (ef.has_default_attr || ef.has_index_attr) {
+                return Err(syn::Error::new_spanned(
+                    ef.field,
+                    "`#[lazy]` cannot be combined with `#[default]` or `#[index]`",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// With `no_lifetime`, the struct is a `'static` handle that may outlive any revision,
+    /// so its fields may not reference the `'db` lifetime (e.g., be tracked structs).
+    fn check_no_lifetime_fields(&self) -> syn::Result<()> {
+        if self.args.no_lifetime.is_none() {
+            return Ok(());
+        }
+
+        let Some(db_lt) = self.struct_item.generics.lifetimes().next() else {
+            return Ok(());
+        };
+
+        for ef in &self.fields {
+            if mentions_lifetime(ef.field.ty.to_token_stream(), &db_lt.lifetime) {
+                return Err(syn::Error::new_spanned(
+                    &ef.field.ty,
+                    format!(
+                        "fields of a `no_lifetime` struct cannot reference the `{}` lifetime",
+                        db_lt.lifetime
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Check that the generic parameters look as expected for this kind of struct.
     fn check_generics(&self) -> syn::Result<()> {
         if A::HAS_LIFETIME {
@@ -231,7 +385,7 @@ where
         self.fields
             .iter()
             .filter_map(|f| {
-                if f.has_default_attr {
+                if f.has_default_attr || f.has_lazy_attr {
                     None
                 } else {
                     let ident = f.field.ident.as_ref().unwrap();
@@ -242,6 +396,25 @@ where
             .collect()
     }
 
+    /// The initial value of each field in the builder: the `#[default(..)]` expression,
+    /// `Default::default()` for a bare `#[default]` or a `#[lazy]` field, or else the
+    /// required argument named like the field.
+    pub(crate) fn field_defaults(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .map(
+                |f| match (&f.default_expr, f.has_default_attr || f.has_lazy_attr) {
+                    (Some(expr), _) => quote!(#expr),
+                    (None, true) => quote!(::core::default::Default::default()),
+                    (None, false) => {
+                        let ident = f.field.ident.as_ref().unwrap();
+                        quote!(#ident)
+                    }
+                },
+            )
+            .collect()
+    }
+
     pub(crate) fn field_vis(&self) -> Vec<&syn::Visibility> {
         self.fields.iter().map(|f| &f.field.vis).collect()
     }
@@ -285,6 +458,61 @@ where
             .collect()
     }
 
+    /// The default durability of each field: the one given with `#[durability(..)]`, if any.
+    pub(crate) fn field_durabilities(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .map(|f| match &f.durability {
+                Some(durability) => {
+                    let durability =
+                        syn::Ident::new(&durability.to_string().to_uppercase(), durability.span());
+                    quote!(salsa::Durability::#durability)
+                }
+                None => quote!(salsa::Durability::LOW),
+            })
+            .collect()
+    }
+
+    /// For each `#[index]` field: its index, visibility, the name of its lookup method
+    /// (`find_by_foo`) and its type.
+    pub(crate) fn index_fields(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.has_index_attr)
+            .map(|(index, f)| {
+                let index = Literal::usize_unsuffixed(index);
+                let vis = &f.field.vis;
+                let find_id = quote::format_ident!("find_by_{}", f.field.ident.as_ref().unwrap());
+                let ty = &f.field.ty;
+                quote!(#index #vis #find_id #ty)
+            })
+            .collect()
+    }
+
+    /// Whether each field is `#[lazy]`.
+    pub(crate) fn field_lazy(&self) -> Vec<bool> {
+        self.fields.iter().map(|f| f.has_lazy_attr).collect()
+    }
+
+    /// For each `#[lazy]` field: its index, visibility, the name of the method registering
+    /// its loader (`register_foo_loader`) and its type.
+    pub(crate) fn lazy_fields(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.has_lazy_attr)
+            .map(|(index, f)| {
+                let index = Literal::usize_unsuffixed(index);
+                let vis = &f.field.vis;
+                let register_id =
+                    quote::format_ident!("register_{}_loader", f.field.ident.as_ref().unwrap());
+                let ty = &f.field.ty;
+                quote!(#index #vis #register_id #ty)
+            })
+            .collect()
+    }
+
     pub(crate) fn field_tys(&self) -> Vec<&syn::Type> {
         self.fields.iter().map(|f| &f.field.ty).collect()
     }
@@ -329,6 +557,22 @@ where
         self.args.no_debug.is_none()
     }
 
+    pub fn generate_debug_with_db(&self) -> bool {
+        self.args.debug_with_db.is_some()
+    }
+
+    pub fn generate_transfer_impl(&self) -> bool {
+        self.args.transfer.is_some()
+    }
+
+    pub fn generate_speculate_impl(&self) -> bool {
+        self.args.speculate.is_some()
+    }
+
+    pub fn generate_fingerprint_impl(&self) -> bool {
+        self.args.fingerprint.is_some()
+    }
+
     pub fn generate_lifetime(&self) -> bool {
         self.args.no_lifetime.is_none()
     }
@@ -349,7 +593,7 @@ where
 }
 
 impl<'s> SalsaField<'s> {
-    fn new(field: &'s syn::Field) -> syn::Result<Self> {
+    fn new<A: AllowedOptions>(field: &'s syn::Field, args: &Options<A>) -> syn::Result<Self> {
         let field_name = field.ident.as_ref().unwrap();
         let field_name_str = field_name.to_string();
         if BANNED_FIELD_NAMES.iter().any(|n| *n == field_name_str) {
@@ -362,14 +606,32 @@ impl<'s> SalsaField<'s> {
             ));
         }
 
-        let get_name = Ident::new(&field_name_str, field_name.span());
-        let set_name = Ident::new(&format!("set_{}", field_name_str), field_name.span());
+        let getter_prefix = args
+            .getter_prefix
+            .as_ref()
+            .map_or(String::new(), |p| p.value());
+        let setter_prefix = args
+            .setter_prefix
+            .as_ref()
+            .map_or(String::from("set_"), |p| p.value());
+        let get_name = Ident::new(
+            &format!("{getter_prefix}{field_name_str}"),
+            field_name.span(),
+        );
+        let set_name = Ident::new(
+            &format!("{setter_prefix}{field_name_str}"),
+            field_name.span(),
+        );
         let mut result = SalsaField {
             field,
             has_tracked_attr: false,
             has_ref_attr: false,
             has_default_attr: false,
+            default_expr: None,
             has_no_eq_attr: false,
+            has_index_attr: false,
+            has_lazy_attr: false,
+            durability: None,
             get_name,
             set_name,
         };
@@ -408,3 +670,36 @@ impl<'s> SalsaField<'s> {
         quote!((#clone_ident, #backdate_ident, #default_ident))
     }
 }
+
+/// Parses the argument of `#[get(..)]` or `#[set(..)]`: either the method name itself,
+/// e.g. `#[get(text)]`, or `name = "text"`.
+fn parse_method_name(input: syn::parse::ParseStream) -> syn::Result<Ident> {
+    let ident: Ident = input.parse()?;
+    if input.is_empty() {
+        return Ok(ident);
+    }
+    if ident != "name" {
+        return Err(syn::Error::new(ident.span(), "expected `name = \"..\"`"));
+    }
+    let _eq: syn::Token![=] = input.parse()?;
+    let lit: syn::LitStr = input.parse()?;
+    lit.parse()
+}
+
+/// True if `tokens` contain the lifetime `lt`.
+fn mentions_lifetime(tokens: TokenStream, lt: &syn::Lifetime) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) if mentions_lifetime(group.stream(), lt) => return true,
+            TokenTree::Punct(punct)
+                if punct.as_char() == '\''
+                    && matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if *ident == lt.ident) =>
+            {
+                return true
+            }
+            _ => {}
+        }
+    }
+    false
+}
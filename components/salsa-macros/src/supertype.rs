@@ -0,0 +1,181 @@
+use proc_macro2::TokenStream;
+
+use crate::xform::ChangeLt;
+
+/// Implements the traits that let an enum whose variants each wrap a salsa struct
+/// be used as the key of a tracked function, along with conversions from and to the variants.
+pub(crate) fn supertype_derive(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let syn::Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(Supertype)` only supports `enum`",
+        ));
+    };
+
+    if let Some(param) = input
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+    {
+        return Err(syn::Error::new_spanned(
+            param,
+            "`derive(Supertype)` does not support type or const parameters",
+        ));
+    }
+    let db_lt = match input.generics.lifetimes().collect::<Vec<_>>()[..] {
+        [] => None,
+        [param] => Some(param.lifetime.ident.to_string()),
+        [_, param, ..] => {
+            return Err(syn::Error::new_spanned(
+                param,
+                "`derive(Supertype)` supports at most one lifetime parameter",
+            ))
+        }
+    };
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(Supertype)` requires at least one variant",
+        ));
+    }
+    let mut variants = vec![];
+    for variant in &data.variants {
+        match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                variants.push((&variant.ident, &fields.unnamed[0].ty));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "each variant of a `derive(Supertype)` enum must wrap exactly one salsa struct",
+                ))
+            }
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let to_static = |ty: &syn::Type| match &db_lt {
+        Some(db_lt) => ChangeLt::to_static(db_lt).in_type(ty),
+        None => ty.clone(),
+    };
+    let static_ident = to_static(&parse_quote!(#ident #ty_generics));
+
+    let variant_idents = variants
+        .iter()
+        .map(|(variant, _)| variant)
+        .collect::<Vec<_>>();
+    let variant_tys = variants.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+    let static_variant_tys = variant_tys
+        .iter()
+        .map(|ty| to_static(ty))
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        const _: () = {
+            use salsa::plumbing as zalsa;
+
+            impl #impl_generics zalsa::AsId for #ident #ty_generics #where_clause {
+                fn as_id(&self) -> zalsa::Id {
+                    match self {
+                        #(Self::#variant_idents(value) => zalsa::AsId::as_id(value),)*
+                    }
+                }
+            }
+
+            impl #impl_generics zalsa::FromIdWithDb for #ident #ty_generics #where_clause {
+                fn from_id(id: zalsa::Id, db: &dyn zalsa::Database) -> Self {
+                    #(
+                        if zalsa::is_entry_of::<#variant_tys>(db, id) {
+                            return Self::#variant_idents(zalsa::FromId::from_id(id));
+                        }
+                    )*
+                    panic!("{id:?} is not an entry of a variant of `{}`", stringify!(#ident))
+                }
+            }
+
+            impl #impl_generics zalsa::SalsaStructInDb for #ident #ty_generics #where_clause {
+                fn lookup_ingredient_index(
+                    _aux: &dyn zalsa::JarAux,
+                ) -> core::option::Option<zalsa::IngredientIndex> {
+                    // The variants each have their own ingredient.
+                    None
+                }
+
+                fn lookup_ingredient_indices(aux: &dyn zalsa::JarAux) -> Vec<zalsa::IngredientIndex> {
+                    let mut indices = vec![];
+                    #(
+                        indices.extend(
+                            <#variant_tys as zalsa::SalsaStructInDb>::lookup_ingredient_indices(aux),
+                        );
+                    )*
+                    indices
+                }
+
+                fn register_ingredients(db: &dyn zalsa::Database) {
+                    #(<#variant_tys as zalsa::SalsaStructInDb>::register_ingredients(db);)*
+                }
+            }
+
+            // Lets functions keyed by the enum be `specify`-ed, if all variants are tracked structs.
+            impl #impl_generics zalsa::TrackedStructInDb for #ident #ty_generics
+            where
+                // The higher-ranked bounds defer the check to the use site.
+                #(for<'__salsa_register> #variant_tys: zalsa::TrackedStructInDb,)*
+            {
+                fn database_key_index(db: &dyn zalsa::Database, id: zalsa::Id) -> zalsa::DatabaseKeyIndex {
+                    #(
+                        if zalsa::is_entry_of::<#variant_tys>(db, id) {
+                            return <#variant_tys as zalsa::TrackedStructInDb>::database_key_index(db, id);
+                        }
+                    )*
+                    panic!("{id:?} is not an entry of a variant of `{}`", stringify!(#ident))
+                }
+            }
+
+            #[allow(non_local_definitions)]
+            impl zalsa::JarGroup for #static_ident
+            where
+                // The higher-ranked bounds defer the check to the use site.
+                #(for<'__salsa_register> #static_variant_tys: zalsa::JarGroup,)*
+            {
+                fn register(registry: &zalsa::Registry<'_>) {
+                    #(registry.add::<#static_variant_tys>();)*
+                }
+            }
+
+            #(
+                impl #impl_generics From<#variant_tys> for #ident #ty_generics #where_clause {
+                    fn from(value: #variant_tys) -> Self {
+                        Self::#variant_idents(value)
+                    }
+                }
+
+                impl #impl_generics TryFrom<#ident #ty_generics> for #variant_tys #where_clause {
+                    type Error = #ident #ty_generics;
+
+                    fn try_from(value: #ident #ty_generics) -> Result<Self, Self::Error> {
+                        match value {
+                            #ident::#variant_idents(value) => Ok(value),
+                            #[allow(unreachable_patterns)]
+                            value => Err(value),
+                        }
+                    }
+                }
+            )*
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Returns the salsa struct wrapped by this value, if it is a `T`.
+                #[allow(dead_code)]
+                pub fn downcast<T>(self) -> Option<T>
+                where
+                    T: TryFrom<Self>,
+                {
+                    T::try_from(self).ok()
+                }
+            }
+        };
+    })
+}
@@ -29,8 +29,26 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const NO_EQ: bool = true;
 
+    const INTERN_OUTPUT: bool = true;
+
+    const VALIDATE_ARGS: bool = true;
+
+    const AUTO_CANCEL_CHECK: bool = true;
+
+    const FIREWALL: bool = true;
+
+    const BROADCAST: bool = true;
+
+    const KEEP_STALE_ON_PANIC: bool = true;
+
+    const MEMOIZE_PANICS: bool = true;
+
+    const FINGERPRINT: bool = true;
+
     const NO_DEBUG: bool = false;
 
+    const DEBUG_WITH_DB: bool = false;
+
     const NO_LIFETIME: bool = false;
 
     const NO_CLONE: bool = false;
@@ -45,9 +63,33 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const LRU: bool = true;
 
+    const HISTORY: bool = true;
+
     const CONSTRUCTOR_NAME: bool = false;
 
+    const GETTER_PREFIX: bool = false;
+
+    const SETTER_PREFIX: bool = false;
+
     const ID: bool = false;
+
+    const IDENTITY: bool = false;
+
+    const EQ_WITH: bool = true;
+
+    const UPDATE_WITH: bool = true;
+
+    const CACHE_KEY: bool = true;
+
+    const HASHER: bool = false;
+
+    const VOLATILE: bool = true;
+
+    const TRANSFER: bool = false;
+
+    const SPECULATE: bool = false;
+
+    const LAZY: bool = false;
 }
 
 struct Macro {
@@ -75,6 +117,28 @@ impl Macro {
         let (cycle_recovery_fn, cycle_recovery_strategy) = self.cycle_recovery();
         let is_specifiable = self.args.specify.is_some();
         let no_eq = self.args.no_eq.is_some();
+        let has_eq_with = self.args.eq_with.is_some();
+        let eq_with = self.args.eq_with.iter();
+        let has_update_with = self.args.update_with.is_some();
+        let update_with = self.args.update_with.iter();
+        let has_cache_key = self.args.cache_key.is_some();
+        let cache_key = self.args.cache_key.iter();
+        let intern_output = self.args.intern_output.is_some();
+        let validate_args = self.args.validate_args.is_some();
+        let auto_cancel_check = self.args.auto_cancel_check.is_some();
+        let firewall = self.args.firewall.is_some();
+        let broadcast = self.args.broadcast.is_some();
+        let keep_stale_on_panic = self.args.keep_stale_on_panic.is_some();
+        let memoize_panics = self.args.memoize_panics.is_some();
+        let fingerprint = self.args.fingerprint.is_some();
+        let generic_params: Vec<_> = item
+            .sig
+            .generics
+            .type_params()
+            .map(|param| &param.ident)
+            .collect();
+        let generic_bounds = self.generic_bounds(&item);
+        let is_generic = !generic_params.is_empty();
 
         let mut inner_fn = item.clone();
         inner_fn.vis = syn::Visibility::Inherited;
@@ -101,6 +165,18 @@ impl Macro {
             }
         }
 
+        if let Some(token) = &self.args.validate_args {
+            match function_type {
+                FunctionType::Constant | FunctionType::RequiresInterning => {
+                    return Err(syn::Error::new_spanned(
+                        token,
+                        "only functions with a single salsa struct as their input can validate their arguments",
+                    ))
+                }
+                FunctionType::SalsaStruct => {}
+            }
+        }
+
         if let (Some(_), Some(token)) = (&self.args.lru, &self.args.specify) {
             return Err(syn::Error::new_spanned(
                 token,
@@ -108,6 +184,83 @@ impl Macro {
             ));
         }
 
+        if let (Some(_), Some(token)) = (&self.args.volatile_ttl, &self.args.specify) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `specify` and `volatile` options cannot be used together",
+            ));
+        }
+
+        if let (Some(_), Some(token)) = (&self.args.no_eq, &self.args.intern_output) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `no_eq` and `intern_output` options cannot be used together",
+            ));
+        }
+
+        if let (Some(_), Some(eq_with)) = (&self.args.no_eq, &self.args.eq_with) {
+            return Err(syn::Error::new_spanned(
+                eq_with,
+                "the `no_eq` and `eq_with` options cannot be used together",
+            ));
+        }
+
+        if let Some(token) = &self.args.fingerprint {
+            if self.args.no_eq.is_some() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `fingerprint` and `no_eq` options cannot be used together",
+                ));
+            }
+            if self.args.eq_with.is_some() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `fingerprint` and `eq_with` options cannot be used together",
+                ));
+            }
+        }
+
+        if let Some(token) = &self.args.firewall {
+            if self.args.no_eq.is_some() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `firewall` and `no_eq` options cannot be used together",
+                ));
+            }
+            if self.args.lru.is_some() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `firewall` and `lru` options cannot be used together",
+                ));
+            }
+        }
+
+        if let (Some(_), Some(token)) = (&self.args.keep_stale_on_panic, &self.args.memoize_panics)
+        {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `keep_stale_on_panic` and `memoize_panics` options cannot be used together",
+            ));
+        }
+
+        if is_generic {
+            if function_type != FunctionType::SalsaStruct {
+                return Err(syn::Error::new_spanned(
+                    &item.sig.generics,
+                    "generic tracked functions must take a single salsa struct argument besides the database",
+                ));
+            }
+
+            for input_ty in &input_tys {
+                if mentions_any(input_ty.to_token_stream(), &generic_params) {
+                    return Err(syn::Error::new_spanned(
+                        input_ty,
+                        "the arguments of a generic tracked function cannot depend on its type parameters",
+                    ));
+                }
+            }
+        }
+
         let needs_interner = match function_type {
             FunctionType::Constant | FunctionType::RequiresInterning => true,
             FunctionType::SalsaStruct => false,
@@ -115,6 +268,10 @@ impl Macro {
 
         let lru = Literal::usize_unsuffixed(self.args.lru.unwrap_or(0));
 
+        let volatile_ttl = Literal::usize_unsuffixed(self.args.volatile_ttl.unwrap_or(0));
+
+        let history = Literal::usize_unsuffixed(self.args.history.unwrap_or(0));
+
         let return_ref: bool = self.args.return_ref.is_some();
 
         Ok(crate::debug::dump_tokens(
@@ -134,8 +291,27 @@ impl Macro {
                 cycle_recovery_strategy: #cycle_recovery_strategy,
                 is_specifiable: #is_specifiable,
                 no_eq: #no_eq,
+                has_eq_with: #has_eq_with,
+                eq_with: (#(#eq_with)*),
+                has_update_with: #has_update_with,
+                update_with: (#(#update_with)*),
+                has_cache_key: #has_cache_key,
+                cache_key: (#(#cache_key)*),
+                intern_output: #intern_output,
+                validate_args: #validate_args,
+                auto_cancel_check: #auto_cancel_check,
+                firewall: #firewall,
+                broadcast: #broadcast,
+                keep_stale_on_panic: #keep_stale_on_panic,
+                memoize_panics: #memoize_panics,
+                fingerprint: #fingerprint,
+                is_generic: #is_generic,
+                generic_params: [#(#generic_params),*],
+                generic_bounds: [#(#generic_bounds,)*],
                 needs_interner: #needs_interner,
                 lru: #lru,
+                volatile_ttl: #volatile_ttl,
+                history: #history,
                 return_ref: #return_ref,
                 unused_names: [
                     #zalsa,
@@ -150,7 +326,7 @@ impl Macro {
     }
 
     fn validity_check<'item>(&self, item: &'item syn::ItemFn) -> syn::Result<ValidFn<'item>> {
-        db_lifetime::require_optional_db_lifetime(&item.sig.generics)?;
+        check_generics(&item.sig.generics)?;
 
         if item.sig.inputs.is_empty() {
             return Err(syn::Error::new_spanned(
@@ -176,6 +352,23 @@ impl Macro {
         }
     }
 
+    /// The bounds of the type parameters, each of which must also be `'static`
+    /// to identify the ingredient of its instance of the function.
+    fn generic_bounds(&self, item: &ItemFn) -> Vec<TokenStream> {
+        let generics = &item.sig.generics;
+        let param_bounds = generics.type_params().map(|param| {
+            let ident = &param.ident;
+            let bounds = param.bounds.iter();
+            quote!(#ident: #(#bounds +)* 'static)
+        });
+        let where_predicates = generics
+            .where_clause
+            .iter()
+            .flat_map(|where_clause| &where_clause.predicates)
+            .map(|predicate| predicate.to_token_stream());
+        param_bounds.chain(where_predicates).collect()
+    }
+
     fn input_ids(&self, item: &ItemFn) -> Vec<syn::Ident> {
         fn_util::input_ids(&self.hygiene, &item.sig, 1)
     }
@@ -207,6 +400,39 @@ fn function_type(item_fn: &syn::ItemFn) -> FunctionType {
     }
 }
 
+/// Requires the generics of a tracked function to consist of an optional `'db` lifetime
+/// followed by type parameters.
+fn check_generics(generics: &syn::Generics) -> syn::Result<()> {
+    for (param, index) in generics.params.iter().zip(0..) {
+        match param {
+            syn::GenericParam::Lifetime(_) if index > 0 => {
+                return Err(syn::Error::new_spanned(
+                    param,
+                    "only a single lifetime parameter is accepted",
+                ))
+            }
+            syn::GenericParam::Lifetime(_) | syn::GenericParam::Type(_) => {}
+            syn::GenericParam::Const(_) => {
+                return Err(syn::Error::new_spanned(
+                    param,
+                    "tracked functions cannot have const parameters",
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// True if `tokens` contain any of `idents`.
+fn mentions_any(tokens: TokenStream, idents: &[&syn::Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => idents.iter().any(|&i| *i == ident),
+        proc_macro2::TokenTree::Group(group) => mentions_any(group.stream(), idents),
+        _ => false,
+    })
+}
+
 pub fn check_db_argument<'arg>(
     fn_arg: &'arg syn::FnArg,
     explicit_lt: Option<&'arg syn::LifetimeParam>,
@@ -271,6 +497,14 @@ pub fn check_db_argument<'arg>(
                 return Err(syn::Error::new(typed.ty.span(), tykind_error_msg));
             };
 
+            if let Some(m) = &ref_type.mutability {
+                return Err(syn::Error::new_spanned(
+                    m,
+                    "tracked functions can only read the database, so it must be taken as `&dyn Db`; \
+                     set inputs before calling the tracked function instead",
+                ));
+            }
+
             if let Some(lt) = explicit_lt {
                 if ref_type.lifetime.is_none() {
                     return Err(syn::Error::new_spanned(
@@ -281,10 +515,6 @@ pub fn check_db_argument<'arg>(
             }
 
             let extract_db_path = || -> Result<&'arg syn::Path, Span> {
-                if let Some(m) = &ref_type.mutability {
-                    return Err(m.span());
-                }
-
                 let syn::Type::TraitObject(d) = &*ref_type.elem else {
                     return Err(ref_type.span());
                 };
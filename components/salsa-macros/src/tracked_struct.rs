@@ -35,8 +35,26 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const NO_EQ: bool = false;
 
+    const INTERN_OUTPUT: bool = false;
+
+    const VALIDATE_ARGS: bool = false;
+
+    const AUTO_CANCEL_CHECK: bool = false;
+
+    const FIREWALL: bool = false;
+
+    const BROADCAST: bool = false;
+
+    const KEEP_STALE_ON_PANIC: bool = false;
+
+    const MEMOIZE_PANICS: bool = false;
+
+    const FINGERPRINT: bool = false;
+
     const NO_DEBUG: bool = true;
 
+    const DEBUG_WITH_DB: bool = true;
+
     const NO_LIFETIME: bool = false;
 
     const NO_CLONE: bool = false;
@@ -51,9 +69,33 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const LRU: bool = false;
 
+    const HISTORY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
+    const GETTER_PREFIX: bool = true;
+
+    const SETTER_PREFIX: bool = false;
+
     const ID: bool = false;
+
+    const IDENTITY: bool = true;
+
+    const EQ_WITH: bool = false;
+
+    const UPDATE_WITH: bool = false;
+
+    const CACHE_KEY: bool = false;
+
+    const HASHER: bool = false;
+
+    const VOLATILE: bool = false;
+
+    const TRANSFER: bool = false;
+
+    const SPECULATE: bool = false;
+
+    const LAZY: bool = false;
 }
 
 impl SalsaStructAllowedOptions for TrackedStruct {
@@ -65,7 +107,11 @@ impl SalsaStructAllowedOptions for TrackedStruct {
 
     const ELIDABLE_LIFETIME: bool = false;
 
-    const ALLOW_DEFAULT: bool = false;
+    const ALLOW_DEFAULT: bool = true;
+
+    const ALLOW_DURABILITY: bool = false;
+
+    const ALLOW_INDEX: bool = false;
 }
 
 struct Macro {
@@ -100,21 +146,28 @@ impl Macro {
         let relative_tracked_indices = (0..absolute_tracked_indices.len()).collect::<Vec<_>>();
 
         let absolute_untracked_indices = salsa_struct.untracked_field_indices();
+        let relative_untracked_indices = (0..absolute_untracked_indices.len()).collect::<Vec<_>>();
 
         let field_options = salsa_struct.field_options();
         let tracked_options = salsa_struct.tracked_options();
         let untracked_options = salsa_struct.untracked_options();
 
         let field_tys = salsa_struct.field_tys();
+        let required_fields = salsa_struct.required_fields();
+        let field_defaults = salsa_struct.field_defaults();
         let tracked_tys = salsa_struct.tracked_tys();
         let untracked_tys = salsa_struct.untracked_tys();
 
         let num_fields = salsa_struct.num_fields();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let generate_debug_with_db = salsa_struct.generate_debug_with_db();
+        let custom_identity = self.args.identity.is_some();
+        let identity_fn = self.args.identity.iter();
 
         let zalsa = self.hygiene.ident("zalsa");
         let zalsa_struct = self.hygiene.ident("zalsa_struct");
         let Configuration = self.hygiene.ident("Configuration");
+        let Builder = self.hygiene.ident("Builder");
         let CACHE = self.hygiene.ident("CACHE");
         let Db = self.hygiene.ident("Db");
         let NonNull = self.hygiene.ident("NonNull");
@@ -137,6 +190,8 @@ impl Macro {
                     untracked_getters: [#(#untracked_vis #untracked_getter_ids),*],
 
                     field_tys: [#(#field_tys),*],
+                    required_fields: [#(#required_fields),*],
+                    field_defaults: [#(#field_defaults),*],
                     tracked_tys: [#(#tracked_tys),*],
                     untracked_tys: [#(#untracked_tys),*],
 
@@ -146,17 +201,22 @@ impl Macro {
                     relative_tracked_indices: [#(#relative_tracked_indices),*],
 
                     absolute_untracked_indices: [#(#absolute_untracked_indices),*],
+                    relative_untracked_indices: [#(#relative_untracked_indices),*],
 
                     field_options: [#(#field_options),*],
                     tracked_options: [#(#tracked_options),*],
                     untracked_options: [#(#untracked_options),*],
 
                     num_fields: #num_fields,
+                    custom_identity: #custom_identity,
+                    identity_fn: (#(#identity_fn)*),
                     generate_debug_impl: #generate_debug_impl,
+                    generate_debug_with_db: #generate_debug_with_db,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
                         #Configuration,
+                        #Builder,
                         #CACHE,
                         #Db,
                         #NonNull,
@@ -8,7 +8,7 @@ pub(crate) struct ChangeLt<'a> {
     to: String,
 }
 
-impl ChangeLt<'_> {
+impl<'a> ChangeLt<'a> {
     pub fn elided_to(db_lt: &syn::Lifetime) -> Self {
         ChangeLt {
             from: Some("_"),
@@ -16,6 +16,13 @@ impl ChangeLt<'_> {
         }
     }
 
+    pub fn to_static(from: &'a str) -> Self {
+        ChangeLt {
+            from: Some(from),
+            to: "static".to_string(),
+        }
+    }
+
     pub fn in_type(mut self, ty: &syn::Type) -> syn::Type {
         let mut ty = ty.clone();
         self.visit_type_mut(&mut ty);
@@ -11,7 +11,7 @@ use accumulated::AnyAccumulated;
 
 use crate::{
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, Jar, MaybeChangedAfter},
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, MaybeChangedAfter},
     plumbing::JarAux,
     zalsa::IngredientIndex,
     zalsa_local::QueryOrigin,
@@ -32,8 +32,23 @@ pub trait Accumulator: Clone + Debug + Send + Sync + Any + Sized {
         Db: ?Sized + Database;
 }
 
+/// A value accumulated by a tracked function, along with the query that accumulated it;
+/// returned by the `accumulated_with_provenance` method of tracked functions.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct AccumulatedWithProvenance<A> {
+    pub value: A,
+
+    /// The query that accumulated the value, e.g. to map a diagnostic back to its file.
+    pub query: DatabaseKeyIndex,
+
+    /// The revision in which the query executed and accumulated the value.
+    /// Values of queries that were validated since are reported with their original revision.
+    pub accumulated_at: Revision,
+}
+
 pub struct JarImpl<A: Accumulator> {
-    phantom: PhantomData<A>,
+    phantom: PhantomData<fn() -> A>,
 }
 
 impl<A: Accumulator> Default for JarImpl<A> {
@@ -56,6 +71,10 @@ impl<A: Accumulator> Jar for JarImpl<A> {
     fn salsa_struct_type_id(&self) -> Option<std::any::TypeId> {
         None
     }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self::default())
+    }
 }
 
 pub struct IngredientImpl<A: Accumulator> {
@@ -85,7 +104,8 @@ impl<A: Accumulator> IngredientImpl<A> {
 
     pub fn push(&self, db: &dyn Database, value: A) {
         let zalsa_local = db.zalsa_local();
-        if let Err(()) = zalsa_local.accumulate(self.index, value) {
+        let revision = db.zalsa().current_revision();
+        if let Err(()) = zalsa_local.accumulate(self.index, value, revision) {
             panic!("cannot accumulate values outside of an active tracked function");
         }
     }
@@ -100,6 +120,10 @@ impl<A: Accumulator> Ingredient for IngredientImpl<A> {
         self.index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Accumulator
+    }
+
     fn maybe_changed_after(
         &self,
         _db: &dyn Database,
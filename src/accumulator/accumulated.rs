@@ -19,6 +19,10 @@ impl<A: Accumulator> Accumulated<A> {
         self.values.push(value);
     }
 
+    pub fn values(&self) -> &[A] {
+        &self.values
+    }
+
     pub fn extend_with_accumulated(&self, values: &mut Vec<A>) {
         values.extend_from_slice(&self.values);
     }
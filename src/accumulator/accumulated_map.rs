@@ -2,17 +2,26 @@ use std::ops;
 
 use rustc_hash::FxHashMap;
 
-use crate::IngredientIndex;
+use crate::{IngredientIndex, Revision};
 
 use super::{accumulated::Accumulated, Accumulator, AnyAccumulated};
 
 #[derive(Default, Debug)]
 pub struct AccumulatedMap {
     map: FxHashMap<IngredientIndex, Box<dyn AnyAccumulated>>,
+
+    /// The revision in which the values were accumulated, if any.
+    revision: Option<Revision>,
 }
 
 impl AccumulatedMap {
-    pub fn accumulate<A: Accumulator>(&mut self, index: IngredientIndex, value: A) {
+    pub fn accumulate<A: Accumulator>(
+        &mut self,
+        index: IngredientIndex,
+        value: A,
+        revision: Revision,
+    ) {
+        self.revision = Some(revision);
         self.map
             .entry(index)
             .or_insert_with(|| <Box<Accumulated<A>>>::default())
@@ -34,6 +43,21 @@ impl AccumulatedMap {
             .extend_with_accumulated(output);
     }
 
+    /// Returns the values accumulated for the accumulator `index`,
+    /// along with the revision in which they were accumulated.
+    pub fn accumulated_at<A: Accumulator>(
+        &self,
+        index: IngredientIndex,
+    ) -> Option<(&[A], Revision)> {
+        let a = self.map.get(&index)?;
+        let values = a
+            .as_dyn_any()
+            .downcast_ref::<Accumulated<A>>()
+            .unwrap()
+            .values();
+        Some((values, self.revision?))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
     }
@@ -47,6 +71,7 @@ impl Clone for AccumulatedMap {
                 .iter()
                 .map(|(&key, value)| (key, value.cloned()))
                 .collect(),
+            revision: self.revision,
         }
     }
 }
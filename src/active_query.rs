@@ -1,20 +1,21 @@
 use std::ops::Not;
 
-use crossbeam::atomic::AtomicCell;
-
-use super::zalsa_local::{QueryEdges, QueryOrigin, QueryRevisions};
+use super::zalsa_local::{CompletedQuery, QueryEdges, QueryOrigin, QueryRevisions};
 use crate::key::OutputDependencyIndex;
 use crate::tracked_struct::{DisambiguatorMap, IdentityHash, IdentityMap};
 use crate::zalsa_local::QueryEdge;
 use crate::{
     accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues},
-    durability::Durability,
+    durability::{Durabilities, Durability},
     hash::FxIndexSet,
     key::{DatabaseKeyIndex, InputDependencyIndex},
     tracked_struct::Disambiguator,
     Cycle, Revision,
 };
 
+/// The number of inputs in [`ActiveQuery::recent_reads`].
+const RECENT_READS: usize = 4;
+
 #[derive(Debug)]
 pub(crate) struct ActiveQuery {
     /// What query is executing
@@ -23,6 +24,10 @@ pub(crate) struct ActiveQuery {
     /// Minimum durability of inputs observed so far.
     pub(crate) durability: Durability,
 
+    /// Durabilities of the inputs observed so far, including those read by the
+    /// queries observed so far.
+    pub(crate) durabilities: Durabilities,
+
     /// Maximum revision of all inputs observed. If we observe an
     /// untracked read, this will be set to the most recent revision.
     pub(crate) changed_at: Revision,
@@ -35,6 +40,10 @@ pub(crate) struct ActiveQuery {
     /// * accumulators pushed to
     input_outputs: FxIndexSet<QueryEdge>,
 
+    /// The last few distinct inputs read, which are checked before `input_outputs` so that
+    /// reading the same inputs over and over (e.g., in a loop) does not hash them each time.
+    recent_reads: [Option<InputDependencyIndex>; RECENT_READS],
+
     /// True if there was an untracked read.
     untracked_read: bool,
 
@@ -61,6 +70,20 @@ pub(crate) struct ActiveQuery {
     /// [`InputAccumulatedValues::Empty`] if any input read during the query's execution
     /// has any accumulated values.
     pub(super) accumulated_inputs: InputAccumulatedValues,
+
+    /// True if cancellation is checked after each read, see
+    /// [`Configuration::AUTO_CANCEL_CHECK`](`crate::function::Configuration::AUTO_CANCEL_CHECK`).
+    pub(crate) auto_cancel_check: bool,
+}
+
+/// The reads recorded by an [`ActiveQuery`] at some point of its execution.
+pub(super) struct ReadsSnapshot {
+    input_outputs_len: usize,
+    durability: Durability,
+    durabilities: Durabilities,
+    changed_at: Revision,
+    untracked_read: bool,
+    accumulated_inputs: InputAccumulatedValues,
 }
 
 impl ActiveQuery {
@@ -68,42 +91,85 @@ impl ActiveQuery {
         ActiveQuery {
             database_key_index,
             durability: Durability::MAX,
+            durabilities: Durabilities::default(),
             changed_at: Revision::start(),
             input_outputs: FxIndexSet::default(),
+            recent_reads: [None; RECENT_READS],
             untracked_read: false,
             cycle: None,
             disambiguator_map: Default::default(),
             tracked_struct_ids: Default::default(),
             accumulated: Default::default(),
             accumulated_inputs: Default::default(),
+            auto_cancel_check: false,
         }
     }
 
+    /// Records a read of `input`, returning false if it was already read, in which
+    /// case it is not recorded again.
     pub(super) fn add_read(
         &mut self,
         input: InputDependencyIndex,
         durability: Durability,
+        durabilities: Durabilities,
         revision: Revision,
         accumulated: InputAccumulatedValues,
-    ) {
-        self.input_outputs.insert(QueryEdge::Input(input));
+    ) -> bool {
+        let new = !self.recent_reads.contains(&Some(input))
+            && self.input_outputs.insert(QueryEdge::Input(input));
+        if new {
+            self.recent_reads.rotate_right(1);
+            self.recent_reads[0] = Some(input);
+        }
         self.durability = self.durability.min(durability);
+        self.durabilities |= durabilities;
         self.changed_at = self.changed_at.max(revision);
         self.accumulated_inputs |= accumulated;
+        new
     }
 
     pub(super) fn add_untracked_read(&mut self, changed_at: Revision) {
         self.untracked_read = true;
         self.durability = Durability::MIN;
+        self.durabilities |= Durabilities::UNTRACKED;
         self.changed_at = changed_at;
     }
 
     pub(super) fn add_synthetic_read(&mut self, durability: Durability, revision: Revision) {
         self.untracked_read = true;
         self.durability = self.durability.min(durability);
+        self.durabilities |= Durabilities::at_least(durability);
         self.changed_at = self.changed_at.max(revision);
     }
 
+    /// Captures the reads recorded so far, see [`ActiveQuery::restore_reads`].
+    pub(super) fn reads_snapshot(&self) -> ReadsSnapshot {
+        ReadsSnapshot {
+            input_outputs_len: self.input_outputs.len(),
+            durability: self.durability,
+            durabilities: self.durabilities,
+            changed_at: self.changed_at,
+            untracked_read: self.untracked_read,
+            accumulated_inputs: self.accumulated_inputs,
+        }
+    }
+
+    /// Forgets the reads recorded since `snapshot` was taken; outputs recorded since are kept.
+    pub(super) fn restore_reads(&mut self, snapshot: ReadsSnapshot) {
+        let recorded = self.input_outputs.split_off(snapshot.input_outputs_len);
+        self.recent_reads = [None; RECENT_READS];
+        self.input_outputs.extend(
+            recorded
+                .into_iter()
+                .filter(|edge| matches!(edge, QueryEdge::Output(_))),
+        );
+        self.durability = snapshot.durability;
+        self.durabilities = snapshot.durabilities;
+        self.changed_at = snapshot.changed_at;
+        self.untracked_read = snapshot.untracked_read;
+        self.accumulated_inputs = snapshot.accumulated_inputs;
+    }
+
     /// Adds a key to our list of outputs.
     pub(super) fn add_output(&mut self, key: OutputDependencyIndex) {
         self.input_outputs.insert(QueryEdge::Output(key));
@@ -114,25 +180,49 @@ impl ActiveQuery {
         self.input_outputs.contains(&QueryEdge::Output(key))
     }
 
-    pub(crate) fn into_revisions(self) -> QueryRevisions {
-        let edges = QueryEdges::new(self.input_outputs);
+    /// Reinitializes this frame to execute `database_key_index`, keeping the
+    /// allocations of its buffers, see [`ZalsaLocal::push_query`](`crate::zalsa_local::ZalsaLocal::push_query`).
+    pub(super) fn reset(&mut self, database_key_index: DatabaseKeyIndex) {
+        let mut input_outputs = std::mem::take(&mut self.input_outputs);
+        let mut disambiguator_map = std::mem::take(&mut self.disambiguator_map);
+        input_outputs.clear();
+        disambiguator_map.clear();
+        *self = ActiveQuery {
+            input_outputs,
+            disambiguator_map,
+            ..ActiveQuery::new(database_key_index)
+        };
+    }
+
+    /// The number of edges this frame can record without reallocating.
+    pub(super) fn edge_capacity(&self) -> usize {
+        self.input_outputs.capacity()
+    }
+
+    /// Extracts the result of the execution, leaving the buffers of this frame
+    /// empty so that they can be reused.
+    pub(crate) fn take_completed(&mut self) -> CompletedQuery {
+        let edges = QueryEdges::new(self.input_outputs.drain(..));
+        self.recent_reads = [None; RECENT_READS];
         let origin = if self.untracked_read {
             QueryOrigin::DerivedUntracked(edges)
         } else {
             QueryOrigin::Derived(edges)
         };
-        let accumulated = self
-            .accumulated
-            .is_empty()
-            .not()
-            .then(|| Box::new(self.accumulated));
-        QueryRevisions {
-            changed_at: self.changed_at,
-            origin,
+        let accumulated = std::mem::take(&mut self.accumulated);
+        let accumulated = accumulated.is_empty().not().then(|| Box::new(accumulated));
+        CompletedQuery {
+            revisions: QueryRevisions {
+                changed_at: self.changed_at,
+                origin,
+                tracked_struct_ids: std::mem::take(&mut self.tracked_struct_ids),
+                accumulated,
+            },
             durability: self.durability,
-            tracked_struct_ids: self.tracked_struct_ids,
-            accumulated_inputs: AtomicCell::new(self.accumulated_inputs),
-            accumulated,
+            // Readers also depend on the durability itself, which e.g. is reported
+            // as changed when the memo is invalidated.
+            durabilities: self.durabilities | Durabilities::of(self.durability),
+            accumulated_inputs: self.accumulated_inputs,
         }
     }
 
@@ -141,7 +231,34 @@ impl ActiveQuery {
     pub(super) fn add_from(&mut self, other: &ActiveQuery) {
         self.changed_at = self.changed_at.max(other.changed_at);
         self.durability = self.durability.min(other.durability);
+        self.durabilities |= other.durabilities;
+        self.untracked_read |= other.untracked_read;
+        self.input_outputs
+            .extend(other.input_outputs.iter().copied());
+    }
+
+    /// Adds the reads of `other`, which executed on behalf of this query on another thread;
+    /// see [`crate::join`].
+    ///
+    /// # Panics
+    ///
+    /// If `other` created tracked structs, specified values or accumulated values, which
+    /// must happen within the tracked functions it invoked instead.
+    pub(super) fn add_reads_from(&mut self, other: &ActiveQuery) {
+        assert!(
+            other
+                .input_outputs
+                .iter()
+                .all(|edge| matches!(edge, QueryEdge::Input(_)))
+                && other.accumulated.is_empty(),
+            "`salsa::join` closures can only read the database: create tracked structs, \
+             specify or accumulate values within the tracked functions they invoke instead"
+        );
+        self.changed_at = self.changed_at.max(other.changed_at);
+        self.durability = self.durability.min(other.durability);
+        self.durabilities |= other.durabilities;
         self.untracked_read |= other.untracked_read;
+        self.accumulated_inputs |= other.accumulated_inputs;
         self.input_outputs
             .extend(other.input_outputs.iter().copied());
     }
@@ -153,6 +270,7 @@ impl ActiveQuery {
             let p: InputDependencyIndex = p.into();
             self.input_outputs.shift_remove(&QueryEdge::Input(p));
         }
+        self.recent_reads = [None; RECENT_READS];
     }
 
     /// Copy the changed-at, durability, and dependencies from `cycle_query`.
@@ -160,7 +278,9 @@ impl ActiveQuery {
     pub(crate) fn take_inputs_from(&mut self, cycle_query: &ActiveQuery) {
         self.changed_at = cycle_query.changed_at;
         self.durability = cycle_query.durability;
+        self.durabilities = cycle_query.durabilities;
         self.input_outputs.clone_from(&cycle_query.input_outputs);
+        self.recent_reads = [None; RECENT_READS];
     }
 
     pub(super) fn disambiguate(&mut self, key: IdentityHash) -> Disambiguator {
@@ -0,0 +1,108 @@
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::{Database, Update};
+
+/// The computation of an ad-hoc query, as passed to [`Database::memo`].
+type Compute<V> = Arc<dyn Fn(&dyn Database) -> V + Send + Sync>;
+
+/// Identifies an ad-hoc query by the type of its closure and the user's key.
+///
+/// The closure of the first call with a given key is kept along with it, so that the
+/// query can be re-executed when one of its inputs changed. It is ignored when comparing
+/// keys: closures of the same type are assumed to compute the same value for the same key.
+#[salsa::interned(no_lifetime)]
+struct AdHocKey {
+    key: ErasedKey,
+}
+
+#[derive(Clone)]
+struct ErasedKey {
+    closure_type: TypeId,
+    key: Arc<dyn DynKey>,
+
+    /// A `Compute<V>`, where `V` is the value of the query.
+    compute: Arc<dyn Any + Send + Sync>,
+}
+
+/// Object-safe `Hash` and `Eq` for the keys of ad-hoc queries.
+trait DynKey: Any + fmt::Debug + Send + Sync {
+    fn dyn_eq(&self, other: &dyn DynKey) -> bool;
+
+    fn dyn_hash(&self, state: &mut dyn Hasher);
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<K> DynKey for K
+where
+    K: Hash + Eq + fmt::Debug + Send + Sync + 'static,
+{
+    fn dyn_eq(&self, other: &dyn DynKey) -> bool {
+        other.as_any().downcast_ref::<K>() == Some(self)
+    }
+
+    fn dyn_hash(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl PartialEq for ErasedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.closure_type == other.closure_type && self.key.dyn_eq(&*other.key)
+    }
+}
+
+impl Eq for ErasedKey {}
+
+impl Hash for ErasedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.closure_type.hash(state);
+        self.key.dyn_hash(state);
+    }
+}
+
+impl fmt::Debug for ErasedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+#[salsa::tracked]
+fn ad_hoc<V>(db: &dyn Database, key: AdHocKey) -> V
+where
+    V: Clone + Eq + fmt::Debug + Send + Sync + Update + 'static,
+{
+    let key = key.key(db);
+    let compute = key
+        .compute
+        .downcast_ref::<Compute<V>>()
+        .expect("ad-hoc query with a different value type");
+    compute(db)
+}
+
+pub(crate) fn memo<K, V, F>(db: &dyn Database, key: K, compute: F) -> V
+where
+    K: Hash + Eq + fmt::Debug + Send + Sync + 'static,
+    V: Clone + Eq + fmt::Debug + Send + Sync + Update + 'static,
+    F: Fn(&dyn Database) -> V + Send + Sync + 'static,
+{
+    let compute: Compute<V> = Arc::new(compute);
+    let key = AdHocKey::new(
+        db,
+        ErasedKey {
+            closure_type: TypeId::of::<F>(),
+            key: Arc::new(key),
+            compute: Arc::new(compute),
+        },
+    );
+    ad_hoc::<V>(db, key)
+}
@@ -16,12 +16,16 @@ thread_local! {
 struct Attached {
     /// Pointer to the currently attached database.
     database: Cell<Option<NonNull<dyn Database>>>,
+
+    /// True while a [`BlockingStrategy`](`crate::BlockingStrategy`) waits on this thread.
+    blocking: Cell<bool>,
 }
 
 impl Attached {
     const fn new() -> Self {
         Self {
             database: Cell::new(None),
+            blocking: Cell::new(false),
         }
     }
 
@@ -35,6 +39,16 @@ impl Attached {
 
         impl<'s> DbGuard<'s> {
             fn new(attached: &'s Attached, db: &dyn Database) -> Self {
+                // The thread is still waiting for a query executing on another thread,
+                // so it must not execute (and possibly wait for) queries itself.
+                if attached.blocking.get() {
+                    panic!(
+                        "cannot invoke tracked functions within `BlockingStrategy::block`: \
+                        the queries of this thread are suspended while it waits, \
+                        so work that uses salsa must run on other threads"
+                    );
+                }
+
                 if let Some(current_db) = attached.database.get() {
                     let new_db = NonNull::from(db);
 
@@ -71,6 +85,25 @@ impl Attached {
         op()
     }
 
+    fn while_blocking<R>(&self, op: impl FnOnce() -> R) -> R {
+        struct BlockingGuard<'s> {
+            attached: &'s Attached,
+            blocking: bool,
+        }
+
+        impl Drop for BlockingGuard<'_> {
+            fn drop(&mut self) {
+                self.attached.blocking.set(self.blocking);
+            }
+        }
+
+        let _guard = BlockingGuard {
+            attached: self,
+            blocking: self.blocking.replace(true),
+        };
+        op()
+    }
+
     /// Access the "attached" database. Returns `None` if no database is attached.
     /// Databases are attached with `attach_database`.
     fn with<R>(&self, op: impl FnOnce(&dyn Database) -> R) -> Option<R> {
@@ -91,8 +124,41 @@ where
     ATTACHED.with(|a| a.attach(db, op))
 }
 
+/// Execute `op`, the [`BlockingStrategy`](`crate::BlockingStrategy`) of a thread waiting
+/// for a query executing on another thread. Tracked functions invoked by `op` panic, as the
+/// queries of the thread are suspended meanwhile.
+pub(crate) fn while_blocking<R>(op: impl FnOnce() -> R) -> R {
+    ATTACHED.with(|a| a.while_blocking(op))
+}
+
 /// Access the "attached" database. Returns `None` if no database is attached.
 /// Databases are attached with `attach_database`.
 pub fn with_attached_database<R>(op: impl FnOnce(&dyn Database) -> R) -> Option<R> {
     ATTACHED.with(|a| a.with(op))
 }
+
+/// Formats a salsa struct with `fmt` while `db` is attached, so that the values of its fields,
+/// including those of nested salsa structs, are printed.
+///
+/// Returned by the `debug` method of salsa structs with the `debug_with_db` option.
+pub struct DebugWithDb<'db, T> {
+    value: T,
+    db: &'db dyn Database,
+    fmt: fn(T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+}
+
+impl<'db, T> DebugWithDb<'db, T> {
+    pub fn new(
+        value: T,
+        db: &'db dyn Database,
+        fmt: fn(T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+    ) -> Self {
+        Self { value, db, fmt }
+    }
+}
+
+impl<T: Copy> std::fmt::Debug for DebugWithDb<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        attach(self.db, || (self.fmt)(self.value, f))
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::hash::FxIndexMap;
+use crate::zalsa::Zalsa;
+use crate::{DatabaseKeyIndex, Revision};
+
+/// A violation of the invariants of the database, reported by
+/// [`Database::audit_consistency`](`crate::Database::audit_consistency`).
+///
+/// The `Display` impl names the affected queries; attach the database
+/// (see [`Database::attach`](`crate::Database::attach`)) to get their full keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConsistencyViolation {
+    /// The memo for `key` was verified in a revision that has not yet started.
+    VerifiedInFuture {
+        key: DatabaseKeyIndex,
+        verified_at: Revision,
+        current_revision: Revision,
+    },
+
+    /// The memo for `key` claims its value changed after it was last verified.
+    ChangedAfterVerified {
+        key: DatabaseKeyIndex,
+        changed_at: Revision,
+        verified_at: Revision,
+    },
+
+    /// `output` is recorded as an output by more than one query.
+    MultipleExecutors {
+        output: DatabaseKeyIndex,
+        executors: Vec<DatabaseKeyIndex>,
+    },
+}
+
+impl fmt::Display for ConsistencyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsistencyViolation::VerifiedInFuture {
+                key,
+                verified_at,
+                current_revision,
+            } => write!(
+                f,
+                "{key:?} was verified at {verified_at:?}, after the current revision {current_revision:?}"
+            ),
+            ConsistencyViolation::ChangedAfterVerified {
+                key,
+                changed_at,
+                verified_at,
+            } => write!(
+                f,
+                "{key:?} changed at {changed_at:?}, after it was verified at {verified_at:?}"
+            ),
+            ConsistencyViolation::MultipleExecutors { output, executors } => {
+                write!(f, "{output:?} is an output of {executors:?}")
+            }
+        }
+    }
+}
+
+impl Zalsa {
+    /// Walks all memos, checking the invariants described by [`ConsistencyViolation`].
+    pub(crate) fn audit_consistency(&mut self) -> Vec<ConsistencyViolation> {
+        let current_revision = self.current_revision();
+        let mut violations = vec![];
+        let mut executors: FxIndexMap<DatabaseKeyIndex, Vec<DatabaseKeyIndex>> = Default::default();
+
+        let mut memos = vec![];
+        self.table_mut()
+            .for_each_memo_table(|struct_index, id, memo_table| {
+                memos.extend(
+                    memo_table
+                        .memos()
+                        .into_iter()
+                        .map(|(memo_index, memo)| (struct_index, id, memo_index, memo)),
+                )
+            });
+
+        for (struct_index, key_index, memo_index, memo) in memos {
+            let key = DatabaseKeyIndex {
+                ingredient_index: self.ingredient_index_for_memo(struct_index, memo_index),
+                key_index,
+            };
+
+            let verified_at = memo.verified_at();
+            if verified_at > current_revision {
+                violations.push(ConsistencyViolation::VerifiedInFuture {
+                    key,
+                    verified_at,
+                    current_revision,
+                });
+            }
+
+            let changed_at = memo.changed_at();
+            if changed_at > verified_at {
+                violations.push(ConsistencyViolation::ChangedAfterVerified {
+                    key,
+                    changed_at,
+                    verified_at,
+                });
+            }
+
+            for output in memo.origin().outputs() {
+                executors.entry(output.into()).or_default().push(key);
+            }
+        }
+
+        violations.extend(
+            executors
+                .into_iter()
+                .filter(|(_, executors)| executors.len() > 1)
+                .map(
+                    |(output, executors)| ConsistencyViolation::MultipleExecutors {
+                        output,
+                        executors,
+                    },
+                ),
+        );
+
+        violations
+    }
+}
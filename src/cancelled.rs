@@ -19,6 +19,25 @@ pub enum Cancelled {
     /// The query was blocked on another thread, and that thread panicked.
     #[non_exhaustive]
     PropagatedPanic,
+
+    /// An [`Interceptor`](`crate::Interceptor`) vetoed the execution of the query.
+    #[non_exhaustive]
+    Vetoed,
+
+    /// The query ran with [`Priority::Low`](`crate::Priority::Low`) and a thread
+    /// of higher priority was waiting for it.
+    #[non_exhaustive]
+    Preempted,
+
+    /// The query ran within [`Database::run_with_fuel`](`crate::Database::run_with_fuel`)
+    /// and would have executed more queries than its budget allows.
+    #[non_exhaustive]
+    FuelExhausted,
+
+    /// The query ran within [`Database::run_with_deadline`](`crate::Database::run_with_deadline`)
+    /// and the deadline passed.
+    #[non_exhaustive]
+    DeadlineExceeded,
 }
 
 impl Cancelled {
@@ -48,6 +67,10 @@ impl std::fmt::Display for Cancelled {
         let why = match self {
             Cancelled::PendingWrite => "pending write",
             Cancelled::PropagatedPanic => "propagated panic",
+            Cancelled::Vetoed => "veto from an interceptor",
+            Cancelled::Preempted => "preemption by a query of higher priority",
+            Cancelled::FuelExhausted => "exhausted fuel",
+            Cancelled::DeadlineExceeded => "exceeded deadline",
         };
         f.write_str("cancelled because of ")?;
         f.write_str(why)
@@ -50,8 +50,9 @@ impl Cycle {
     }
 
     /// Iterate over the [`DatabaseKeyIndex`] for each query participating
-    /// in the cycle. The start point of this iteration within the cycle
-    /// is arbitrary but deterministic, but the ordering is otherwise determined
+    /// in the cycle. The iteration starts at the participant with the smallest
+    /// function name and, among those, the smallest id, so that it does not depend
+    /// on which participant was entered first; the ordering is otherwise determined
     /// by the execution.
     pub fn participant_keys(&self) -> impl Iterator<Item = DatabaseKeyIndex> + '_ {
         self.participants.iter().copied()
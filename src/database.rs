@@ -1,8 +1,11 @@
-use std::{any::Any, borrow::Cow};
+use std::{any::Any, borrow::Cow, fmt, hash::Hash, time::Instant};
 
 use crate::{
+    salsa_struct::SalsaStructInDb,
+    views::MissingView,
     zalsa::{IngredientIndex, ZalsaDatabase},
-    Durability, Event, Revision,
+    Cancelled, ConsistencyViolation, Durability, Event, InputChange, Priority, Revision, StateDiff,
+    StateSummary, Update,
 };
 
 /// The trait implemented by all Salsa databases.
@@ -31,6 +34,100 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_mut.report_tracked_write(durability);
     }
 
+    /// Like [`synthetic_write`](`Self::synthetic_write`), but restricted to the input struct `I`:
+    /// acts as though every field of every `I` with durability `durability` or lower was set
+    /// to an equal value. Queries that read such a field re-execute (and may be backdated),
+    /// while queries reading only other inputs are merely revalidated.
+    ///
+    /// # Panics
+    ///
+    /// If `I` is not a `#[salsa::input]` struct.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation, see [`synthetic_write`](`Self::synthetic_write`).
+    fn synthetic_write_for<I: SalsaStructInDb>(&mut self, durability: Durability)
+    where
+        Self: Sized,
+    {
+        let zalsa_mut = self.zalsa_mut();
+        let indices = zalsa_mut.lookup_salsa_struct_indices::<I>();
+        if indices.is_empty() {
+            // No `I` was created yet, so there is nothing to invalidate.
+            zalsa_mut.report_tracked_write(durability);
+        }
+        for index in indices {
+            let (ingredient, runtime) = zalsa_mut.lookup_ingredient_mut(index);
+            ingredient.synthetic_write(runtime, durability);
+        }
+    }
+
+    /// Executes `op`, applying all writes it performs in a single new revision.
+    ///
+    /// Normally, every `set_*` call on an input starts a new revision, cancelling
+    /// other handles and invalidating memoized values each time. Within a batch,
+    /// only the first write cancels other handles and starts a new revision;
+    /// subsequent writes are applied to that same revision.
+    ///
+    /// Tracked functions cannot be invoked until the batch ends: attempting to do
+    /// so unwinds with [`Cancelled::PendingWrite`](`crate::Cancelled::PendingWrite`).
+    fn batch<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R
+    where
+        Self: Sized,
+    {
+        struct EndBatch<'db, Db: ZalsaDatabase>(&'db mut Db);
+
+        impl<Db: ZalsaDatabase> Drop for EndBatch<'_, Db> {
+            fn drop(&mut self) {
+                self.0.zalsa_end_batch();
+            }
+        }
+
+        self.zalsa_begin_batch();
+        let guard = EndBatch(self);
+        op(&mut *guard.0)
+    }
+
+    /// Executes `op` against hypothetical changes to the inputs, which are rolled back
+    /// once `op` returns or panics.
+    ///
+    /// Within `op`, fields of inputs can be set and inputs created as usual, and tracked
+    /// functions observe these changes. Afterwards, the fields are restored, the inputs
+    /// created by `op` are retired, and the values memoized within `op` are discarded.
+    /// Memos that were not read within `op` remain valid; those that were are re-executed
+    /// (and usually backdated) when next invoked.
+    ///
+    /// There is no overlay: the changes and their rollback start new revisions like ordinary
+    /// writes, so speculating costs about as much as applying the changes, running the
+    /// queries, and reverting them.
+    ///
+    /// Only the fields of inputs declared with `#[salsa::input(speculate)]` can be set within
+    /// `op`, as their previous values are cloned to be restored. Writes performed through other
+    /// handles of the database while `op` executes are rolled back as well.
+    ///
+    /// # Panics
+    ///
+    /// If called within another speculation, or if `op` sets a field of an input not declared
+    /// with `speculate` or retires an input.
+    fn speculate<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R
+    where
+        Self: Sized,
+    {
+        struct EndSpeculation<'db, Db: Database>(&'db mut Db);
+
+        impl<Db: Database> Drop for EndSpeculation<'_, Db> {
+            fn drop(&mut self) {
+                let created = self.0.zalsa_mut().end_speculation();
+                for (retire, id) in created {
+                    retire(self.0.as_dyn_database_mut(), id);
+                }
+            }
+        }
+
+        self.zalsa().begin_speculation();
+        let guard = EndSpeculation(self);
+        op(&mut *guard.0)
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -41,6 +138,126 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_local.report_untracked_read(db.zalsa().current_revision())
     }
 
+    /// Reports that the query depends on some state unknown to salsa (e.g., a configuration
+    /// flag) that changes no more often than the inputs of durability `durability`.
+    ///
+    /// Unlike with [`report_untracked_read`](Self::report_untracked_read), the query is only
+    /// re-executed once an input of durability `durability` or higher changed, e.g. with
+    /// [`synthetic_write`](Self::synthetic_write). If the query also reads inputs of lower
+    /// durability, it is re-executed whenever an input of that lower durability changes.
+    fn report_synthetic_read(&self, durability: Durability) {
+        let db = self.as_dyn_database();
+        let zalsa = db.zalsa();
+        db.zalsa_local()
+            .report_synthetic_read(durability, zalsa.last_changed_revision(durability))
+    }
+
+    /// Discards all memoized values, so that every tracked function executes again
+    /// when it is next invoked.
+    ///
+    /// Inputs and interned values are kept: existing handles to them (e.g., interned symbols)
+    /// remain valid. Tracked structs are deleted along with the memos that created them,
+    /// so handles to them must not be used afterwards. This is a faster alternative to
+    /// building a new database from scratch, e.g., to recover after detecting an
+    /// inconsistency in the results of tracked functions.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn discard_memos(&mut self) {
+        self.zalsa_mut().discard_memos();
+    }
+
+    /// Walks all memoized values, checking the invariants the incremental algorithm relies on,
+    /// and returns the violations that were found.
+    ///
+    /// This is a debugging aid, e.g., when developing custom ingredients or chasing results
+    /// that fail to update: it visits every memo and is not meant to run in production.
+    /// Unlike a write, it does not start a new revision.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn audit_consistency(&mut self) -> Vec<ConsistencyViolation> {
+        self.zalsa_exclusive().audit_consistency()
+    }
+
+    /// Summarizes the memoized values of the database and the inputs they read, so that
+    /// a later process can find out which of those values are still valid with
+    /// [`import_and_diff`](`Self::import_and_diff`). See [`StateSummary`].
+    ///
+    /// **WARNING:** Like [`audit_consistency`](`Self::audit_consistency`), this method
+    /// requires exclusive access to the database and blocks until all snapshots are dropped.
+    fn export_state_summary(&mut self) -> StateSummary {
+        let memos = self.zalsa_exclusive().memo_origins();
+        StateSummary::export(self.as_dyn_database(), memos)
+    }
+
+    /// Compares the fingerprints of the inputs in `summary`, exported by
+    /// [`export_state_summary`](`Self::export_state_summary`) in an earlier process,
+    /// with those of the inputs of this database, and splits the memos of `summary` into
+    /// those that are still valid and those that must be recomputed.
+    fn import_and_diff(&self, summary: &StateSummary) -> StateDiff {
+        summary.diff(self.as_dyn_database())
+    }
+
+    /// Returns the most recent changes to input fields, oldest first.
+    ///
+    /// Each time the field of an input is set, the revision of the change and the field are
+    /// recorded. This can be used to find out what changed between two revisions.
+    /// The log is disabled unless a capacity is set with
+    /// [`Storage::set_revision_log_capacity`](`crate::Storage::set_revision_log_capacity`).
+    fn revision_log(&self) -> Vec<InputChange> {
+        self.zalsa().revision_log()
+    }
+
+    /// Returns the keys of the queries executing on this handle, from the outermost to the
+    /// innermost one, which is the caller when invoked from within a tracked function.
+    ///
+    /// Meant for assertions and diagnostics, e.g. attaching "while computing X" notes to errors.
+    /// The [`Debug`](`std::fmt::Debug`) output of the keys names the queries while a database is
+    /// [attached](`Self::attach`), as is the case within tracked functions.
+    #[cfg(feature = "debug")]
+    fn active_query_stack(&self) -> Vec<crate::DatabaseKeyIndex> {
+        self.zalsa_local().active_query_stack()
+    }
+
+    /// Describes the ingredients registered in this database so far, in the order of
+    /// their [`IngredientIndex`](`crate::IngredientIndex`).
+    ///
+    /// Ingredients are registered along with their jar, e.g., when a tracked function is
+    /// first invoked. Counting the memos of tracked functions visits all their keys.
+    fn ingredients(&self) -> Vec<crate::IngredientInfo> {
+        let db = self.as_dyn_database();
+        let zalsa = self.zalsa();
+        zalsa
+            .ingredients()
+            .map(|ingredient| crate::IngredientInfo::new(db, ingredient))
+            .collect()
+    }
+
+    /// Returns counters of executions, reuses, backdates and evictions, and the time spent
+    /// blocked on other threads, for each tracked function.
+    ///
+    /// The counters are maintained with relaxed atomics, so values read while other threads
+    /// execute queries may be slightly out of date.
+    #[cfg(feature = "stats")]
+    fn ingredient_stats(&self) -> Vec<crate::IngredientStats> {
+        self.zalsa().ingredient_stats()
+    }
+
+    /// Returns how many frames queries executed through this handle allocated, and how
+    /// many they reused from queries that completed before them.
+    ///
+    /// The counters are cumulative since the handle was created, and do not include
+    /// other handles to the same database.
+    #[cfg(feature = "stats")]
+    fn allocation_stats(&self) -> crate::AllocationStats {
+        self.zalsa_local().allocation_stats()
+    }
+
     /// Return the "debug name" (i.e., the struct name, etc) for an "ingredient",
     /// which are the fine-grained components we use to track data. This is intended
     /// for debugging and the contents of the returned string are not semver-guaranteed.
@@ -72,6 +289,110 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_local.unwind_if_revision_cancelled(db);
     }
 
+    /// Sets the [`Priority`][] of the queries executed through this handle.
+    ///
+    /// Unlike the settings of [`Storage`](`crate::Storage`), the priority is specific to
+    /// this handle: clones start with the priority of the handle they were cloned from.
+    fn set_query_priority(&self, priority: Priority) {
+        self.zalsa_local().set_priority(priority);
+    }
+
+    /// Returns the value of `compute` for `key`, memoized as an ad-hoc query.
+    ///
+    /// This is for framework code that cannot declare its queries at compile time
+    /// (e.g., analysis passes provided by user scripts): like a tracked function, the query
+    /// records the dependencies of `compute` and is only re-executed when one of them
+    /// changed, and its readers depend on it. Queries are identified by the type of
+    /// `compute` and by `key`, so `compute` must compute its value from `key` and the
+    /// database alone: later calls with an equal key reuse the closure of the first call.
+    ///
+    /// Use [`salsa::memo`](`crate::memo`) to invoke this on a `dyn Db`.
+    fn memo<K, V, F>(&self, key: K, compute: F) -> V
+    where
+        Self: Sized,
+        K: Hash + Eq + fmt::Debug + Send + Sync + 'static,
+        V: Clone + Eq + fmt::Debug + Send + Sync + Update + 'static,
+        F: Fn(&dyn Database) -> V + Send + Sync + 'static,
+    {
+        crate::ad_hoc::memo(self.as_dyn_database(), key, compute)
+    }
+
+    /// Executes `op`, allowing it to execute at most `fuel` queries through this handle.
+    ///
+    /// Executing one more query unwinds with [`Cancelled::FuelExhausted`], which can be
+    /// caught with [`Cancelled::catch`] to fall back to stale data, bounding the latency of
+    /// interactive requests. Validating a memo without re-executing it consumes no fuel.
+    /// Within nested calls, the executions of the inner call are also charged to the outer.
+    fn run_with_fuel<R>(&self, fuel: usize, op: impl FnOnce() -> R) -> R
+    where
+        Self: Sized,
+    {
+        self.zalsa_local().run_with_fuel(fuel, op)
+    }
+
+    /// Executes `op`, unwinding with [`Cancelled::DeadlineExceeded`] at the first
+    /// cancellation checkpoint reached through this handle after `deadline`.
+    ///
+    /// Checkpoints are reached whenever a query is invoked, and by calling
+    /// [`unwind_if_revision_cancelled`](`Self::unwind_if_revision_cancelled`) within
+    /// long-running queries, which bounds the latency of interactive requests: e.g., a
+    /// language server can respond with partial results once the deadline is exceeded,
+    /// by catching the cancellation with [`Cancelled::catch`]. Within nested calls,
+    /// the earliest deadline applies.
+    fn run_with_deadline<R>(&self, deadline: Instant, op: impl FnOnce() -> R) -> R
+    where
+        Self: Sized,
+    {
+        self.zalsa_local().run_with_deadline(deadline, op)
+    }
+
+    /// Like [`unwind_if_revision_cancelled`](`Self::unwind_if_revision_cancelled`),
+    /// but returns [`Cancelled::PendingWrite`] instead of unwinding, so that long-running
+    /// queries can stop at a convenient point with `?`.
+    ///
+    /// Preemption of [low-priority](`crate::Priority::Low`) queries is only reported by
+    /// unwinding, as the queries waiting for the preempted one must not observe its error.
+    fn check_cancelled(&self) -> Result<(), Cancelled> {
+        let db = self.as_dyn_database();
+        db.zalsa_local().check_cancelled(db)
+    }
+
+    /// Executes `op` without being interrupted by cancellation.
+    ///
+    /// Within the scope, [`unwind_if_revision_cancelled`](`Self::unwind_if_revision_cancelled`)
+    /// (and hence any tracked function invocation) does not unwind, even if a write is pending.
+    /// This is meant for queries performing multi-step external side effects that must not be
+    /// left halfway done. If the revision was cancelled, unwinding occurs as soon as the scope exits.
+    ///
+    /// Keep these scopes short: a pending write blocks until the scope exits.
+    /// Use [`no_cancel_scope`](`crate::no_cancel_scope`) from within tracked functions,
+    /// where the database is a trait object.
+    fn no_cancel_scope<R>(&self, op: impl FnOnce() -> R) -> R
+    where
+        Self: Sized,
+    {
+        no_cancel_scope(self, op)
+    }
+
+    /// Executes `op` without recording the reads it performs as dependencies of the
+    /// current query.
+    ///
+    /// **WARNING:** this is an escape hatch that deliberately breaks incremental correctness.
+    /// The current query is *not* re-executed when a value read within `op` changes, so its
+    /// result may go stale. Only use it for reads that cannot influence the query's result,
+    /// such as interned debug names for logging or metrics counters. Tracked functions invoked
+    /// within `op` still record their own dependencies; only the edge from the current query
+    /// to them is dropped. Outside of a query, `op` is simply executed.
+    ///
+    /// Use [`with_no_dependencies`](`crate::with_no_dependencies`) from within tracked
+    /// functions, where the database is a trait object.
+    fn with_no_dependencies<R>(&self, op: impl FnOnce() -> R) -> R
+    where
+        Self: Sized,
+    {
+        with_no_dependencies(self, op)
+    }
+
     /// Execute `op` with the database in thread-local storage for debug print-outs.
     fn attach<R>(&self, op: impl FnOnce(&Self) -> R) -> R
     where
@@ -99,6 +420,31 @@ impl<T: Database> AsDynDatabase for T {
     }
 }
 
+/// Executes `op` without recording its reads as dependencies of the current query;
+/// see [`Database::with_no_dependencies`] and its warning.
+pub fn with_no_dependencies<Db: ?Sized + Database, R>(db: &Db, op: impl FnOnce() -> R) -> R {
+    db.zalsa_local().without_tracked_reads(op)
+}
+
+/// Returns the value of `compute` for `key`, memoized as an ad-hoc query;
+/// see [`Database::memo`].
+pub fn memo<Db, K, V, F>(db: &Db, key: K, compute: F) -> V
+where
+    Db: ?Sized + Database,
+    K: Hash + Eq + fmt::Debug + Send + Sync + 'static,
+    V: Clone + Eq + fmt::Debug + Send + Sync + Update + 'static,
+    F: Fn(&dyn Database) -> V + Send + Sync + 'static,
+{
+    crate::ad_hoc::memo(db.as_dyn_database(), key, compute)
+}
+
+/// Executes `op` without being interrupted by cancellation;
+/// see [`Database::no_cancel_scope`].
+pub fn no_cancel_scope<Db: ?Sized + Database, R>(db: &Db, op: impl FnOnce() -> R) -> R {
+    let db = db.as_dyn_database();
+    db.zalsa_local().no_cancel_scope(db, op)
+}
+
 pub fn current_revision<Db: ?Sized + Database>(db: &Db) -> Revision {
     db.zalsa().current_revision()
 }
@@ -108,9 +454,15 @@ impl dyn Database {
     ///
     /// # Panics
     ///
-    /// If the view has not been added to the database (see [`crate::views::Views`]).
-    #[track_caller]
+    /// Unwinds with [`MissingView`] if the view has not been added to the database
+    /// (see [`crate::views::Views`]).
     pub fn as_view<DbView: ?Sized + Database>(&self) -> &DbView {
-        self.zalsa().views().try_view_as(self).unwrap()
+        match self.zalsa().views().try_view_as(self) {
+            Some(view) => view,
+            None => MissingView {
+                view: std::any::type_name::<DbView>(),
+            }
+            .throw(),
+        }
     }
 }
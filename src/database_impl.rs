@@ -1,4 +1,9 @@
-use crate::{self as salsa, Database, Event, Storage};
+use std::{panic::RefUnwindSafe, sync::Arc, time::Duration};
+
+use crate::{
+    self as salsa, BlockingStrategy, Database, Event, Interceptor, JarGroup, MemoCacheBackend,
+    Storage,
+};
 
 #[salsa::db]
 /// Default database implementation that you can use if you don't
@@ -6,6 +11,9 @@ use crate::{self as salsa, Database, Event, Storage};
 #[derive(Default, Clone)]
 pub struct DatabaseImpl {
     storage: Storage<Self>,
+
+    /// Invoked with every event instead of logging it; see [`DatabaseBuilder::event_sink`].
+    event_sink: Option<Arc<dyn Fn(Event) + Send + Sync + RefUnwindSafe>>,
 }
 
 impl DatabaseImpl {
@@ -14,6 +22,30 @@ impl DatabaseImpl {
         Self::default()
     }
 
+    /// Creates a new database with the interned values and high-durability inputs of `old`,
+    /// but none of its memoized values; see [`Storage::new_from`] for what is copied.
+    ///
+    /// This resets the analysis while keeping handles to symbols and other interned values
+    /// valid, e.g., after detecting an inconsistency in the results of tracked functions.
+    /// Unlike with [`Database::discard_memos`], `old` can still be used meanwhile.
+    pub fn new_from(old: &Self) -> Self {
+        Self {
+            storage: Storage::new_from(&old.storage),
+            event_sink: old.event_sink.clone(),
+        }
+    }
+
+    /// Returns a builder to configure a new database in one place.
+    pub fn builder() -> DatabaseBuilder {
+        DatabaseBuilder::default()
+    }
+
+    /// Registers the ingredients of the [`JarGroup`][] `G`; see [`Storage::register`].
+    pub fn with_jar<G: JarGroup>(self) -> Self {
+        self.storage.register::<G>();
+        self
+    }
+
     pub fn storage(&self) -> &Storage<Self> {
         &self.storage
     }
@@ -21,8 +53,112 @@ impl DatabaseImpl {
 
 #[salsa::db]
 impl Database for DatabaseImpl {
-    /// Default behavior: tracing debug log the event.
+    /// Default behavior: invoke the event sink, if any, or tracing debug log the event.
     fn salsa_event(&self, event: &dyn Fn() -> Event) {
-        tracing::debug!("salsa_event({:?})", event());
+        match &self.event_sink {
+            Some(event_sink) => event_sink(event()),
+            None => tracing::debug!("salsa_event({:?})", event()),
+        }
+    }
+}
+
+/// Configures a [`DatabaseImpl`]; see [`DatabaseImpl::builder`].
+///
+/// Each method corresponds to a setter of [`Storage`], which can still be used afterwards
+/// through [`DatabaseImpl::storage`].
+#[derive(Default)]
+#[must_use]
+pub struct DatabaseBuilder {
+    db: DatabaseImpl,
+}
+
+impl DatabaseBuilder {
+    /// Creates the database.
+    pub fn build(self) -> DatabaseImpl {
+        self.db
+    }
+
+    /// Invokes `event_sink` with every event instead of logging it with `tracing`.
+    pub fn event_sink(
+        mut self,
+        event_sink: impl Fn(Event) + Send + Sync + RefUnwindSafe + 'static,
+    ) -> Self {
+        self.db.event_sink = Some(Arc::new(event_sink));
+        self
+    }
+
+    /// Sets the LRU capacity of tracked functions that do not set one with the `lru` option;
+    /// see [`Storage::set_default_lru_capacity`].
+    pub fn default_lru_capacity(self, capacity: usize) -> Self {
+        self.db.storage.set_default_lru_capacity(capacity);
+        self
+    }
+
+    /// Sets every how many executions a tracked function is checked for determinism;
+    /// see [`Storage::set_determinism_check_interval`].
+    #[cfg(feature = "check_determinism")]
+    pub fn determinism_check_interval(self, interval: usize) -> Self {
+        self.db.storage.set_determinism_check_interval(interval);
+        self
+    }
+
+    /// Sets the number of worker threads used for parallel revalidation;
+    /// see [`Storage::set_revalidation_threads`].
+    pub fn revalidation_threads(self, num_threads: usize) -> Self {
+        self.db.storage.set_revalidation_threads(num_threads);
+        self
+    }
+
+    /// Sets the number of input changes retained in the revision log;
+    /// see [`Storage::set_revision_log_capacity`].
+    pub fn revision_log_capacity(self, capacity: usize) -> Self {
+        self.db.storage.set_revision_log_capacity(capacity);
+        self
+    }
+
+    /// Sets the strategy invoked when a thread has to wait for a query executing on another
+    /// thread; see [`Storage::set_blocking_strategy`].
+    pub fn blocking_strategy(self, blocking_strategy: impl BlockingStrategy) -> Self {
+        self.db.storage.set_blocking_strategy(blocking_strategy);
+        self
+    }
+
+    /// Sets how long a thread may wait for a query executing on another thread;
+    /// see [`Storage::set_deadlock_timeout`].
+    pub fn deadlock_timeout(self, timeout: Option<Duration>) -> Self {
+        self.db.storage.set_deadlock_timeout(timeout);
+        self
+    }
+
+    /// Sets how often a thread waiting for a query executing on another thread reports
+    /// that it is still waiting; see [`Storage::set_block_warning_interval`].
+    pub fn block_warning_interval(self, interval: Option<Duration>) -> Self {
+        self.db.storage.set_block_warning_interval(interval);
+        self
+    }
+
+    /// Enables or disables leak detection for [`ReadGuard`](`crate::ReadGuard`)s;
+    /// see [`Storage::set_leak_detection`].
+    pub fn leak_detection(self, enabled: bool) -> Self {
+        self.db.storage.set_leak_detection(enabled);
+        self
+    }
+
+    /// Registers an [`Interceptor`][]; see [`Storage::add_interceptor`].
+    pub fn interceptor(self, interceptor: impl Interceptor) -> Self {
+        self.db.storage.add_interceptor(interceptor);
+        self
+    }
+
+    /// Sets the [`MemoCacheBackend`][]; see [`Storage::set_memo_cache`].
+    pub fn memo_cache(self, backend: impl MemoCacheBackend) -> Self {
+        self.db.storage.set_memo_cache(backend);
+        self
+    }
+
+    /// Registers the ingredients of the [`JarGroup`][] `G`; see [`Storage::register`].
+    pub fn with_jar<G: JarGroup>(self) -> Self {
+        self.db.storage.register::<G>();
+        self
     }
 }
@@ -0,0 +1,92 @@
+use std::{
+    fmt,
+    panic::{self, UnwindSafe},
+    thread::ThreadId,
+    time::Duration,
+};
+
+use crate::DatabaseKeyIndex;
+
+/// A panic payload indicating that a thread waited longer than the
+/// [deadlock timeout](`crate::Storage::set_deadlock_timeout`) for a query
+/// executing on another thread.
+///
+/// Salsa detects cycles between queries, but cannot see waits that happen outside
+/// of it: a thread that holds a lock while waiting for a query, whose execution on
+/// another thread needs that same lock, would otherwise wait forever. The thread that
+/// timed out unwinds with a `Deadlock` describing the chain of waits it was part of,
+/// releasing whatever it held in the process.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Deadlock {
+    /// How long the thread waited before giving up.
+    pub timeout: Duration,
+
+    /// The waits that blocked the thread, starting with its own: each thread
+    /// waits for a query executing on the thread of the next wait.
+    pub waits: Vec<QueryWait>,
+}
+
+/// A thread waiting for a query executing on another thread, see [`Deadlock`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryWait {
+    /// The waiting thread.
+    pub thread_id: ThreadId,
+
+    /// The query being waited for.
+    pub database_key: DatabaseKeyIndex,
+
+    /// The thread executing the query.
+    pub other_thread_id: ThreadId,
+}
+
+impl Deadlock {
+    pub(crate) fn throw(self) -> ! {
+        // We use resume and not panic here to avoid running the panic
+        // hook (that is, to avoid collecting and printing backtrace).
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// Runs `f`, and catches any deadlock.
+    pub fn catch<F, T>(f: F) -> Result<T, Deadlock>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(deadlock) => Err(*deadlock),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.waits.first() else {
+            return write!(f, "query wait timed out after {:?}", self.timeout);
+        };
+        write!(
+            f,
+            "possible deadlock: {:?} waited more than {:?} for a query executing on another thread",
+            first.thread_id, self.timeout
+        )?;
+        for wait in &self.waits {
+            write!(
+                f,
+                "\n  {:?} waits for `{:?}` executing on {:?}",
+                wait.thread_id, wait.database_key, wait.other_thread_id
+            )?;
+        }
+        let last = self.waits.last().unwrap_or(first);
+        write!(
+            f,
+            "\n  {:?} is not waiting for a query; is it waiting for a lock held by one of the threads above?",
+            last.other_thread_id
+        )
+    }
+}
+
+impl std::error::Error for Deadlock {}
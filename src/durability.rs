@@ -68,6 +68,15 @@ impl Durability {
     pub(crate) fn index(self) -> usize {
         self.0 as usize
     }
+
+    pub(crate) fn from_index(index: usize) -> Self {
+        match index {
+            0 => Self::LOW,
+            1 => Self::MEDIUM,
+            2 => Self::HIGH,
+            _ => panic!("invalid durability index {index}"),
+        }
+    }
 }
 
 impl Default for Durability {
@@ -75,3 +84,81 @@ impl Default for Durability {
         Durability::LOW
     }
 }
+
+/// A set of durabilities: those of the inputs that a query read, directly or through
+/// other queries, which lets verification skip a query whose inputs of these durabilities
+/// did not change even though inputs of other durabilities did.
+///
+/// Besides the durabilities, the set may contain [`Durabilities::UNTRACKED`] for queries
+/// that (transitively) depend on state unknown to salsa, which may change in any revision.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Durabilities(u8);
+
+impl Durabilities {
+    /// Untracked state, e.g. read with [`Database::report_untracked_read`](`crate::Database::report_untracked_read`).
+    pub(crate) const UNTRACKED: Durabilities = Durabilities(1 << Durability::LEN);
+
+    /// Number of bits needed to store a set.
+    pub(crate) const BITS: u32 = Durability::LEN as u32 + 1;
+
+    /// The set containing only `durability`.
+    pub(crate) fn of(durability: Durability) -> Self {
+        Durabilities(1 << durability.index())
+    }
+
+    /// The durabilities `durability` and higher: a conservative set for values of
+    /// durability `durability` whose precise inputs are unknown.
+    pub(crate) fn at_least(durability: Durability) -> Self {
+        Durabilities(((1 << Durability::LEN) - 1) & !((1 << durability.index()) - 1))
+    }
+
+    pub(crate) fn is_subset(self, other: Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub(crate) fn contains(self, durability: Durability) -> bool {
+        Self::of(durability).is_subset(self)
+    }
+
+    pub(crate) fn is_untracked(self) -> bool {
+        Self::UNTRACKED.is_subset(self)
+    }
+
+    pub(crate) fn to_bits(self) -> u64 {
+        u64::from(self.0)
+    }
+
+    pub(crate) fn from_bits(bits: u64) -> Self {
+        Durabilities((bits & ((1 << Self::BITS) - 1)) as u8)
+    }
+}
+
+impl std::ops::BitOr for Durabilities {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Durabilities(self.0 | other.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Durabilities {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl std::fmt::Debug for Durabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut set = f.debug_set();
+        for index in 0..Durability::LEN {
+            let durability = Durability::from_index(index);
+            if self.contains(durability) {
+                set.entry(&durability);
+            }
+        }
+        if self.is_untracked() {
+            set.entry(&format_args!("untracked"));
+        }
+        set.finish()
+    }
+}
@@ -0,0 +1,634 @@
+use std::{
+    any::TypeId,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use append_only_vec::AppendOnlyVec;
+use parking_lot::Mutex;
+
+use crate::{
+    accumulator::accumulated_map::InputAccumulatedValues,
+    cycle::CycleRecoveryStrategy,
+    durability::Durabilities,
+    hash::{FxDashMap, FxIndexMap},
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, JarAux, MaybeChangedAfter},
+    key::{InputDependencyIndex, OutputDependencyIndex},
+    update::{update_fallback, Update},
+    zalsa::IngredientIndex,
+    zalsa_local::QueryOrigin,
+    Database, DatabaseKeyIndex, Durability, Id, Revision,
+};
+
+/// A map returned by a tracked function that records when each of its entries last changed.
+///
+/// A tracked function returning a plain map invalidates all of its readers whenever any
+/// entry changes. A function returning `Entries` instead stamps each entry with its own
+/// `changed_at` revision and durability. Readers that access the map through
+/// [`Entries::project`] only depend on the entries they look up, so that they are not
+/// re-executed when unrelated entries change:
+///
+/// ```ignore
+/// #[salsa::tracked(return_ref)]
+/// fn symbols(db: &dyn Db, module: Module) -> salsa::Entries<String, Symbol> {
+///     let mut symbols = salsa::Entries::new(db);
+///     for item in module.items(db) {
+///         symbols.insert(db, item.name(db), Symbol::from(item));
+///     }
+///     symbols
+/// }
+///
+/// #[salsa::tracked]
+/// fn resolve(db: &dyn Db, module: Module, name: String) -> Option<Symbol> {
+///     let symbols = salsa::Entries::project(db, || symbols(db, module));
+///     symbols.get(db, &name).cloned()
+/// }
+/// ```
+///
+/// The durability of an entry is the minimum durability of the inputs read by the function
+/// before inserting it, which is why each key can be inserted only once per execution.
+///
+/// The stamps are kept by the database for every key that was ever inserted, along with the
+/// last value inserted for that key, until the function that created the map is deleted.
+/// Looking up a key that was never inserted records a dependency on the addition of any key.
+pub struct Entries<K, V> {
+    ingredient_index: IngredientIndex,
+    owner: DatabaseKeyIndex,
+    build: u64,
+    map: FxIndexMap<K, Arc<V>>,
+}
+
+/// A view on [`Entries`] obtained with [`Entries::project`] whose lookups are tracked per entry.
+pub struct ProjectedEntries<'db, K, V> {
+    entries: &'db Entries<K, V>,
+}
+
+impl<K, V> Entries<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + Sync + 'static,
+    V: Eq + Send + Sync + 'static,
+{
+    /// Creates an empty map owned by the tracked function currently executing on `db`.
+    ///
+    /// Each execution of the function may create at most one `Entries<K, V>`:
+    /// creating another one marks the entries of the first as removed.
+    ///
+    /// # Panics
+    ///
+    /// If no tracked function is executing.
+    pub fn new<Db: ?Sized + Database>(db: &Db) -> Self {
+        let Some((owner, _)) = db.zalsa_local().active_query() else {
+            panic!("`Entries` can only be created inside a tracked function");
+        };
+        let ingredient = EntriesIngredient::<K, V>::from_db(db);
+        let (build, sentinel) = ingredient.start_build(owner, db.zalsa().current_revision());
+        // The sentinel is an output of the owner, so that the entries are reclaimed
+        // once the owner is deleted (or no longer creates a map).
+        db.zalsa_local()
+            .add_output(OutputDependencyIndex::new(ingredient.index, sentinel));
+        Self {
+            ingredient_index: ingredient.index,
+            owner,
+            build,
+            map: Default::default(),
+        }
+    }
+
+    /// Inserts `value` for `key`, stamping the entry with the current revision
+    /// if its value differs from the one inserted by the previous execution.
+    ///
+    /// # Panics
+    ///
+    /// If `key` was already inserted, or if this is not invoked by the tracked function
+    /// that created the map.
+    pub fn insert<Db: ?Sized + Database>(&mut self, db: &Db, key: K, value: V) {
+        let Some((active_query, stamp)) = db.zalsa_local().active_query() else {
+            panic!("`Entries::insert` invoked outside of a tracked function");
+        };
+        assert_eq!(
+            active_query, self.owner,
+            "`Entries` can only be populated by the tracked function that created them"
+        );
+        if self.map.contains_key(&key) {
+            panic!("key `{key:?}` was inserted twice into `Entries`");
+        }
+
+        let value = Arc::new(value);
+        EntriesIngredient::<K, V>::lookup(db, self.ingredient_index).record_insert(
+            self.owner,
+            self.build,
+            &key,
+            &value,
+            stamp.durability,
+            db.zalsa().current_revision(),
+        );
+        self.map.insert(key, value);
+    }
+
+    /// Returns the value of `key`, without recording a dependency on that entry.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|value| &**value)
+    }
+
+    /// Iterates over the entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().map(|(key, value)| (key, &**value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Invokes `op`, typically a call to a tracked function returning a reference to `Entries`,
+    /// without recording a dependency on its result. The entries are then read through the
+    /// returned [`ProjectedEntries`], which records a dependency on each entry looked up.
+    pub fn project<'db, Db: ?Sized + Database>(
+        db: &'db Db,
+        op: impl FnOnce() -> &'db Self,
+    ) -> ProjectedEntries<'db, K, V> {
+        let entries = db.zalsa_local().without_tracked_reads(op);
+        ProjectedEntries { entries }
+    }
+}
+
+impl<'db, K, V> ProjectedEntries<'db, K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + Sync + 'static,
+    V: Eq + Send + Sync + 'static,
+{
+    /// Returns the value of `key`, recording a dependency on that entry (or on its absence).
+    pub fn get<Db: ?Sized + Database>(&self, db: &Db, key: &K) -> Option<&'db V> {
+        let entries = self.entries;
+        let ingredient = EntriesIngredient::<K, V>::lookup(db, entries.ingredient_index);
+        let Some((id, changed_at, durability)) = ingredient.stamp(entries.owner, key) else {
+            // The owner was deleted, so its entries are no longer tracked.
+            db.zalsa_local()
+                .report_untracked_read(db.zalsa().current_revision());
+            return entries.get(key);
+        };
+        db.zalsa_local().report_tracked_read(
+            db.as_dyn_database(),
+            InputDependencyIndex::new(entries.ingredient_index, id),
+            durability,
+            Durabilities::at_least(durability),
+            changed_at,
+            InputAccumulatedValues::Empty,
+        );
+        entries.get(key)
+    }
+
+    /// The underlying map. Reading it does not record any dependency.
+    pub fn untracked(&self) -> &'db Entries<K, V> {
+        self.entries
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq> PartialEq for Entries<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<K: Hash + Eq, V: Eq> Eq for Entries<K, V> {}
+
+impl<K: Clone, V> Clone for Entries<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            ingredient_index: self.ingredient_index,
+            owner: self.owner,
+            build: self.build,
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Entries<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for ProjectedEntries<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProjectedEntries")
+            .field(&self.entries)
+            .finish()
+    }
+}
+
+unsafe impl<K, V> Update for Entries<K, V>
+where
+    K: Hash + Eq + 'static,
+    V: PartialEq + 'static,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        unsafe { update_fallback(old_pointer, new_value) }
+    }
+}
+
+struct JarImpl<K, V> {
+    phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> Jar for JarImpl<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + Sync + 'static,
+    V: Eq + Send + Sync + 'static,
+{
+    fn create_ingredients(
+        &self,
+        _aux: &dyn JarAux,
+        first_index: IngredientIndex,
+    ) -> Vec<Box<dyn Ingredient>> {
+        vec![Box::new(EntriesIngredient::<K, V>::new(first_index))]
+    }
+
+    fn salsa_struct_type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self {
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// Stores the stamps of the entries of all [`Entries<K, V>`] in the database.
+/// Each entry is identified by the function that created the map and its key;
+/// reading an entry records a dependency on this ingredient with the id of the entry.
+struct EntriesIngredient<K, V> {
+    index: IngredientIndex,
+
+    /// Ids of the entries, indices into `slots`.
+    ids: FxDashMap<(DatabaseKeyIndex, K), Id>,
+
+    slots: AppendOnlyVec<Mutex<Slot<K, V>>>,
+
+    /// Slots of deleted owners, along with the revision in which they were freed.
+    free_slots: Mutex<Vec<(Id, Revision)>>,
+
+    /// The functions that created `Entries`.
+    owners: FxDashMap<DatabaseKeyIndex, Owner>,
+
+    next_build: AtomicU64,
+}
+
+struct Owner {
+    /// The latest execution.
+    build: u64,
+
+    /// The execution before the latest one, 0 if none.
+    previous_build: u64,
+
+    /// The revision of the latest execution.
+    revision: Revision,
+
+    /// The slot that lookups of absent keys depend on. It changes whenever a key is added.
+    sentinel: Id,
+
+    /// The slots of the entries, excluding the sentinel.
+    slots: Vec<Id>,
+}
+
+struct Slot<K, V> {
+    /// `None` once the owner was deleted.
+    owner: Option<DatabaseKeyIndex>,
+
+    /// `None` for the sentinel of the owner.
+    key: Option<K>,
+
+    /// The revision in which the slot was (re)allocated. Readers that were last verified
+    /// before then depended on a previous use of the slot.
+    created_at: Revision,
+
+    /// The value inserted by the latest execution, `None` if it did not insert the key.
+    value: Option<Arc<V>>,
+    changed_at: Revision,
+    durability: Durability,
+
+    /// The execution `value` is up to date with.
+    build: u64,
+}
+
+impl<K, V> EntriesIngredient<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + Sync + 'static,
+    V: Eq + Send + Sync + 'static,
+{
+    fn new(index: IngredientIndex) -> Self {
+        Self {
+            index,
+            ids: Default::default(),
+            slots: AppendOnlyVec::new(),
+            free_slots: Default::default(),
+            owners: Default::default(),
+            next_build: AtomicU64::new(1),
+        }
+    }
+
+    fn from_db<Db: ?Sized + Database>(db: &Db) -> &Self {
+        let jar = JarImpl::<K, V> {
+            phantom: PhantomData,
+        };
+        let zalsa = db.zalsa();
+        let index = zalsa.add_or_lookup_jar_by_type(&jar);
+        zalsa.lookup_ingredient(index).assert_type::<Self>()
+    }
+
+    fn lookup<Db: ?Sized + Database>(db: &Db, index: IngredientIndex) -> &Self {
+        db.zalsa().lookup_ingredient(index).assert_type::<Self>()
+    }
+
+    fn slot(&self, id: Id) -> &Mutex<Slot<K, V>> {
+        &self.slots[id.as_u32() as usize]
+    }
+
+    /// Records that `owner` starts executing (again) in `revision`.
+    /// Entries that it does not insert this time are considered removed.
+    ///
+    /// Returns the number of the execution and the sentinel of `owner`.
+    fn start_build(&self, owner: DatabaseKeyIndex, revision: Revision) -> (u64, Id) {
+        let build = self.next_build.fetch_add(1, Ordering::Relaxed);
+        if let Some(mut state) = self.owners.get_mut(&owner) {
+            state.previous_build = state.build;
+            state.build = build;
+            state.revision = revision;
+            return (build, state.sentinel);
+        }
+
+        let sentinel = self.allocate_slot(owner, None, revision);
+        self.owners.insert(
+            owner,
+            Owner {
+                build,
+                previous_build: 0,
+                revision,
+                sentinel,
+                slots: vec![],
+            },
+        );
+        (build, sentinel)
+    }
+
+    /// Allocates a slot for `key` of `owner`, reusing a slot freed in an earlier revision.
+    fn allocate_slot(&self, owner: DatabaseKeyIndex, key: Option<K>, revision_now: Revision) -> Id {
+        let slot = Slot {
+            owner: Some(owner),
+            key,
+            created_at: revision_now,
+            value: None,
+            changed_at: Revision::start(),
+            durability: Durability::MAX,
+            build: 0,
+        };
+
+        let reusable = {
+            let mut free_slots = self.free_slots.lock();
+            // A slot freed in this revision may still be read by queries verified in it.
+            free_slots
+                .iter()
+                .position(|&(_, freed_at)| freed_at < revision_now)
+                .map(|index| free_slots.swap_remove(index).0)
+        };
+        match reusable {
+            Some(id) => {
+                *self.slot(id).lock() = slot;
+                id
+            }
+            None => {
+                let index = self.slots.push(Mutex::new(slot));
+                Id::from_u32(u32::try_from(index).expect("too many entries"))
+            }
+        }
+    }
+
+    fn record_insert(
+        &self,
+        owner: DatabaseKeyIndex,
+        build: u64,
+        key: &K,
+        value: &Arc<V>,
+        durability: Durability,
+        revision_now: Revision,
+    ) {
+        let (previous_build, sentinel) = {
+            let state = self
+                .owners
+                .get(&owner)
+                .expect("owner of `Entries` not found");
+            (state.previous_build, state.sentinel)
+        };
+        // Only the owner inserts its keys, so the id cannot be allocated concurrently.
+        let existing = self.ids.get(&(owner, key.clone())).map(|id| *id);
+        let id = existing.unwrap_or_else(|| {
+            let id = self.allocate_slot(owner, Some(key.clone()), revision_now);
+            self.ids.insert((owner, key.clone()), id);
+            if let Some(mut state) = self.owners.get_mut(&owner) {
+                state.slots.push(id);
+            }
+            id
+        });
+
+        let added = {
+            let mut slot = self.slot(id).lock();
+            let added = slot.value.is_none() || slot.build != previous_build;
+            let changed = match &slot.value {
+                Some(old_value) => **old_value != **value,
+                None => true,
+            };
+            // As for memoized values, becoming less durable is a change that readers must observe.
+            if changed || durability < slot.durability {
+                slot.changed_at = revision_now;
+            }
+            slot.value = Some(value.clone());
+            slot.durability = durability;
+            slot.build = build;
+            added
+        };
+        if added {
+            self.slot(sentinel).lock().changed_at = revision_now;
+        }
+    }
+
+    /// Returns the id of the entry for `key` (or the sentinel of `owner` if `key` was
+    /// never inserted) along with the revision in which it last changed and its durability.
+    /// Returns `None` if `owner` was deleted.
+    fn stamp(&self, owner: DatabaseKeyIndex, key: &K) -> Option<(Id, Revision, Durability)> {
+        let existing = self.ids.get(&(owner, key.clone())).map(|id| *id);
+        let id = match existing {
+            Some(id) => id,
+            None => self.owners.get(&owner)?.sentinel,
+        };
+        let slot = self.refresh(id);
+        Some((id, slot.changed_at, slot.durability))
+    }
+
+    /// Brings the slot `id` up to date with the latest execution of its owner,
+    /// which removed the entry if it did not insert it.
+    fn refresh(&self, id: Id) -> parking_lot::MutexGuard<'_, Slot<K, V>> {
+        let mut slot = self.slot(id).lock();
+        let Some(owner) = slot.owner else {
+            return slot;
+        };
+        let latest = self
+            .owners
+            .get(&owner)
+            .map(|state| (state.build, state.revision));
+        if let Some((build, revision)) = latest {
+            if slot.build != build {
+                if slot.value.take().is_some() {
+                    slot.changed_at = revision;
+                }
+                slot.durability = Durability::MIN;
+                slot.build = build;
+            }
+        }
+        slot
+    }
+
+    /// Frees the slots of `owner`, which was deleted or no longer creates `Entries`.
+    fn reclaim(&self, owner: DatabaseKeyIndex, revision_now: Revision) {
+        let Some((_, state)) = self.owners.remove(&owner) else {
+            return;
+        };
+        let mut freed = Vec::with_capacity(state.slots.len() + 1);
+        for id in state.slots.into_iter().chain([state.sentinel]) {
+            let key = {
+                let mut slot = self.slot(id).lock();
+                slot.owner = None;
+                slot.value = None;
+                slot.key.take()
+            };
+            if let Some(key) = key {
+                self.ids.remove(&(owner, key));
+            }
+            freed.push((id, revision_now));
+        }
+        self.free_slots.lock().extend(freed);
+    }
+}
+
+impl<K, V> Ingredient for EntriesIngredient<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug + Send + Sync + 'static,
+    V: Eq + Send + Sync + 'static,
+{
+    fn ingredient_index(&self) -> IngredientIndex {
+        self.index
+    }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Entries
+    }
+
+    fn maybe_changed_after(
+        &self,
+        db: &dyn Database,
+        input: Id,
+        revision: Revision,
+    ) -> MaybeChangedAfter {
+        let (owner, created_at) = {
+            let slot = self.slot(input).lock();
+            (slot.owner, slot.created_at)
+        };
+        // The entry was reclaimed, or the slot was reused since the reader last verified.
+        let Some(owner) = owner.filter(|_| created_at <= revision) else {
+            return MaybeChangedAfter::Yes;
+        };
+
+        // If the map as a whole did not change, neither did the entry. Otherwise, this
+        // (re-)executes the owner if needed, which updates the stamps of its entries.
+        let owner_dependency = InputDependencyIndex::new(owner.ingredient_index, owner.key_index);
+        if let MaybeChangedAfter::No(_) = owner_dependency.maybe_changed_after(db, revision) {
+            return MaybeChangedAfter::No(InputAccumulatedValues::Empty);
+        }
+
+        // The stamps are only known to be up to date if the owner executed in this revision.
+        let current_revision = db.zalsa().current_revision();
+        let rebuilt = self
+            .owners
+            .get(&owner)
+            .is_some_and(|state| state.revision == current_revision);
+        if !rebuilt {
+            return MaybeChangedAfter::Yes;
+        }
+
+        let slot = self.refresh(input);
+        if slot.owner != Some(owner) || slot.changed_at > revision {
+            MaybeChangedAfter::Yes
+        } else {
+            MaybeChangedAfter::No(InputAccumulatedValues::Empty)
+        }
+    }
+
+    fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+        CycleRecoveryStrategy::Panic
+    }
+
+    fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
+        None
+    }
+
+    fn mark_validated_output(
+        &self,
+        _db: &dyn Database,
+        _executor: DatabaseKeyIndex,
+        _output_key: Id,
+    ) {
+        // The sentinel of the owner: its entries are still those of the validated memo.
+    }
+
+    fn remove_stale_output(
+        &self,
+        db: &dyn Database,
+        executor: DatabaseKeyIndex,
+        _stale_output_key: Id,
+    ) {
+        // The sentinel of the owner, which was deleted or re-executed without creating `Entries`.
+        self.reclaim(executor, db.zalsa().current_revision());
+    }
+
+    fn requires_reset_for_new_revision(&self) -> bool {
+        false
+    }
+
+    fn reset_for_new_revision(&mut self) {
+        panic!("unexpected reset on entries")
+    }
+
+    fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match index {
+            Some(id) => {
+                let slot = self.slot(id).lock();
+                match (&slot.owner, &slot.key) {
+                    (Some(owner), Some(key)) => write!(fmt, "{owner:?}[{key:?}]"),
+                    (Some(owner), None) => write!(fmt, "{owner:?}[..]"),
+                    (None, _) => fmt_index(self.debug_name(), index, fmt),
+                }
+            }
+            None => fmt_index(self.debug_name(), index, fmt),
+        }
+    }
+
+    fn debug_name(&self) -> &'static str {
+        "Entries"
+    }
+}
+
+impl<K, V> fmt::Debug for EntriesIngredient<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(std::any::type_name::<Self>())
+            .field("index", &self.index)
+            .finish()
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe, UnwindSafe},
+};
+
+use crate::{Cancelled, Cycle, Database, Deadlock, Id, InvalidKey, MissingView, StaleHandle};
+
+/// An error that occurred while executing salsa queries.
+///
+/// Salsa reports cancellation and unrecovered cycles by unwinding. Embedders that
+/// must not unwind across their own boundaries (FFI, async task executors, ...)
+/// can use the `try_` entry points instead, which return a `Result`:
+///
+/// * `try_fetch`, generated for tracked functions, e.g., `my_fn::try_fetch(db, input)`;
+/// * `try_new`, generated for inputs, interned and tracked structs;
+/// * [`Setter::try_to`](`crate::Setter::try_to`), to set a field of an input.
+///
+/// [`Error::catch`] turns the unwinds of any other code into a `Result`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The query was cancelled; see [`Cancelled`].
+    Cancelled(Cancelled),
+
+    /// The query participated in a cycle without recovery; see [`Cycle`].
+    Cycle(Cycle),
+
+    /// A query validating its arguments was invoked with a foreign key; see [`InvalidKey`].
+    InvalidKey(InvalidKey),
+
+    /// The query waited too long for another thread; see [`Deadlock`].
+    Deadlock(Deadlock),
+
+    /// A retired or deleted salsa struct was passed to a `try_` entry point; see [`StaleHandle`].
+    StaleHandle(StaleHandle),
+
+    /// The database was upcast to a view it does not have; see [`MissingView`].
+    MissingView(MissingView),
+}
+
+impl Error {
+    /// Runs `f`, catching any salsa cancellation, unrecovered cycle, invalid key, deadlock,
+    /// stale handle, or missing view.
+    ///
+    /// Other panics (e.g., those raised by user code) are propagated.
+    pub fn catch<F, T>(f: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        let payload = match panic::catch_unwind(f) {
+            Ok(t) => return Ok(t),
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<Cancelled>() {
+            Ok(cancelled) => return Err(Error::Cancelled(*cancelled)),
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<Cycle>() {
+            Ok(cycle) => return Err(Error::Cycle(*cycle)),
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<InvalidKey>() {
+            Ok(invalid_key) => return Err(Error::InvalidKey(*invalid_key)),
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<Deadlock>() {
+            Ok(deadlock) => return Err(Error::Deadlock(*deadlock)),
+            Err(payload) => payload,
+        };
+        let payload = match payload.downcast::<StaleHandle>() {
+            Ok(stale_handle) => return Err(Error::StaleHandle(*stale_handle)),
+            Err(payload) => payload,
+        };
+        match payload.downcast::<MissingView>() {
+            Ok(missing_view) => Err(Error::MissingView(*missing_view)),
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// Runs `fetch`, a call of a tracked function whose key is `key` (`None` if the function
+/// takes several arguments), catching salsa unwinds.
+///
+/// Invoked by the `try_fetch` methods generated for tracked functions.
+pub fn try_fetch<T>(
+    db: &dyn Database,
+    key: Option<Id>,
+    fetch: impl FnOnce() -> T,
+) -> Result<T, Error> {
+    if let Some(key) = key {
+        check_handle(db, key)?;
+    }
+    Error::catch(AssertUnwindSafe(fetch))
+}
+
+/// Checks that `id`, if it refers to a salsa struct, was neither retired nor deleted.
+fn check_handle(db: &dyn Database, id: Id) -> Result<(), StaleHandle> {
+    let zalsa = db.zalsa();
+    let Some(ingredient_index) = zalsa.table().try_ingredient_index(id) else {
+        return Ok(());
+    };
+    let ingredient = zalsa.lookup_ingredient(ingredient_index);
+    if ingredient.is_stale_handle(db, id) {
+        return Err(StaleHandle {
+            struct_name: ingredient.debug_name(),
+            id,
+        });
+    }
+    Ok(())
+}
+
+impl From<Cancelled> for Error {
+    fn from(cancelled: Cancelled) -> Self {
+        Error::Cancelled(cancelled)
+    }
+}
+
+impl From<Cycle> for Error {
+    fn from(cycle: Cycle) -> Self {
+        Error::Cycle(cycle)
+    }
+}
+
+impl From<InvalidKey> for Error {
+    fn from(invalid_key: InvalidKey) -> Self {
+        Error::InvalidKey(invalid_key)
+    }
+}
+
+impl From<Deadlock> for Error {
+    fn from(deadlock: Deadlock) -> Self {
+        Error::Deadlock(deadlock)
+    }
+}
+
+impl From<StaleHandle> for Error {
+    fn from(stale_handle: StaleHandle) -> Self {
+        Error::StaleHandle(stale_handle)
+    }
+}
+
+impl From<MissingView> for Error {
+    fn from(missing_view: MissingView) -> Self {
+        Error::MissingView(missing_view)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cancelled(cancelled) => fmt::Display::fmt(cancelled, f),
+            Error::Cycle(cycle) => write!(f, "unexpected cycle: {cycle:?}"),
+            Error::InvalidKey(invalid_key) => fmt::Display::fmt(invalid_key, f),
+            Error::Deadlock(deadlock) => fmt::Display::fmt(deadlock, f),
+            Error::StaleHandle(stale_handle) => fmt::Display::fmt(stale_handle, f),
+            Error::MissingView(missing_view) => fmt::Display::fmt(missing_view, f),
+        }
+    }
+}
+
+// `Display` already prints the inner error, so it is not reported as the source as well.
+impl std::error::Error for Error {}
@@ -1,4 +1,4 @@
-use std::thread::ThreadId;
+use std::{thread::ThreadId, time::Duration};
 
 use crate::{
     key::DatabaseKeyIndex,
@@ -53,6 +53,22 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that the current thread is still waiting for another thread
+    /// (with id `other_thread_id`) to complete the given query (`database_key`).
+    ///
+    /// Occurs every [block warning interval](`crate::Storage::set_block_warning_interval`)
+    /// for as long as the wait lasts, so that threads hanging on a query can be identified.
+    StillBlockedOn {
+        /// The id of the thread we are blocked on.
+        other_thread_id: ThreadId,
+
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// How long the current thread has been waiting so far.
+        waited: Duration,
+    },
+
     /// Indicates that the function for this query will be executed.
     /// This is either because it has never executed before or because
     /// its inputs may be out of date.
@@ -61,6 +77,27 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that a function declared with `#[salsa::tracked(firewall)]` was re-executed
+    /// and produced a value equal to its old one, so that its readers do not have to be
+    /// re-executed.
+    ///
+    /// Executes after the function has been executed.
+    DidAbsorbChange {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
+    /// Indicates that re-executing a function declared with `#[salsa::tracked(keep_stale_on_panic)]`
+    /// panicked, and that its previous value is used in the current revision instead.
+    /// The function is re-executed in the next revision.
+    DidKeepStaleValue {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// The panic message, if the panic payload is a string.
+        message: Option<String>,
+    },
+
     /// Indicates that `unwind_if_cancelled` was called and salsa will check if
     /// the current revision has been cancelled.
     WillCheckCancellation,
@@ -85,6 +122,13 @@ pub enum EventKind {
         key: DatabaseKeyIndex,
     },
 
+    /// The value memoized for a query was evicted to reclaim memory (e.g., by the LRU).
+    /// Its dependencies are kept, so that it can still be verified and is recomputed if needed.
+    DidEvictMemo {
+        /// The query whose value was evicted.
+        database_key: DatabaseKeyIndex,
+    },
+
     /// Discarded accumulated data from a given fn
     DidDiscardAccumulated {
         /// The key of the fn that accumulated results
@@ -0,0 +1,233 @@
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    marker::PhantomData,
+};
+
+use crate::{
+    accumulator::accumulated_map::InputAccumulatedValues,
+    cycle::CycleRecoveryStrategy,
+    durability::Durabilities,
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, JarAux, MaybeChangedAfter},
+    key::InputDependencyIndex,
+    zalsa::IngredientIndex,
+    zalsa_local::QueryOrigin,
+    Database, DatabaseKeyIndex, Durability, Id, Revision,
+};
+
+/// An ingredient implemented outside of salsa, e.g. a memory-mapped file store
+/// or a cache backed by a remote service.
+///
+/// The ingredient stores values identified by [`Id`]s of its choosing. Tracked functions
+/// reading a value record a dependency on it with [`report_read`]; salsa then asks the
+/// ingredient with [`maybe_changed_after`](`Self::maybe_changed_after`) whether the value
+/// changed when deciding if those functions must re-execute.
+///
+/// The ingredient is created in each database on first use, by [`ingredient`] or
+/// [`Registry::add_external`](`crate::Registry::add_external`).
+///
+/// Unlike the rest of [`plumbing`](`crate::plumbing`), this trait is subject to semver.
+pub trait ExternalIngredient: Any + fmt::Debug + Send + Sync {
+    /// Creates the ingredient, given its index in the database.
+    fn new(index: IngredientIndex) -> Self
+    where
+        Self: Sized;
+
+    /// The name of the ingredient, used in debug output.
+    fn debug_name(&self) -> &'static str;
+
+    /// Has the value `id` changed after `revision`?
+    fn maybe_changed_after(&self, db: &dyn Database, id: Id, revision: Revision) -> bool;
+
+    /// Formats the value `id` for debug output.
+    fn fmt_index(&self, id: Id, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}({id:?})", self.debug_name())
+    }
+
+    /// Invoked when a new revision is about to start. No `&`-reference handed out
+    /// by the ingredient in an earlier revision is live anymore.
+    fn reset_for_new_revision(&mut self) {}
+}
+
+/// Returns the ingredient `I` of `db`, creating it if this is its first use.
+pub fn ingredient<I: ExternalIngredient, Db: ?Sized + Database>(db: &Db) -> &I {
+    let index = ingredient_index::<I, Db>(db);
+    &db.zalsa()
+        .lookup_ingredient(index)
+        .assert_type::<ExternalIngredientImpl<I>>()
+        .inner
+}
+
+/// Returns the index of the ingredient `I` of `db`, creating it if this is its first use.
+pub fn ingredient_index<I: ExternalIngredient, Db: ?Sized + Database>(db: &Db) -> IngredientIndex {
+    db.zalsa().add_or_lookup_jar_by_type(&JarImpl::<I>::new())
+}
+
+/// Starts a new revision in which values of `durability` or less may have changed,
+/// returning the ingredient `I` along with the new revision, which the ingredient
+/// should record as the revision in which the values it modifies changed.
+///
+/// Like setting an input, this cancels queries running on other handles to `db`.
+pub fn ingredient_mut<I: ExternalIngredient, Db: ?Sized + Database>(
+    db: &mut Db,
+    durability: Durability,
+) -> (&mut I, Revision) {
+    let index = ingredient_index::<I, Db>(db);
+    let zalsa = db.zalsa_mut();
+    // Values of any durability up to `durability` may change, not just those of `durability`.
+    for index in 0..=durability.index() {
+        zalsa.report_tracked_write(Durability::from_index(index));
+    }
+    let revision = zalsa.current_revision();
+    let (ingredient, _) = zalsa.lookup_ingredient_mut(index);
+    let ingredient = ingredient.assert_type_mut::<ExternalIngredientImpl<I>>();
+    (&mut ingredient.inner, revision)
+}
+
+/// Records that the active tracked function, if any, read the value `id` of the ingredient
+/// with index `index`, which last changed in `changed_at` and has the given `durability`.
+pub fn report_read<Db: ?Sized + Database>(
+    db: &Db,
+    index: IngredientIndex,
+    id: Id,
+    durability: Durability,
+    changed_at: Revision,
+) {
+    db.zalsa_local().report_tracked_read(
+        db.as_dyn_database(),
+        InputDependencyIndex::new(index, id),
+        durability,
+        Durabilities::at_least(durability),
+        changed_at,
+        InputAccumulatedValues::Empty,
+    );
+}
+
+/// Records that the active tracked function, if any, read each of the given values of the
+/// ingredient with index `index`, given as their id, durability and the revision in which
+/// they last changed.
+///
+/// This is equivalent to calling [`report_read`] for each value, but cheaper when reading
+/// many values in a loop: collect the reads and report them at once.
+pub fn report_reads<Db: ?Sized + Database>(
+    db: &Db,
+    index: IngredientIndex,
+    reads: &[(Id, Durability, Revision)],
+) {
+    db.zalsa_local().report_tracked_reads(
+        db.as_dyn_database(),
+        reads.iter().map(|&(id, durability, changed_at)| {
+            (InputDependencyIndex::new(index, id), durability, changed_at)
+        }),
+    );
+}
+
+pub(crate) struct JarImpl<I> {
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> JarImpl<I> {
+    pub(crate) fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I: ExternalIngredient> Jar for JarImpl<I> {
+    fn create_ingredients(
+        &self,
+        _aux: &dyn JarAux,
+        first_index: IngredientIndex,
+    ) -> Vec<Box<dyn Ingredient>> {
+        vec![Box::new(ExternalIngredientImpl {
+            index: first_index,
+            inner: I::new(first_index),
+        })]
+    }
+
+    fn salsa_struct_type_id(&self) -> Option<TypeId> {
+        None
+    }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self::new())
+    }
+}
+
+/// Adapts an [`ExternalIngredient`] to the internal [`Ingredient`] trait.
+#[derive(Debug)]
+struct ExternalIngredientImpl<I> {
+    index: IngredientIndex,
+    inner: I,
+}
+
+impl<I: ExternalIngredient> Ingredient for ExternalIngredientImpl<I> {
+    fn ingredient_index(&self) -> IngredientIndex {
+        self.index
+    }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::External
+    }
+
+    fn maybe_changed_after(
+        &self,
+        db: &dyn Database,
+        input: Id,
+        revision: Revision,
+    ) -> MaybeChangedAfter {
+        self.inner.maybe_changed_after(db, input, revision).into()
+    }
+
+    fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+        CycleRecoveryStrategy::Panic
+    }
+
+    fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
+        None
+    }
+
+    fn mark_validated_output(
+        &self,
+        _db: &dyn Database,
+        executor: DatabaseKeyIndex,
+        output_key: Id,
+    ) {
+        unreachable!(
+            "mark_validated_output({:?}, {:?}): external ingredients have no outputs",
+            executor, output_key
+        );
+    }
+
+    fn remove_stale_output(
+        &self,
+        _db: &dyn Database,
+        executor: DatabaseKeyIndex,
+        stale_output_key: Id,
+    ) {
+        unreachable!(
+            "remove_stale_output({:?}, {:?}): external ingredients have no outputs",
+            executor, stale_output_key
+        );
+    }
+
+    fn requires_reset_for_new_revision(&self) -> bool {
+        true
+    }
+
+    fn reset_for_new_revision(&mut self) {
+        self.inner.reset_for_new_revision();
+    }
+
+    fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match index {
+            Some(id) => self.inner.fmt_index(id, fmt),
+            None => fmt_index(self.debug_name(), index, fmt),
+        }
+    }
+
+    fn debug_name(&self) -> &'static str {
+        self.inner.debug_name()
+    }
+}
@@ -0,0 +1,299 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+
+use crate::id::FromIdWithDb;
+use crate::interned::{self, HashEqLike, JarImpl};
+use crate::plumbing::{AsId, JarAux, JarGroup, Registry};
+use crate::salsa_struct::SalsaStructInDb;
+use crate::zalsa::IngredientCache;
+use crate::{Database, Id, IngredientIndex, Update};
+
+/// A normalized path interned in the database.
+///
+/// Paths are normalized lexically, without accessing the file system: `.` components are
+/// removed, `..` components cancel the preceding normal component, and on Windows path
+/// prefixes (e.g., drive letters) are lowercased and either separator is accepted. Two paths
+/// that normalize to the same path yield the same `FilePath`, so comparing and hashing
+/// compare ids rather than paths.
+///
+/// Every ancestor of an interned path is interned as well, so [`parent`](`Self::parent`)
+/// and [`starts_with`](`Self::starts_with`) follow ids without comparing any text.
+#[derive(Copy, Clone)]
+pub struct FilePath<'db> {
+    id: Id,
+    data: &'db PathData,
+}
+
+impl<'db> FilePath<'db> {
+    /// Interns the normalized form of `path`.
+    pub fn new(db: &'db dyn Database, path: impl AsRef<Path>) -> Self {
+        Self::new_normalized(db, &normalize(path.as_ref()))
+    }
+
+    fn new_normalized(db: &'db dyn Database, path: &Path) -> Self {
+        // The parent is interned first: interning it while the ingredient builds the data of
+        // `path` would deadlock.
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(|parent| Self::new_normalized(db, parent).id);
+        let id = ingredient(db).intern_id(db, PathKey { path, parent }, |_, key| PathData {
+            path: key.path.into(),
+            parent: key.parent,
+        });
+        Self::from_id(id, db)
+    }
+
+    /// The normalized path, which lives as long as the database is borrowed.
+    pub fn as_path(self) -> &'db Path {
+        &self.data.path
+    }
+
+    /// The parent of this path, if any. The parent of a relative path with a single
+    /// component is `None`, as is the parent of a root.
+    pub fn parent(self, db: &'db dyn Database) -> Option<Self> {
+        self.data.parent.map(|parent| Self::from_id(parent, db))
+    }
+
+    /// This path followed by its ancestors, from the nearest to the farthest.
+    pub fn ancestors(self, db: &'db dyn Database) -> impl Iterator<Item = Self> + 'db {
+        std::iter::successors(Some(self), move |path| path.parent(db))
+    }
+
+    /// Whether `prefix` is this path or one of its ancestors.
+    pub fn starts_with(self, db: &dyn Database, prefix: FilePath<'_>) -> bool {
+        let mut id = Some(self.id);
+        let mut data = self.data;
+        while let Some(current) = id {
+            if current == prefix.id {
+                return true;
+            }
+            id = data.parent;
+            if let Some(parent) = id {
+                data = ingredient(db).data(db, parent);
+            }
+        }
+        false
+    }
+
+    /// The id of this path, which can be stored in inputs and outlive the borrow of the
+    /// database.
+    pub fn file_id(self) -> FileId {
+        FileId(self.id)
+    }
+
+    /// The file with this path, if one was created.
+    pub fn file(self, db: &dyn Database) -> Option<File> {
+        File::find_by_path(db, &self.file_id())
+    }
+}
+
+impl Deref for FilePath<'_> {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.data.path
+    }
+}
+
+impl AsRef<Path> for FilePath<'_> {
+    fn as_ref(&self) -> &Path {
+        &self.data.path
+    }
+}
+
+impl PartialEq for FilePath<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for FilePath<'_> {}
+
+impl Hash for FilePath<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.id, state);
+    }
+}
+
+impl fmt::Debug for FilePath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.data.path, f)
+    }
+}
+
+impl AsId for FilePath<'_> {
+    fn as_id(&self) -> Id {
+        self.id
+    }
+}
+
+impl FromIdWithDb for FilePath<'_> {
+    fn from_id(id: Id, db: &dyn Database) -> Self {
+        let data = ingredient(db).data(db, id);
+        // SAFETY: Interned values are only freed along with the database.
+        let data = unsafe { std::mem::transmute::<&PathData, &PathData>(data) };
+        FilePath { id, data }
+    }
+}
+
+impl SalsaStructInDb for FilePath<'_> {
+    fn lookup_ingredient_index(aux: &dyn JarAux) -> Option<IngredientIndex> {
+        aux.lookup_jar_by_type(&JarImpl::<Configuration>::default())
+    }
+
+    fn register_ingredients(db: &dyn Database) {
+        ingredient(db);
+    }
+}
+
+impl JarGroup for FilePath<'static> {
+    fn register(registry: &Registry<'_>) {
+        registry.add_jar(&JarImpl::<Configuration>::default());
+    }
+}
+
+unsafe impl Update for FilePath<'_> {
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        if unsafe { *old_pointer } != new_value {
+            unsafe { *old_pointer = new_value };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The id of a [`FilePath`], which does not borrow the database.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FileId(Id);
+
+impl FileId {
+    /// The path with this id.
+    pub fn path(self, db: &dyn Database) -> FilePath<'_> {
+        FilePath::from_id(self.0, db)
+    }
+}
+
+impl AsId for FileId {
+    fn as_id(&self) -> Id {
+        self.0
+    }
+}
+
+unsafe impl Update for FileId {
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        if unsafe { *old_pointer } != new_value {
+            unsafe { *old_pointer = new_value };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The contents of the file with a given path.
+///
+/// The path of a file is a [`FileId`], so it is always normalized, and the file with a
+/// given path can be found with [`FilePath::file`].
+#[crate::input]
+pub struct File {
+    #[index]
+    pub path: FileId,
+
+    #[return_ref]
+    pub contents: String,
+}
+
+/// Normalizes `path` lexically, see [`FilePath`].
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // `..` is its own parent at a root.
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                Some(Component::ParentDir | Component::CurDir) | None => {
+                    normalized.push(Component::ParentDir)
+                }
+            },
+            Component::Prefix(prefix) => {
+                let prefix: OsString = prefix.as_os_str().to_ascii_lowercase();
+                normalized.push(prefix)
+            }
+            Component::RootDir | Component::Normal(_) => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn ingredient(db: &dyn Database) -> &interned::IngredientImpl<Configuration> {
+    static CACHE: IngredientCache<interned::IngredientImpl<Configuration>> = IngredientCache::new();
+    CACHE.get_or_create(db, || {
+        db.zalsa()
+            .add_or_lookup_jar_by_type(&JarImpl::<Configuration>::default())
+    })
+}
+
+struct Configuration;
+
+impl interned::Configuration for Configuration {
+    const DEBUG_NAME: &'static str = "FilePath";
+
+    type Fields<'db> = PathData;
+
+    // A `FilePath` also holds its data, which requires the database to look up.
+    type Struct<'db> = Id;
+
+    type Hasher = interned::DefaultHasher;
+
+    fn struct_from_id<'db>(id: Id) -> Self::Struct<'db> {
+        id
+    }
+
+    fn deref_struct(id: Id) -> Id {
+        id
+    }
+}
+
+/// The interned path, along with the id of its parent. Only the path is hashed.
+#[derive(Clone, PartialEq, Eq)]
+struct PathData {
+    path: Box<Path>,
+    parent: Option<Id>,
+}
+
+impl Hash for PathData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.path, state);
+    }
+}
+
+/// Looks up a [`PathData`] without allocating its path.
+struct PathKey<'a> {
+    path: &'a Path,
+    parent: Option<Id>,
+}
+
+impl Hash for PathKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(self.path, state);
+    }
+}
+
+impl HashEqLike<PathKey<'_>> for PathData {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        Hash::hash(self, h);
+    }
+
+    fn eq(&self, key: &PathKey<'_>) -> bool {
+        *self.path == *key.path
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+/// A content-addressed, 128-bit fingerprint of a value.
+///
+/// Unlike [`Hash`], the fingerprint of a value must not depend on the machine or build that
+/// computes it, so that fingerprints can be compared across processes, e.g., to compute the
+/// keys of a [`MemoCacheBackend`](`crate::MemoCacheBackend`). Values that compare equal must have
+/// the same fingerprint, and values that do not should have different fingerprints.
+///
+/// Tracked functions declared with `fingerprint` compare the fingerprints of their old and
+/// new value, rather than the values themselves, to decide whether the value changed.
+pub trait Fingerprint {
+    /// Feeds the contents of `self` into `fingerprinter`.
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter);
+}
+
+/// Returns the fingerprint of `value`.
+pub fn fingerprint_of<T: Fingerprint + ?Sized>(value: &T) -> u128 {
+    let mut fingerprinter = Fingerprinter::new();
+    value.fingerprint(&mut fingerprinter);
+    fingerprinter.finish()
+}
+
+/// Computes a [`Fingerprint`] with the 128-bit FNV-1a hash function.
+#[derive(Clone, Debug)]
+pub struct Fingerprinter {
+    state: u128,
+}
+
+impl Fingerprinter {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    pub fn new() -> Self {
+        Self {
+            state: Self::OFFSET_BASIS,
+        }
+    }
+
+    /// Feeds `bytes` into the fingerprint.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = (self.state ^ u128::from(byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Feeds a length or index into the fingerprint, independently of the width of `usize`.
+    pub fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    /// Returns the fingerprint of the bytes fed so far.
+    pub fn finish(&self) -> u128 {
+        self.state
+    }
+}
+
+impl Default for Fingerprinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! impl_fingerprint_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Fingerprint for $t {
+                fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+                    fingerprinter.write(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_fingerprint_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Fingerprint for usize {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        fingerprinter.write_len(*self);
+    }
+}
+
+impl Fingerprint for isize {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        fingerprinter.write(&(*self as i64).to_le_bytes());
+    }
+}
+
+impl Fingerprint for bool {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        fingerprinter.write(&[u8::from(*self)]);
+    }
+}
+
+impl Fingerprint for char {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        u32::from(*self).fingerprint(fingerprinter);
+    }
+}
+
+impl Fingerprint for () {
+    fn fingerprint(&self, _fingerprinter: &mut Fingerprinter) {}
+}
+
+impl Fingerprint for str {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        fingerprinter.write_len(self.len());
+        fingerprinter.write(self.as_bytes());
+    }
+}
+
+impl Fingerprint for String {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        self.as_str().fingerprint(fingerprinter);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for [T] {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        fingerprinter.write_len(self.len());
+        for element in self {
+            element.fingerprint(fingerprinter);
+        }
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for Vec<T> {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        self.as_slice().fingerprint(fingerprinter);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for Option<T> {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        match self {
+            None => fingerprinter.write(&[0]),
+            Some(value) => {
+                fingerprinter.write(&[1]);
+                value.fingerprint(fingerprinter);
+            }
+        }
+    }
+}
+
+impl<T: Fingerprint, E: Fingerprint> Fingerprint for Result<T, E> {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        match self {
+            Ok(value) => {
+                fingerprinter.write(&[0]);
+                value.fingerprint(fingerprinter);
+            }
+            Err(error) => {
+                fingerprinter.write(&[1]);
+                error.fingerprint(fingerprinter);
+            }
+        }
+    }
+}
+
+impl<T: Fingerprint + ?Sized> Fingerprint for &T {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        T::fingerprint(self, fingerprinter);
+    }
+}
+
+impl<T: Fingerprint + ?Sized> Fingerprint for Box<T> {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        T::fingerprint(self, fingerprinter);
+    }
+}
+
+impl<T: Fingerprint + ?Sized> Fingerprint for Arc<T> {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        T::fingerprint(self, fingerprinter);
+    }
+}
+
+macro_rules! impl_fingerprint_for_tuple {
+    ($(($($name:ident),+))*) => {
+        $(
+            impl<$($name: Fingerprint),+> Fingerprint for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+                    let ($($name,)+) = self;
+                    $($name.fingerprint(fingerprinter);)+
+                }
+            }
+        )*
+    };
+}
+
+impl_fingerprint_for_tuple! {
+    (A)
+    (A, B)
+    (A, B, C)
+    (A, B, C, D)
+}
@@ -3,30 +3,43 @@ use std::{any::Any, fmt, sync::Arc};
 use crate::{
     accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues},
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, MaybeChangedAfter},
+    ingredient::{fmt_index, IngredientKind, MaybeChangedAfter},
     key::DatabaseKeyIndex,
     plumbing::JarAux,
     salsa_struct::SalsaStructInDb,
+    stats::StatCounters,
     zalsa::{IngredientIndex, MemoIngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Cycle, Database, Id, Revision, Update,
+    Cycle, Database, Id, Revision,
 };
 
 use self::delete::DeletedEntries;
+use self::history::History;
+pub use self::history::OutputDiff;
+pub use self::intern_output::{intern_output, InternOutput, OutputInterner};
+use self::key_map::KeyMap;
 
 use super::ingredient::Ingredient;
 
 mod accumulated;
 mod backdate;
 mod delete;
+#[cfg(feature = "check_determinism")]
+mod determinism;
 mod diff_outputs;
 mod execute;
 mod fetch;
+mod fingerprint;
+mod history;
 mod inputs;
+mod intern_output;
+mod key_map;
 mod lru;
 mod maybe_changed_after;
 mod memo;
+mod memo_cache;
 mod specify;
+mod validate;
 
 pub trait Configuration: Any {
     const DEBUG_NAME: &'static str;
@@ -43,20 +56,98 @@ pub trait Configuration: Any {
     type Input<'db>: Send + Sync;
 
     /// The value computed by the function.
-    type Output<'db>: fmt::Debug + Send + Sync + Update;
+    type Output<'db>: fmt::Debug + Send + Sync;
+
+    /// The table deduplicating the outputs, if the function was declared with `intern_output`.
+    /// `()` otherwise.
+    type OutputInterner: Default + Send + Sync + 'static;
 
     /// Determines whether this function can recover from being a participant in a cycle
     /// (and, if so, how).
     const CYCLE_STRATEGY: CycleRecoveryStrategy;
 
+    /// If true, the function was declared with `auto_cancel_check`: cancellation is
+    /// checked after each read it performs, not only when it invokes tracked functions.
+    const AUTO_CANCEL_CHECK: bool;
+
+    /// If true, the function was declared with `firewall`: it has no LRU capacity, so that its
+    /// old value can always be compared with the new one, and backdating the new value emits
+    /// [`EventKind::DidAbsorbChange`](`crate::EventKind::DidAbsorbChange`).
+    const FIREWALL: bool;
+
+    /// If true, the function was declared with `broadcast`: the thread computing (or verifying)
+    /// a memo hands it to the threads that blocked on it, which return it without looking
+    /// it up and verifying it again.
+    const BROADCAST: bool;
+
+    /// If true, the function was declared with `keep_stale_on_panic`: if re-executing it panics,
+    /// its previous value is kept for the current revision and
+    /// [`EventKind::DidKeepStaleValue`](`crate::EventKind::DidKeepStaleValue`) is emitted.
+    const KEEP_STALE_ON_PANIC: bool;
+
+    /// If true, the function was declared with `memoize_panics`: if executing it panics, the
+    /// panic is memoized and raised again when the function is invoked, without re-executing
+    /// it, until its inputs change.
+    const MEMOIZE_PANICS: bool;
+
+    /// If nonzero, the function was declared with `volatile(ttl_revisions = N)`: its memos are
+    /// re-executed once they were computed `N` revisions ago, even if no input changed.
+    const VOLATILE_TTL: usize;
+
+    /// If nonzero, the function was declared with `history = N`: the last `N` values of each
+    /// key are retained, so that the value in a past revision can be compared with a newer one.
+    const HISTORY: usize;
+
+    /// If true, the function was declared with `no_eq`: its values are never compared.
+    const NO_EQ: bool;
+
     /// Invokes after a new result `new_value`` has been computed for which an older memoized
     /// value existed `old_value`. Returns true if the new value is equal to the older one
     /// and hence should be "backdated" (i.e., marked as having last changed in an older revision,
     /// even though it was recomputed).
     ///
-    /// This invokes user's code in form of the `Eq` impl.
+    /// This invokes user's code in form of the `Eq` impl, or of the function given with `eq_with`.
     fn should_backdate_value(old_value: &Self::Output<'_>, new_value: &Self::Output<'_>) -> bool;
 
+    /// If the function was declared with `fingerprint`, returns the fingerprint of `value`,
+    /// which is then compared instead of the value itself when backdating.
+    /// Returns `None` otherwise.
+    fn fingerprint(value: &Self::Output<'_>) -> Option<u128>;
+
+    /// Updates the value at `old_pointer` with `new_value`, as [`Update::maybe_update`](`crate::Update::maybe_update`).
+    /// If the function was declared with `unsafe(update_with = <path>)`, this invokes
+    /// that function, so that outputs like `Box<dyn Trait>` need no `Update` impl.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Update::maybe_update`](`crate::Update::maybe_update`).
+    unsafe fn maybe_update<'db>(
+        old_pointer: *mut Self::Output<'db>,
+        new_value: Self::Output<'db>,
+    ) -> bool;
+
+    /// Invoked with each newly computed value. If the function was declared with `intern_output`,
+    /// returns a value equal to `value` that shares its allocation with other outputs of this function.
+    ///
+    /// This invokes user's code in form of the `Hash` and `Eq` impls.
+    fn intern_output<'db>(
+        outputs: &Self::OutputInterner,
+        value: Self::Output<'db>,
+    ) -> Self::Output<'db>;
+
+    /// If the function was declared with `cache_key = <path>`, invokes that function to
+    /// compute the fingerprint identifying the value of `input` in the
+    /// [memo cache backend](`crate::MemoCacheBackend`). Returns `None` otherwise.
+    fn cache_fingerprint<'db>(db: &'db Self::DbView, input: Self::Input<'db>) -> Option<u128>;
+
+    /// Encodes `value` to store it in the memo cache backend.
+    /// Only invoked if the function was declared with `cache_key`.
+    fn encode_cached(value: &Self::Output<'_>) -> Vec<u8>;
+
+    /// Decodes a value loaded from the memo cache backend.
+    /// Only invoked if the function was declared with `cache_key`.
+    fn decode_cached<'db>(bytes: &[u8]) -> Option<Self::Output<'db>>;
+
     /// Convert from the id used internally to the value that execute is expecting.
     /// This is a no-op if the input to the function is a salsa struct.
     fn id_to_input(db: &Self::DbView, key: Id) -> Self::Input<'_>;
@@ -94,8 +185,9 @@ pub struct IngredientImpl<C: Configuration> {
     /// Used to construct `DatabaseKeyIndex` values.
     index: IngredientIndex,
 
-    /// The index for the memo/sync tables
-    memo_ingredient_index: MemoIngredientIndex,
+    /// The index for the memo/sync tables, for each salsa struct whose entries are the keys
+    /// of this function. There are several if the key is a `Supertype` enum.
+    memo_ingredient_indices: Vec<(IngredientIndex, MemoIngredientIndex)>,
 
     /// Used to find memos to throw out when we have too many memoized values.
     lru: lru::Lru,
@@ -113,6 +205,30 @@ pub struct IngredientImpl<C: Configuration> {
     /// we don't know that we can trust the database to give us the same runtime
     /// everytime and so forth.
     deleted_entries: DeletedEntries<C>,
+
+    /// The revision in which each memo was computed, if the function is volatile.
+    executed_at: KeyMap<Revision>,
+
+    /// The fingerprint of the memoized value of each key, if the function was declared with
+    /// `fingerprint`. Kept beside the memos rather than in them, to keep memos small.
+    fingerprints: KeyMap<u128>,
+
+    /// Keys whose previous value was kept because re-executing the function panicked,
+    /// if the function was declared with `keep_stale_on_panic`.
+    stale: KeyMap<Revision>,
+
+    /// The messages of the panics memoized for each key, if the function was declared with
+    /// `memoize_panics`. The memos of these keys have no value.
+    poisoned: KeyMap<String>,
+
+    /// The previous values of each key, if the function was declared with `history`.
+    history: History<C>,
+
+    /// Previously computed outputs, if the function was declared with `intern_output`.
+    output_interner: C::OutputInterner,
+
+    /// Counters reported by [`Ingredient::stats`], if the `stats` feature is enabled.
+    stats: StatCounters,
 }
 
 /// True if `old_value == new_value`. Invoked by the generated
@@ -126,12 +242,49 @@ impl<C> IngredientImpl<C>
 where
     C: Configuration,
 {
-    pub fn new(struct_index: IngredientIndex, index: IngredientIndex, aux: &dyn JarAux) -> Self {
+    pub fn new(
+        struct_indices: &[IngredientIndex],
+        index: IngredientIndex,
+        aux: &dyn JarAux,
+    ) -> Self {
+        let lru = lru::Lru::default();
+        // Firewalls keep their old value to compare new ones against, so they are never evicted.
+        if !C::FIREWALL {
+            lru.set_capacity(aux.default_lru_capacity());
+        }
         Self {
             index,
-            memo_ingredient_index: aux.next_memo_ingredient_index(struct_index, index),
-            lru: Default::default(),
+            memo_ingredient_indices: struct_indices
+                .iter()
+                .map(|&struct_index| {
+                    let memo_ingredient_index = aux.next_memo_ingredient_index(struct_index, index);
+                    (struct_index, memo_ingredient_index)
+                })
+                .collect(),
+            lru,
             deleted_entries: Default::default(),
+            executed_at: Default::default(),
+            fingerprints: Default::default(),
+            stale: Default::default(),
+            poisoned: Default::default(),
+            history: Default::default(),
+            output_interner: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    /// The index for the memo/sync tables of the salsa struct whose entry `id` is.
+    fn memo_ingredient_index(&self, zalsa: &Zalsa, id: Id) -> MemoIngredientIndex {
+        match &self.memo_ingredient_indices[..] {
+            [(_, memo_ingredient_index)] => *memo_ingredient_index,
+            memo_ingredient_indices => {
+                let struct_index = zalsa.table().ingredient_index(id);
+                memo_ingredient_indices
+                    .iter()
+                    .find(|(index, _)| *index == struct_index)
+                    .map(|&(_, memo_ingredient_index)| memo_ingredient_index)
+                    .unwrap_or_else(|| panic!("{id:?} is not a key of `{}`", C::DEBUG_NAME))
+            }
         }
     }
 
@@ -146,6 +299,43 @@ where
         self.lru.set_capacity(capacity);
     }
 
+    /// Forces the function to be re-executed for `key` when it is next fetched,
+    /// e.g. because it read some state external to salsa that changed.
+    ///
+    /// This starts a new revision. Unlike with an untracked read, the function is only
+    /// re-executed once, and functions that depend on it are only re-executed if its
+    /// value changes.
+    pub fn invalidate(db: &mut dyn Database, index: IngredientIndex, key: Id) {
+        let zalsa_mut = db.zalsa_mut();
+        let ingredient = zalsa_mut.lookup_ingredient(index).assert_type::<Self>();
+        if let Some(durability) = ingredient.invalidate_memo_for(zalsa_mut, key) {
+            zalsa_mut.report_tracked_write(durability);
+        }
+    }
+
+    /// True if the function is volatile and the memo for `id` was computed
+    /// at least `VOLATILE_TTL` revisions ago.
+    fn is_expired(&self, zalsa: &Zalsa, id: Id) -> bool {
+        if C::VOLATILE_TTL == 0 {
+            return false;
+        }
+        let Some(executed_at) = self.executed_at.get(&id).map(|r| *r) else {
+            return true;
+        };
+        zalsa.current_revision().as_usize() - executed_at.as_usize() >= C::VOLATILE_TTL
+    }
+
+    /// True if the function kept the previous value of `id` because re-executing it panicked,
+    /// in which case the value must not be reused in later revisions.
+    fn is_stale(&self, id: Id) -> bool {
+        C::KEEP_STALE_ON_PANIC && self.stale.contains_key(&id)
+    }
+
+    /// True if the function memoized a panic for `id`.
+    fn is_poisoned(&self, id: Id) -> bool {
+        C::MEMOIZE_PANICS && self.poisoned.contains_key(&id)
+    }
+
     /// Returns a reference to the memo value that lives as long as self.
     /// This is UNSAFE: the caller is responsible for ensuring that the
     /// memo will not be released so long as the `&self` is valid.
@@ -153,6 +343,10 @@ where
     /// when this function is called and (b) ensuring that any entries
     /// removed from the memo-map are added to `deleted_entries`, which is
     /// only cleared with `&mut self`.
+    ///
+    /// This is what lets `fetch`, and thus `return_ref` functions and the generated
+    /// `get_ref`, hand out references into memoized values that live as long as the
+    /// database is borrowed: a new revision requires `&mut` access to the database.
     unsafe fn extend_memo_lifetime<'this>(
         &'this self,
         memo: &memo::Memo<C::Output<'this>>,
@@ -172,10 +366,18 @@ where
             // value is returned) and anything removed from map is added to deleted entries (ensured elsewhere).
             self.extend_memo_lifetime(&memo)
         };
+        let changed_at = db_memo.revisions.changed_at;
         if let Some(old_value) = self.insert_memo_into_table_for(zalsa, id, memo) {
+            if C::HISTORY != 0 && old_value.revisions.changed_at != changed_at {
+                // Unless the new value was backdated, the old value is a previous one.
+                self.record_history(id, &old_value);
+            }
             // In case there is a reference to the old memo out there, we have to store it
             // in the deleted entries. This will get cleared when a new revision starts.
             self.deleted_entries.push(old_value);
+        } else if C::HISTORY != 0 {
+            // The key is new, or its memo was removed since: its history is stale.
+            self.clear_history(id);
         }
         db_memo
     }
@@ -189,6 +391,24 @@ where
         self.index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedFunction
+    }
+
+    fn memo_count(&self, db: &dyn Database) -> Option<usize> {
+        let zalsa = db.zalsa();
+        let count = self
+            .memo_ingredient_indices
+            .iter()
+            .flat_map(|&(struct_index, _)| zalsa.table().ids(struct_index))
+            .filter(|&id| {
+                self.get_memo_from_table_for(zalsa, id)
+                    .is_some_and(|memo| memo.value.is_some())
+            })
+            .count();
+        Some(count)
+    }
+
     fn maybe_changed_after(
         &self,
         db: &dyn Database,
@@ -227,6 +447,16 @@ where
         // Since its `verified_at` field has not changed, it will be considered dirty if it is invoked.
     }
 
+    fn discard_key(&self, key_index: Id) {
+        self.executed_at.remove(&key_index);
+        self.fingerprints.remove(&key_index);
+        self.stale.remove(&key_index);
+        self.poisoned.remove(&key_index);
+        if C::HISTORY != 0 {
+            self.clear_history(key_index);
+        }
+    }
+
     fn requires_reset_for_new_revision(&self) -> bool {
         true
     }
@@ -243,6 +473,16 @@ where
         C::DEBUG_NAME
     }
 
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> Option<crate::IngredientStats> {
+        Some(self.stats.snapshot(self.index, C::DEBUG_NAME))
+    }
+
+    #[cfg(feature = "stats")]
+    fn record_blocked(&self, duration: std::time::Duration) {
+        self.stats.record_blocked(duration);
+    }
+
     fn accumulated<'db>(
         &'db self,
         db: &'db dyn Database,
@@ -1,11 +1,10 @@
 use super::{Configuration, IngredientImpl};
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
-use crate::zalsa_local::QueryOrigin;
 use crate::{
-    accumulator::{self, accumulated_map::AccumulatedMap},
+    accumulator::{self, accumulated_map::AccumulatedMap, AccumulatedWithProvenance},
     hash::FxHashSet,
     zalsa::ZalsaDatabase,
-    AsDynDatabase, DatabaseKeyIndex, Id,
+    AsDynDatabase, DatabaseKeyIndex, Id, Revision,
 };
 
 impl<C> IngredientImpl<C>
@@ -17,6 +16,43 @@ where
     pub fn accumulated_by<A>(&self, db: &C::DbView, key: Id) -> Vec<A>
     where
         A: accumulator::Accumulator,
+    {
+        let mut output = vec![];
+        self.visit_accumulated::<A>(db, key, |_, values, _| output.extend_from_slice(values));
+        output
+    }
+
+    /// Helper used by `accumulated_with_provenance` functions. Like [`Self::accumulated_by`],
+    /// but also returns the query that accumulated each value and when.
+    pub fn accumulated_with_provenance_by<A>(
+        &self,
+        db: &C::DbView,
+        key: Id,
+    ) -> Vec<AccumulatedWithProvenance<A>>
+    where
+        A: accumulator::Accumulator,
+    {
+        let mut output = vec![];
+        self.visit_accumulated::<A>(db, key, |query, values, accumulated_at| {
+            output.extend(values.iter().map(|value| AccumulatedWithProvenance {
+                value: value.clone(),
+                query,
+                accumulated_at,
+            }))
+        });
+        output
+    }
+
+    /// Invokes `op` with the values of type `A` accumulated by `key` and each of its
+    /// (transitive) inputs, in execution order, along with the query that accumulated
+    /// them and the revision in which it did.
+    fn visit_accumulated<A>(
+        &self,
+        db: &C::DbView,
+        key: Id,
+        mut op: impl FnMut(DatabaseKeyIndex, &[A], Revision),
+    ) where
+        A: accumulator::Accumulator,
     {
         let (zalsa, zalsa_local) = db.zalsas();
 
@@ -34,9 +70,8 @@ where
         zalsa_local.report_untracked_read(zalsa.current_revision());
 
         let Some(accumulator) = <accumulator::IngredientImpl<A>>::from_db(db) else {
-            return vec![];
+            return;
         };
-        let mut output = vec![];
 
         // First ensure the result is up to date
         self.fetch(db, key);
@@ -55,10 +90,12 @@ where
             }
 
             let ingredient = zalsa.lookup_ingredient(k.ingredient_index);
-            // Extend `output` with any values accumulated by `k`.
+            // Report any values accumulated by `k`.
             let (accumulated_map, input) = ingredient.accumulated(db, k.key_index);
-            if let Some(accumulated_map) = accumulated_map {
-                accumulated_map.extend_with_accumulated(accumulator.index(), &mut output);
+            if let Some((values, revision)) =
+                accumulated_map.and_then(|map| map.accumulated_at::<A>(accumulator.index()))
+            {
+                op(k, values, revision);
             }
             // Skip over the inputs because we know that the entire sub-graph has no accumulated values
             if input.is_empty() {
@@ -75,21 +112,16 @@ where
                 continue;
             };
 
-            if let QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) = &origin {
-                stack.reserve(edges.input_outputs.len());
-            }
-
+            let len = stack.len();
             stack.extend(
                 origin
                     .inputs()
-                    .filter_map(|input| TryInto::<DatabaseKeyIndex>::try_into(input).ok())
-                    .rev(),
+                    .filter_map(|input| TryInto::<DatabaseKeyIndex>::try_into(input).ok()),
             );
+            stack[len..].reverse();
 
             visited.reserve(stack.len());
         }
-
-        output
     }
 
     pub(super) fn accumulated_map<'db>(
@@ -101,7 +133,7 @@ where
         let memo = self.refresh_memo(db, key);
         (
             memo.revisions.accumulated.as_deref(),
-            memo.revisions.accumulated_inputs.load(),
+            memo.accumulated_inputs(),
         )
     }
 }
@@ -1,4 +1,4 @@
-use crate::zalsa_local::QueryRevisions;
+use crate::{stats::Stat, zalsa_local::CompletedQuery, Id, Revision};
 
 use super::{memo::Memo, Configuration, IngredientImpl};
 
@@ -6,31 +6,67 @@ impl<C> IngredientImpl<C>
 where
     C: Configuration,
 {
-    /// If the value/durability of this memo is equal to what is found in `revisions`/`value`,
-    /// then update `revisions.changed_at` to match `self.revisions.changed_at`. This is invoked
-    /// on an old memo when a new memo has been produced to check whether there have been changed.
+    /// If the value/durability of this memo is equal to what is found in `completed`/`value`,
+    /// then update `completed.revisions.changed_at` to match `self.revisions.changed_at` and return true.
+    /// This is invoked on an old memo when a new memo has been produced to check whether there
+    /// have been changed.
+    ///
+    /// If the function was declared with `fingerprint`, the values are compared by their
+    /// fingerprints, `new_fingerprint` being the one of `value`.
+    ///
+    /// If the value did change although its inputs did not since the old memo was last verified,
+    /// `completed.revisions.changed_at` is moved to `revision_now`, as readers that have seen the
+    /// old value would consider the new one unchanged. This happens when the memo was invalidated,
+    /// or when a value assigned with `specify` replaces an executed one or vice versa. An old
+    /// value that was evicted by LRU, on the other hand, is recomputed from the same inputs, and
+    /// dating it from those inputs is accurate.
     pub(super) fn backdate_if_appropriate(
         &self,
+        id: Id,
         old_memo: &Memo<C::Output<'_>>,
-        revisions: &mut QueryRevisions,
+        completed: &mut CompletedQuery,
         value: &C::Output<'_>,
-    ) {
+        new_fingerprint: Option<u128>,
+        revision_now: Revision,
+    ) -> bool {
         if let Some(old_value) = &old_memo.value {
             // Careful: if the value became less durable than it
             // used to be, that is a "breaking change" that our
             // consumers must be aware of. Becoming *more* durable
             // is not. See the test `constant_to_non_constant`.
-            if revisions.durability >= old_memo.revisions.durability
-                && C::should_backdate_value(old_value, value)
+            // Likewise, consumers only check for changes to inputs of
+            // the durabilities the value depended on when they read it.
+            if completed.durability >= old_memo.durability()
+                && completed.durabilities.is_subset(old_memo.durabilities())
+                && self.values_equal(id, old_value, value, new_fingerprint)
             {
                 tracing::debug!(
                     "value is equal, back-dating to {:?}",
                     old_memo.revisions.changed_at,
                 );
 
-                assert!(old_memo.revisions.changed_at <= revisions.changed_at);
-                revisions.changed_at = old_memo.revisions.changed_at;
+                assert!(
+                    old_memo.revisions.changed_at <= completed.revisions.changed_at
+                        || old_memo.revisions.origin.is_assigned()
+                            != completed.revisions.origin.is_assigned()
+                );
+                completed.revisions.changed_at = old_memo.revisions.changed_at;
+                self.stats.record(Stat::Backdate);
+                return true;
             }
         }
+
+        let origin_changed =
+            old_memo.revisions.origin.is_assigned() != completed.revisions.origin.is_assigned();
+        let recomputed = old_memo.value.is_none() && !old_memo.is_invalidated() && !origin_changed;
+        if !recomputed && completed.revisions.changed_at <= old_memo.verified_at() {
+            tracing::debug!(
+                "value changed, but its inputs did not since {:?}, dating to {:?}",
+                old_memo.verified_at(),
+                revision_now,
+            );
+            completed.revisions.changed_at = revision_now;
+        }
+        false
     }
 }
@@ -0,0 +1,38 @@
+use crate::{
+    zalsa::ZalsaDatabase, zalsa_local::CompletedQuery, AsDynDatabase as _, DatabaseKeyIndex,
+};
+
+use super::{Configuration, IngredientImpl};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Executes the query again after it computed `value`, and panics if the new value is
+    /// not equal to it.
+    ///
+    /// The second execution runs in a new stack frame, seeded with the tracked structs created
+    /// by the first one, whose dependencies and outputs are discarded.
+    pub(super) fn check_determinism<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        database_key_index: DatabaseKeyIndex,
+        completed: &CompletedQuery,
+        value: &C::Output<'db>,
+    ) {
+        let active_query = db.zalsa_local().push_query(database_key_index);
+        active_query.seed_tracked_struct_ids(&completed.revisions.tracked_struct_ids);
+        let id = database_key_index.key_index;
+        let again = C::execute(db, C::id_to_input(db, id));
+        drop(active_query.pop());
+
+        if !C::should_backdate_value(value, &again) {
+            panic!(
+                "`{}` is not deterministic: executing it again returned a different value\n\
+                 first:  {value:#?}\n\
+                 second: {again:#?}",
+                database_key_index.to_stable_string(db.as_dyn_database()),
+            );
+        }
+    }
+}
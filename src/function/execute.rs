@@ -1,7 +1,12 @@
-use std::sync::Arc;
+use std::{any::Any, panic::AssertUnwindSafe, sync::Arc};
 
 use crate::{
-    zalsa::ZalsaDatabase, zalsa_local::ActiveQueryGuard, Cycle, Database, Event, EventKind,
+    durability::Durabilities,
+    stats::Stat,
+    zalsa::ZalsaDatabase,
+    zalsa_local::{self, ActiveQueryGuard},
+    AsDynDatabase as _, Cancelled, Cycle, Database, Deadlock, Durability, Event, EventKind,
+    Intercept, InvalidKey, MissingView,
 };
 
 use super::{memo::Memo, Configuration, IngredientImpl};
@@ -23,13 +28,14 @@ where
         &'db self,
         db: &'db C::DbView,
         active_query: ActiveQueryGuard<'_>,
-        opt_old_memo: Option<Arc<Memo<C::Output<'_>>>>,
+        opt_old_memo: Option<Arc<Memo<C::Output<'db>>>>,
     ) -> &'db Memo<C::Output<'db>> {
         let zalsa = db.zalsa();
         let revision_now = zalsa.current_revision();
         let database_key_index = active_query.database_key_index;
 
         tracing::info!("{:?}: executing query", database_key_index);
+        self.stats.record(Stat::Execution);
 
         db.salsa_event(&|| {
             Event::new(EventKind::WillExecute {
@@ -37,6 +43,24 @@ where
             })
         });
 
+        let interceptors = zalsa.interceptors();
+        for interceptor in interceptors.iter() {
+            if interceptor.will_execute(db.as_dyn_database(), database_key_index) == Intercept::Veto
+            {
+                tracing::debug!("{database_key_index:?}: execution vetoed by interceptor");
+                Cancelled::Vetoed.throw();
+            }
+        }
+
+        db.zalsa_local().consume_fuel(database_key_index);
+
+        if C::AUTO_CANCEL_CHECK {
+            active_query.enable_auto_cancel_check();
+        }
+
+        // Whatever was associated with the previous value is stale now.
+        zalsa.evict_side_table_entries(database_key_index);
+
         // If we already executed this query once, then use the tracked-struct ids from the
         // previous execution as the starting point for the new one.
         if let Some(old_memo) = &opt_old_memo {
@@ -47,7 +71,41 @@ where
         // stale, or value is absent. Let's execute!
         let database_key_index = active_query.database_key_index;
         let id = database_key_index.key_index;
-        let value = match Cycle::catch(|| C::execute(db, C::id_to_input(db, id))) {
+        // Functions declared with `cache_key` first look up their value in the memo cache
+        // backend; the reads of the fingerprint function are the dependencies of a loaded value.
+        let cache_key = self.memo_cache_key(db, id);
+        let cached = cache_key.as_ref().and_then(|key| self.load_cached(db, key));
+        let loaded = cached.is_some();
+        let execute = || match cached {
+            Some(value) => {
+                tracing::debug!("{database_key_index:?}: loaded value from the memo cache");
+                Ok(value)
+            }
+            None => Cycle::catch(|| C::execute(db, C::id_to_input(db, id))),
+        };
+        let result = match &opt_old_memo {
+            Some(old_memo) if C::KEEP_STALE_ON_PANIC && old_memo.value.is_some() => {
+                match std::panic::catch_unwind(AssertUnwindSafe(execute)) {
+                    Ok(result) => result,
+                    Err(payload) => {
+                        return self.keep_stale_value(db, active_query, old_memo, payload);
+                    }
+                }
+            }
+            _ if C::MEMOIZE_PANICS => {
+                // Frames unwound by panics caught earlier are irrelevant.
+                zalsa_local::take_unwound_query();
+                match std::panic::catch_unwind(AssertUnwindSafe(execute)) {
+                    Ok(result) => result,
+                    Err(payload) => {
+                        self.memoize_panic(db, active_query, opt_old_memo.as_deref(), payload)
+                    }
+                }
+            }
+            _ => execute(),
+        };
+        let recovered = result.is_err();
+        let value = match result {
             Ok(v) => v,
             Err(cycle) => {
                 tracing::debug!(
@@ -71,19 +129,188 @@ where
                 }
             }
         };
-        let mut revisions = active_query.pop();
+        let mut completed = active_query.pop();
+
+        // Functions reading untracked state or volatile ones are expected to return
+        // different values when executed again.
+        #[cfg(feature = "check_determinism")]
+        if !C::NO_EQ
+            && !recovered
+            && C::VOLATILE_TTL == 0
+            && !matches!(
+                completed.revisions.origin,
+                zalsa_local::QueryOrigin::DerivedUntracked(_)
+            )
+            && zalsa.should_check_determinism()
+        {
+            self.check_determinism(db, database_key_index, &completed, &value);
+        }
+
+        if let Some(key) = &cache_key {
+            if !loaded && !recovered {
+                self.store_cached(db, key, &value);
+            }
+        }
+
+        let value = C::intern_output(&self.output_interner, value);
+
+        if C::KEEP_STALE_ON_PANIC {
+            self.stale.remove(&id);
+        }
+        if C::MEMOIZE_PANICS {
+            self.poisoned.remove(&id);
+        }
+
+        if C::VOLATILE_TTL != 0 {
+            // Like for an untracked read, readers must verify the value in each new revision,
+            // and it may have changed since the inputs it read did.
+            completed.durability = Durability::LOW;
+            completed.durabilities |= Durabilities::UNTRACKED;
+            completed.revisions.changed_at = revision_now;
+            self.executed_at.insert(id, revision_now);
+        }
+
+        for interceptor in interceptors.iter().rev() {
+            interceptor.did_execute(db.as_dyn_database(), database_key_index);
+        }
 
         // If the new value is equal to the old one, then it didn't
         // really change, even if some of its inputs have. So we can
         // "backdate" its `changed_at` revision to be the same as the
         // old value.
+        let fingerprint = C::fingerprint(&value);
         if let Some(old_memo) = &opt_old_memo {
-            self.backdate_if_appropriate(old_memo, &mut revisions, &value);
-            self.diff_outputs(db, database_key_index, old_memo, &mut revisions);
+            let backdated = self.backdate_if_appropriate(
+                id,
+                old_memo,
+                &mut completed,
+                &value,
+                fingerprint,
+                revision_now,
+            );
+            if backdated {
+                for interceptor in interceptors.iter() {
+                    interceptor.did_backdate(db.as_dyn_database(), database_key_index);
+                }
+            }
+            if C::FIREWALL && backdated {
+                db.salsa_event(&|| {
+                    Event::new(EventKind::DidAbsorbChange {
+                        database_key: database_key_index,
+                    })
+                });
+            }
+            self.diff_outputs(db, database_key_index, old_memo, &mut completed.revisions);
+        }
+
+        if let Some(fingerprint) = fingerprint {
+            self.fingerprints.insert(id, fingerprint);
         }
 
-        tracing::debug!("{database_key_index:?}: read_upgrade: result.revisions = {revisions:#?}");
+        tracing::debug!("{database_key_index:?}: read_upgrade: result = {completed:#?}");
 
-        self.insert_memo(zalsa, id, Memo::new(Some(value), revision_now, revisions))
+        self.insert_memo(zalsa, id, Memo::new(Some(value), revision_now, completed))
     }
+
+    /// Invoked when re-executing a function declared with `keep_stale_on_panic` panicked with
+    /// `payload`: verifies `old_memo` in the current revision, so that its value is used instead.
+    /// Panics raised by salsa itself, e.g. for cancellation, are propagated.
+    fn keep_stale_value<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        active_query: ActiveQueryGuard<'_>,
+        old_memo: &Memo<C::Output<'db>>,
+        payload: Box<dyn Any + Send>,
+    ) -> &'db Memo<C::Output<'db>> {
+        let is_salsa_panic = match payload.downcast_ref::<Cancelled>() {
+            Some(cancelled) => !matches!(cancelled, Cancelled::PropagatedPanic),
+            None => {
+                payload.is::<InvalidKey>()
+                    || payload.is::<Deadlock>()
+                    || payload.is::<MissingView>()
+            }
+        };
+        if is_salsa_panic {
+            std::panic::resume_unwind(payload);
+        }
+
+        let database_key_index = active_query.database_key_index;
+        let id = database_key_index.key_index;
+        drop(active_query);
+
+        let message = panic_message(&*payload);
+        tracing::warn!("{database_key_index:?}: keeping stale value after panic: {message:?}");
+        db.salsa_event(&|| {
+            Event::new(EventKind::DidKeepStaleValue {
+                database_key: database_key_index,
+                message: message.clone(),
+            })
+        });
+
+        let revision_now = db.zalsa().current_revision();
+        self.stale.insert(id, revision_now);
+        let dyn_db = db.as_dyn_database();
+        old_memo.mark_as_verified(
+            dyn_db,
+            revision_now,
+            database_key_index,
+            old_memo.accumulated_inputs(),
+        );
+        old_memo.mark_outputs_as_verified(dyn_db, database_key_index);
+
+        // SAFETY: `old_memo` is still in the memo table, as we hold the claim on `id`.
+        unsafe { self.extend_memo_lifetime(old_memo) }
+    }
+
+    /// Invoked when executing a function declared with `memoize_panics` panicked with `payload`:
+    /// stores a memo without value, recording the dependencies read before the panic, and
+    /// propagates the panic. The panic is raised again when the memo is fetched.
+    ///
+    /// The panic is not memoized if it was raised by salsa itself, or if it unwound through
+    /// another query, whose dependencies are then missing.
+    fn memoize_panic(
+        &self,
+        db: &C::DbView,
+        active_query: ActiveQueryGuard<'_>,
+        opt_old_memo: Option<&Memo<C::Output<'_>>>,
+        payload: Box<dyn Any + Send>,
+    ) -> ! {
+        let is_salsa_panic = payload.is::<Cancelled>()
+            || payload.is::<Cycle>()
+            || payload.is::<InvalidKey>()
+            || payload.is::<Deadlock>()
+            || payload.is::<MissingView>();
+        if is_salsa_panic || zalsa_local::take_unwound_query() {
+            std::panic::resume_unwind(payload);
+        }
+
+        let database_key_index = active_query.database_key_index;
+        let id = database_key_index.key_index;
+        let mut completed = active_query.pop();
+        if let Some(old_memo) = opt_old_memo {
+            self.diff_outputs(db, database_key_index, old_memo, &mut completed.revisions);
+        }
+
+        let message =
+            panic_message(&*payload).unwrap_or_else(|| format!("{database_key_index:?} panicked"));
+        tracing::debug!("{database_key_index:?}: memoizing panic: {message:?}");
+        self.poisoned.insert(id, message);
+
+        let zalsa = db.zalsa();
+        let memo = self.insert_memo(
+            zalsa,
+            id,
+            Memo::new(None, zalsa.current_revision(), completed),
+        );
+        self.report_read_of(db, id, memo);
+        std::panic::resume_unwind(payload)
+    }
+}
+
+/// Returns the message of a panic, if its payload is a string.
+fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
 }
@@ -1,7 +1,10 @@
 use super::{memo::Memo, Configuration, IngredientImpl};
 use crate::{
-    accumulator::accumulated_map::InputAccumulatedValues, runtime::StampedValue,
-    zalsa::ZalsaDatabase, AsDynDatabase as _, Id,
+    accumulator::accumulated_map::InputAccumulatedValues,
+    runtime::StampedValue,
+    table::sync::{ClaimResult, SharedMemo},
+    zalsa::ZalsaDatabase,
+    AsDynDatabase as _, Id,
 };
 
 impl<C> IngredientImpl<C>
@@ -17,25 +20,54 @@ where
             value,
             durability,
             changed_at,
-        } = memo.revisions.stamped_value(memo.value.as_ref().unwrap());
+        } = memo.stamped_value();
 
         if let Some(evicted) = self.lru.record_use(id) {
-            self.evict_value_from_memo_for(zalsa, evicted);
+            self.evict_value_from_memo_for(db.as_dyn_database(), zalsa, evicted);
         }
 
         zalsa_local.report_tracked_read(
+            db.as_dyn_database(),
             self.database_key_index(id).into(),
             durability,
+            memo.durabilities(),
             changed_at,
             match &memo.revisions.accumulated {
                 Some(_) => InputAccumulatedValues::Any,
-                None => memo.revisions.accumulated_inputs.load(),
+                None => memo.accumulated_inputs(),
             },
         );
 
         value
     }
 
+    /// Records that the active query read `memo`, the memo of `id`.
+    pub(super) fn report_read_of(&self, db: &C::DbView, id: Id, memo: &Memo<C::Output<'_>>) {
+        db.zalsa_local().report_tracked_read(
+            db.as_dyn_database(),
+            self.database_key_index(id).into(),
+            memo.durability(),
+            memo.durabilities(),
+            memo.revisions.changed_at,
+            match &memo.revisions.accumulated {
+                Some(_) => InputAccumulatedValues::Any,
+                None => memo.accumulated_inputs(),
+            },
+        );
+    }
+
+    /// Raises the panic memoized for `id` again, after recording that the active query
+    /// read `memo`, the verified memo of `id`.
+    fn throw_poisoned(&self, db: &C::DbView, id: Id, memo: &Memo<C::Output<'_>>) -> ! {
+        let message = self
+            .poisoned
+            .get(&id)
+            .map(|message| message.clone())
+            .unwrap_or_default();
+        self.report_read_of(db, id, memo);
+        std::panic::resume_unwind(Box::new(message))
+    }
+
     #[inline]
     pub(super) fn refresh_memo<'db>(
         &'db self,
@@ -61,6 +93,12 @@ where
                 // still valid for the current revision.
                 return unsafe { Some(self.extend_memo_lifetime(memo)) };
             }
+            if memo.value.is_none()
+                && self.is_poisoned(id)
+                && self.shallow_verify_memo(db, zalsa, self.database_key_index(id), memo)
+            {
+                self.throw_poisoned(db, id, memo);
+            }
         }
         None
     }
@@ -69,13 +107,25 @@ where
         let (zalsa, zalsa_local) = db.zalsas();
         let database_key_index = self.database_key_index(id);
 
-        // Try to claim this query: if someone else has claimed it already, go back and start again.
-        let _claim_guard = zalsa.sync_table_for(id).claim(
+        // Try to claim this query: if someone else has claimed it already, use the memo
+        // they shared, if any, or go back and start again.
+        let claim_guard = match zalsa.sync_table_for(id).claim(
             db.as_dyn_database(),
             zalsa_local,
             database_key_index,
-            self.memo_ingredient_index,
-        )?;
+            self.memo_ingredient_index(zalsa, id),
+        ) {
+            ClaimResult::Claimed(claim_guard) => claim_guard,
+            // SAFETY: The memo was shared by this ingredient, for the same key, in the current
+            // revision (memos are only shared if `C::BROADCAST`). It was in the memo table when it
+            // was shared: if it was replaced since, the memo table moved it to `deleted_entries`
+            // (see `insert_memo_into_table_for`), which is only cleared by `reset_for_new_revision`.
+            // That requires `&mut` access to the database, which cannot be obtained while `db`
+            // is borrowed for `'db`.
+            ClaimResult::Released(shared_memo) => {
+                return shared_memo.map(|memo| unsafe { memo.as_ref() })
+            }
+        };
 
         // Push the query on the stack.
         let active_query = zalsa_local.push_query(database_key_index);
@@ -87,10 +137,27 @@ where
             if old_memo.value.is_some() && self.deep_verify_memo(db, old_memo, &active_query) {
                 // Unsafety invariant: memo is present in memo_map and we have verified that it is
                 // still valid for the current revision.
-                return unsafe { Some(self.extend_memo_lifetime(old_memo)) };
+                let memo = unsafe { self.extend_memo_lifetime(old_memo) };
+                if C::BROADCAST {
+                    claim_guard.share(SharedMemo::new(memo));
+                }
+                return Some(memo);
+            }
+            if old_memo.value.is_none()
+                && self.is_poisoned(id)
+                && self.deep_verify_memo(db, old_memo, &active_query)
+            {
+                // Release the claim and the frame first: they are not unwound.
+                drop(active_query);
+                drop(claim_guard);
+                self.throw_poisoned(db, id, old_memo);
             }
         }
 
-        Some(self.execute(db, active_query, opt_old_memo))
+        let memo = self.execute(db, active_query, opt_old_memo);
+        if C::BROADCAST {
+            claim_guard.share(SharedMemo::new(memo));
+        }
+        Some(memo)
     }
 }
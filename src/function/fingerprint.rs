@@ -0,0 +1,45 @@
+use crate::Id;
+
+use super::{Configuration, IngredientImpl};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Fetches the value of `id` and returns its fingerprint.
+    /// Only invoked if the function was declared with `fingerprint`.
+    pub fn fetch_fingerprint<'db>(&'db self, db: &'db C::DbView, id: Id) -> u128 {
+        let value = self.fetch(db, id);
+        if let Some(fingerprint) = self.fingerprints.get(&id) {
+            return *fingerprint;
+        }
+        let fingerprint = C::fingerprint(value)
+            .unwrap_or_else(|| panic!("`{}` has no `fingerprint`", C::DEBUG_NAME));
+        self.fingerprints.insert(id, fingerprint);
+        fingerprint
+    }
+
+    /// True if the new value of `id`, whose fingerprint is `new_fingerprint` if the function
+    /// was declared with `fingerprint`, should be considered equal to the old one.
+    ///
+    /// The fingerprint of the old value is the one recorded when it was memoized, if any,
+    /// so that the old value does not have to be fingerprinted again.
+    pub(super) fn values_equal(
+        &self,
+        id: Id,
+        old_value: &C::Output<'_>,
+        new_value: &C::Output<'_>,
+        new_fingerprint: Option<u128>,
+    ) -> bool {
+        match new_fingerprint {
+            Some(new_fingerprint) => {
+                let old_fingerprint = match self.fingerprints.get(&id) {
+                    Some(fingerprint) => Some(*fingerprint),
+                    None => C::fingerprint(old_value),
+                };
+                old_fingerprint == Some(new_fingerprint)
+            }
+            None => C::should_backdate_value(old_value, new_value),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use crate::{zalsa::Zalsa, Id, Revision};
+
+use super::{memo::ArcMemo, Configuration, IngredientImpl};
+
+/// How the value of a tracked function declared with `history = N` changed
+/// between two revisions, as returned by its `diff` function.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OutputDiff<'db, V> {
+    /// The value did not change between the two revisions.
+    Unchanged,
+
+    /// The value changed, possibly back to an equal value: `old` is the value
+    /// in the older revision, and `new` the one in the newer revision.
+    Changed { old: &'db V, new: &'db V },
+
+    /// The value in one of the revisions is not known, because the function was not
+    /// invoked in that revision or because the value is no longer retained.
+    Unknown,
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Retains the value of `old_memo`, which is being replaced, as one of the previous values of `id`.
+    pub(super) fn record_history(&self, id: Id, old_memo: &ArcMemo<'_, C>) {
+        if old_memo.value.is_none() {
+            return;
+        }
+
+        // SAFETY: The history only holds memos that were in the memo table. Memos removed
+        // from the history are moved to `deleted_entries`, like those removed from the table.
+        let old_memo =
+            unsafe { std::mem::transmute::<ArcMemo<'_, C>, ArcMemo<'static, C>>(old_memo.clone()) };
+        let mut history = self.history.entry(id).or_default();
+        if history
+            .back()
+            .is_some_and(|last| last.revisions.changed_at == old_memo.revisions.changed_at)
+        {
+            // A later verification of the same value, e.g., before its value was evicted.
+            let last = history.pop_back().unwrap();
+            self.deleted_entries.push(last);
+        }
+        history.push_back(old_memo);
+        while history.len() > C::HISTORY {
+            let oldest = history.pop_front().unwrap();
+            self.deleted_entries.push(oldest);
+        }
+    }
+
+    /// Forgets the previous values of `id`, whose memo was removed.
+    pub(super) fn clear_history(&self, id: Id) {
+        if let Some((_, history)) = self.history.remove(&id) {
+            for memo in history {
+                self.deleted_entries.push(memo);
+            }
+        }
+    }
+
+    /// Compares the value of `id` in revision `old_revision` with its value in `new_revision`.
+    pub fn diff<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+        old_revision: Revision,
+        new_revision: Revision,
+    ) -> OutputDiff<'db, C::Output<'db>> {
+        let (Some(old), Some(new)) = (
+            self.value_at(zalsa, id, old_revision),
+            self.value_at(zalsa, id, new_revision),
+        ) else {
+            return OutputDiff::Unknown;
+        };
+        if old.0 == new.0 {
+            OutputDiff::Unchanged
+        } else {
+            OutputDiff::Changed {
+                old: old.1,
+                new: new.1,
+            }
+        }
+    }
+
+    /// Returns the revision in which the value of `id` in revision `revision` last changed,
+    /// together with that value, if it is retained.
+    fn value_at<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+        revision: Revision,
+    ) -> Option<(Revision, &'db C::Output<'db>)> {
+        let covers = |memo: &ArcMemo<'_, C>| {
+            memo.value.is_some()
+                && memo.revisions.changed_at <= revision
+                && revision <= memo.verified_at()
+        };
+
+        let memo = match self.get_memo_from_table_for(zalsa, id) {
+            Some(memo) if covers(&memo) => memo,
+            _ => {
+                let history = self.history.get(&id)?;
+                let memo = history.iter().rev().find(|memo| covers(memo))?;
+                // SAFETY: The memo is only transmuted back from `'static` to `'db`.
+                unsafe { std::mem::transmute::<ArcMemo<'static, C>, ArcMemo<'db, C>>(memo.clone()) }
+            }
+        };
+
+        // SAFETY: The memo is in the memo table or in the history. Memos removed from either are
+        // moved to `deleted_entries`, which is only cleared with `&mut self`.
+        let memo = unsafe { self.extend_memo_lifetime(&memo) };
+        Some((memo.revisions.changed_at, memo.value.as_ref().unwrap()))
+    }
+}
+
+/// The previous values of each key, oldest first.
+pub(super) type History<C> = super::KeyMap<VecDeque<ArcMemo<'static, C>>>;
@@ -1,4 +1,4 @@
-use crate::{zalsa::Zalsa, zalsa_local::QueryOrigin, Id};
+use crate::{zalsa::Zalsa, zalsa_local::QueryOrigin, Database, DependencyInfo, Id};
 
 use super::{Configuration, IngredientImpl};
 
@@ -10,4 +10,16 @@ where
         self.get_memo_from_table_for(zalsa, key)
             .map(|m| m.revisions.origin.clone())
     }
+
+    /// Lists the direct dependencies recorded when the memoized value for `key` was computed,
+    /// in the order in which they were read. Returns an empty list if there is no memoized value
+    /// or if it was not computed by executing the function (e.g., it was specified).
+    ///
+    /// If the function read untracked state, the list is incomplete.
+    pub fn dependencies(&self, db: &dyn Database, key: Id) -> Vec<DependencyInfo> {
+        let Some(origin) = self.origin(db.zalsa(), key) else {
+            return vec![];
+        };
+        origin.inputs().map(|input| input.info(db)).collect()
+    }
 }
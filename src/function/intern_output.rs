@@ -0,0 +1,84 @@
+use std::{
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::hash::FxDashMap;
+
+/// Minimum number of values retained before unused values are purged.
+const MIN_PURGE_LEN: usize = 64;
+
+/// Deduplicates the outputs of a tracked function declared with `intern_output`,
+/// so that outputs that compare equal (across all keys) share a single allocation.
+///
+/// Values are only retained while a memo (or the user) still holds them:
+/// once the table has doubled in size, values that are only referenced
+/// by the table itself are dropped.
+pub struct OutputInterner<T> {
+    values: FxDashMap<Arc<T>, ()>,
+
+    /// Length of `values` at which unused values are purged.
+    purge_at: AtomicUsize,
+}
+
+impl<T> Default for OutputInterner<T>
+where
+    T: Hash + Eq,
+{
+    fn default() -> Self {
+        Self {
+            values: Default::default(),
+            purge_at: AtomicUsize::new(MIN_PURGE_LEN),
+        }
+    }
+}
+
+impl<T> OutputInterner<T>
+where
+    T: Hash + Eq + Send + Sync + 'static,
+{
+    fn intern(&self, value: Arc<T>) -> Arc<T> {
+        if let Some(existing) = self.values.get(&value) {
+            return existing.key().clone();
+        }
+
+        if self.values.len() >= self.purge_at.load(Ordering::Relaxed) {
+            self.values.retain(|value, ()| Arc::strong_count(value) > 1);
+            self.purge_at
+                .store(MIN_PURGE_LEN.max(self.values.len() * 2), Ordering::Relaxed);
+        }
+
+        self.values.entry(value).or_insert(()).key().clone()
+    }
+}
+
+impl<T> std::fmt::Debug for OutputInterner<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputInterner").finish_non_exhaustive()
+    }
+}
+
+/// Implemented by the types a tracked function declared with `intern_output` may return.
+pub trait InternOutput {
+    /// The table deduplicating values of this type.
+    type Interner: Default + Send + Sync + 'static;
+}
+
+impl<T> InternOutput for Arc<T>
+where
+    T: Hash + Eq + Send + Sync + 'static,
+{
+    type Interner = OutputInterner<T>;
+}
+
+/// Returns an `Arc` equal to `value` that may be shared with the outputs of other keys.
+/// Invoked by the generated code for `intern_output`.
+pub fn intern_output<T>(outputs: &OutputInterner<T>, value: Arc<T>) -> Arc<T>
+where
+    T: Hash + Eq + Send + Sync + 'static,
+{
+    outputs.intern(value)
+}
@@ -0,0 +1,47 @@
+use std::sync::OnceLock;
+
+use dashmap::mapref::{entry::Entry, one::Ref};
+
+use crate::hash::FxDashMap;
+use crate::Id;
+
+/// Data kept for some keys of a function, only allocated once the first entry is inserted.
+/// Most functions are declared without the options that need such data, so the
+/// maps of those functions stay empty.
+pub(super) struct KeyMap<V> {
+    map: OnceLock<FxDashMap<Id, V>>,
+}
+
+impl<V> Default for KeyMap<V> {
+    fn default() -> Self {
+        Self {
+            map: OnceLock::new(),
+        }
+    }
+}
+
+impl<V> KeyMap<V> {
+    fn map(&self) -> &FxDashMap<Id, V> {
+        self.map.get_or_init(Default::default)
+    }
+
+    pub(super) fn get(&self, id: &Id) -> Option<Ref<'_, Id, V>> {
+        self.map.get()?.get(id)
+    }
+
+    pub(super) fn contains_key(&self, id: &Id) -> bool {
+        self.map.get().is_some_and(|map| map.contains_key(id))
+    }
+
+    pub(super) fn insert(&self, id: Id, value: V) -> Option<V> {
+        self.map().insert(id, value)
+    }
+
+    pub(super) fn entry(&self, id: Id) -> Entry<'_, Id, V> {
+        self.map().entry(id)
+    }
+
+    pub(super) fn remove(&self, id: &Id) -> Option<(Id, V)> {
+        self.map.get()?.remove(id)
+    }
+}
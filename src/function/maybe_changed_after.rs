@@ -2,6 +2,8 @@ use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
     ingredient::MaybeChangedAfter,
     key::DatabaseKeyIndex,
+    stats::Stat,
+    table::sync::ClaimResult,
     zalsa::{Zalsa, ZalsaDatabase},
     zalsa_local::{ActiveQueryGuard, QueryEdge, QueryOrigin},
     AsDynDatabase as _, Id, Revision,
@@ -34,7 +36,7 @@ where
                     return if memo.revisions.changed_at > revision {
                         MaybeChangedAfter::Yes
                     } else {
-                        MaybeChangedAfter::No(memo.revisions.accumulated_inputs.load())
+                        MaybeChangedAfter::No(memo.accumulated_inputs())
                     };
                 }
                 drop(memo_guard); // release the arc-swap guard before cold path
@@ -59,12 +61,14 @@ where
         let (zalsa, zalsa_local) = db.zalsas();
         let database_key_index = self.database_key_index(key_index);
 
-        let _claim_guard = zalsa.sync_table_for(key_index).claim(
+        let ClaimResult::Claimed(_claim_guard) = zalsa.sync_table_for(key_index).claim(
             db.as_dyn_database(),
             zalsa_local,
             database_key_index,
-            self.memo_ingredient_index,
-        )?;
+            self.memo_ingredient_index(zalsa, key_index),
+        ) else {
+            return None;
+        };
         let active_query = zalsa_local.push_query(database_key_index);
 
         // Load the current memo, if any.
@@ -83,7 +87,7 @@ where
             return Some(if old_memo.revisions.changed_at > revision {
                 MaybeChangedAfter::Yes
             } else {
-                MaybeChangedAfter::No(old_memo.revisions.accumulated_inputs.load())
+                MaybeChangedAfter::No(old_memo.accumulated_inputs())
             });
         }
 
@@ -91,7 +95,10 @@ where
         // It is possible the result will be equal to the old value and hence
         // backdated. In that case, although we will have computed a new memo,
         // the value has not logically changed.
-        if old_memo.value.is_some() {
+        //
+        // Revalidation threads never execute queries: the verifying thread may not even
+        // read this one, if an earlier input of the memo it verifies changed.
+        if old_memo.value.is_some() && !zalsa_local.is_verify_only() {
             let memo = self.execute(db, active_query, Some(old_memo));
             let changed_at = memo.revisions.changed_at;
 
@@ -100,7 +107,7 @@ where
             } else {
                 MaybeChangedAfter::No(match &memo.revisions.accumulated {
                     Some(_) => InputAccumulatedValues::Any,
-                    None => memo.revisions.accumulated_inputs.load(),
+                    None => memo.accumulated_inputs(),
                 })
             });
         }
@@ -119,7 +126,7 @@ where
         database_key_index: DatabaseKeyIndex,
         memo: &Memo<C::Output<'_>>,
     ) -> bool {
-        let verified_at = memo.verified_at.load();
+        let verified_at = memo.verified_at();
         let revision_now = zalsa.current_revision();
 
         tracing::debug!(
@@ -127,11 +134,26 @@ where
             memo = memo.tracing_debug()
         );
 
+        if memo.is_invalidated() {
+            return false;
+        }
+
+        if zalsa.is_speculative_revision(verified_at) {
+            // Verified against inputs that were rolled back at the end of a speculation.
+            return false;
+        }
+
         if verified_at == revision_now {
             // Already verified.
+            self.stats.record(Stat::ShallowVerifyHit);
             return true;
         }
 
+        if self.is_stale(database_key_index.key_index) {
+            // The previous value was kept in an earlier revision: retry executing the function.
+            return false;
+        }
+
         if memo.check_durability(zalsa) {
             // No input of the suitable durability has changed since last verified.
             let db = db.as_dyn_database();
@@ -139,9 +161,10 @@ where
                 db,
                 revision_now,
                 database_key_index,
-                memo.revisions.accumulated_inputs.load(),
+                memo.accumulated_inputs(),
             );
             memo.mark_outputs_as_verified(db, database_key_index);
+            self.stats.record(Stat::ShallowVerifyHit);
             return true;
         }
 
@@ -174,6 +197,26 @@ where
             return true;
         }
 
+        if old_memo.is_invalidated() {
+            // Invalidated because it read some external state that changed.
+            return false;
+        }
+
+        if zalsa.is_speculative_revision(old_memo.verified_at()) {
+            // Its inputs may since have been restored to older values.
+            return false;
+        }
+
+        if self.is_stale(database_key_index.key_index) {
+            // Kept after a panic, the value is not derived from the recorded inputs.
+            return false;
+        }
+
+        if self.is_expired(zalsa, database_key_index.key_index) {
+            // Volatile and computed too many revisions ago.
+            return false;
+        }
+
         let inputs = match &old_memo.revisions.origin {
             QueryOrigin::Assigned(_) => {
                 // If the value was assigneed by another query,
@@ -191,12 +234,19 @@ where
             }
             QueryOrigin::BaseInput => {
                 // This value was `set` by the mutator thread -- ie, it's a base input and it cannot be out of date.
+                self.stats.record(Stat::DeepVerifyHit);
                 return true;
             }
             QueryOrigin::DerivedUntracked(_) => {
                 // Untracked inputs? Have to assume that it changed.
                 return false;
             }
+            QueryOrigin::Derived(_) if old_memo.check_durabilities(zalsa) => {
+                // Only inputs of durabilities this memo did not read have changed, so none
+                // of its inputs can have changed: there is no need to check them one by one.
+                old_memo.mark_outputs_as_verified(db.as_dyn_database(), database_key_index);
+                old_memo.accumulated_inputs()
+            }
             QueryOrigin::Derived(edges) => {
                 // Fully tracked inputs? Iterate over the inputs and check them, one by one.
                 //
@@ -204,9 +254,17 @@ where
                 // they executed. It's possible that if the value of some input I0 is no longer
                 // valid, then some later input I1 might never have executed at all, so verifying
                 // it is still up to date is meaningless.
-                let last_verified_at = old_memo.verified_at.load();
+                //
+                // Inputs are prefetched in parallel if enabled, but only until we return:
+                // once an input changed, the later ones need not be verified.
+                let last_verified_at = old_memo.verified_at();
+                let prefetch = zalsa
+                    .revalidation_pool()
+                    .as_ref()
+                    .filter(|_| !db.zalsa_local().is_verify_only())
+                    .map(|pool| pool.prefetch(db.as_dyn_database(), edges, last_verified_at));
                 let mut inputs = InputAccumulatedValues::Empty;
-                for &edge in edges.input_outputs.iter() {
+                for edge in edges.iter() {
                     match edge {
                         QueryEdge::Input(dependency_index) => {
                             match dependency_index
@@ -242,6 +300,9 @@ where
                         }
                     }
                 }
+                if let Some(prefetch) = prefetch {
+                    prefetch.finish();
+                }
                 inputs
             }
         };
@@ -252,6 +313,7 @@ where
             database_key_index,
             inputs,
         );
+        self.stats.record(Stat::DeepVerifyHit);
         true
     }
 }
@@ -1,15 +1,17 @@
 use std::any::Any;
 use std::fmt::Debug;
 use std::fmt::Formatter;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
-use crossbeam::atomic::AtomicCell;
-
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
-use crate::zalsa_local::QueryOrigin;
+use crate::durability::Durabilities;
+use crate::runtime::StampedValue;
+use crate::stats::Stat;
+use crate::zalsa_local::{CompletedQuery, QueryOrigin};
 use crate::{
-    key::DatabaseKeyIndex, zalsa::Zalsa, zalsa_local::QueryRevisions, Event, EventKind, Id,
-    Revision,
+    key::DatabaseKeyIndex, zalsa::Zalsa, zalsa_local::QueryRevisions, Database, Durability, Event,
+    EventKind, Id, Revision,
 };
 
 use super::{Configuration, IngredientImpl};
@@ -42,7 +44,7 @@ impl<C: Configuration> IngredientImpl<C> {
         let static_memo = unsafe { self.to_static(memo) };
         let old_static_memo = zalsa
             .memo_table_for(id)
-            .insert(self.memo_ingredient_index, static_memo)?;
+            .insert(self.memo_ingredient_index(zalsa, id), static_memo)?;
         unsafe { Some(self.to_self(old_static_memo)) }
     }
 
@@ -54,16 +56,24 @@ impl<C: Configuration> IngredientImpl<C> {
         zalsa: &'db Zalsa,
         id: Id,
     ) -> Option<ArcMemo<'db, C>> {
-        let static_memo = zalsa.memo_table_for(id).get(self.memo_ingredient_index)?;
+        let static_memo = zalsa
+            .memo_table_for(id)
+            .get(self.memo_ingredient_index(zalsa, id))?;
         unsafe { Some(self.to_self(static_memo)) }
     }
 
     /// Evicts the existing memo for the given key, replacing it
     /// with an equivalent memo that has no value. If the memo is untracked, BaseInput,
     /// or has values assigned as output of another query, this has no effect.
-    pub(super) fn evict_value_from_memo_for<'db>(&'db self, zalsa: &'db Zalsa, id: Id) {
+    pub(super) fn evict_value_from_memo_for<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        zalsa: &'db Zalsa,
+        id: Id,
+    ) {
+        let mut evicted = false;
         let old = zalsa.memo_table_for(id).map_memo::<Memo<C::Output<'_>>>(
-            self.memo_ingredient_index,
+            self.memo_ingredient_index(zalsa, id),
             |memo| {
                 match memo.revisions.origin {
                     QueryOrigin::Assigned(_)
@@ -76,37 +86,70 @@ impl<C: Configuration> IngredientImpl<C> {
                         memo
                     }
                     QueryOrigin::Derived(_) => {
+                        if memo.value.is_some() {
+                            self.stats.record(Stat::Eviction);
+                            evicted = true;
+                        }
+                        zalsa.evict_side_table_entries(self.database_key_index(id));
                         // QueryRevisions: !Clone to discourage cloning, we need it here though
-                        let &QueryRevisions {
+                        let QueryRevisions {
                             changed_at,
-                            durability,
-                            ref origin,
-                            ref tracked_struct_ids,
-                            ref accumulated,
-                            ref accumulated_inputs,
+                            origin,
+                            tracked_struct_ids,
+                            accumulated,
                         } = &memo.revisions;
                         // Re-assemble the memo but with the value set to `None`
-                        Arc::new(Memo::new(
-                            None,
-                            memo.verified_at.load(),
-                            QueryRevisions {
-                                changed_at,
-                                durability,
+                        Arc::new(Memo {
+                            value: None,
+                            state: AtomicMemoState::from_packed(memo.state.packed()),
+                            revisions: QueryRevisions {
+                                changed_at: *changed_at,
                                 origin: origin.clone(),
                                 tracked_struct_ids: tracked_struct_ids.clone(),
                                 accumulated: accumulated.clone(),
-                                accumulated_inputs: AtomicCell::new(accumulated_inputs.load()),
                             },
-                        ))
+                        })
                     }
                 }
             },
         );
         if let Some(old) = old {
+            if C::HISTORY != 0 && evicted {
+                // Retain the evicted value, as the memo remains but without value.
+                self.record_history(id, &old);
+            }
             // In case there is a reference to the old memo out there, we have to store it
             // in the deleted entries. This will get cleared when a new revision starts.
             self.deleted_entries.push(old);
         }
+        if evicted {
+            db.salsa_event(&|| {
+                Event::new(EventKind::DidEvictMemo {
+                    database_key: self.database_key_index(id),
+                })
+            });
+        }
+    }
+
+    /// Marks the memo for the given key as invalidated, so that it is re-executed when it
+    /// is next verified. Its value is kept, so that the new value can be backdated.
+    ///
+    /// Returns the durability of the memo, or `None` if there is no memo to invalidate
+    /// (including memos whose values were assigned as output of another query).
+    pub(super) fn invalidate_memo_for<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+    ) -> Option<Durability> {
+        let memo = self.get_memo_from_table_for(zalsa, id)?;
+        match memo.revisions.origin {
+            QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => None,
+            QueryOrigin::Derived(_) | QueryOrigin::DerivedUntracked(_) => {
+                zalsa.evict_side_table_entries(self.database_key_index(id));
+                memo.state.invalidate();
+                Some(memo.durability())
+            }
+        }
     }
 }
 
@@ -115,9 +158,9 @@ pub(super) struct Memo<V> {
     /// The result of the query, if we decide to memoize it.
     pub(super) value: Option<V>,
 
-    /// Last revision when this memo was verified; this begins
-    /// as the current revision.
-    pub(super) verified_at: AtomicCell<Revision>,
+    /// Last revision when this memo was verified (this begins as the current revision),
+    /// along with the durability and accumulated inputs of the memo.
+    state: AtomicMemoState,
 
     /// Revision information
     pub(super) revisions: QueryRevisions,
@@ -126,24 +169,86 @@ pub(super) struct Memo<V> {
 // Memo's are stored a lot, make sure their size is doesn't randomly increase.
 // #[cfg(test)]
 const _: [(); std::mem::size_of::<Memo<std::num::NonZeroUsize>>()] =
-    [(); std::mem::size_of::<[usize; 12]>()];
+    [(); std::mem::size_of::<[usize; 11]>()];
+
+// Values no larger than a word, like the `bool`s of predicate queries, are stored inline
+// in the memo, in the space of the memo itself: they need no allocation of their own.
+const _: [(); std::mem::size_of::<Memo<bool>>()] =
+    [(); std::mem::size_of::<Memo<std::num::NonZeroUsize>>()];
 
 impl<V> Memo<V> {
-    pub(super) fn new(value: Option<V>, revision_now: Revision, revisions: QueryRevisions) -> Self {
+    pub(super) fn new(value: Option<V>, revision_now: Revision, completed: CompletedQuery) -> Self {
+        let CompletedQuery {
+            revisions,
+            durability,
+            durabilities,
+            accumulated_inputs,
+        } = completed;
         Memo {
             value,
-            verified_at: AtomicCell::new(revision_now),
+            state: AtomicMemoState::new(revision_now, durability, durabilities, accumulated_inputs),
             revisions,
         }
     }
+
+    /// Last revision when this memo was verified.
+    pub(super) fn verified_at(&self) -> Revision {
+        self.state.load().0
+    }
+
+    /// Minimum durability of the inputs to this memo.
+    pub(super) fn durability(&self) -> Durability {
+        self.state.load().1
+    }
+
+    /// Durabilities of the inputs to this memo, including those read through other queries.
+    pub(super) fn durabilities(&self) -> Durabilities {
+        self.state.durabilities()
+    }
+
+    /// True if the memo was invalidated with [`IngredientImpl::invalidate`]
+    /// and must be re-executed.
+    pub(super) fn is_invalidated(&self) -> bool {
+        self.state.is_invalidated()
+    }
+
+    /// [`InputAccumulatedValues::Any`] if any input read by this memo has any direct or
+    /// indirect accumulated values, as of the last time the memo was verified.
+    pub(super) fn accumulated_inputs(&self) -> InputAccumulatedValues {
+        self.state.load().2
+    }
+
+    pub(super) fn stamped_value(&self) -> StampedValue<&V> {
+        StampedValue {
+            value: self.value.as_ref().unwrap(),
+            durability: self.durability(),
+            changed_at: self.revisions.changed_at,
+        }
+    }
+
     /// True if this memo is known not to have changed based on its durability.
     pub(super) fn check_durability(&self, zalsa: &Zalsa) -> bool {
-        let last_changed = zalsa.last_changed_revision(self.revisions.durability);
-        let verified_at = self.verified_at.load();
+        let (verified_at, durability, _) = self.state.load();
+        let last_changed = zalsa.last_changed_revision(durability);
         tracing::debug!(
             "check_durability(last_changed={:?} <= verified_at={:?}) = {:?}",
             last_changed,
-            self.verified_at,
+            verified_at,
+            last_changed <= verified_at,
+        );
+        last_changed <= verified_at
+    }
+
+    /// True if no input of the durabilities of the inputs to this memo has changed since it
+    /// was last verified: unlike [`check_durability`](Self::check_durability), this ignores
+    /// changes to inputs of higher durabilities than the minimum, which the memo did not read.
+    pub(super) fn check_durabilities(&self, zalsa: &Zalsa) -> bool {
+        let verified_at = self.verified_at();
+        let last_changed = zalsa.last_changed_revision_of(self.durabilities());
+        tracing::debug!(
+            "check_durabilities(last_changed={:?} <= verified_at={:?}) = {:?}",
+            last_changed,
+            verified_at,
             last_changed <= verified_at,
         );
         last_changed <= verified_at
@@ -163,9 +268,11 @@ impl<V> Memo<V> {
                 database_key: database_key_index,
             })
         });
+        for interceptor in db.zalsa().interceptors().iter() {
+            interceptor.did_validate(db, database_key_index);
+        }
 
-        self.verified_at.store(revision_now);
-        self.revisions.accumulated_inputs.store(accumulated);
+        self.state.store_verified(revision_now, accumulated);
     }
 
     pub(super) fn mark_outputs_as_verified(
@@ -194,7 +301,10 @@ impl<V> Memo<V> {
                             &"None"
                         },
                     )
-                    .field("verified_at", &self.memo.verified_at)
+                    .field("verified_at", &self.memo.verified_at())
+                    .field("durability", &self.memo.durability())
+                    .field("durabilities", &self.memo.durabilities())
+                    .field("accumulated_inputs", &self.memo.accumulated_inputs())
                     .field("revisions", &self.memo.revisions)
                     .finish()
             }
@@ -208,4 +318,128 @@ impl<V: Debug + Send + Sync + Any> crate::table::memo::Memo for Memo<V> {
     fn origin(&self) -> &QueryOrigin {
         &self.revisions.origin
     }
+
+    fn verified_at(&self) -> Revision {
+        Memo::verified_at(self)
+    }
+
+    fn changed_at(&self) -> Revision {
+        self.revisions.changed_at
+    }
+
+    fn has_value(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+/// The `verified_at` revision of a memo, packed into a single 64-bit word together with the
+/// durability, the input durabilities and the accumulated inputs of the memo, which would
+/// otherwise each take up a word of padding. This also lets verification update the
+/// revision and the accumulated inputs with a single store.
+///
+/// The word is 64 bits wide even on 32-bit targets, so that it always has room for
+/// every revision besides the other fields.
+///
+/// The lowest two bits hold the durability, the next bits the input durabilities, the next
+/// bit whether there are accumulated inputs, the next whether the memo was invalidated,
+/// and the remaining bits hold the revision.
+struct AtomicMemoState {
+    data: AtomicU64,
+}
+
+const DURABILITY_BITS: u32 = 2;
+const DURABILITY_MASK: u64 = (1 << DURABILITY_BITS) - 1;
+const ACCUMULATED_BIT: u64 = 1 << (DURABILITY_BITS + Durabilities::BITS);
+const INVALIDATED_BIT: u64 = ACCUMULATED_BIT << 1;
+const REVISION_SHIFT: u32 = DURABILITY_BITS + Durabilities::BITS + 2;
+
+impl AtomicMemoState {
+    fn new(
+        verified_at: Revision,
+        durability: Durability,
+        durabilities: Durabilities,
+        accumulated_inputs: InputAccumulatedValues,
+    ) -> Self {
+        Self::from_packed(
+            Self::pack(verified_at, durability, accumulated_inputs)
+                | durabilities.to_bits() << DURABILITY_BITS,
+        )
+    }
+
+    fn from_packed(data: u64) -> Self {
+        Self {
+            data: AtomicU64::new(data),
+        }
+    }
+
+    fn pack(
+        verified_at: Revision,
+        durability: Durability,
+        accumulated_inputs: InputAccumulatedValues,
+    ) -> u64 {
+        let revision = verified_at.as_usize() as u64;
+        assert!(
+            revision <= u64::MAX >> REVISION_SHIFT,
+            "revision {verified_at:?} is too large for a memo"
+        );
+        let accumulated = match accumulated_inputs {
+            InputAccumulatedValues::Empty => 0,
+            InputAccumulatedValues::Any => ACCUMULATED_BIT,
+        };
+        (revision << REVISION_SHIFT) | accumulated | durability.index() as u64
+    }
+
+    fn packed(&self) -> u64 {
+        self.data.load(Ordering::Acquire)
+    }
+
+    fn load(&self) -> (Revision, Durability, InputAccumulatedValues) {
+        let data = self.packed();
+        let accumulated_inputs = if data & ACCUMULATED_BIT != 0 {
+            InputAccumulatedValues::Any
+        } else {
+            InputAccumulatedValues::Empty
+        };
+        (
+            Revision::from((data >> REVISION_SHIFT) as usize),
+            Durability::from_index((data & DURABILITY_MASK) as usize),
+            accumulated_inputs,
+        )
+    }
+
+    fn durabilities(&self) -> Durabilities {
+        Durabilities::from_bits(self.packed() >> DURABILITY_BITS)
+    }
+
+    /// Updates the revision and accumulated inputs; the other fields of a memo never change.
+    fn store_verified(&self, verified_at: Revision, accumulated_inputs: InputAccumulatedValues) {
+        let data = self.packed();
+        let durability = Durability::from_index((data & DURABILITY_MASK) as usize);
+        let kept = INVALIDATED_BIT | ((ACCUMULATED_BIT - 1) & !DURABILITY_MASK);
+        self.data.store(
+            Self::pack(verified_at, durability, accumulated_inputs) | (data & kept),
+            Ordering::Release,
+        );
+    }
+
+    fn invalidate(&self) {
+        self.data.fetch_or(INVALIDATED_BIT, Ordering::AcqRel);
+    }
+
+    fn is_invalidated(&self) -> bool {
+        self.packed() & INVALIDATED_BIT != 0
+    }
+}
+
+impl Debug for AtomicMemoState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (verified_at, durability, accumulated_inputs) = self.load();
+        f.debug_struct("AtomicMemoState")
+            .field("verified_at", &verified_at)
+            .field("durability", &durability)
+            .field("durabilities", &self.durabilities())
+            .field("accumulated_inputs", &accumulated_inputs)
+            .field("invalidated", &self.is_invalidated())
+            .finish()
+    }
 }
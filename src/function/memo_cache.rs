@@ -0,0 +1,43 @@
+use crate::{zalsa::ZalsaDatabase, Id, MemoCacheKey};
+
+use super::{Configuration, IngredientImpl};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Returns the key of `id` in the memo cache backend, if the database has a backend
+    /// and the function was declared with `cache_key`.
+    ///
+    /// Invokes the fingerprint function given by the user, whose reads are recorded
+    /// as dependencies of the active query.
+    pub(super) fn memo_cache_key(&self, db: &C::DbView, id: Id) -> Option<MemoCacheKey> {
+        db.zalsa().memo_cache()?;
+        let fingerprint = C::cache_fingerprint(db, C::id_to_input(db, id))?;
+        Some(MemoCacheKey {
+            function: C::DEBUG_NAME,
+            fingerprint,
+        })
+    }
+
+    /// Loads the value stored for `key` in the memo cache backend, if any.
+    pub(super) fn load_cached<'db>(
+        &self,
+        db: &'db C::DbView,
+        key: &MemoCacheKey,
+    ) -> Option<C::Output<'db>> {
+        let bytes = db.zalsa().memo_cache()?.load(key)?;
+        let value = C::decode_cached(&bytes);
+        if value.is_none() {
+            tracing::warn!("{key:?}: could not decode the value loaded from the memo cache");
+        }
+        value
+    }
+
+    /// Stores `value` for `key` in the memo cache backend.
+    pub(super) fn store_cached(&self, db: &C::DbView, key: &MemoCacheKey, value: &C::Output<'_>) {
+        if let Some(backend) = db.zalsa().memo_cache() {
+            backend.store(key, C::encode_cached(value));
+        }
+    }
+}
@@ -1,10 +1,9 @@
-use crossbeam::atomic::AtomicCell;
-
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
+    durability::Durabilities,
     tracked_struct::TrackedStructInDb,
     zalsa::ZalsaDatabase,
-    zalsa_local::{QueryOrigin, QueryRevisions},
+    zalsa_local::{CompletedQuery, QueryOrigin, QueryRevisions},
     AsDynDatabase as _, Database, DatabaseKeyIndex, Id,
 };
 
@@ -65,25 +64,41 @@ where
         // - a result that is NOT verified and has untracked inputs, which will re-execute (and likely panic)
 
         let revision = db.zalsa().current_revision();
-        let mut revisions = QueryRevisions {
-            changed_at: current_deps.changed_at,
+        let mut completed = CompletedQuery {
+            revisions: QueryRevisions {
+                changed_at: current_deps.changed_at,
+                origin: QueryOrigin::Assigned(active_query_key),
+                tracked_struct_ids: Default::default(),
+                accumulated: Default::default(),
+            },
             durability: current_deps.durability,
-            origin: QueryOrigin::Assigned(active_query_key),
-            tracked_struct_ids: Default::default(),
-            accumulated: Default::default(),
+            durabilities: Durabilities::at_least(current_deps.durability),
             accumulated_inputs: Default::default(),
         };
 
+        let fingerprint = C::fingerprint(&value);
         if let Some(old_memo) = self.get_memo_from_table_for(zalsa, key) {
-            self.backdate_if_appropriate(&old_memo, &mut revisions, &value);
-            self.diff_outputs(db, database_key_index, &old_memo, &mut revisions);
+            self.backdate_if_appropriate(
+                key,
+                &old_memo,
+                &mut completed,
+                &value,
+                fingerprint,
+                revision,
+            );
+            self.diff_outputs(
+                db,
+                self.database_key_index(key),
+                &old_memo,
+                &mut completed.revisions,
+            );
         }
 
-        let memo = Memo {
-            value: Some(value),
-            verified_at: AtomicCell::new(revision),
-            revisions,
-        };
+        if let Some(fingerprint) = fingerprint {
+            self.fingerprints.insert(key, fingerprint);
+        }
+
+        let memo = Memo::new(Some(value), revision, completed);
 
         tracing::debug!(
             "specify: about to add memo {:#?} for key {:?}",
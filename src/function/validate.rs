@@ -0,0 +1,40 @@
+use crate::{Database, Id, InvalidKey, InvalidKeyReason};
+
+use super::{Configuration, IngredientImpl};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Checks that `key` refers to an existing entry of the salsa struct(s) whose entries are
+    /// the keys of this function, unwinding with [`InvalidKey`] otherwise.
+    ///
+    /// Invoked before fetching by tracked functions declared with `validate_args`, so that
+    /// foreign keys are reported before they reach `id_to_input` or the memo tables.
+    pub fn validate_key(&self, db: &dyn Database, key: Id) {
+        let zalsa = db.zalsa();
+        let (struct_index, _) = self.memo_ingredient_indices[0];
+        let result = match zalsa.table().check_id(key, struct_index) {
+            // The key may be an entry of another variant of a `Supertype` enum.
+            Err(InvalidKeyReason::WrongIngredient { found, .. })
+                if self
+                    .memo_ingredient_indices
+                    .iter()
+                    .any(|&(index, _)| index == found) =>
+            {
+                zalsa.table().check_id(key, found)
+            }
+            result => result,
+        };
+        if let Err(reason) = result {
+            let function = C::DEBUG_NAME;
+            tracing::debug!("{function}: invalid key {key:?} ({reason:?})");
+            InvalidKey {
+                function,
+                id: key,
+                reason,
+            }
+            .throw();
+        }
+    }
+}
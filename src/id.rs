@@ -2,6 +2,8 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::num::NonZeroU32;
 
+use crate::Database;
+
 /// The `Id` of a salsa struct in the database [`Table`](`crate::table::Table`).
 ///
 /// The higher-order bits of an `Id` identify a [`Page`](`crate::table::Page`)
@@ -61,6 +63,18 @@ pub trait FromId: AsId + Copy + Eq + Hash + Debug {
     fn from_id(id: Id) -> Self;
 }
 
+/// Internal Salsa trait for types that can be created from an [`Id`][] given the database,
+/// such as `Supertype` enums, whose variant depends on the struct that allocated the id.
+pub trait FromIdWithDb {
+    fn from_id(id: Id, db: &dyn Database) -> Self;
+}
+
+impl<T: FromId> FromIdWithDb for T {
+    fn from_id(id: Id, _db: &dyn Database) -> Self {
+        FromId::from_id(id)
+    }
+}
+
 impl AsId for Id {
     fn as_id(&self) -> Id {
         *self
@@ -8,7 +8,7 @@ use crate::{
     cycle::CycleRecoveryStrategy,
     zalsa::{IngredientIndex, MemoIngredientIndex},
     zalsa_local::QueryOrigin,
-    Database, DatabaseKeyIndex, Id,
+    Database, DatabaseKeyIndex, Durability, Id, Runtime,
 };
 
 use super::Revision;
@@ -26,6 +26,10 @@ pub trait Jar: Any {
 
     /// If this jar's first ingredient is a salsa struct, return its `TypeId`
     fn salsa_struct_type_id(&self) -> Option<TypeId>;
+
+    /// Returns a copy of this jar, kept by the database to create the same ingredients
+    /// in the databases created from it with [`Storage::new_from`](`crate::Storage::new_from`).
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync>;
 }
 
 /// Methods on the Salsa database available to jars while they are creating their ingredients.
@@ -50,11 +54,20 @@ pub trait JarAux {
         struct_ingredient_index: IngredientIndex,
         ingredient_index: IngredientIndex,
     ) -> MemoIngredientIndex;
+
+    /// Returns the LRU capacity of tracked functions that do not set one with the `lru` option.
+    fn default_lru_capacity(&self) -> usize;
 }
 
 pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     fn debug_name(&self) -> &'static str;
 
+    /// Debug name of the salsa struct whose field this ingredient stores, if any.
+    /// Qualifies the field name in [`DatabaseKeyIndex::to_stable_string`].
+    fn owner_debug_name(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Has the value for `input` in this ingredient changed after `revision`?
     fn maybe_changed_after<'db>(
         &'db self,
@@ -66,6 +79,14 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     /// What were the inputs (if any) that were used to create the value at `key_index`.
     fn origin(&self, db: &dyn Database, key_index: Id) -> Option<QueryOrigin>;
 
+    /// The [`Fingerprint`](`crate::Fingerprint`) of the value at `key_index`, if it has one.
+    ///
+    /// In practice, returns `Some` only for fields of inputs declared with `fingerprint`.
+    fn fingerprint(&self, db: &dyn Database, key_index: Id) -> Option<u128> {
+        _ = (db, key_index);
+        None
+    }
+
     /// What values were accumulated during the creation of the value at `key_index`
     /// (if any).
     ///
@@ -100,9 +121,33 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
         stale_output_key: Id,
     );
 
+    /// Invoked when the memo of `key_index` was discarded because the struct it belongs
+    /// to was retired or deleted, to forget anything else this ingredient keeps about it.
+    fn discard_key(&self, key_index: Id) {
+        let _ = key_index;
+    }
+
     /// Returns the [`IngredientIndex`] of this ingredient.
     fn ingredient_index(&self) -> IngredientIndex;
 
+    /// What kind of ingredient this is, as reported by [`Database::ingredients`].
+    fn kind(&self) -> IngredientKind;
+
+    /// True if `key_index` was retired or deleted, so that handles to it must no longer be
+    /// used; see [`StaleHandle`](`crate::StaleHandle`).
+    ///
+    /// In practice, returns `true` only for retired inputs and deleted tracked structs.
+    fn is_stale_handle(&self, db: &dyn Database, key_index: Id) -> bool {
+        _ = (db, key_index);
+        false
+    }
+
+    /// The number of keys with a memoized value, if this ingredient memoizes values.
+    fn memo_count(&self, db: &dyn Database) -> Option<usize> {
+        let _ = db;
+        None
+    }
+
     /// If this ingredient is a participant in a cycle, what is its cycle recovery strategy?
     /// (Really only relevant to [`crate::function::FunctionIngredient`],
     /// since only function ingredients push themselves onto the active query stack.)
@@ -125,6 +170,108 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     fn reset_for_new_revision(&mut self);
 
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Marks the fields of all entries of this input ingredient whose durability is at most
+    /// `durability` as changed in the current revision.
+    /// Invoked by [`Database::synthetic_write_for`](`crate::Database::synthetic_write_for`).
+    fn synthetic_write(&mut self, runtime: &mut Runtime, durability: Durability) {
+        let _ = (runtime, durability);
+        panic!(
+            "unexpected call to `synthetic_write`: `{}` is not an input",
+            self.debug_name()
+        )
+    }
+
+    /// Deletes the entries of this ingredient that were created by tracked functions,
+    /// as the memos that created them were discarded.
+    /// Invoked by [`Database::discard_memos`](`crate::Database::discard_memos`).
+    fn discard_entries(&mut self, runtime: &mut Runtime) {
+        let _ = runtime;
+    }
+
+    /// Copies the state of `old`, the same ingredient in the database this one's was created
+    /// from, once the table of `runtime` received the copies of its entries.
+    /// Invoked by [`Storage::new_from`](`crate::Storage::new_from`).
+    fn copy_from(&mut self, old: &dyn Ingredient, runtime: &Runtime) {
+        let _ = (old, runtime);
+    }
+
+    /// Returns the counters of this ingredient, if it keeps any.
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> Option<crate::IngredientStats> {
+        None
+    }
+
+    /// Records that a thread was blocked for `duration` on another thread
+    /// executing or verifying a value of this ingredient.
+    #[cfg(feature = "stats")]
+    fn record_blocked(&self, duration: std::time::Duration) {
+        _ = duration;
+    }
+}
+
+/// The kind of an ingredient, see [`IngredientInfo`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IngredientKind {
+    /// The entries of a `#[salsa::input]` struct.
+    Input,
+
+    /// A field of a `#[salsa::input]` struct.
+    InputField,
+
+    /// The entries of a `#[salsa::interned]` struct.
+    Interned,
+
+    /// The entries of a `#[salsa::tracked]` struct.
+    TrackedStruct,
+
+    /// A tracked field of a `#[salsa::tracked]` struct.
+    TrackedField,
+
+    /// The memoized values of a `#[salsa::tracked]` function.
+    TrackedFunction,
+
+    /// The values accumulated with a `#[salsa::accumulator]`.
+    Accumulator,
+
+    /// The entries of an [`Entries`](`crate::Entries`) map.
+    Entries,
+
+    /// An ingredient implemented outside of salsa, see
+    /// [`ExternalIngredient`](`crate::plumbing::external::ExternalIngredient`).
+    External,
+}
+
+/// Describes an ingredient registered in a database, as returned by
+/// [`Database::ingredients`](`crate::Database::ingredients`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngredientInfo {
+    pub ingredient_index: IngredientIndex,
+
+    /// The name of the struct, field, function or accumulator.
+    pub debug_name: &'static str,
+
+    /// The name of the struct a field belongs to, for fields.
+    pub owner_debug_name: Option<&'static str>,
+
+    pub kind: IngredientKind,
+
+    /// The number of keys with a memoized value, for tracked functions.
+    pub memos: Option<usize>,
+}
+
+impl IngredientInfo {
+    pub(crate) fn new(db: &dyn Database, ingredient: &dyn Ingredient) -> Self {
+        Self {
+            ingredient_index: ingredient.ingredient_index(),
+            debug_name: ingredient.debug_name(),
+            owner_debug_name: ingredient.owner_debug_name(),
+            kind: ingredient.kind(),
+            memos: ingredient.memo_count(db),
+        }
+    }
 }
 
 impl dyn Ingredient {
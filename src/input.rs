@@ -1,7 +1,12 @@
 use std::{
     any::{Any, TypeId},
     fmt,
+    hash::Hash,
     ops::DerefMut,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
 };
 
 pub mod input_field;
@@ -9,19 +14,24 @@ pub mod setter;
 pub mod singleton;
 
 use input_field::FieldIngredientImpl;
+use smallvec::SmallVec;
 
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
     cycle::CycleRecoveryStrategy,
+    durability::Durabilities,
+    event::{Event, EventKind},
+    hash::FxDashMap,
     id::{AsId, FromId},
-    ingredient::{fmt_index, Ingredient, MaybeChangedAfter},
+    ingredient::{fmt_index, Ingredient, IngredientKind, MaybeChangedAfter},
     input::singleton::{Singleton, SingletonChoice},
     key::{DatabaseKeyIndex, InputDependencyIndex},
     plumbing::{Jar, JarAux, Stamp},
+    runtime::stamp,
     table::{memo::MemoTable, sync::SyncTable, Slot, Table},
     zalsa::{IngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Database, Durability, Id, Revision, Runtime,
+    Database, Durability, Id, Revision, Runtime, StaleHandle,
 };
 
 pub trait Configuration: Any {
@@ -38,11 +48,76 @@ pub trait Configuration: Any {
     type Fields: Send + Sync;
 
     /// A array of [`StampedValue<()>`](`StampedValue`) tuples, one per each of the value fields.
-    type Stamps: Send + Sync + fmt::Debug + DerefMut<Target = [Stamp]>;
+    type Stamps: Send + Sync + Clone + fmt::Debug + DerefMut<Target = [Stamp]>;
+
+    /// Indices of the fields marked `#[index]`, by which inputs can be looked up.
+    const INDEXED_FIELDS: &'static [usize];
+
+    /// Hashes the field `field_index`, which must be one of the `INDEXED_FIELDS`.
+    fn hash_field(fields: &Self::Fields, field_index: usize) -> u64;
+
+    /// Clones the fields, so that they can be restored after a [speculation](`Database::speculate`).
+    /// Returns `None` unless the input was declared with `#[salsa::input(speculate)]`.
+    fn clone_fields(fields: &Self::Fields) -> Option<Self::Fields>;
+
+    /// Clones the fields for a database created from this one, see [`Storage::new_from`](`crate::Storage::new_from`).
+    /// Returns `None` if some field does not implement `Clone`.
+    fn fork_fields(fields: &Self::Fields) -> Option<Self::Fields>;
+
+    /// Returns the [`Fingerprint`](`crate::Fingerprint`) of the field `field_index`,
+    /// or `None` unless the input was declared with `#[salsa::input(fingerprint)]`.
+    fn fingerprint_field(fields: &Self::Fields, field_index: usize) -> Option<u128>;
+}
+
+/// This is used by the macro generated code to implement [`Configuration::fork_fields`].
+/// Clones a field if its type implements `Clone`, and else returns `None`.
+///
+/// To use:
+///
+/// ```rust,ignore
+/// use crate::input::fork_helper::Fallback;
+/// input::fork_helper::Dispatch::<$ty>::maybe_clone(field);
+/// ```
+///
+/// It is important that you specify the `$ty` explicitly.
+///
+/// This uses the same ["method dispatch hack"](https://github.com/nvzqz/impls#how-it-works)
+/// as [`crate::update::helper`].
+pub mod fork_helper {
+    use std::marker::PhantomData;
+
+    pub struct Dispatch<D>(PhantomData<D>);
+
+    impl<D> Dispatch<D>
+    where
+        D: Clone,
+    {
+        pub fn maybe_clone(value: &D) -> Option<D> {
+            Some(value.clone())
+        }
+    }
+
+    pub trait Fallback<T> {
+        fn maybe_clone(value: &T) -> Option<T>;
+    }
+
+    impl<T> Fallback<T> for Dispatch<T> {
+        fn maybe_clone(_value: &T) -> Option<T> {
+            None
+        }
+    }
 }
 
+/// Loads the value of a `#[lazy]` field of type `T` when it is first read,
+/// along with the durability to record it with.
+pub type Loader<C, T> =
+    Box<dyn Fn(&dyn Database, <C as Configuration>::Struct) -> (T, Durability) + Send + Sync>;
+
+/// The value of a `#[lazy]` field produced by its [`Loader`].
+type LoadedValue = Box<dyn Any + Send + Sync>;
+
 pub struct JarImpl<C: Configuration> {
-    _phantom: std::marker::PhantomData<C>,
+    _phantom: std::marker::PhantomData<fn() -> C>,
 }
 
 impl<C: Configuration> Default for JarImpl<C> {
@@ -71,11 +146,23 @@ impl<C: Configuration> Jar for JarImpl<C> {
     fn salsa_struct_type_id(&self) -> Option<std::any::TypeId> {
         Some(TypeId::of::<<C as Configuration>::Struct>())
     }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self::default())
+    }
 }
 
 pub struct IngredientImpl<C: Configuration> {
     ingredient_index: IngredientIndex,
     singleton: C::Singleton,
+
+    /// Maps an indexed field and the hash of its value to the inputs having that value.
+    index: FxDashMap<(usize, u64), SmallVec<[Id; 1]>>,
+
+    /// The [`Loader`] registered for each `#[lazy]` field, by field index.
+    /// Shared with the databases created from this one.
+    loaders: Vec<Option<Arc<dyn Any + Send + Sync>>>,
+
     _phantom: std::marker::PhantomData<C::Struct>,
 }
 
@@ -84,6 +171,8 @@ impl<C: Configuration> IngredientImpl<C> {
         Self {
             ingredient_index: index,
             singleton: Default::default(),
+            index: Default::default(),
+            loaders: (0..C::FIELD_DEBUG_NAMES.len()).map(|_| None).collect(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -96,6 +185,22 @@ impl<C: Configuration> IngredientImpl<C> {
         table.get_raw(id)
     }
 
+    /// Returns the fields of `id`.
+    ///
+    /// # Panics
+    ///
+    /// If the input was retired.
+    fn live_fields(value: &Value<C>, id: Id) -> &C::Fields {
+        match value.live_fields() {
+            Some(fields) => fields,
+            None => StaleHandle {
+                struct_name: C::DEBUG_NAME,
+                id,
+            }
+            .throw(),
+        }
+    }
+
     pub fn database_key_index(&self, id: C::Struct) -> DatabaseKeyIndex {
         DatabaseKeyIndex {
             ingredient_index: self.ingredient_index,
@@ -103,21 +208,183 @@ impl<C: Configuration> IngredientImpl<C> {
         }
     }
 
-    pub fn new_input(&self, db: &dyn Database, fields: C::Fields, stamps: C::Stamps) -> C::Struct {
+    /// Creates a new input. The `#[lazy]` fields for which `unset` is true are loaded
+    /// when they are first read.
+    pub fn new_input(
+        &self,
+        db: &dyn Database,
+        fields: C::Fields,
+        stamps: C::Stamps,
+        unset: &[bool],
+    ) -> C::Struct {
         let (zalsa, zalsa_local) = db.zalsas();
+        let lazy: Box<[_]> = if unset.contains(&true) {
+            unset
+                .iter()
+                .map(|&unset| unset.then(OnceLock::new))
+                .collect()
+        } else {
+            Box::default()
+        };
 
         let id = self.singleton.with_lock(|| {
             zalsa_local.allocate(zalsa.table(), self.ingredient_index, |_| Value::<C> {
-                fields,
+                fields: Some(fields),
                 stamps,
+                lazy,
+                retired: AtomicBool::new(false),
                 memos: Default::default(),
                 syncs: Default::default(),
             })
         });
 
+        let fields = Self::live_fields(Self::data(zalsa, id), id);
+        for &field_index in C::INDEXED_FIELDS {
+            self.index_field(id, field_index, fields);
+        }
+        zalsa.record_speculative_input(self.ingredient_index, id, |db, id| {
+            Self::retire(db, FromId::from_id(id))
+        });
+
         FromId::from_id(id)
     }
 
+    fn index_field(&self, id: Id, field_index: usize, fields: &C::Fields) {
+        self.index
+            .entry((field_index, C::hash_field(fields, field_index)))
+            .or_default()
+            .push(id);
+    }
+
+    fn unindex_field(&self, id: Id, field_index: usize, fields: &C::Fields) {
+        let key = (field_index, C::hash_field(fields, field_index));
+        self.index.remove_if_mut(&key, |_, ids| {
+            ids.retain(|&mut other| other != id);
+            ids.is_empty()
+        });
+    }
+
+    /// Returns an input whose field `field_index`, one of the `#[index]` fields, equals `value`.
+    ///
+    /// `field` selects that field from the fields tuple.
+    pub fn find<T: Hash + PartialEq>(
+        &self,
+        db: &dyn Database,
+        field_index: usize,
+        value: &T,
+        field: impl Fn(&C::Fields) -> &T,
+    ) -> Option<C::Struct> {
+        db.report_untracked_read();
+        let zalsa = db.zalsa();
+        let ids = self.index.get(&(field_index, crate::hash::hash(value)))?;
+        ids.iter()
+            .copied()
+            .find(|&id| field(Self::live_fields(Self::data(zalsa, id), id)) == value)
+            .map(FromId::from_id)
+    }
+
+    /// Returns all inputs created so far, in no particular order.
+    ///
+    /// This does not record a dependency on the inputs created later.
+    pub fn all<'db>(&'db self, db: &'db dyn Database) -> impl Iterator<Item = C::Struct> + 'db {
+        let zalsa = db.zalsa();
+        zalsa
+            .table()
+            .ids(self.ingredient_index)
+            .filter(move |&id| !Self::data(zalsa, id).is_retired())
+            .map(C::Struct::from_id)
+    }
+
+    /// Retires the input `id`: its fields are dropped and the functions that read them are
+    /// invalidated. Reading a field through a handle to the input panics from then on.
+    ///
+    /// The memoized values of tracked functions taking this input are discarded,
+    /// together with the tracked structs they created.
+    ///
+    /// The id is not reused for new inputs, as ids carry no generation that would tell
+    /// handles to the retired input apart from handles to the new one.
+    ///
+    /// # Panics
+    ///
+    /// If the input was already retired.
+    pub fn retire(db: &mut dyn Database, id: C::Struct) {
+        let id = id.as_id();
+        let zalsa_mut = db.zalsa_mut();
+        let index = zalsa_mut.add_or_lookup_jar_by_type(&<JarImpl<C>>::default());
+        let (ingredient, runtime) = zalsa_mut.lookup_ingredient_mut(index);
+        let memo_table = ingredient
+            .assert_type_mut::<Self>()
+            .mark_retired(runtime, id);
+
+        let db: &dyn Database = db;
+        let zalsa = db.zalsa();
+        let ingredient = zalsa.lookup_ingredient(index).assert_type::<Self>();
+        zalsa.evict_side_table_entries(ingredient.database_key_index(FromId::from_id(id)));
+        for (memo_ingredient_index, memo) in memo_table.into_memos() {
+            let ingredient_index = zalsa.ingredient_index_for_memo(index, memo_ingredient_index);
+
+            let executor = DatabaseKeyIndex {
+                ingredient_index,
+                key_index: id,
+            };
+
+            db.salsa_event(&|| Event::new(EventKind::DidDiscard { key: executor }));
+            zalsa.evict_side_table_entries(executor);
+            zalsa.lookup_ingredient(ingredient_index).discard_key(id);
+
+            for stale_output in memo.origin().outputs() {
+                stale_output.remove_stale_output(db, executor);
+            }
+        }
+    }
+
+    /// Marks the input `id` as retired, invalidating the reads of its fields,
+    /// and returns its memos.
+    fn mark_retired(&mut self, runtime: &mut Runtime, id: Id) -> MemoTable {
+        assert!(
+            runtime.speculation_mut().is_none(),
+            "cannot retire input `{id:?}` within `Database::speculate`"
+        );
+
+        let r = Self::data_raw(runtime.table(), id);
+
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        // Also, we don't access any other data from the table while `r` is active.
+        let r = unsafe { &mut *r };
+
+        let fields = Self::live_fields(r, id);
+        for &field_index in C::INDEXED_FIELDS {
+            self.unindex_field(id, field_index, fields);
+        }
+
+        for (field_index, stamp) in r.stamps.iter_mut().enumerate() {
+            runtime.report_tracked_write(stamp.durability);
+            stamp.changed_at = runtime.current_revision();
+            runtime.record_input_change(
+                DatabaseKeyIndex {
+                    ingredient_index: self.ingredient_index.successor(field_index),
+                    key_index: id,
+                },
+                stamp.durability,
+            );
+        }
+
+        if self.singleton.index() == Some(id) {
+            self.singleton.clear();
+        }
+
+        r.fields = None;
+        r.lazy = Box::default();
+        r.retired.store(true, Ordering::Release);
+        std::mem::take(&mut r.memos)
+    }
+
+    /// True if the input `id` was retired.
+    #[cfg(feature = "fallible_v1")]
+    pub(crate) fn is_retired(&self, runtime: &Runtime, id: C::Struct) -> bool {
+        runtime.table().get::<Value<C>>(id.as_id()).is_retired()
+    }
+
     /// Change the value of the field `field_index` to a new value.
     ///
     /// # Parameters
@@ -126,14 +393,16 @@ impl<C: Configuration> IngredientImpl<C> {
     /// * `id`, id of the input struct
     /// * `field_index`, index of the field that will be changed
     /// * `durability`, durability of the new value. If omitted, uses the durability of the previous value.
-    /// * `setter`, function that modifies the fields tuple; should only modify the element for `field_index`
+    /// * `setter`, function that modifies the fields tuple; should only modify the element for `field_index`.
+    ///   For a `#[lazy]` field that was loaded, it is also passed the loaded value, which it should
+    ///   store in the tuple before modifying it.
     pub fn set_field<R>(
         &mut self,
         runtime: &mut Runtime,
         id: C::Struct,
         field_index: usize,
         durability: Option<Durability>,
-        setter: impl FnOnce(&mut C::Fields) -> R,
+        setter: impl FnOnce(&mut C::Fields, Option<LoadedValue>) -> R,
     ) -> R {
         let id: Id = id.as_id();
         let r = Self::data_raw(runtime.table(), id);
@@ -142,15 +411,85 @@ impl<C: Configuration> IngredientImpl<C> {
         // Also, we don't access any other data from the table while `r` is active.
         let r = unsafe { &mut *r };
 
-        let stamp = &mut r.stamps[field_index];
+        assert!(
+            !r.is_retired(),
+            "cannot set a field of retired input `{id:?}`"
+        );
 
-        if stamp.durability != Durability::MIN {
-            runtime.report_tracked_write(stamp.durability);
+        if let Some(speculation) = runtime.speculation_mut() {
+            if speculation.needs_undo(self.ingredient_index, id) {
+                let fields = C::clone_fields(r.fields.as_ref().unwrap()).unwrap_or_else(|| {
+                    panic!(
+                        "cannot set a field of `{}` within `Database::speculate`: \
+                         the input must be declared with `#[salsa::input(speculate)]`",
+                        C::DEBUG_NAME
+                    )
+                });
+                let stamps = r.stamps.clone();
+                speculation.record_undo(
+                    self.ingredient_index,
+                    id,
+                    Box::new(move |ingredient, runtime| {
+                        ingredient
+                            .assert_type_mut::<Self>()
+                            .restore(runtime, id, fields, stamps)
+                    }),
+                );
+            }
         }
+        // Once set, a `#[lazy]` field is an ordinary field: its loaded value, if any,
+        // moves into the tuple, and its stamp replaces the placeholder one.
+        let loaded = r
+            .lazy
+            .get_mut(field_index)
+            .and_then(Option::take)
+            .and_then(OnceLock::into_inner);
+        let stamp = &mut r.stamps[field_index];
+        let loaded = loaded.map(|loaded| {
+            *stamp = loaded.stamp;
+            loaded.value
+        });
+
+        runtime.report_tracked_write(stamp.durability);
 
         stamp.durability = durability.unwrap_or(stamp.durability);
         stamp.changed_at = runtime.current_revision();
-        setter(&mut r.fields)
+        runtime.record_input_change(
+            DatabaseKeyIndex {
+                ingredient_index: self.ingredient_index.successor(field_index),
+                key_index: id,
+            },
+            stamp.durability,
+        );
+
+        let indexed = C::INDEXED_FIELDS.contains(&field_index);
+        let fields = r.fields.as_mut().unwrap();
+        if indexed {
+            self.unindex_field(id, field_index, fields);
+        }
+        let result = setter(fields, loaded);
+        if indexed {
+            self.index_field(id, field_index, fields);
+        }
+        result
+    }
+
+    /// Restores the fields and stamps of `id` saved at the beginning of a speculation.
+    fn restore(&mut self, runtime: &mut Runtime, id: Id, fields: C::Fields, stamps: C::Stamps) {
+        let r = Self::data_raw(runtime.table(), id);
+
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        // Also, we don't access any other data from the table while `r` is active.
+        let r = unsafe { &mut *r };
+
+        let current = Self::live_fields(r, id);
+        for &field_index in C::INDEXED_FIELDS {
+            self.unindex_field(id, field_index, current);
+            self.index_field(id, field_index, &fields);
+        }
+
+        r.fields = Some(fields);
+        r.stamps = stamps;
     }
 
     /// Get the singleton input previously created.
@@ -174,14 +513,81 @@ impl<C: Configuration> IngredientImpl<C> {
         let field_ingredient_index = self.ingredient_index.successor(field_index);
         let id = id.as_id();
         let value = Self::data(zalsa, id);
+        let fields = Self::live_fields(value, id);
         let stamp = &value.stamps[field_index];
         zalsa_local.report_tracked_read(
+            db,
             InputDependencyIndex::new(field_ingredient_index, id),
             stamp.durability,
+            Durabilities::of(stamp.durability),
+            stamp.changed_at,
+            InputAccumulatedValues::Empty,
+        );
+        fields
+    }
+
+    /// Registers the loader of the `#[lazy]` field `field_index`, replacing the previous one.
+    ///
+    /// Fields that were already loaded keep their values.
+    pub fn register_loader<T: Any + Send + Sync>(
+        &mut self,
+        field_index: usize,
+        loader: Loader<C, T>,
+    ) {
+        self.loaders[field_index] = Some(Arc::new(loader));
+    }
+
+    /// Access the `#[lazy]` field `field_index` of an input, loading it if it is unset.
+    ///
+    /// `field` selects the field from the fields tuple, where it is stored once set.
+    ///
+    /// # Panics
+    ///
+    /// If the field is unset and no loader was registered for it.
+    pub fn lazy_field<'db, T: Any + Send + Sync>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        id: C::Struct,
+        field_index: usize,
+        field: impl FnOnce(&'db C::Fields) -> &'db T,
+    ) -> &'db T {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let id = id.as_id();
+        let value = Self::data(zalsa, id);
+        let fields = Self::live_fields(value, id);
+        let (field_value, stamp) = match value.lazy.get(field_index).and_then(Option::as_ref) {
+            Some(slot) => {
+                let loaded = slot.get_or_init(|| self.load::<T>(db, id, field_index));
+                (loaded.value.downcast_ref::<T>().unwrap(), loaded.stamp)
+            }
+            None => (field(fields), value.stamps[field_index]),
+        };
+        zalsa_local.report_tracked_read(
+            db,
+            InputDependencyIndex::new(self.ingredient_index.successor(field_index), id),
+            stamp.durability,
+            Durabilities::of(stamp.durability),
             stamp.changed_at,
             InputAccumulatedValues::Empty,
         );
-        &value.fields
+        field_value
+    }
+
+    /// Invokes the loader of the `#[lazy]` field `field_index` for the input `id`.
+    fn load<T: Any + Send + Sync>(&self, db: &dyn Database, id: Id, field_index: usize) -> Loaded {
+        let Some(loader) = &self.loaders[field_index] else {
+            panic!(
+                "no loader registered for the lazy field `{}::{}`",
+                C::DEBUG_NAME,
+                C::FIELD_DEBUG_NAMES[field_index]
+            )
+        };
+        let loader = loader.downcast_ref::<Loader<C, T>>().unwrap();
+        let (value, durability) = loader(db, FromId::from_id(id));
+        Loaded {
+            value: Box::new(value),
+            stamp: stamp(db.zalsa().current_revision(), durability),
+        }
     }
 
     #[cfg(feature = "salsa_unstable")]
@@ -196,15 +602,19 @@ impl<C: Configuration> IngredientImpl<C> {
             .iter()
             .filter_map(|page| page.cast_type::<crate::table::Page<Value<C>>>())
             .flat_map(|page| page.slots())
+            .filter(|value| !value.is_retired())
     }
 
     /// Peek at the field values without recording any read dependency.
-    /// Used for debug printouts.
-    pub fn leak_fields<'db>(&'db self, db: &'db dyn Database, id: C::Struct) -> &'db C::Fields {
+    /// Used for debug printouts. Returns `None` if the input was retired.
+    pub fn leak_fields<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        id: C::Struct,
+    ) -> Option<&'db C::Fields> {
         let zalsa = db.zalsa();
         let id = id.as_id();
-        let value = Self::data(zalsa, id);
-        &value.fields
+        Self::data(zalsa, id).live_fields()
     }
 }
 
@@ -213,6 +623,10 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         self.ingredient_index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Input
+    }
+
     fn maybe_changed_after(
         &self,
         _db: &dyn Database,
@@ -232,6 +646,10 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         None
     }
 
+    fn is_stale_handle(&self, db: &dyn Database, key_index: Id) -> bool {
+        Self::data(db.zalsa(), key_index).is_retired()
+    }
+
     fn mark_validated_output(
         &self,
         _db: &dyn Database,
@@ -271,6 +689,50 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
+
+    fn synthetic_write(&mut self, runtime: &mut Runtime, durability: Durability) {
+        runtime.report_tracked_write(durability);
+
+        let ids: Vec<Id> = runtime.table().ids(self.ingredient_index).collect();
+        for id in ids {
+            let r = Self::data_raw(runtime.table(), id);
+
+            // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+            // Also, we don't access any other data from the table while `r` is active.
+            let r = unsafe { &mut *r };
+            if r.is_retired() {
+                continue;
+            }
+
+            for (field_index, stamp) in r.stamps.iter_mut().enumerate() {
+                if stamp.durability > durability {
+                    continue;
+                }
+                runtime.report_tracked_write(stamp.durability);
+                stamp.changed_at = runtime.current_revision();
+                runtime.record_input_change(
+                    DatabaseKeyIndex {
+                        ingredient_index: self.ingredient_index.successor(field_index),
+                        key_index: id,
+                    },
+                    stamp.durability,
+                );
+            }
+        }
+    }
+
+    fn copy_from(&mut self, old: &dyn Ingredient, runtime: &Runtime) {
+        self.loaders.clone_from(&old.assert_type::<Self>().loaders);
+        for id in runtime.table().ids(self.ingredient_index) {
+            let value: &Value<C> = runtime.table().get(id);
+            if let Some(fields) = value.live_fields() {
+                self.singleton.with_lock(|| id);
+                for &field_index in C::INDEXED_FIELDS {
+                    self.index_field(id, field_index, fields);
+                }
+            }
+        }
+    }
 }
 
 impl<C: Configuration> std::fmt::Debug for IngredientImpl<C> {
@@ -286,15 +748,24 @@ pub struct Value<C>
 where
     C: Configuration,
 {
-    /// Fields of this input struct.
+    /// Fields of this input struct, or `None` if it was retired.
     ///
     /// They can change across revisions, but they do not change within
     /// a particular revision.
-    fields: C::Fields,
+    fields: Option<C::Fields>,
 
     /// The revision and durability information for each field: when did this field last change.
     stamps: C::Stamps,
 
+    /// For each `#[lazy]` field that was not set yet, its value once loaded, by field index.
+    /// Empty if there are no such fields.
+    ///
+    /// Unlike `fields`, these are loaded within a revision, on first read.
+    lazy: Box<[Option<OnceLock<Loaded>>]>,
+
+    /// Set when the input is retired, after which its fields are gone.
+    retired: AtomicBool,
+
     /// Memos
     memos: MemoTable,
 
@@ -312,10 +783,29 @@ where
     /// a particular revision.
     #[cfg(feature = "salsa_unstable")]
     pub fn fields(&self) -> &C::Fields {
-        &self.fields
+        self.fields.as_ref().expect("retired inputs are not listed")
+    }
+
+    fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Acquire)
+    }
+
+    fn live_fields(&self) -> Option<&C::Fields> {
+        if self.is_retired() {
+            None
+        } else {
+            self.fields.as_ref()
+        }
     }
 }
 
+/// The value of a `#[lazy]` field, and when it was loaded.
+#[derive(Debug)]
+struct Loaded {
+    value: LoadedValue,
+    stamp: Stamp,
+}
+
 pub trait HasBuilder {
     type Builder;
 }
@@ -331,4 +821,42 @@ where
     unsafe fn syncs(&self, _current_revision: Revision) -> &SyncTable {
         &self.syncs
     }
+
+    fn memos_mut(&mut self) -> &mut MemoTable {
+        &mut self.memos
+    }
+
+    fn fork(&self) -> Option<Self> {
+        // Inputs of lower durability are retired: they are expected to be set again.
+        // So are those that cannot be cloned.
+        let fields = self
+            .live_fields()
+            .filter(|_| {
+                self.stamps
+                    .iter()
+                    .all(|stamp| stamp.durability == Durability::HIGH)
+            })
+            .and_then(C::fork_fields);
+        let mut stamps = self.stamps.clone();
+        for stamp in stamps.iter_mut() {
+            stamp.changed_at = Revision::start();
+        }
+        // Lazy fields are loaded again.
+        let lazy = match fields {
+            Some(_) => self
+                .lazy
+                .iter()
+                .map(|slot| slot.as_ref().map(|_| OnceLock::new()))
+                .collect(),
+            None => Box::default(),
+        };
+        Some(Self {
+            retired: AtomicBool::new(fields.is_none()),
+            fields,
+            stamps,
+            lazy,
+            memos: Default::default(),
+            syncs: Default::default(),
+        })
+    }
 }
@@ -1,5 +1,5 @@
 use crate::cycle::CycleRecoveryStrategy;
-use crate::ingredient::{fmt_index, Ingredient, MaybeChangedAfter};
+use crate::ingredient::{fmt_index, Ingredient, IngredientKind, MaybeChangedAfter};
 use crate::input::Configuration;
 use crate::zalsa::IngredientIndex;
 use crate::zalsa_local::QueryOrigin;
@@ -20,6 +20,7 @@ use super::{IngredientImpl, Value};
 /// structs.
 pub struct FieldIngredientImpl<C: Configuration> {
     index: IngredientIndex,
+    struct_index: IngredientIndex,
     field_index: usize,
     phantom: PhantomData<fn() -> Value<C>>,
 }
@@ -31,6 +32,7 @@ where
     pub(super) fn new(struct_index: IngredientIndex, field_index: usize) -> Self {
         Self {
             index: struct_index.successor(field_index),
+            struct_index,
             field_index,
             phantom: PhantomData,
         }
@@ -45,6 +47,10 @@ where
         self.index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::InputField
+    }
+
     fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
         CycleRecoveryStrategy::Panic
     }
@@ -58,13 +64,25 @@ where
         let zalsa = db.zalsa();
         let value = <IngredientImpl<C>>::data(zalsa, input);
 
-        MaybeChangedAfter::from(value.stamps[self.field_index].changed_at > revision)
+        // A retired input has changed: its fields are gone.
+        MaybeChangedAfter::from(
+            value.is_retired() || value.stamps[self.field_index].changed_at > revision,
+        )
     }
 
     fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
         None
     }
 
+    fn fingerprint(&self, db: &dyn Database, key_index: Id) -> Option<u128> {
+        let zalsa = db.zalsa();
+
+        // The key may have been read in another run, in which more inputs were created.
+        zalsa.table().check_id(key_index, self.struct_index).ok()?;
+        let value = <IngredientImpl<C>>::data(zalsa, key_index);
+        C::fingerprint_field(value.live_fields()?, self.field_index)
+    }
+
     fn mark_validated_output(
         &self,
         _db: &dyn Database,
@@ -96,6 +114,10 @@ where
     fn debug_name(&self) -> &'static str {
         C::FIELD_DEBUG_NAMES[self.field_index]
     }
+
+    fn owner_debug_name(&self) -> Option<&'static str> {
+        Some(C::DEBUG_NAME)
+    }
 }
 
 impl<C> std::fmt::Debug for FieldIngredientImpl<C>
@@ -1,5 +1,8 @@
+use std::any::Any;
 use std::marker::PhantomData;
 
+#[cfg(feature = "fallible_v1")]
+use crate::id::AsId;
 use crate::input::{Configuration, IngredientImpl};
 use crate::{Durability, Runtime};
 
@@ -8,6 +11,16 @@ pub trait Setter: Sized {
     type FieldTy;
     fn with_durability(self, durability: Durability) -> Self;
     fn to(self, value: Self::FieldTy) -> Self::FieldTy;
+
+    /// Like [`to`](`Setter::to`), but returns [`Error::StaleHandle`](`crate::Error::StaleHandle`)
+    /// rather than panicking if the input was retired.
+    #[cfg(feature = "fallible_v1")]
+    fn try_to(self, value: Self::FieldTy) -> Result<Self::FieldTy, crate::Error>;
+
+    /// Modifies the field in place with `op`, e.g., to push onto a `Vec`
+    /// without cloning it first. Like [`to`](`Setter::to`), this marks the
+    /// field as changed in the current revision, even if `op` leaves it untouched.
+    fn update<R>(self, op: impl FnOnce(&mut Self::FieldTy) -> R) -> R;
 }
 
 #[must_use]
@@ -24,7 +37,7 @@ pub struct SetterImpl<'setter, C: Configuration, S, F> {
 impl<'setter, C, S, F> SetterImpl<'setter, C, S, F>
 where
     C: Configuration,
-    S: FnOnce(&mut C::Fields, F) -> F,
+    S: FnOnce(&mut C::Fields) -> &mut F,
 {
     pub fn new(
         runtime: &'setter mut Runtime,
@@ -48,7 +61,8 @@ where
 impl<C, S, F> Setter for SetterImpl<'_, C, S, F>
 where
     C: Configuration,
-    S: FnOnce(&mut C::Fields, F) -> F,
+    S: FnOnce(&mut C::Fields) -> &mut F,
+    F: Any,
 {
     type FieldTy = F;
 
@@ -58,6 +72,22 @@ where
     }
 
     fn to(self, value: F) -> F {
+        self.update(|field| std::mem::replace(field, value))
+    }
+
+    #[cfg(feature = "fallible_v1")]
+    fn try_to(self, value: F) -> Result<F, crate::Error> {
+        if self.ingredient.is_retired(self.runtime, self.id) {
+            return Err(crate::StaleHandle {
+                struct_name: C::DEBUG_NAME,
+                id: self.id.as_id(),
+            }
+            .into());
+        }
+        Ok(self.to(value))
+    }
+
+    fn update<R>(self, op: impl FnOnce(&mut F) -> R) -> R {
         let Self {
             runtime,
             id,
@@ -68,8 +98,12 @@ where
             phantom: _,
         } = self;
 
-        ingredient.set_field(runtime, id, field_index, durability, |tuple| {
-            setter(tuple, value)
+        ingredient.set_field(runtime, id, field_index, durability, |tuple, loaded| {
+            let field = setter(tuple);
+            if let Some(loaded) = loaded {
+                *field = *loaded.downcast::<F>().unwrap();
+            }
+            op(field)
         })
     }
 }
@@ -10,6 +10,9 @@ mod sealed {
 pub trait SingletonChoice: sealed::Sealed + Default {
     fn with_lock(&self, cb: impl FnOnce() -> Id) -> Id;
     fn index(&self) -> Option<Id>;
+
+    /// Forgets the singleton, e.g. because it was retired, so that a new one can be created.
+    fn clear(&self);
 }
 
 pub struct Singleton {
@@ -32,6 +35,11 @@ impl SingletonChoice for Singleton {
     fn index(&self) -> Option<Id> {
         self.index.load().map(FromId::from_id)
     }
+
+    fn clear(&self) {
+        let _guard = self.lock.lock();
+        self.index.store(None);
+    }
 }
 
 impl Default for Singleton {
@@ -52,4 +60,6 @@ impl SingletonChoice for NotSingleton {
     fn index(&self) -> Option<Id> {
         None
     }
+
+    fn clear(&self) {}
 }
@@ -0,0 +1,52 @@
+use crate::{Database, DatabaseKeyIndex};
+
+/// A hook invoked around the execution of every tracked function.
+///
+/// Interceptors are registered with [`Storage::add_interceptor`](`crate::Storage::add_interceptor`)
+/// and are shared by all handles to the database. They are invoked in registration order
+/// before a tracked function executes, and in reverse order once it has executed
+/// (whether or not its value then turns out to be unchanged). They are also notified when
+/// a memoized value is reused without executing the function, and when a value is backdated.
+///
+/// The ingredient of the function being executed can be obtained from
+/// [`DatabaseKeyIndex::ingredient_index`], e.g., to look up its
+/// [debug name](`Database::ingredient_debug_name`).
+pub trait Interceptor: Send + Sync + 'static {
+    /// Invoked before the tracked function identified by `database_key` executes.
+    ///
+    /// Returning [`Intercept::Veto`] prevents the execution: the query unwinds with
+    /// [`Cancelled::Vetoed`](`crate::Cancelled::Vetoed`), which can be caught with
+    /// [`Cancelled::catch`](`crate::Cancelled::catch`).
+    fn will_execute(&self, db: &dyn Database, database_key: DatabaseKeyIndex) -> Intercept {
+        let _ = (db, database_key);
+        Intercept::Proceed
+    }
+
+    /// Invoked after the tracked function identified by `database_key` has executed.
+    /// Not invoked if the function unwound.
+    fn did_execute(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        let _ = (db, database_key);
+    }
+
+    /// Invoked when the memoized value of `database_key` was found to be up to date
+    /// in the current revision without executing the function.
+    fn did_validate(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        let _ = (db, database_key);
+    }
+
+    /// Invoked after [`Interceptor::did_execute`] if the new value of `database_key`
+    /// is equal to its old value, so that functions reading it are not re-executed.
+    fn did_backdate(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        let _ = (db, database_key);
+    }
+}
+
+/// Decision returned by [`Interceptor::will_execute`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Intercept {
+    /// Execute the tracked function.
+    Proceed,
+
+    /// Do not execute the tracked function.
+    Veto,
+}
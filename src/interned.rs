@@ -1,8 +1,8 @@
-use dashmap::SharedValue;
+use dashmap::{DashMap, SharedValue};
 
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
-use crate::durability::Durability;
-use crate::ingredient::{fmt_index, MaybeChangedAfter};
+use crate::durability::{Durabilities, Durability};
+use crate::ingredient::{fmt_index, IngredientKind, MaybeChangedAfter};
 use crate::key::InputDependencyIndex;
 use crate::plumbing::{Jar, JarAux};
 use crate::table::memo::MemoTable;
@@ -10,14 +10,13 @@ use crate::table::sync::SyncTable;
 use crate::table::Slot;
 use crate::zalsa::IngredientIndex;
 use crate::zalsa_local::QueryOrigin;
-use crate::{Database, DatabaseKeyIndex, Id};
+use crate::{Database, DatabaseKeyIndex, Id, Runtime};
 use std::any::TypeId;
 use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 
-use super::hash::FxDashMap;
 use super::ingredient::Ingredient;
 use super::Revision;
 
@@ -30,6 +29,9 @@ pub trait Configuration: Sized + 'static {
     /// The end user struct
     type Struct<'db>: Copy;
 
+    /// Builds the hasher used to look up interned values, see [`DefaultHasher`].
+    type Hasher: BuildHasher + Default + Clone + Send + Sync + 'static;
+
     /// Create an end-user struct from the salsa id
     ///
     /// This call is an "end-step" to the tracked struct lookup/creation
@@ -42,11 +44,15 @@ pub trait Configuration: Sized + 'static {
     fn deref_struct(s: Self::Struct<'_>) -> Id;
 }
 
+/// The hasher used by the interner of an interned struct, unless it sets a `hasher`
+/// (e.g. `#[salsa::interned(hasher = ahash::RandomState)]`).
+pub type DefaultHasher = crate::hash::FxHasher;
+
 pub trait InternedData: Sized + Eq + Hash + Clone + Sync + Send {}
 impl<T: Eq + Hash + Clone + Sync + Send> InternedData for T {}
 
 pub struct JarImpl<C: Configuration> {
-    phantom: PhantomData<C>,
+    phantom: PhantomData<fn() -> C>,
 }
 
 /// The interned ingredient hashes values of type `Data` to produce an `Id`.
@@ -60,7 +66,7 @@ pub struct IngredientImpl<C: Configuration> {
     /// Maps from data to the existing interned id for that data.
     ///
     /// Deadlock requirement: We access `value_map` while holding lock on `key_map`, but not vice versa.
-    key_map: FxDashMap<C::Fields<'static>, Id>,
+    key_map: DashMap<C::Fields<'static>, Id, C::Hasher>,
 
     /// Stores the revision when this interned ingredient was last cleared.
     /// You can clear an interned table at any point, deleting all its entries,
@@ -110,6 +116,10 @@ impl<C: Configuration> Jar for JarImpl<C> {
     fn salsa_struct_type_id(&self) -> Option<std::any::TypeId> {
         Some(TypeId::of::<<C as Configuration>::Struct<'static>>())
     }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self::default())
+    }
 }
 
 impl<C> IngredientImpl<C>
@@ -179,8 +189,10 @@ where
     {
         let zalsa_local = db.zalsa_local();
         zalsa_local.report_tracked_read(
+            db,
             InputDependencyIndex::for_table(self.ingredient_index),
             Durability::MAX,
+            Durabilities::of(Durability::MAX),
             self.reset_at,
             InputAccumulatedValues::Empty,
         );
@@ -253,6 +265,19 @@ where
         self.data(db, C::deref_struct(s))
     }
 
+    /// Returns all values interned so far, in no particular order.
+    ///
+    /// This does not record a dependency on the values interned later.
+    pub fn all<'db>(
+        &'db self,
+        db: &'db dyn Database,
+    ) -> impl Iterator<Item = C::Struct<'db>> + 'db {
+        db.zalsa()
+            .table()
+            .ids(self.ingredient_index)
+            .map(C::struct_from_id)
+    }
+
     #[cfg(feature = "salsa_unstable")]
     /// Returns all data corresponding to the interned struct.
     pub fn entries<'db>(
@@ -282,6 +307,10 @@ where
         self.ingredient_index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Interned
+    }
+
     fn maybe_changed_after(
         &self,
         _db: &dyn Database,
@@ -341,6 +370,13 @@ where
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
+
+    fn copy_from(&mut self, _old: &dyn Ingredient, runtime: &Runtime) {
+        for id in runtime.table().ids(self.ingredient_index) {
+            let value: &Value<C> = runtime.table().get(id);
+            self.key_map.insert(value.fields.clone(), id);
+        }
+    }
 }
 
 impl<C> std::fmt::Debug for IngredientImpl<C>
@@ -365,6 +401,18 @@ where
     unsafe fn syncs(&self, _current_revision: Revision) -> &crate::table::sync::SyncTable {
         &self.syncs
     }
+
+    fn memos_mut(&mut self) -> &mut MemoTable {
+        &mut self.memos
+    }
+
+    fn fork(&self) -> Option<Self> {
+        Some(Self {
+            fields: self.fields.clone(),
+            memos: Default::default(),
+            syncs: Default::default(),
+        })
+    }
 }
 
 /// A trait for types that hash and compare like `O`.
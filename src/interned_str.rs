@@ -0,0 +1,210 @@
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+
+use crate::id::FromIdWithDb;
+use crate::interned::{self, HashEqLike, JarImpl};
+use crate::plumbing::{JarAux, JarGroup, Registry};
+use crate::salsa_struct::SalsaStructInDb;
+use crate::zalsa::IngredientCache;
+use crate::{Database, Id, IngredientIndex, Update};
+
+/// A string interned in the database.
+///
+/// Interning the same text twice yields the same `Str`, so comparing, hashing and ordering
+/// compare the ids of the strings rather than their text. Note that this means the order
+/// of `Str`s is the order in which they were first interned, not the lexicographic one.
+///
+/// A `Str` dereferences to the interned text without accessing the database, and can be
+/// used as the argument of tracked functions and as a field of salsa structs.
+#[derive(Copy, Clone)]
+pub struct Str<'db> {
+    id: Id,
+    text: &'db str,
+}
+
+impl<'db> Str<'db> {
+    /// Interns `text`.
+    pub fn new(db: &'db dyn Database, text: &str) -> Self {
+        Self::new_prehashed(db, text, Self::prehash(text))
+    }
+
+    /// Interns `text`, whose [`prehash`](`Self::prehash`) is `hash`, e.g., because it was
+    /// computed along with the text by a lexer.
+    pub fn new_prehashed(db: &'db dyn Database, text: &str, hash: u64) -> Self {
+        debug_assert_eq!(hash, Self::prehash(text), "wrong prehash for {text:?}");
+        let id = ingredient(db).intern_id(db, StrKey { hash, text }, |_, key| StrData {
+            hash: key.hash,
+            text: key.text.into(),
+        });
+        Self::from_id(id, db)
+    }
+
+    /// The hash used to intern `text`.
+    pub fn prehash(text: &str) -> u64 {
+        interned::DefaultHasher::default().hash_one(text)
+    }
+
+    /// The interned text, which lives as long as the database is borrowed.
+    pub fn as_str(self) -> &'db str {
+        self.text
+    }
+}
+
+impl Deref for Str<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.text
+    }
+}
+
+impl AsRef<str> for Str<'_> {
+    fn as_ref(&self) -> &str {
+        self.text
+    }
+}
+
+impl PartialEq for Str<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Str<'_> {}
+
+impl PartialOrd for Str<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Str<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl Hash for Str<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.id, state);
+    }
+}
+
+impl fmt::Debug for Str<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.text, f)
+    }
+}
+
+impl fmt::Display for Str<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.text, f)
+    }
+}
+
+impl crate::plumbing::AsId for Str<'_> {
+    fn as_id(&self) -> Id {
+        self.id
+    }
+}
+
+impl FromIdWithDb for Str<'_> {
+    fn from_id(id: Id, db: &dyn Database) -> Self {
+        let text: &str = &ingredient(db).data(db, id).text;
+        // SAFETY: Interned values are only freed along with the database.
+        let text = unsafe { std::mem::transmute::<&str, &str>(text) };
+        Str { id, text }
+    }
+}
+
+impl SalsaStructInDb for Str<'_> {
+    fn lookup_ingredient_index(aux: &dyn JarAux) -> Option<IngredientIndex> {
+        aux.lookup_jar_by_type(&JarImpl::<Configuration>::default())
+    }
+
+    fn register_ingredients(db: &dyn Database) {
+        ingredient(db);
+    }
+}
+
+impl JarGroup for Str<'static> {
+    fn register(registry: &Registry<'_>) {
+        registry.add_jar(&JarImpl::<Configuration>::default());
+    }
+}
+
+unsafe impl Update for Str<'_> {
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        if unsafe { *old_pointer } != new_value {
+            unsafe { *old_pointer = new_value };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn ingredient(db: &dyn Database) -> &interned::IngredientImpl<Configuration> {
+    static CACHE: IngredientCache<interned::IngredientImpl<Configuration>> = IngredientCache::new();
+    CACHE.get_or_create(db, || {
+        db.zalsa()
+            .add_or_lookup_jar_by_type(&JarImpl::<Configuration>::default())
+    })
+}
+
+struct Configuration;
+
+impl interned::Configuration for Configuration {
+    const DEBUG_NAME: &'static str = "Str";
+
+    type Fields<'db> = StrData;
+
+    // A `Str` also holds its text, which requires the database to look up.
+    type Struct<'db> = Id;
+
+    type Hasher = interned::DefaultHasher;
+
+    fn struct_from_id<'db>(id: Id) -> Self::Struct<'db> {
+        id
+    }
+
+    fn deref_struct(id: Id) -> Id {
+        id
+    }
+}
+
+/// The interned text, along with its prehash. Only the prehash is hashed.
+#[derive(Clone, PartialEq, Eq)]
+struct StrData {
+    hash: u64,
+    text: Box<str>,
+}
+
+impl Hash for StrData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// Looks up a [`StrData`] without allocating its text.
+struct StrKey<'a> {
+    hash: u64,
+    text: &'a str,
+}
+
+impl Hash for StrKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl HashEqLike<StrKey<'_>> for StrData {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        Hash::hash(self, h);
+    }
+
+    fn eq(&self, key: &StrKey<'_>) -> bool {
+        self.hash == key.hash && *self.text == *key.text
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    fmt,
+    panic::{self, UnwindSafe},
+};
+
+use crate::{Id, IngredientIndex};
+
+/// A panic payload indicating that a tracked function declared with
+/// `#[salsa::tracked(validate_args)]` was invoked with a key that does not
+/// belong to the database, e.g., a salsa struct created in a different database.
+///
+/// Note that validation cannot detect every foreign key: a struct created in another
+/// database with the same layout may well refer to a valid entry of this one.
+/// Ids do not record the database that created them, as that would grow every
+/// [`Id`] (and every salsa struct) beyond 32 bits.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct InvalidKey {
+    /// Name of the tracked function that was invoked.
+    pub function: &'static str,
+
+    /// The rejected id.
+    pub id: Id,
+
+    /// Why the id was rejected.
+    pub reason: InvalidKeyReason,
+}
+
+/// Why an [`InvalidKey`] was rejected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidKeyReason {
+    /// The id refers to a page that was never allocated in this database.
+    UnknownPage,
+
+    /// The id refers to an entry of a different salsa struct.
+    WrongIngredient {
+        expected: IngredientIndex,
+        found: IngredientIndex,
+    },
+
+    /// The id refers to an entry that was never allocated in this database.
+    Unallocated,
+}
+
+impl InvalidKey {
+    pub(crate) fn throw(self) -> ! {
+        // We use resume and not panic here to avoid running the panic
+        // hook (that is, to avoid collecting and printing backtrace).
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// Runs `f`, and catches any invalid key.
+    pub fn catch<F, T>(f: F) -> Result<T, InvalidKey>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(invalid_key) => Err(*invalid_key),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let why = match self.reason {
+            InvalidKeyReason::UnknownPage | InvalidKeyReason::Unallocated => {
+                "it was not allocated in this database"
+            }
+            InvalidKeyReason::WrongIngredient { .. } => "it belongs to a different salsa struct",
+        };
+        write!(
+            f,
+            "`{}` invoked with invalid key `{:?}`: {why}",
+            self.function, self.id
+        )
+    }
+}
+
+impl std::error::Error for InvalidKey {}
@@ -0,0 +1,73 @@
+use crate::Database;
+
+/// Executes `a` and `b`, potentially in parallel, and returns both results.
+///
+/// `a` executes on the current thread, while `b` may execute on a thread of the rayon
+/// thread pool, using a fork of `db`. When called from a tracked function, the reads
+/// performed by `b` become dependencies of that function, just like those of `a`, so
+/// that independent sub-queries can be executed in parallel without losing track of
+/// their dependencies:
+///
+/// ```
+/// # #[salsa::input]
+/// # struct File { text: String }
+/// # #[salsa::tracked]
+/// # fn lines(db: &dyn salsa::Database, file: File) -> usize { file.text(db).lines().count() }
+/// # #[salsa::tracked]
+/// # fn words(db: &dyn salsa::Database, file: File) -> usize { file.text(db).split_whitespace().count() }
+/// #[salsa::tracked]
+/// fn stats(db: &dyn salsa::Database, file: File) -> (usize, usize) {
+///     salsa::join(db, |db| lines(db, file), |db| words(db, file))
+/// }
+/// ```
+///
+/// As with [`par_map`](`crate::par_map`), `Db` is a database trait object, such as
+/// `dyn Database` or the trait of a `#[salsa::db]` database.
+///
+/// `b` must only read the database, e.g. by invoking tracked functions, which may in turn
+/// create tracked structs or accumulate values. Neither closure may invoke the tracked
+/// function calling `join`, which would be a cycle that salsa cannot detect across the fork.
+///
+/// # Panics
+///
+/// If `Db` is not a view of the database, e.g., its concrete type. If `b` creates tracked structs, specifies or accumulates values itself. If either closure
+/// panics, the panic is propagated once both completed.
+pub fn join<Db, A, B, RA, RB>(db: &Db, a: A, b: B) -> (RA, RB)
+where
+    Db: Database + ?Sized,
+    A: FnOnce(&Db) -> RA,
+    B: FnOnce(&Db) -> RB + Send,
+    RB: Send,
+{
+    let fork = ForkedDb(db.fork_db());
+    let parent = db.zalsa_local().active_query().map(|(key, _)| key);
+
+    let mut result_b = None;
+    let result_a = rayon::in_place_scope(|scope| {
+        scope.spawn(|_| {
+            let fork = fork;
+            let db = fork.0.as_view::<Db>();
+            result_b = Some(match parent {
+                Some(parent) => {
+                    let (result, frame) = db.zalsa_local().record_reads_for(parent, || b(db));
+                    (result, Some(frame))
+                }
+                None => (b(db), None),
+            });
+        });
+        a(db)
+    });
+
+    let (result_b, frame) = result_b.unwrap();
+    if let Some(frame) = frame {
+        db.zalsa_local().add_reads_from(&frame);
+    }
+    (result_a, result_b)
+}
+
+/// A fork of the database, moved to the thread executing the second closure of [`join`].
+struct ForkedDb(Box<dyn Database>);
+
+/// SAFETY: the fork is created for the exclusive use of the thread it is moved to,
+/// where it is also dropped.
+unsafe impl Send for ForkedDb {}
@@ -1,8 +1,11 @@
 use core::fmt;
 
 use crate::{
-    accumulator::accumulated_map::InputAccumulatedValues, cycle::CycleRecoveryStrategy,
-    ingredient::MaybeChangedAfter, zalsa::IngredientIndex, Database, Id,
+    accumulator::accumulated_map::InputAccumulatedValues,
+    cycle::CycleRecoveryStrategy,
+    ingredient::{Ingredient, MaybeChangedAfter},
+    zalsa::IngredientIndex,
+    Database, Id,
 };
 
 /// An integer that uniquely identifies a particular query instance within the
@@ -33,6 +36,14 @@ impl OutputDependencyIndex {
         }
     }
 
+    pub(crate) fn ingredient_index(&self) -> IngredientIndex {
+        self.ingredient_index
+    }
+
+    pub(crate) fn key_index(&self) -> Id {
+        self.key_index
+    }
+
     pub(crate) fn remove_stale_output(&self, db: &dyn Database, executor: DatabaseKeyIndex) {
         db.zalsa()
             .lookup_ingredient(self.ingredient_index)
@@ -100,11 +111,59 @@ impl InputDependencyIndex {
         }
     }
 
+    /// The key this dependency refers to, unless it refers to a table as a whole.
+    pub(crate) fn database_key_index(&self) -> Option<DatabaseKeyIndex> {
+        Some(DatabaseKeyIndex {
+            ingredient_index: self.ingredient_index,
+            key_index: self.key_index?,
+        })
+    }
+
+    pub(crate) fn ingredient_index(&self) -> IngredientIndex {
+        self.ingredient_index
+    }
+
+    /// The key this dependency refers to, or `None` if it refers to a table as a whole.
+    pub(crate) fn key_index(&self) -> Option<Id> {
+        self.key_index
+    }
+
+    /// True if this dependency refers to a particular key (rather than a table as a whole).
+    pub(crate) fn is_keyed(&self) -> bool {
+        self.key_index.is_some()
+    }
+
     pub fn set_key_index(&mut self, key_index: Id) {
         self.key_index = Some(key_index);
     }
 }
 
+impl InputDependencyIndex {
+    /// Describes this dependency for users, see [`DependencyInfo`].
+    pub(crate) fn info(&self, db: &dyn Database) -> DependencyInfo {
+        let ingredient = db.zalsa().lookup_ingredient(self.ingredient_index);
+        DependencyInfo {
+            ingredient: ingredient.debug_name(),
+            key: self.key_index,
+            name: crate::attach::attach(db, || format!("{self:?}")),
+        }
+    }
+}
+
+/// A direct dependency of a memoized value, as listed by the `dependencies` function
+/// generated for tracked functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyInfo {
+    /// Name of the ingredient that was read, e.g. of a tracked function or of an input field.
+    pub ingredient: &'static str,
+
+    /// The key that was read, or `None` for a read of a whole table (e.g. of interned values).
+    pub key: Option<Id>,
+
+    /// Human-readable description of the dependency, e.g. `parse(Id(0))`.
+    pub name: String,
+}
+
 impl fmt::Debug for InputDependencyIndex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         crate::attach::with_attached_database(|db| {
@@ -143,8 +202,89 @@ impl DatabaseKeyIndex {
     pub(crate) fn cycle_recovery_strategy(self, db: &dyn Database) -> CycleRecoveryStrategy {
         self.ingredient_index.cycle_recovery_strategy(db)
     }
+
+    /// Encodes this key as text that [`DatabaseKeyIndex::parse`] maps back to the same query
+    /// in another run, e.g. `parse(Id(0))` or `File.text(Id(2))`.
+    ///
+    /// Unlike the ingredient index, the encoding does not depend on the order in which
+    /// ingredients were registered. The id only refers to the same struct in another run
+    /// if structs are created in the same order.
+    pub fn to_stable_string(self, db: &dyn Database) -> String {
+        let ingredient = db.zalsa().lookup_ingredient(self.ingredient_index);
+        format!("{}({:?})", stable_name(ingredient), self.key_index)
+    }
+
+    /// Parses the encoding of a key produced by [`DatabaseKeyIndex::to_stable_string`].
+    ///
+    /// Only ingredients already registered in `db` are found, e.g., a tracked function
+    /// is registered once it was first invoked or its jar was registered.
+    pub fn parse(db: &dyn Database, text: &str) -> Result<Self, ParseKeyError> {
+        let malformed = || ParseKeyError::Malformed(text.to_string());
+        let (name, key) = text
+            .strip_suffix("))")
+            .and_then(|text| text.rsplit_once("(Id("))
+            .ok_or_else(malformed)?;
+        let key = u32::from_str_radix(key, 16)
+            .ok()
+            .filter(|&key| key < Id::MAX_U32)
+            .ok_or_else(malformed)?;
+
+        let mut matching = db
+            .zalsa()
+            .ingredients()
+            .enumerate()
+            .filter(|(_, ingredient)| stable_name(*ingredient) == name);
+        let (index, _) = matching
+            .next()
+            .ok_or_else(|| ParseKeyError::UnknownIngredient(name.to_string()))?;
+        if matching.next().is_some() {
+            return Err(ParseKeyError::AmbiguousIngredient(name.to_string()));
+        }
+        Ok(Self {
+            ingredient_index: IngredientIndex::from(index),
+            key_index: Id::from_u32(key),
+        })
+    }
+}
+
+/// Name of `ingredient` in [`DatabaseKeyIndex::to_stable_string`]: its debug name,
+/// qualified by the struct's for fields.
+fn stable_name(ingredient: &dyn Ingredient) -> String {
+    match ingredient.owner_debug_name() {
+        Some(owner) => format!("{owner}.{}", ingredient.debug_name()),
+        None => ingredient.debug_name().to_string(),
+    }
+}
+
+/// Error returned by [`DatabaseKeyIndex::parse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseKeyError {
+    /// The text is not of the form `name(Id(key))`.
+    Malformed(String),
+
+    /// No ingredient registered in the database has the given name.
+    UnknownIngredient(String),
+
+    /// Several ingredients have the given name, e.g., tracked functions of the same name
+    /// in different modules.
+    AmbiguousIngredient(String),
 }
 
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseKeyError::Malformed(text) => write!(f, "malformed database key `{text}`"),
+            ParseKeyError::UnknownIngredient(name) => write!(f, "no ingredient named `{name}`"),
+            ParseKeyError::AmbiguousIngredient(name) => {
+                write!(f, "several ingredients are named `{name}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
 impl std::fmt::Debug for DatabaseKeyIndex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         crate::attach::with_attached_database(|db| {
@@ -178,6 +318,15 @@ impl From<DatabaseKeyIndex> for OutputDependencyIndex {
     }
 }
 
+impl From<OutputDependencyIndex> for DatabaseKeyIndex {
+    fn from(value: OutputDependencyIndex) -> Self {
+        Self {
+            ingredient_index: value.ingredient_index,
+            key_index: value.key_index,
+        }
+    }
+}
+
 impl TryFrom<InputDependencyIndex> for DatabaseKeyIndex {
     type Error = ();
 
@@ -1,57 +1,138 @@
+extern crate self as salsa;
+
 mod accumulator;
 mod active_query;
+mod ad_hoc;
 mod array;
 mod attach;
+mod audit;
 mod cancelled;
 mod cycle;
 mod database;
 mod database_impl;
+mod deadlock;
 mod durability;
+mod entries;
+#[cfg(feature = "fallible_v1")]
+mod error;
 mod event;
+mod external;
+mod files;
+mod fingerprint;
 mod function;
 mod hash;
 mod id;
 mod ingredient;
 mod input;
+mod interceptor;
 mod interned;
+mod interned_str;
+mod invalid_key;
+mod join;
 mod key;
+mod memo_cache;
 mod nonce;
 mod par_map;
+mod priority;
+mod read_guard;
+mod registry;
+mod revalidation;
 mod revision;
 mod runtime;
 mod salsa_struct;
+mod side_table;
+mod stale_handle;
+mod state_summary;
+mod stats;
 mod storage;
 mod table;
+pub mod testing;
+mod trace;
+mod tracked_list;
 mod tracked_struct;
+mod transfer;
 mod update;
+pub mod vfs;
 mod views;
 mod zalsa;
 mod zalsa_local;
 
+pub use self::accumulator::AccumulatedWithProvenance;
 pub use self::accumulator::Accumulator;
+pub use self::audit::ConsistencyViolation;
 pub use self::cancelled::Cancelled;
 pub use self::cycle::Cycle;
+pub use self::database::memo;
+pub use self::database::no_cancel_scope;
+pub use self::database::with_no_dependencies;
 pub use self::database::AsDynDatabase;
 pub use self::database::Database;
+pub use self::database_impl::DatabaseBuilder;
 pub use self::database_impl::DatabaseImpl;
+pub use self::deadlock::Deadlock;
+pub use self::deadlock::QueryWait;
 pub use self::durability::Durability;
+pub use self::entries::Entries;
+pub use self::entries::ProjectedEntries;
+#[cfg(feature = "fallible_v1")]
+pub use self::error::Error;
 pub use self::event::Event;
 pub use self::event::EventKind;
+pub use self::files::{File, FileId, FilePath};
+pub use self::fingerprint::fingerprint_of;
+pub use self::fingerprint::Fingerprint;
+pub use self::fingerprint::Fingerprinter;
+pub use self::function::OutputDiff;
 pub use self::id::Id;
+pub use self::ingredient::IngredientInfo;
+pub use self::ingredient::IngredientKind;
 pub use self::input::setter::Setter;
+pub use self::interceptor::Intercept;
+pub use self::interceptor::Interceptor;
+pub use self::interned_str::Str;
+pub use self::invalid_key::InvalidKey;
+pub use self::invalid_key::InvalidKeyReason;
 pub use self::key::DatabaseKeyIndex;
+pub use self::key::DependencyInfo;
+pub use self::key::ParseKeyError;
+pub use self::memo_cache::CacheValue;
+pub use self::memo_cache::MemoCacheBackend;
+pub use self::memo_cache::MemoCacheKey;
+pub use self::priority::Priority;
+pub use self::read_guard::guard_read;
+pub use self::read_guard::ReadGuard;
+pub use self::registry::JarGroup;
+pub use self::registry::Registry;
 pub use self::revision::Revision;
+pub use self::runtime::BlockingStrategy;
+pub use self::runtime::InputChange;
 pub use self::runtime::Runtime;
+pub use self::runtime::Waiter;
+pub use self::side_table::SideTable;
+pub use self::stale_handle::StaleHandle;
+pub use self::state_summary::ParseStateSummaryError;
+pub use self::state_summary::StateDiff;
+pub use self::state_summary::StateSummary;
+#[cfg(feature = "stats")]
+pub use self::stats::{AllocationStats, IngredientStats};
 pub use self::storage::Storage;
+pub use self::tracked_list::ProjectedList;
+pub use self::tracked_list::TrackedList;
+pub use self::transfer::transfer;
+pub use self::transfer::Transfer;
 pub use self::update::Update;
+pub use self::views::MissingView;
 pub use self::zalsa::IngredientIndex;
 pub use crate::attach::with_attached_database;
+pub use join::join;
 pub use par_map::par_map;
 pub use salsa_macros::accumulator;
 pub use salsa_macros::db;
 pub use salsa_macros::input;
 pub use salsa_macros::interned;
 pub use salsa_macros::tracked;
+pub use salsa_macros::InputGroup;
+pub use salsa_macros::Supertype;
 pub use salsa_macros::Update;
 
 pub mod prelude {
@@ -60,54 +141,90 @@ pub mod prelude {
     pub use crate::Setter;
 }
 
+/// Expands to the given items if the `fallible_v1` feature is enabled; used by the
+/// macros to generate the `try_` entry points.
+#[cfg(feature = "fallible_v1")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! if_fallible_v1 {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "fallible_v1"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! if_fallible_v1 {
+    ($($item:tt)*) => {};
+}
+
 /// Internal names used by salsa macros.
 ///
 /// # WARNING
 ///
-/// The contents of this module are NOT subject to semver.
+/// The contents of this module are NOT subject to semver,
+/// with the exception of [`plumbing::external`](`self::plumbing::external`).
 pub mod plumbing {
     pub use crate::accumulator::Accumulator;
     pub use crate::array::Array;
     pub use crate::attach::attach;
     pub use crate::attach::with_attached_database;
+    pub use crate::attach::DebugWithDb;
     pub use crate::cycle::Cycle;
     pub use crate::cycle::CycleRecoveryStrategy;
     pub use crate::database::current_revision;
     pub use crate::database::Database;
+    pub use crate::fingerprint::fingerprint_of;
     pub use crate::function::should_backdate_value;
+    pub use crate::hash::hash;
     pub use crate::id::AsId;
     pub use crate::id::FromId;
+    pub use crate::id::FromIdWithDb;
     pub use crate::id::Id;
     pub use crate::ingredient::Ingredient;
     pub use crate::ingredient::Jar;
     pub use crate::ingredient::JarAux;
     pub use crate::key::DatabaseKeyIndex;
+    pub use crate::memo_cache::CacheValue;
+    pub use crate::registry::JarGroup;
+    pub use crate::registry::Registry;
     pub use crate::revision::Revision;
     pub use crate::runtime::stamp;
     pub use crate::runtime::Runtime;
     pub use crate::runtime::Stamp;
     pub use crate::runtime::StampedValue;
+    pub use crate::salsa_struct::is_entry_of;
     pub use crate::salsa_struct::SalsaStructInDb;
     pub use crate::storage::HasStorage;
     pub use crate::storage::Storage;
+    pub use crate::trace::TraceContext;
     pub use crate::tracked_struct::TrackedStructInDb;
+    pub use crate::transfer::Transfer;
     pub use crate::update::always_update;
     pub use crate::update::helper::Dispatch as UpdateDispatch;
     pub use crate::update::helper::Fallback as UpdateFallback;
+    pub use crate::update::update_fallback;
     pub use crate::update::Update;
     pub use crate::zalsa::views;
+    pub use crate::zalsa::GenericIngredientCache;
     pub use crate::zalsa::IngredientCache;
     pub use crate::zalsa::IngredientIndex;
     pub use crate::zalsa::Zalsa;
     pub use crate::zalsa::ZalsaDatabase;
+    pub use crate::zalsa_local::assert_no_executing_query;
     pub use crate::zalsa_local::ZalsaLocal;
 
+    pub use tracing;
+
+    #[cfg(feature = "fallible_v1")]
+    pub use crate::error::try_fetch;
+    pub use crate::if_fallible_v1;
+
     pub use salsa_macro_rules::macro_if;
     pub use salsa_macro_rules::maybe_backdate;
     pub use salsa_macro_rules::maybe_clone;
     pub use salsa_macro_rules::maybe_cloned_ty;
-    pub use salsa_macro_rules::maybe_default;
-    pub use salsa_macro_rules::maybe_default_tt;
     pub use salsa_macro_rules::setup_accumulator_impl;
     pub use salsa_macro_rules::setup_input_struct;
     pub use salsa_macro_rules::setup_interned_struct;
@@ -116,12 +233,24 @@ pub mod plumbing {
     pub use salsa_macro_rules::setup_tracked_struct;
     pub use salsa_macro_rules::unexpected_cycle_recovery;
 
+    /// A semver-stable API for ingredients implemented outside of salsa,
+    /// see [`ExternalIngredient`](`self::external::ExternalIngredient`).
+    pub mod external {
+        pub use crate::external::ingredient;
+        pub use crate::external::ingredient_index;
+        pub use crate::external::ingredient_mut;
+        pub use crate::external::report_read;
+        pub use crate::external::report_reads;
+        pub use crate::external::ExternalIngredient;
+    }
+
     pub mod accumulator {
         pub use crate::accumulator::IngredientImpl;
         pub use crate::accumulator::JarImpl;
     }
 
     pub mod input {
+        pub use crate::input::fork_helper;
         pub use crate::input::input_field::FieldIngredientImpl;
         pub use crate::input::setter::SetterImpl;
         pub use crate::input::singleton::NotSingleton;
@@ -135,6 +264,7 @@ pub mod plumbing {
 
     pub mod interned {
         pub use crate::interned::Configuration;
+        pub use crate::interned::DefaultHasher;
         pub use crate::interned::HashEqLike;
         pub use crate::interned::IngredientImpl;
         pub use crate::interned::JarImpl;
@@ -143,8 +273,11 @@ pub mod plumbing {
     }
 
     pub mod function {
+        pub use crate::function::intern_output;
         pub use crate::function::Configuration;
         pub use crate::function::IngredientImpl;
+        pub use crate::function::InternOutput;
+        pub use crate::function::OutputInterner;
     }
 
     pub mod tracked_struct {
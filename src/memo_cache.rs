@@ -0,0 +1,104 @@
+/// A cache of the values of tracked functions shared beyond a single database,
+/// e.g. by the machines of a build farm, similar to `sccache` but per query.
+///
+/// The backend is registered with [`Storage::set_memo_cache`](`crate::Storage::set_memo_cache`)
+/// and is only consulted for tracked functions declared with `cache_key = <path>`:
+///
+/// ```ignore
+/// #[salsa::tracked(cache_key = source_fingerprint)]
+/// fn compile(db: &dyn Db, file: SourceFile) -> Artifact { .. }
+///
+/// fn source_fingerprint(db: &dyn Db, file: SourceFile) -> u128 { .. }
+/// ```
+///
+/// Before executing such a function, salsa invokes the fingerprint function and
+/// [loads](`MemoCacheBackend::load`) the value stored for that fingerprint, if any,
+/// instead of executing the function. Otherwise, the function executes and its value is
+/// [stored](`MemoCacheBackend::store`) in the backend. Values are encoded with [`CacheValue`].
+///
+/// The reads of the fingerprint function are the only dependencies of a value loaded from
+/// the backend, so the fingerprint must cover everything the function reads and be stable
+/// across machines and builds: e.g., a hash of the contents of the inputs, not of their ids.
+/// A value loaded from the backend creates no tracked structs and accumulates no values.
+pub trait MemoCacheBackend: Send + Sync + 'static {
+    /// Returns the encoded value stored for `key`, if any.
+    fn load(&self, key: &MemoCacheKey) -> Option<Vec<u8>>;
+
+    /// Stores the encoded `value` of `key`.
+    fn store(&self, key: &MemoCacheKey, value: Vec<u8>);
+}
+
+/// Identifies a value in a [`MemoCacheBackend`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MemoCacheKey {
+    /// The name of the tracked function.
+    pub function: &'static str,
+
+    /// The fingerprint computed by the `cache_key` function of the tracked function.
+    pub fingerprint: u128,
+}
+
+/// Encoding of the values of tracked functions stored in a [`MemoCacheBackend`].
+///
+/// The encoding must be stable across machines and builds sharing the backend.
+pub trait CacheValue: Sized {
+    /// Encodes `self`.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes a value encoded with [`CacheValue::encode`], returning `None` if `bytes`
+    /// is not a valid encoding, in which case the function is executed.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_cache_value_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl CacheValue for $t {
+                fn encode(&self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+
+                fn decode(bytes: &[u8]) -> Option<Self> {
+                    Some(Self::from_le_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_cache_value_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl CacheValue for bool {
+    fn encode(&self) -> Vec<u8> {
+        vec![u8::from(*self)]
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl CacheValue for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl CacheValue for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
@@ -0,0 +1,25 @@
+/// Priority of the queries executed through a database handle,
+/// set with [`Database::set_query_priority`](`crate::Database::set_query_priority`).
+///
+/// Priorities matter when a thread waits for a query executing on another thread
+/// of lower priority:
+///
+/// * If the executing thread has [`Priority::Low`], it is preempted: it unwinds with
+///   [`Cancelled::Preempted`](`crate::Cancelled::Preempted`) at its next cancellation
+///   check, and the waiting thread executes the query itself.
+/// * Otherwise, the executing thread inherits the priority of the waiting thread until
+///   the query completes. A thread running with an inherited priority defers cancellation,
+///   so that a pending write does not discard the work a higher-priority thread waits for.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work, e.g., indexing or precomputing results that may be needed later.
+    /// Preempted by threads of higher priority that wait for it.
+    Low,
+
+    /// The default priority.
+    #[default]
+    Normal,
+
+    /// Latency-sensitive work, e.g., answering a request of the user.
+    High,
+}
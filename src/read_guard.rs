@@ -0,0 +1,149 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::{Database, Revision};
+
+/// A reference to a memoized value (or any other value borrowed from the database)
+/// that detects being used after the database was modified.
+///
+/// References returned by salsa borrow from the database and thus cannot outlive a
+/// revision in safe code. Code that extends their lifetime nonetheless (e.g., to stash
+/// a `&str` returned by a getter in a `thread_local`) reads stale or freed memory once
+/// a new revision starts. Wrapping such references with [`guard_read`] and storing the
+/// guard instead turns these reads into a panic naming the revision and the query in
+/// which the value was read.
+///
+/// Guards only check anything when leak detection is enabled with
+/// [`Storage::set_leak_detection`](`crate::Storage::set_leak_detection`); otherwise,
+/// they are a plain reference.
+pub struct ReadGuard<'db, T: ?Sized> {
+    value: &'db T,
+    tag: Option<Tag>,
+}
+
+/// Records when a [`ReadGuard`] was created.
+#[derive(Clone)]
+struct Tag {
+    /// Generation of the database's memoized values, incremented whenever the
+    /// database is modified.
+    generation: Arc<AtomicUsize>,
+
+    /// Value of `generation` when the guard was created.
+    created_in: usize,
+
+    revision: Revision,
+
+    /// The tracked function that created the guard, if any.
+    query: Option<String>,
+}
+
+/// Wraps `value`, borrowed from `db`, in a [`ReadGuard`].
+pub fn guard_read<'db, Db: ?Sized + Database, T: ?Sized>(
+    db: &'db Db,
+    value: &'db T,
+) -> ReadGuard<'db, T> {
+    let zalsa = db.zalsa();
+    let tag = zalsa.read_generation().map(|generation| Tag {
+        created_in: generation.load(Ordering::Acquire),
+        generation,
+        revision: zalsa.current_revision(),
+        query: db
+            .zalsa_local()
+            .active_query()
+            .map(|(key, _)| crate::attach::attach(db, || format!("{key:?}"))),
+    });
+    ReadGuard { value, tag }
+}
+
+impl<'db, T: ?Sized> ReadGuard<'db, T> {
+    /// Returns the guarded reference, borrowed from the guard.
+    ///
+    /// The check happens only once, when the reference is returned: modifying the
+    /// database through another handle while the reference is in use is not detected.
+    ///
+    /// # Panics
+    ///
+    /// If leak detection is enabled and the database was modified since the guard was created.
+    #[track_caller]
+    pub fn get(&self) -> &T {
+        if let Some(tag) = &self.tag {
+            if tag.is_stale() {
+                panic!(
+                    "leaked read: a value read in {revision:?}{query} was used after the database was \
+                    modified; references to memoized values must not be held across revisions",
+                    revision = tag.revision,
+                    query = match &tag.query {
+                        Some(query) => format!(" by `{query}`"),
+                        None => String::new(),
+                    },
+                );
+            }
+        }
+        self.value
+    }
+
+    /// Returns true if leak detection is enabled and the database was modified
+    /// since the guard was created, i.e., if [`ReadGuard::get`] would panic.
+    pub fn is_stale(&self) -> bool {
+        self.tag.as_ref().is_some_and(Tag::is_stale)
+    }
+
+    /// Extends the lifetime of the guard, so that it can be stored beyond the borrow of the database.
+    ///
+    /// # Safety
+    ///
+    /// The guard must not be dereferenced after the database was modified or dropped,
+    /// nor while another handle to the database may modify it. Leak detection catches
+    /// some violations of the former, when the guard is dereferenced after the
+    /// modification, but it is a debugging aid and not a guarantee.
+    pub unsafe fn detach(self) -> ReadGuard<'static, T>
+    where
+        T: 'static,
+    {
+        ReadGuard {
+            // SAFETY: Upheld by the caller.
+            value: unsafe { std::mem::transmute::<&'db T, &'static T>(self.value) },
+            tag: self.tag,
+        }
+    }
+}
+
+impl Tag {
+    fn is_stale(&self) -> bool {
+        self.generation.load(Ordering::Acquire) != self.created_in
+    }
+}
+
+impl<T: ?Sized> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: ?Sized> Clone for ReadGuard<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            tag: self.tag.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tag {
+            Some(tag) if tag.is_stale() => f
+                .debug_struct("ReadGuard")
+                .field("revision", &tag.revision)
+                .field("query", &tag.query)
+                .field("stale", &true)
+                .finish(),
+            _ => fmt::Debug::fmt(self.value, f),
+        }
+    }
+}
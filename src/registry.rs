@@ -0,0 +1,72 @@
+use crate::{
+    external::{ExternalIngredient, JarImpl},
+    ingredient::Jar,
+    zalsa::IngredientIndex,
+    zalsa::Zalsa,
+};
+
+/// A group of salsa structs, tracked functions and accumulators whose ingredients
+/// are registered together, ahead of their first use; see [`Storage::register`](`crate::Storage::register`).
+///
+/// Every salsa struct, tracked function and accumulator is a group of its own.
+/// Plugins extending the query graph of a host typically implement this trait for a
+/// type of their own, registering their items:
+///
+/// ```
+/// # #[salsa::input]
+/// # struct File { text: String }
+/// # #[salsa::tracked]
+/// # fn word_count(db: &dyn salsa::Database, file: File) -> usize { 0 }
+/// struct WordCountPlugin;
+///
+/// impl salsa::JarGroup for WordCountPlugin {
+///     fn register(registry: &salsa::Registry<'_>) {
+///         registry.add::<File>().add::<word_count>();
+///     }
+/// }
+///
+/// let db = salsa::DatabaseImpl::new().with_jar::<WordCountPlugin>();
+/// ```
+///
+/// Ingredients are otherwise registered lazily when first used, so their indices depend on
+/// the order in which queries happen to run. Registering them up front makes the indices
+/// the same for every database registering the same groups in the same order.
+///
+/// Items defined in a dynamically loaded library can be registered like any other, provided
+/// the library links against the same build of salsa as the host, so that type ids agree.
+pub trait JarGroup: 'static {
+    /// Registers the ingredients of this group with `registry`.
+    fn register(registry: &Registry<'_>);
+}
+
+/// Registers the ingredients of [`JarGroup`]s with a database.
+///
+/// Registering the same item again has no effect.
+pub struct Registry<'a> {
+    zalsa: &'a Zalsa,
+}
+
+impl<'a> Registry<'a> {
+    pub(crate) fn new(zalsa: &'a Zalsa) -> Self {
+        Self { zalsa }
+    }
+
+    /// Registers the ingredients of the group `G`.
+    pub fn add<G: JarGroup>(&self) -> &Self {
+        G::register(self);
+        self
+    }
+
+    /// Registers the external ingredient `I`.
+    pub fn add_external<I: ExternalIngredient>(&self) -> &Self {
+        self.zalsa.add_or_lookup_jar_by_type(&JarImpl::<I>::new());
+        self
+    }
+
+    /// Registers the ingredients of `jar`, returning the index of the first one.
+    ///
+    /// **NOT SEMVER STABLE**
+    pub fn add_jar(&self, jar: &dyn Jar) -> IngredientIndex {
+        self.zalsa.add_or_lookup_jar_by_type(jar)
+    }
+}
@@ -0,0 +1,183 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::key::InputDependencyIndex;
+use crate::zalsa_local::QueryEdges;
+use crate::{Cancelled, Cycle, Database, Revision};
+
+/// How many verification tasks may be queued per worker thread before
+/// further work is left to the thread doing the deep verification.
+const MAX_PENDING_PER_THREAD: usize = 4;
+
+/// A bounded pool of worker threads used to speculatively revalidate the inputs
+/// of a memo while its dependencies are walked in order by the thread that is
+/// verifying it.
+///
+/// After a change to a high-durability input, the first read of a query tends
+/// to trigger a "revalidation storm": every memo of high durability has to be
+/// deep-verified, and all of that work happens on a single thread. When the pool
+/// is enabled, `deep_verify_memo` queues the
+/// inputs of each memo it verifies so that they can be verified in parallel.
+///
+/// The work done by the pool is purely a warm-up: the verifying thread still walks
+/// every input in execution order, but it typically finds those inputs already
+/// verified (or blocks on the worker currently verifying them). Once it returns, e.g.
+/// because an input changed, the inputs the tasks did not verify yet are skipped: the
+/// later inputs may not even be read when the memo is re-executed.
+///
+/// The tasks never execute queries: a memo whose inputs changed is reported as changed,
+/// and left for the verifying thread to re-execute if it reads it. Each task runs on a fork
+/// of the database and verifies a share of the inputs. Tasks observe cancellation like any
+/// other handle, so a pending write will unwind (and discard) any queued work. Other panics
+/// are raised again on a verifying thread, by [`Prefetch::finish`] or the next call to
+/// [`RevalidationPool::prefetch`].
+pub(crate) struct RevalidationPool {
+    pool: rayon::ThreadPool,
+
+    /// Number of tasks that have been queued but not yet completed.
+    pending: Arc<AtomicUsize>,
+
+    /// Upper bound for `pending`.
+    max_pending: usize,
+
+    /// The panic of a task, if any, to raise again on the next verifying thread.
+    panic: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
+}
+
+impl RevalidationPool {
+    pub(crate) fn new(num_threads: usize) -> Self {
+        assert!(num_threads > 0);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("salsa-revalidation-{index}"))
+            .build()
+            .expect("failed to create revalidation thread pool");
+        Self {
+            pool,
+            pending: Default::default(),
+            max_pending: num_threads * MAX_PENDING_PER_THREAD,
+            panic: Default::default(),
+        }
+    }
+
+    /// Queues the inputs from `edges` to be verified against `last_verified_at`.
+    ///
+    /// The first input is skipped, as the caller is about to verify it itself. The others
+    /// are shared among at most one task per worker thread, each on its own fork of `db`,
+    /// and each verifying its share in order. Once the pool is saturated, no more tasks
+    /// are queued.
+    ///
+    /// The inputs not verified yet when the returned [`Prefetch`] is dropped are skipped;
+    /// the caller should keep it until it has walked the inputs.
+    pub(crate) fn prefetch(
+        &self,
+        db: &dyn Database,
+        edges: &QueryEdges,
+        last_verified_at: Revision,
+    ) -> Prefetch {
+        resume_panic(&self.panic);
+
+        let prefetch = Prefetch {
+            obsolete: Default::default(),
+            panic: self.panic.clone(),
+        };
+
+        // SAFETY: The edges belong to a memo, which is freed at the earliest when a new
+        // revision starts (see `deleted_entries`). Each task holds a fork of `db` until it
+        // no longer reads the edges, which prevents new revisions from starting meanwhile.
+        let edges = unsafe { std::mem::transmute::<&QueryEdges, &'static QueryEdges>(edges) };
+        let tasks = self
+            .pool
+            .current_num_threads()
+            .min(prefetched_inputs(edges).count());
+        for task in 0..tasks {
+            if !self.try_reserve() {
+                break;
+            }
+
+            let fork = db.fork_db();
+            fork.zalsa_local().set_verify_only();
+            let pending = self.pending.clone();
+            let obsolete = prefetch.obsolete.clone();
+            let panic = self.panic.clone();
+            self.pool.spawn(move || {
+                // Interleave the shares, so that the first inputs are verified first.
+                for input in prefetched_inputs(edges).skip(task).step_by(tasks) {
+                    if obsolete.load(Ordering::Acquire) || fork.zalsa().load_cancellation_flag() {
+                        break;
+                    }
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        input.maybe_changed_after(&*fork, last_verified_at)
+                    }));
+                    if let Err(payload) = result {
+                        // Cancellation and cycles are for the verifying thread to deal with,
+                        // if it reaches this input at all.
+                        if !payload.is::<Cancelled>() && !payload.is::<Cycle>() {
+                            panic.lock().get_or_insert(payload);
+                        }
+                        break;
+                    }
+                }
+                drop(fork);
+                pending.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+        prefetch
+    }
+
+    fn try_reserve(&self) -> bool {
+        self.pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                (pending < self.max_pending).then_some(pending + 1)
+            })
+            .is_ok()
+    }
+}
+
+/// The inputs of `edges` that the tasks verify: the first one is verified by the caller,
+/// and the inputs that are not keyed are cheap to verify.
+fn prefetched_inputs(edges: &QueryEdges) -> impl Iterator<Item = InputDependencyIndex> + '_ {
+    edges.inputs().skip(1).filter(|input| input.is_keyed())
+}
+
+/// The tasks queued by one call to [`RevalidationPool::prefetch`];
+/// the inputs they did not verify yet are skipped once this is dropped.
+pub(crate) struct Prefetch {
+    obsolete: Arc<AtomicBool>,
+    panic: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
+}
+
+impl Prefetch {
+    /// Skips the inputs not verified yet, and raises the panic of a task again, if any.
+    /// Invoked once the caller verified all inputs itself.
+    pub(crate) fn finish(self) {
+        self.obsolete.store(true, Ordering::Release);
+        resume_panic(&self.panic);
+    }
+}
+
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.obsolete.store(true, Ordering::Release);
+    }
+}
+
+/// Raises the panic of a task again, if any.
+fn resume_panic(panic: &Mutex<Option<Box<dyn Any + Send>>>) {
+    if let Some(payload) = panic.lock().take() {
+        std::panic::resume_unwind(payload);
+    }
+}
+
+impl std::fmt::Debug for RevalidationPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RevalidationPool")
+            .field("num_threads", &self.pool.current_num_threads())
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
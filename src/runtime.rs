@@ -6,19 +6,34 @@ use std::{
         Arc,
     },
     thread::ThreadId,
+    time::Duration,
 };
 
+use arc_swap::ArcSwapOption;
 use parking_lot::Mutex;
 
 use crate::{
-    active_query::ActiveQuery, cycle::CycleRecoveryStrategy, durability::Durability,
-    key::DatabaseKeyIndex, revision::AtomicRevision, table::Table, zalsa_local::ZalsaLocal,
-    Cancelled, Cycle, Database, Event, EventKind, Revision,
+    active_query::ActiveQuery,
+    cycle::CycleRecoveryStrategy,
+    durability::{Durabilities, Durability},
+    key::DatabaseKeyIndex,
+    revision::AtomicRevision,
+    table::sync::SharedMemo,
+    table::Table,
+    zalsa_local::ZalsaLocal,
+    Cancelled, Cycle, Database, Deadlock, Event, EventKind, Priority, Revision,
 };
 
+pub use self::blocking::{BlockingStrategy, Waiter};
 use self::dependency_graph::DependencyGraph;
+pub use self::revision_log::InputChange;
+use self::revision_log::RevisionLog;
+pub(crate) use self::speculation::{Retire, Speculation};
 
+mod blocking;
 mod dependency_graph;
+mod revision_log;
+mod speculation;
 
 pub struct Runtime {
     /// Set to true when the current revision has been canceled.
@@ -37,19 +52,48 @@ pub struct Runtime {
     /// with durability less than D may have changed too.
     revisions: [AtomicRevision; Durability::LEN],
 
+    /// Stores the revision in which an input of exactly each durability last changed.
+    /// Unlike `revisions`, a change to a value of durability D says nothing about the
+    /// values of other durabilities, see [`Runtime::last_changed_revision_of`].
+    durability_changes: [AtomicRevision; Durability::LEN],
+
     /// The dependency graph tracks which runtimes are blocked on one
     /// another, waiting for queries to terminate.
     dependency_graph: Mutex<DependencyGraph>,
 
+    /// Invoked when a thread has to wait for a query executing on another thread, if set.
+    blocking_strategy: ArcSwapOption<Box<dyn BlockingStrategy>>,
+
     /// Data for instances
     table: Table,
+
+    /// Log of the most recent input changes, if enabled.
+    revision_log: Mutex<RevisionLog>,
+
+    /// The active [`Database::speculate`](`crate::Database::speculate`) call, if any.
+    speculation: Mutex<Option<Speculation>>,
+
+    /// Revisions of past speculations, as `(start, end)` ranges, both exclusive.
+    /// Memos verified in these revisions observed inputs that were rolled back.
+    ///
+    /// Speculations do not nest and revisions only grow, so the ranges are disjoint
+    /// and sorted, see [`Runtime::is_speculative_revision`].
+    speculative_revisions: Vec<(Revision, Revision)>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum WaitResult {
-    Completed,
+    /// The query completed; if its memo was verified in the current revision,
+    /// it is shared with the waiting threads.
+    Completed(Option<SharedMemo>),
     Panicked,
     Cycle(Cycle),
+
+    /// The query was preempted by a thread of higher priority: the waiting threads retry.
+    Preempted,
+
+    /// Not sent by another thread: the waiting thread gave up after the deadlock timeout.
+    Deadlock(Deadlock),
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -82,9 +126,14 @@ impl Default for Runtime {
     fn default() -> Self {
         Runtime {
             revisions: [const { AtomicRevision::start() }; Durability::LEN],
+            durability_changes: [const { AtomicRevision::start() }; Durability::LEN],
             revision_canceled: Default::default(),
             dependency_graph: Default::default(),
+            blocking_strategy: Default::default(),
             table: Default::default(),
+            revision_log: Default::default(),
+            speculation: Default::default(),
+            speculative_revisions: Default::default(),
         }
     }
 }
@@ -112,6 +161,7 @@ impl Runtime {
         for rev in &self.revisions[1..=durability.index()] {
             rev.store(new_revision);
         }
+        self.durability_changes[durability.index()].store(new_revision);
     }
 
     /// The revision in which values with durability `d` may have last
@@ -126,6 +176,21 @@ impl Runtime {
         self.revisions[d.index()].load()
     }
 
+    /// The revision in which a value read by a query whose inputs have the durabilities
+    /// `durabilities` may have last changed: a finer bound than
+    /// [`last_changed_revision`](Self::last_changed_revision) of their minimum, which
+    /// also accounts for changes to inputs of higher durabilities that the query did not read.
+    pub(crate) fn last_changed_revision_of(&self, durabilities: Durabilities) -> Revision {
+        if durabilities.is_untracked() {
+            return self.current_revision();
+        }
+        (0..Durability::LEN)
+            .filter(|&index| durabilities.contains(Durability::from_index(index)))
+            .map(|index| self.durability_changes[index].load())
+            .max()
+            .unwrap_or_else(Revision::start)
+    }
+
     pub(crate) fn load_cancellation_flag(&self) -> bool {
         self.revision_canceled.load(Ordering::Acquire)
     }
@@ -134,10 +199,133 @@ impl Runtime {
         self.revision_canceled.store(true, Ordering::Release);
     }
 
+    pub(crate) fn reset_cancellation_flag(&mut self) {
+        self.revision_canceled.store(false, Ordering::Release);
+    }
+
+    /// Records that the input field `key` was set in the current revision.
+    pub(crate) fn record_input_change(&mut self, key: DatabaseKeyIndex, durability: Durability) {
+        let revision = self.current_revision();
+        self.revision_log.get_mut().record(InputChange {
+            revision,
+            key,
+            durability,
+        });
+    }
+
+    pub(crate) fn set_revision_log_capacity(&self, capacity: usize) {
+        self.revision_log.lock().set_capacity(capacity);
+    }
+
+    pub(crate) fn revision_log(&self) -> Vec<InputChange> {
+        self.revision_log.lock().changes()
+    }
+
+    /// Begins a speculation in the current revision.
+    ///
+    /// # Panics
+    ///
+    /// If a speculation is already active.
+    pub(crate) fn begin_speculation(&self) {
+        let mut speculation = self.speculation.lock();
+        assert!(
+            speculation.is_none(),
+            "`Database::speculate` cannot be called within another speculation"
+        );
+        *speculation = Some(Speculation::new(self.current_revision()));
+    }
+
+    /// The active speculation, if any.
+    pub(crate) fn speculation_mut(&mut self) -> Option<&mut Speculation> {
+        self.speculation.get_mut().as_mut()
+    }
+
+    /// Records that the input `id` was created during the active speculation, if any.
+    pub(crate) fn record_speculative_input(
+        &self,
+        ingredient_index: crate::zalsa::IngredientIndex,
+        id: crate::Id,
+        retire: Retire,
+    ) {
+        if let Some(speculation) = &mut *self.speculation.lock() {
+            speculation.record_created(ingredient_index, id, retire);
+        }
+    }
+
+    /// Ends the active speculation, whose revisions end before the current one.
+    pub(crate) fn end_speculation(&mut self) -> Speculation {
+        let speculation = self
+            .speculation
+            .get_mut()
+            .take()
+            .expect("no active speculation");
+        let range = (speculation.start(), self.current_revision());
+        debug_assert!(self
+            .speculative_revisions
+            .last()
+            .map_or(true, |&(_, end)| end <= range.0));
+        self.speculative_revisions.push(range);
+        speculation
+    }
+
+    /// True if `revision` belongs to a past speculation.
+    pub(crate) fn is_speculative_revision(&self, revision: Revision) -> bool {
+        // The first range that ends after `revision` is the only one that may contain it.
+        let index = self
+            .speculative_revisions
+            .partition_point(|&(_, end)| end <= revision);
+        self.speculative_revisions
+            .get(index)
+            .is_some_and(|&(start, _)| start < revision)
+    }
+
+    pub(crate) fn set_blocking_strategy(&self, blocking_strategy: Box<dyn BlockingStrategy>) {
+        self.blocking_strategy
+            .store(Some(Arc::new(blocking_strategy)));
+    }
+
+    pub(crate) fn set_deadlock_timeout(&self, timeout: Option<Duration>) {
+        self.dependency_graph.lock().set_deadlock_timeout(timeout);
+    }
+
+    pub(crate) fn set_block_warning_interval(&self, interval: Option<Duration>) {
+        self.dependency_graph
+            .lock()
+            .set_block_warning_interval(interval);
+    }
+
+    /// True if a thread with a priority higher than `priority` is (transitively)
+    /// blocked on the thread `id`.
+    pub(crate) fn has_waiters_above(&self, id: ThreadId, priority: Priority) -> bool {
+        self.dependency_graph
+            .lock()
+            .inherited_priority(id)
+            .is_some_and(|inherited| inherited > priority)
+    }
+
+    /// See [`DependencyGraph::preempt_flag`][]
+    pub(crate) fn preempt_flag(&self, id: ThreadId) -> Arc<AtomicBool> {
+        self.dependency_graph.lock().preempt_flag(id)
+    }
+
+    /// See [`DependencyGraph::release_preempt_flag`][]
+    pub(crate) fn release_preempt_flag(&self, id: ThreadId, flag: Arc<AtomicBool>) {
+        self.dependency_graph.lock().release_preempt_flag(id, flag)
+    }
+
+    /// See [`DependencyGraph::take_preempt_request`][]
+    pub(crate) fn take_preempt_request(&self, id: ThreadId, flag: &AtomicBool) -> bool {
+        self.dependency_graph.lock().take_preempt_request(id, flag)
+    }
+
     pub(crate) fn table(&self) -> &Table {
         &self.table
     }
 
+    pub(crate) fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
     /// Increments the "current revision" counter and clears
     /// the cancellation flag.
     ///
@@ -157,6 +345,8 @@ impl Runtime {
     /// it will be dropped after we have successfully registered the
     /// dependency.
     ///
+    /// Returns the memo shared by `other_id`, if any.
+    ///
     /// # Propagating panics
     ///
     /// If the thread `other_id` panics, then our thread is considered
@@ -179,10 +369,16 @@ impl Runtime {
         database_key: DatabaseKeyIndex,
         other_id: ThreadId,
         query_mutex_guard: QueryMutexGuard,
-    ) {
+    ) -> Option<SharedMemo> {
         let mut dg = self.dependency_graph.lock();
         let thread_id = std::thread::current().id();
 
+        assert!(
+            !dg.is_blocked(thread_id),
+            "cannot wait for a query executing on another thread \
+            while a `BlockingStrategy` waits on this thread"
+        );
+
         if dg.depends_on(other_id, thread_id) {
             self.unblock_cycle_and_maybe_throw(db, local_state, &mut dg, database_key, other_id);
 
@@ -198,21 +394,42 @@ impl Runtime {
             })
         });
 
+        let blocking_strategy = self.blocking_strategy.load();
+        #[cfg(feature = "stats")]
+        let blocked_since = std::time::Instant::now();
         let result = local_state.with_query_stack(|stack| {
             let (new_stack, result) = DependencyGraph::block_on(
                 dg,
                 thread_id,
+                local_state.priority(),
                 database_key,
                 other_id,
                 mem::take(stack),
                 query_mutex_guard,
+                blocking_strategy.as_deref().map(|strategy| &**strategy),
+                &|waited| {
+                    db.salsa_event(&|| {
+                        Event::new(EventKind::StillBlockedOn {
+                            other_thread_id: other_id,
+                            database_key,
+                            waited,
+                        })
+                    })
+                },
             );
             *stack = new_stack;
             result
         });
 
+        #[cfg(feature = "stats")]
+        db.zalsa()
+            .lookup_ingredient(database_key.ingredient_index)
+            .record_blocked(blocked_since.elapsed());
+
         match result {
-            WaitResult::Completed => (),
+            WaitResult::Completed(shared_memo) => shared_memo,
+
+            WaitResult::Preempted => None,
 
             // If the other thread panicked, then we consider this thread
             // cancelled. The assumption is that the panic will be detected
@@ -220,6 +437,8 @@ impl Runtime {
             WaitResult::Panicked => Cancelled::PropagatedPanic.throw(),
 
             WaitResult::Cycle(c) => c.throw(),
+
+            WaitResult::Deadlock(deadlock) => deadlock.throw(),
         }
     }
 
@@ -268,15 +487,15 @@ impl Runtime {
                     },
                 );
 
-                // We want to give the participants in a deterministic order
-                // (at least for this execution, not necessarily across executions),
-                // no matter where it started on the stack. Find the minimum
-                // key and rotate it to the front.
+                // We want to give the participants in a deterministic order,
+                // no matter where it started on the stack (and hence on which thread
+                // entered the cycle first). Find the minimum key, by function name and
+                // then by id, and rotate it to the front.
 
-                if let Some((_, index)) = v
+                if let Some((_, _, index)) = v
                     .iter()
                     .enumerate()
-                    .map(|(idx, key)| (key.ingredient_index.debug_name(db), idx))
+                    .map(|(idx, key)| (key.ingredient_index.debug_name(db), key.key_index, idx))
                     .min()
                 {
                     v.rotate_left(index);
@@ -0,0 +1,139 @@
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::dependency_graph::{DependencyGraph, QueryStack};
+use super::WaitResult;
+use crate::key::DatabaseKeyIndex;
+
+/// Decides what a thread does while it waits for a query that is being executed
+/// by another thread.
+///
+/// By default, the waiting thread is parked until the other thread completes the query.
+/// When the database is used from a thread pool, many workers contending on one hot query
+/// can park most of the pool this way. A `BlockingStrategy` registered with
+/// [`Storage::set_blocking_strategy`](`crate::Storage::set_blocking_strategy`) lets the
+/// executor know that the thread is about to block, so that it can hand the thread's other
+/// tasks to another worker (e.g., with `tokio::task::block_in_place`), and lets the thread
+/// poll the [`Waiter`] instead of parking (e.g., to back off or to report progress).
+///
+/// # Work executed while waiting
+///
+/// The queries of the waiting thread are suspended until [`BlockingStrategy::block`]
+/// returns, so the strategy cannot execute salsa queries on this thread. In particular,
+/// it must not run the tasks of a work-stealing pool that may use a salsa database
+/// (e.g., with `rayon::yield_now`), and salsa panics if a tracked function is invoked
+/// from within [`BlockingStrategy::block`]. Tasks that do not use salsa may run.
+pub trait BlockingStrategy: Send + Sync + 'static {
+    /// Invoked when the current thread has to wait for a query executing on another thread.
+    ///
+    /// The strategy returns once [`Waiter::is_done`] returns true, typically by calling
+    /// [`Waiter::wait`] or [`Waiter::wait_timeout`]. If it returns earlier, salsa parks the
+    /// thread until the query completed (or the deadlock timeout elapsed).
+    fn block(&self, waiter: &mut Waiter<'_>);
+}
+
+/// Handle on the wait for a query executing on another thread, see [`BlockingStrategy`].
+pub struct Waiter<'a> {
+    dependency_graph: &'a Mutex<DependencyGraph>,
+    condvar: &'a Condvar,
+    deadline: Option<Instant>,
+    thread_id: ThreadId,
+    database_key: DatabaseKeyIndex,
+    other_thread_id: ThreadId,
+    result: Option<(QueryStack, WaitResult)>,
+}
+
+impl<'a> Waiter<'a> {
+    pub(super) fn new(
+        dependency_graph: &'a Mutex<DependencyGraph>,
+        condvar: &'a Condvar,
+        deadline: Option<Instant>,
+        thread_id: ThreadId,
+        database_key: DatabaseKeyIndex,
+        other_thread_id: ThreadId,
+    ) -> Self {
+        Self {
+            dependency_graph,
+            condvar,
+            deadline,
+            thread_id,
+            database_key,
+            other_thread_id,
+            result: None,
+        }
+    }
+
+    /// The query being waited for.
+    pub fn database_key(&self) -> DatabaseKeyIndex {
+        self.database_key
+    }
+
+    /// The thread executing the query being waited for.
+    pub fn other_thread_id(&self) -> ThreadId {
+        self.other_thread_id
+    }
+
+    /// Returns true once the query being waited for has completed. Never blocks.
+    pub fn is_done(&mut self) -> bool {
+        if self.result.is_none() {
+            self.result = self
+                .dependency_graph
+                .lock()
+                .take_wait_result(self.thread_id);
+        }
+        self.result.is_some()
+    }
+
+    /// Parks the current thread until the query being waited for has completed.
+    ///
+    /// If a [deadlock timeout](`crate::Storage::set_deadlock_timeout`) is set, returns
+    /// once it elapsed, even though the query has not completed.
+    pub fn wait(&mut self) {
+        loop {
+            let timeout = match self.deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) => timeout,
+                    None => return,
+                },
+                None => Duration::MAX,
+            };
+            if self.wait_timeout(timeout) {
+                return;
+            }
+        }
+    }
+
+    /// Parks the current thread until the query being waited for has completed
+    /// or `timeout` has elapsed. Returns true if the query has completed.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> bool {
+        if self.result.is_some() {
+            return true;
+        }
+
+        let mut dg = self.dependency_graph.lock();
+        self.result = dg.take_wait_result(self.thread_id);
+        if self.result.is_none() {
+            self.condvar.wait_for(&mut dg, timeout);
+            self.result = dg.take_wait_result(self.thread_id);
+        }
+        self.result.is_some()
+    }
+
+    /// Returns the result if the query has completed; otherwise the caller keeps waiting.
+    pub(super) fn into_result(mut self) -> Option<(QueryStack, WaitResult)> {
+        self.is_done();
+        self.result.take()
+    }
+}
+
+impl std::fmt::Debug for Waiter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Waiter")
+            .field("database_key", &self.database_key)
+            .field("other_thread_id", &self.other_thread_id)
+            .field("done", &self.result.is_some())
+            .finish()
+    }
+}
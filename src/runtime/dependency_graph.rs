@@ -1,14 +1,21 @@
+use std::collections::hash_map::Entry;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 use crate::active_query::ActiveQuery;
+use crate::deadlock::{Deadlock, QueryWait};
 use crate::key::DatabaseKeyIndex;
 use crate::runtime::WaitResult;
+use crate::Priority;
 use parking_lot::{Condvar, MutexGuard};
+
+use super::blocking::{BlockingStrategy, Waiter};
 use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
 
-type QueryStack = Vec<ActiveQuery>;
+pub(super) type QueryStack = Vec<ActiveQuery>;
 
 #[derive(Debug, Default)]
 pub(super) struct DependencyGraph {
@@ -26,6 +33,33 @@ pub(super) struct DependencyGraph {
     /// it stores its `WaitResult` here. As they wake up, each query Q in Qs will
     /// come here to fetch their results.
     wait_results: FxHashMap<ThreadId, (QueryStack, WaitResult)>,
+
+    /// Number of threads of each priority that are (transitively) blocked on a thread,
+    /// kept up to date as edges are added and removed.
+    waiters: FxHashMap<ThreadId, Waiters>,
+
+    /// Flags of the low-priority threads that checked whether they were preempted,
+    /// set whenever a thread of higher priority (transitively) blocks on them.
+    /// Lets those threads skip locking the graph until then.
+    preempt_requested: FxHashMap<ThreadId, Arc<AtomicBool>>,
+
+    /// How long a thread may wait for another before reporting a [`Deadlock`].
+    deadlock_timeout: Option<Duration>,
+
+    /// How often a waiting thread reports that it is still waiting.
+    block_warning_interval: Option<Duration>,
+}
+
+/// Number of threads of each [`Priority`], indexed by the priority.
+#[derive(Copy, Clone, Debug, Default)]
+struct Waiters([usize; 3]);
+
+impl Waiters {
+    fn highest(&self) -> Option<Priority> {
+        [Priority::High, Priority::Normal, Priority::Low]
+            .into_iter()
+            .find(|&priority| self.0[priority as usize] > 0)
+    }
 }
 
 #[derive(Debug)]
@@ -34,6 +68,9 @@ struct Edge {
     blocked_on_key: DatabaseKeyIndex,
     stack: QueryStack,
 
+    /// Priority of the blocked thread.
+    priority: Priority,
+
     /// Signalled whenever a query with dependents completes.
     /// Allows those dependents to check if they are ready to unblock.
     condvar: Arc<parking_lot::Condvar>,
@@ -177,35 +214,213 @@ impl DependencyGraph {
     /// * No path from `to_id` to `from_id`
     ///   (i.e., `me.depends_on(to_id, from_id)` is false)
     /// * `held_mutex` is a read lock (or stronger) on `database_key`
+    ///
+    /// `still_blocked` is invoked, without holding the lock on the graph, with the time
+    /// waited so far whenever the block warning interval elapses.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn block_on<QueryMutexGuard>(
         mut me: MutexGuard<'_, Self>,
         from_id: ThreadId,
+        from_priority: Priority,
         database_key: DatabaseKeyIndex,
         to_id: ThreadId,
         from_stack: QueryStack,
         query_mutex_guard: QueryMutexGuard,
+        blocking_strategy: Option<&dyn BlockingStrategy>,
+        still_blocked: &dyn Fn(Duration),
     ) -> (QueryStack, WaitResult) {
-        let condvar = me.add_edge(from_id, database_key, to_id, from_stack);
+        let condvar = me.add_edge(from_id, from_priority, database_key, to_id, from_stack);
 
         // Release the mutex that prevents `database_key`
         // from completing, now that the edge has been added.
         drop(query_mutex_guard);
 
+        let start = Instant::now();
+        let timeout = me.deadlock_timeout;
+        let deadline = timeout.and_then(|timeout| start.checked_add(timeout));
+        let warning_interval = me.block_warning_interval;
+        let mut next_warning = warning_interval.and_then(|interval| start.checked_add(interval));
+
+        if let Some(blocking_strategy) = blocking_strategy {
+            let dependency_graph = MutexGuard::mutex(&me);
+            drop(me);
+            let mut waiter = Waiter::new(
+                dependency_graph,
+                &condvar,
+                deadline,
+                from_id,
+                database_key,
+                to_id,
+            );
+            crate::attach::while_blocking(|| blocking_strategy.block(&mut waiter));
+            if let Some(stack_and_result) = waiter.into_result() {
+                return stack_and_result;
+            }
+            me = dependency_graph.lock();
+        }
+
         loop {
-            if let Some(stack_and_result) = me.wait_results.remove(&from_id) {
-                debug_assert!(!me.edges.contains_key(&from_id));
+            if let Some(stack_and_result) = me.take_wait_result(from_id) {
                 return stack_and_result;
             }
-            condvar.wait(&mut me);
+
+            let wake_up = match (deadline, next_warning) {
+                (Some(deadline), Some(warning)) => Some(deadline.min(warning)),
+                (deadline, warning) => deadline.or(warning),
+            };
+            let Some(wake_up) = wake_up else {
+                condvar.wait(&mut me);
+                continue;
+            };
+            if !condvar.wait_until(&mut me, wake_up).timed_out() {
+                continue;
+            }
+            if let Some(stack_and_result) = me.take_wait_result(from_id) {
+                return stack_and_result;
+            }
+
+            let now = Instant::now();
+            if let (Some(timeout), Some(deadline)) = (timeout, deadline) {
+                if now >= deadline {
+                    return me.give_up(from_id, timeout);
+                }
+            }
+            if let (Some(interval), Some(warning)) = (warning_interval, next_warning) {
+                if now >= warning {
+                    MutexGuard::unlocked(&mut me, || still_blocked(now - start));
+                    next_warning = warning.checked_add(interval);
+                }
+            }
+        }
+    }
+
+    /// Invoked when `from_id` waited for longer than the deadlock timeout:
+    /// removes its edge and reports the chain of waits it was part of.
+    fn give_up(&mut self, from_id: ThreadId, timeout: Duration) -> (QueryStack, WaitResult) {
+        let mut waits = vec![];
+        let mut id = from_id;
+        while let Some(edge) = self.edges.get(&id) {
+            waits.push(QueryWait {
+                thread_id: id,
+                database_key: edge.blocked_on_key,
+                other_thread_id: edge.blocked_on_id,
+            });
+            id = edge.blocked_on_id;
+        }
+
+        let edge = self.remove_edge(from_id);
+        if let Some(dependents) = self.query_dependents.get_mut(&edge.blocked_on_key) {
+            dependents.retain(|id| *id != from_id);
+        }
+
+        let deadlock = Deadlock { timeout, waits };
+        (edge.stack, WaitResult::Deadlock(deadlock))
+    }
+
+    pub(super) fn set_deadlock_timeout(&mut self, timeout: Option<Duration>) {
+        self.deadlock_timeout = timeout;
+    }
+
+    pub(super) fn set_block_warning_interval(&mut self, interval: Option<Duration>) {
+        self.block_warning_interval = interval;
+    }
+
+    /// Highest priority among the threads that are (transitively) blocked on `id`.
+    pub(super) fn inherited_priority(&self, id: ThreadId) -> Option<Priority> {
+        self.waiters.get(&id).and_then(Waiters::highest)
+    }
+
+    /// Adds `from_id`, of priority `from_priority`, and the threads blocked on it to the
+    /// waiters of `to_id` and of every thread `to_id` is (transitively) blocked on.
+    /// Subtracts them instead if `remove` is set, once the edge from `from_id` is gone.
+    fn update_waiters(
+        &mut self,
+        from_id: ThreadId,
+        from_priority: Priority,
+        to_id: ThreadId,
+        remove: bool,
+    ) {
+        let mut delta = self.waiters.get(&from_id).copied().unwrap_or_default();
+        delta.0[from_priority as usize] += 1;
+
+        let mut id = to_id;
+        loop {
+            let waiters = self.waiters.entry(id).or_default();
+            for (count, delta) in waiters.0.iter_mut().zip(delta.0) {
+                if remove {
+                    *count -= delta;
+                } else {
+                    *count += delta;
+                }
+            }
+            if waiters.highest().is_none() {
+                self.waiters.remove(&id);
+            }
+            match self.edges.get(&id) {
+                Some(edge) => id = edge.blocked_on_id,
+                None => break,
+            }
         }
     }
 
+    /// Removes the edge of `id`, which must be blocked.
+    fn remove_edge(&mut self, id: ThreadId) -> Edge {
+        let edge = self.edges.remove(&id).expect("not blocked");
+        self.update_waiters(id, edge.priority, edge.blocked_on_id, true);
+        edge
+    }
+
+    /// Returns the flag set when a thread of priority higher than [`Priority::Low`]
+    /// (transitively) blocks on `id`. The flag of a thread starts out set.
+    pub(super) fn preempt_flag(&mut self, id: ThreadId) -> Arc<AtomicBool> {
+        self.preempt_requested
+            .entry(id)
+            .or_insert_with(|| Arc::new(AtomicBool::new(true)))
+            .clone()
+    }
+
+    /// Releases `flag`, a [preempt flag](`Self::preempt_flag`) of `id`, removing it from
+    /// the graph once no handle running on `id` holds it anymore.
+    pub(super) fn release_preempt_flag(&mut self, id: ThreadId, flag: Arc<AtomicBool>) {
+        drop(flag);
+        if let Entry::Occupied(entry) = self.preempt_requested.entry(id) {
+            if Arc::strong_count(entry.get()) == 1 {
+                entry.remove();
+            }
+        }
+    }
+
+    /// True if a thread of priority higher than [`Priority::Low`] is (transitively)
+    /// blocked on `id`. Otherwise, clears `flag`, the [preempt flag](`Self::preempt_flag`) of `id`.
+    pub(super) fn take_preempt_request(&self, id: ThreadId, flag: &AtomicBool) -> bool {
+        let requested = self
+            .inherited_priority(id)
+            .is_some_and(|inherited| inherited > Priority::Low);
+        if !requested {
+            flag.store(false, Ordering::Release);
+        }
+        requested
+    }
+
+    /// True if `id` is currently blocked on another thread.
+    pub(super) fn is_blocked(&self, id: ThreadId) -> bool {
+        self.edges.contains_key(&id)
+    }
+
+    /// Removes the result stored for `id` once the query it was blocked on completed.
+    pub(super) fn take_wait_result(&mut self, id: ThreadId) -> Option<(QueryStack, WaitResult)> {
+        let stack_and_result = self.wait_results.remove(&id);
+        debug_assert!(stack_and_result.is_none() || !self.edges.contains_key(&id));
+        stack_and_result
+    }
+
     /// Helper for `block_on`: performs actual graph modification
     /// to add a dependency edge from `from_id` to `to_id`, which is
     /// computing `database_key`.
     fn add_edge(
         &mut self,
         from_id: ThreadId,
+        from_priority: Priority,
         database_key: DatabaseKeyIndex,
         to_id: ThreadId,
         from_stack: QueryStack,
@@ -221,6 +436,7 @@ impl DependencyGraph {
                 blocked_on_id: to_id,
                 blocked_on_key: database_key,
                 stack: from_stack,
+                priority: from_priority,
                 condvar: condvar.clone(),
             },
         );
@@ -228,6 +444,23 @@ impl DependencyGraph {
             .entry(database_key)
             .or_default()
             .push(from_id);
+        self.update_waiters(from_id, from_priority, to_id, false);
+
+        // Request the threads `from_id` now (transitively) waits for to check whether
+        // they are preempted.
+        if Some(from_priority).max(self.inherited_priority(from_id)) > Some(Priority::Low) {
+            let mut id = to_id;
+            loop {
+                if let Some(flag) = self.preempt_requested.get(&id) {
+                    flag.store(true, Ordering::Release);
+                }
+                match self.edges.get(&id) {
+                    Some(edge) => id = edge.blocked_on_id,
+                    None => break,
+                }
+            }
+        }
+
         condvar
     }
 
@@ -252,7 +485,7 @@ impl DependencyGraph {
     /// This will cause it resume execution (though it will have to grab
     /// the lock on this data structure first, to recover the wait result).
     fn unblock_runtime(&mut self, id: ThreadId, wait_result: WaitResult) {
-        let edge = self.edges.remove(&id).expect("not blocked");
+        let edge = self.remove_edge(id);
         self.wait_results.insert(id, (edge.stack, wait_result));
 
         // Now that we have inserted the `wait_results`,
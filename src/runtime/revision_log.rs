@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+
+use crate::key::DatabaseKeyIndex;
+use crate::{Durability, Revision};
+
+/// A change to the field of an input, as recorded in the
+/// [revision log](`crate::Database::revision_log`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InputChange {
+    /// The revision in which the field was set.
+    pub revision: Revision,
+
+    /// The field that was set: the ingredient of the field and the id of the input.
+    /// Implements `Debug` as the field name and the id (e.g., `other(Id(1))`) when a database
+    /// is attached.
+    pub key: DatabaseKeyIndex,
+
+    /// The durability of the field after it was set.
+    pub durability: Durability,
+}
+
+/// Bounded log of the most recent input changes.
+#[derive(Debug, Default)]
+pub(crate) struct RevisionLog {
+    /// Maximum number of changes retained. `0` disables the log.
+    capacity: usize,
+
+    /// Retained changes, oldest first.
+    changes: VecDeque<InputChange>,
+}
+
+impl RevisionLog {
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub(crate) fn record(&mut self, change: InputChange) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.changes.push_back(change);
+        self.evict();
+    }
+
+    pub(crate) fn changes(&self) -> Vec<InputChange> {
+        self.changes.iter().copied().collect()
+    }
+
+    fn evict(&mut self) {
+        while self.changes.len() > self.capacity {
+            self.changes.pop_front();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use rustc_hash::FxHashSet;
+
+use crate::ingredient::Ingredient;
+use crate::zalsa::IngredientIndex;
+use crate::{Database, Id, Revision, Runtime};
+
+/// Restores the fields of an input to their values before the speculation.
+pub(crate) type Undo = Box<dyn FnOnce(&mut dyn Ingredient, &mut Runtime) + Send + Sync>;
+
+/// Retires an input created during the speculation.
+pub(crate) type Retire = fn(&mut dyn Database, Id);
+
+/// State of an active [`Database::speculate`](`crate::Database::speculate`) call.
+pub(crate) struct Speculation {
+    /// The revision in which the speculation began.
+    start: Revision,
+
+    /// Inputs whose fields were saved or that were created during the speculation.
+    saved: FxHashSet<(IngredientIndex, Id)>,
+
+    /// Restores the saved inputs, in the order they were saved.
+    pub(crate) undo: Vec<(IngredientIndex, Undo)>,
+
+    /// Inputs created during the speculation, to be retired once it ends.
+    pub(crate) created: Vec<(Retire, Id)>,
+}
+
+impl Speculation {
+    pub(crate) fn new(start: Revision) -> Self {
+        Self {
+            start,
+            saved: Default::default(),
+            undo: Default::default(),
+            created: Default::default(),
+        }
+    }
+
+    /// Records that the input `id` is about to be modified. Returns `false` if it was
+    /// already saved or created during the speculation, in which case `undo` is not needed.
+    pub(crate) fn needs_undo(&self, ingredient_index: IngredientIndex, id: Id) -> bool {
+        !self.saved.contains(&(ingredient_index, id))
+    }
+
+    pub(crate) fn record_undo(&mut self, ingredient_index: IngredientIndex, id: Id, undo: Undo) {
+        if self.saved.insert((ingredient_index, id)) {
+            self.undo.push((ingredient_index, undo));
+        }
+    }
+
+    pub(crate) fn record_created(
+        &mut self,
+        ingredient_index: IngredientIndex,
+        id: Id,
+        retire: Retire,
+    ) {
+        self.saved.insert((ingredient_index, id));
+        self.created.push((retire, id));
+    }
+
+    pub(crate) fn start(&self) -> Revision {
+        self.start
+    }
+}
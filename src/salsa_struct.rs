@@ -1,5 +1,26 @@
-use crate::{plumbing::JarAux, IngredientIndex};
+use crate::{plumbing::JarAux, Database, Id, IngredientIndex};
 
 pub trait SalsaStructInDb {
     fn lookup_ingredient_index(aux: &dyn JarAux) -> Option<IngredientIndex>;
+
+    /// The ingredients of the salsa structs whose ids values of this type hold: the
+    /// ingredient of the struct itself, or those of the variants of a `Supertype` enum.
+    fn lookup_ingredient_indices(aux: &dyn JarAux) -> Vec<IngredientIndex> {
+        Self::lookup_ingredient_index(aux).into_iter().collect()
+    }
+
+    /// Registers the ingredients of the salsa structs whose ids values of this type hold.
+    ///
+    /// Invoked before creating the ingredient of a tracked function that takes this type,
+    /// which needs the ingredients of all the structs its keys may belong to.
+    fn register_ingredients(db: &dyn Database) {
+        let _ = db;
+    }
+}
+
+/// True if `id` is an entry of the salsa struct `S`.
+/// Used by `Supertype` enums to find the variant of an id.
+pub fn is_entry_of<S: SalsaStructInDb>(db: &dyn Database, id: Id) -> bool {
+    let zalsa = db.zalsa();
+    zalsa.lookup_salsa_struct::<S>() == Some(zalsa.table().ingredient_index(id))
 }
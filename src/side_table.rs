@@ -0,0 +1,123 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::hash::FxDashMap;
+use crate::zalsa::Zalsa;
+use crate::{Database, DatabaseKeyIndex};
+
+/// Associates user data with query keys, discarding it along with the memoized
+/// value of the query.
+///
+/// An entry for `key` is evicted when:
+///
+/// * the query identified by `key` starts executing (again), as its previous value is invalid;
+/// * the value memoized for `key` is evicted by the LRU;
+/// * the salsa struct `key` refers to, or that the query `key` is keyed on, is deleted;
+/// * all memoized values are discarded with [`Database::discard_memos`].
+///
+/// A query may insert an entry for its own key while it executes: that entry then
+/// lives exactly as long as the resulting memoized value.
+///
+/// Side tables are created with [`Storage::side_table`](`crate::Storage::side_table`) and
+/// are shared by all handles to the database. Cloning a `SideTable` yields another
+/// reference to the same entries.
+pub struct SideTable<V> {
+    entries: Arc<Entries<V>>,
+}
+
+struct Entries<V> {
+    map: FxDashMap<DatabaseKeyIndex, V>,
+}
+
+/// Type-erased view on a [`SideTable`], used by the database to evict entries.
+pub(crate) trait Evict: Send + Sync {
+    fn evict(&self, key: DatabaseKeyIndex);
+
+    fn evict_all(&self);
+}
+
+impl<V: Send + Sync + 'static> SideTable<V> {
+    pub(crate) fn new(zalsa: &Zalsa) -> Self {
+        let entries = Arc::new(Entries {
+            map: FxDashMap::default(),
+        });
+        zalsa.add_side_table(Arc::downgrade(&entries) as _);
+        Self { entries }
+    }
+
+    /// Associates `value` with `key`, returning the previous value, if any.
+    pub fn insert(&self, key: DatabaseKeyIndex, value: V) -> Option<V> {
+        self.entries.map.insert(key, value)
+    }
+
+    /// Associates `value` with the key of the tracked function currently executing on `db`,
+    /// returning the previous value, if any.
+    ///
+    /// # Panics
+    ///
+    /// If no tracked function is executing.
+    pub fn insert_for_active_query<Db: ?Sized + Database>(&self, db: &Db, value: V) -> Option<V> {
+        let Some((key, _)) = db.zalsa_local().active_query() else {
+            panic!("`insert_for_active_query` invoked outside of a tracked function");
+        };
+        self.insert(key, value)
+    }
+
+    /// Returns a clone of the value associated with `key`.
+    pub fn get(&self, key: DatabaseKeyIndex) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.entries.map.get(&key).map(|value| value.clone())
+    }
+
+    /// Removes the value associated with `key`, returning it.
+    pub fn remove(&self, key: DatabaseKeyIndex) -> Option<V> {
+        self.entries.map.remove(&key).map(|(_, value)| value)
+    }
+
+    /// Returns true if a value is associated with `key`.
+    pub fn contains_key(&self, key: DatabaseKeyIndex) -> bool {
+        self.entries.map.contains_key(&key)
+    }
+
+    /// Number of entries in the table.
+    pub fn len(&self) -> usize {
+        self.entries.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.map.is_empty()
+    }
+}
+
+impl<V: Send + Sync> Evict for Entries<V> {
+    fn evict(&self, key: DatabaseKeyIndex) {
+        self.map.remove(&key);
+    }
+
+    fn evict_all(&self) {
+        self.map.clear();
+    }
+}
+
+impl<V> Clone for SideTable<V> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for SideTable<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.entries
+                    .map
+                    .iter()
+                    .map(|entry| (*entry.key(), format!("{:?}", entry.value()))),
+            )
+            .finish()
+    }
+}
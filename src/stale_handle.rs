@@ -0,0 +1,57 @@
+use std::{
+    fmt,
+    panic::{self, UnwindSafe},
+};
+
+use crate::Id;
+
+/// Error indicating that a handle to a salsa struct was used after the struct was
+/// retired (see the generated `retire` method of inputs) or deleted (tracked structs
+/// that their creating query no longer creates).
+///
+/// Reported by the `try_` entry points, e.g., `try_fetch`, which check their arguments
+/// before use. Reading a field through a stale handle panics with a `StaleHandle`
+/// payload, which [`StaleHandle::catch`] (or `Error::catch`) converts.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct StaleHandle {
+    /// Name of the salsa struct.
+    pub struct_name: &'static str,
+
+    /// The id of the retired or deleted struct.
+    pub id: Id,
+}
+
+impl fmt::Display for StaleHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}({:?})` was used after it was retired or deleted",
+            self.struct_name, self.id
+        )
+    }
+}
+
+impl StaleHandle {
+    /// Unlike cancellation, using a stale handle is a bug of the caller, so this
+    /// runs the panic hook (e.g., to print the location and backtrace).
+    pub(crate) fn throw(self) -> ! {
+        panic::panic_any(self)
+    }
+
+    /// Runs `f`, and catches any stale handle.
+    pub fn catch<F, T>(f: F) -> Result<T, StaleHandle>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(stale_handle) => Err(*stale_handle),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl std::error::Error for StaleHandle {}
@@ -0,0 +1,258 @@
+use std::{fmt, str::FromStr};
+
+use crate::hash::{FxHashSet, FxIndexMap};
+use crate::zalsa::Zalsa;
+use crate::zalsa_local::QueryOrigin;
+use crate::{Database, DatabaseKeyIndex};
+
+/// The memoized values of a database along with the inputs they read, exported with
+/// [`Database::export_state_summary`](`crate::Database::export_state_summary`).
+///
+/// A summary is meant to be saved along with the memoized values when a process exits,
+/// so that the next process can find out which of those values are still valid with
+/// [`Database::import_and_diff`](`crate::Database::import_and_diff`) once it has created
+/// its inputs again.
+///
+/// Inputs are identified by [fingerprints](`crate::Fingerprint`) of their fields, so only
+/// the fields of inputs declared with `#[salsa::input(fingerprint)]` are summarized; a memo
+/// that read any other value is always dirty. Queries and inputs are named by
+/// [`DatabaseKeyIndex::to_stable_string`], so inputs must be created in the same order in
+/// both processes.
+///
+/// The summary is encoded as text with one line per input or memo (see the `Display`
+/// and `FromStr` impls).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateSummary {
+    /// Fingerprint of each input field read by a memo.
+    inputs: FxIndexMap<String, u128>,
+
+    /// The dependencies of each memo, or `None` if the memo read untracked values
+    /// or was assigned by another query.
+    memos: FxIndexMap<String, Option<Vec<String>>>,
+}
+
+/// The memos of a [`StateSummary`], split by
+/// [`Database::import_and_diff`](`crate::Database::import_and_diff`) into those that are
+/// still valid and those that must be recomputed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StateDiff {
+    /// Inputs whose fingerprint changed, or which do not exist anymore.
+    pub changed_inputs: Vec<String>,
+
+    /// Memos whose value is still valid.
+    pub clean: Vec<String>,
+
+    /// Memos that (transitively) read a changed input or a value missing from the summary.
+    pub dirty: Vec<String>,
+}
+
+impl StateSummary {
+    /// Names of the summarized memos.
+    pub fn memos(&self) -> impl Iterator<Item = &str> {
+        self.memos.keys().map(String::as_str)
+    }
+
+    /// Names and fingerprints of the summarized input fields.
+    pub fn inputs(&self) -> impl Iterator<Item = (&str, u128)> {
+        self.inputs
+            .iter()
+            .map(|(key, &fingerprint)| (key.as_str(), fingerprint))
+    }
+
+    pub(crate) fn export(db: &dyn Database, memos: Vec<(DatabaseKeyIndex, QueryOrigin)>) -> Self {
+        let zalsa = db.zalsa();
+        let mut summary = Self::default();
+        for (key, origin) in memos {
+            let dependencies = match origin {
+                QueryOrigin::Derived(_) => Some(
+                    origin
+                        .inputs()
+                        // Reads of whole tables (e.g. of interned values) never change.
+                        .filter_map(|input| input.database_key_index())
+                        .map(|input| {
+                            let name = input.to_stable_string(db);
+                            if let Some(fingerprint) = zalsa
+                                .lookup_ingredient(input.ingredient_index)
+                                .fingerprint(db, input.key_index)
+                            {
+                                summary.inputs.insert(name.clone(), fingerprint);
+                            }
+                            name
+                        })
+                        .collect(),
+                ),
+                QueryOrigin::DerivedUntracked(_)
+                | QueryOrigin::Assigned(_)
+                | QueryOrigin::BaseInput => None,
+            };
+            summary.memos.insert(key.to_stable_string(db), dependencies);
+        }
+        summary
+    }
+
+    pub(crate) fn diff(&self, db: &dyn Database) -> StateDiff {
+        let zalsa = db.zalsa();
+        let changed_inputs: FxHashSet<&str> = self
+            .inputs
+            .iter()
+            .filter(|(key, &fingerprint)| {
+                let current = DatabaseKeyIndex::parse(db, key).ok().and_then(|key| {
+                    zalsa
+                        .lookup_ingredient(key.ingredient_index)
+                        .fingerprint(db, key.key_index)
+                });
+                current != Some(fingerprint)
+            })
+            .map(|(key, _)| key.as_str())
+            .collect();
+
+        let mut dirty = FxIndexMap::default();
+        for key in self.memos.keys() {
+            self.is_dirty(key, &changed_inputs, &mut dirty);
+        }
+
+        let mut diff = StateDiff {
+            changed_inputs: self
+                .inputs
+                .keys()
+                .filter(|key| changed_inputs.contains(key.as_str()))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+        for key in self.memos.keys() {
+            if dirty[key.as_str()] {
+                diff.dirty.push(key.clone());
+            } else {
+                diff.clean.push(key.clone());
+            }
+        }
+        diff
+    }
+
+    fn is_dirty<'s>(
+        &'s self,
+        key: &'s str,
+        changed_inputs: &FxHashSet<&str>,
+        dirty: &mut FxIndexMap<&'s str, bool>,
+    ) -> bool {
+        if self.inputs.contains_key(key) {
+            return changed_inputs.contains(key);
+        }
+        if let Some(&is_dirty) = dirty.get(key) {
+            return is_dirty;
+        }
+        let Some(Some(dependencies)) = self.memos.get(key) else {
+            return true;
+        };
+
+        // Memos in a cycle are dirty, as they may not reach a fixpoint anymore.
+        dirty.insert(key, true);
+        let is_dirty = dependencies
+            .iter()
+            .any(|dependency| self.is_dirty(dependency, changed_inputs, dirty));
+        dirty.insert(key, is_dirty);
+        is_dirty
+    }
+}
+
+/// Encodes the summary with one line per input field, `input <key> <fingerprint>`,
+/// and one line per memo, `memo <key> <dependencies>..` or `untracked <key>`,
+/// with fields separated by tabs.
+impl fmt::Display for StateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, fingerprint) in &self.inputs {
+            writeln!(f, "input\t{key}\t{fingerprint:032x}")?;
+        }
+        for (key, dependencies) in &self.memos {
+            match dependencies {
+                Some(dependencies) => {
+                    write!(f, "memo\t{key}")?;
+                    for dependency in dependencies {
+                        write!(f, "\t{dependency}")?;
+                    }
+                    writeln!(f)?;
+                }
+                None => writeln!(f, "untracked\t{key}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for StateSummary {
+    type Err = ParseStateSummaryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut summary = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let malformed = || ParseStateSummaryError { line: index + 1 };
+            let mut fields = line.split('\t');
+            match (fields.next(), fields.next()) {
+                (Some("input"), Some(key)) => {
+                    let fingerprint = fields
+                        .next()
+                        .and_then(|fingerprint| u128::from_str_radix(fingerprint, 16).ok())
+                        .ok_or_else(malformed)?;
+                    if fields.next().is_some() {
+                        return Err(malformed());
+                    }
+                    summary.inputs.insert(key.to_string(), fingerprint);
+                }
+                (Some("memo"), Some(key)) => {
+                    let dependencies = fields.map(str::to_string).collect();
+                    summary.memos.insert(key.to_string(), Some(dependencies));
+                }
+                (Some("untracked"), Some(key)) if fields.next().is_none() => {
+                    summary.memos.insert(key.to_string(), None);
+                }
+                _ => return Err(malformed()),
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// Error returned when parsing a [`StateSummary`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStateSummaryError {
+    /// The (1-based) number of the malformed line.
+    pub line: usize,
+}
+
+impl fmt::Display for ParseStateSummaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed state summary at line {}", self.line)
+    }
+}
+
+impl std::error::Error for ParseStateSummaryError {}
+
+impl Zalsa {
+    /// Walks all memos that hold a value, returning their keys and origins.
+    pub(crate) fn memo_origins(&mut self) -> Vec<(DatabaseKeyIndex, QueryOrigin)> {
+        let mut memos = vec![];
+        self.table_mut()
+            .for_each_memo_table(|struct_index, id, memo_table| {
+                memos.extend(
+                    memo_table
+                        .memos()
+                        .into_iter()
+                        .filter(|(_, memo)| memo.has_value())
+                        .map(|(memo_index, memo)| (struct_index, id, memo_index, memo)),
+                )
+            });
+
+        memos
+            .into_iter()
+            .map(|(struct_index, key_index, memo_index, memo)| {
+                let key = DatabaseKeyIndex {
+                    ingredient_index: self.ingredient_index_for_memo(struct_index, memo_index),
+                    key_index,
+                };
+                (key, memo.origin().clone())
+            })
+            .collect()
+    }
+}
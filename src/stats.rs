@@ -0,0 +1,153 @@
+#[cfg(feature = "stats")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[cfg(feature = "stats")]
+use crate::{zalsa::Zalsa, IngredientIndex};
+
+/// Counters of a tracked function, reported by
+/// [`Database::ingredient_stats`](`crate::Database::ingredient_stats`).
+///
+/// The counters are cumulative since the database was created. To measure a single
+/// revision, subtract the counters reported before it from those reported after it.
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngredientStats {
+    pub ingredient_index: IngredientIndex,
+
+    /// The name of the tracked function.
+    pub debug_name: &'static str,
+
+    /// How often the function was executed.
+    pub executions: u64,
+
+    /// How often a memoized value was reused without walking its dependencies,
+    /// because it was already verified in the current revision or because none
+    /// of the inputs of its durability changed.
+    pub shallow_verify_hits: u64,
+
+    /// How often a memoized value was reused after checking that none of its
+    /// dependencies changed.
+    pub deep_verify_hits: u64,
+
+    /// How often the function was re-executed (or its value specified) and produced
+    /// a value equal to the old one, so that its readers did not have to re-execute.
+    pub backdates: u64,
+
+    /// How often a memoized value was evicted by the LRU.
+    pub evictions: u64,
+
+    /// Cumulative time threads spent blocked on another thread executing or verifying
+    /// the function for the same key. A large value points to a query that many threads
+    /// wait for, which might be split up to run in parallel.
+    pub blocked: Duration,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Stat {
+    Execution,
+    ShallowVerifyHit,
+    DeepVerifyHit,
+    Backdate,
+    Eviction,
+}
+
+/// The counters of an ingredient. Without the `stats` feature, this is empty
+/// and recording does nothing.
+#[derive(Default)]
+pub(crate) struct StatCounters {
+    #[cfg(feature = "stats")]
+    counters: [AtomicU64; 5],
+
+    /// Cumulative time blocked on other threads, in nanoseconds.
+    #[cfg(feature = "stats")]
+    blocked_nanos: AtomicU64,
+}
+
+impl StatCounters {
+    #[inline]
+    pub(crate) fn record(&self, stat: Stat) {
+        #[cfg(feature = "stats")]
+        self.counters[stat as usize].fetch_add(1, Ordering::Relaxed);
+        #[cfg(not(feature = "stats"))]
+        let _ = stat;
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn record_blocked(&self, duration: Duration) {
+        self.blocked_nanos.fetch_add(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn snapshot(
+        &self,
+        ingredient_index: IngredientIndex,
+        debug_name: &'static str,
+    ) -> IngredientStats {
+        let get = |stat: Stat| self.counters[stat as usize].load(Ordering::Relaxed);
+        IngredientStats {
+            ingredient_index,
+            debug_name,
+            executions: get(Stat::Execution),
+            shallow_verify_hits: get(Stat::ShallowVerifyHit),
+            deep_verify_hits: get(Stat::DeepVerifyHit),
+            backdates: get(Stat::Backdate),
+            evictions: get(Stat::Eviction),
+            blocked: Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Counters of the frames allocated for executing queries on a database handle,
+/// reported by [`Database::allocation_stats`](`crate::Database::allocation_stats`).
+///
+/// Each executing query records its dependencies in a frame. The frames of completed
+/// queries are kept by the handle, so that later queries reuse their buffers rather
+/// than allocating new ones. A dependency read several times is recorded once.
+#[cfg(feature = "stats")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AllocationStats {
+    /// How often a query was executed in a newly allocated frame.
+    pub frames_allocated: u64,
+
+    /// How often a query was executed in the frame of a completed query.
+    pub frames_reused: u64,
+
+    /// How often a query read a dependency it had already read, which was not recorded again.
+    pub deduplicated_reads: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Allocation {
+    New,
+    Reused,
+    DeduplicatedRead,
+}
+
+#[cfg(feature = "stats")]
+impl AllocationStats {
+    #[inline]
+    pub(crate) fn record(&mut self, allocation: Allocation) {
+        match allocation {
+            Allocation::New => self.frames_allocated += 1,
+            Allocation::Reused => self.frames_reused += 1,
+            Allocation::DeduplicatedRead => self.deduplicated_reads += 1,
+        }
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Zalsa {
+    pub(crate) fn ingredient_stats(&self) -> Vec<IngredientStats> {
+        self.ingredients()
+            .filter_map(|ingredient| ingredient.stats())
+            .collect()
+    }
+}
@@ -1,11 +1,12 @@
-use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc};
+use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc, time::Duration};
 
 use parking_lot::{Condvar, Mutex};
 
 use crate::{
     zalsa::{Zalsa, ZalsaDatabase},
     zalsa_local::{self, ZalsaLocal},
-    Database, Event, EventKind,
+    BlockingStrategy, Database, Event, EventKind, Interceptor, JarGroup, MemoCacheBackend,
+    Registry, SideTable,
 };
 
 /// Access the "storage" of a Salsa database: this is an internal plumbing trait
@@ -35,9 +36,22 @@ pub struct Storage<Db: Database> {
     /// Per-thread state
     zalsa_local: zalsa_local::ZalsaLocal,
 
+    /// State of the [`Database::batch`] calls active on this handle.
+    batch: BatchState,
+
     /// We store references to `Db`
     phantom: PhantomData<fn() -> Db>,
 }
+
+#[derive(Default)]
+struct BatchState {
+    /// Number of nested batches currently active.
+    depth: usize,
+
+    /// Whether a write within the current batch already started the new revision.
+    revision_started: bool,
+}
+
 struct Coordinate {
     /// Counter of the number of clones of actor. Begins at 1.
     /// Incremented when cloned, decremented when dropped.
@@ -47,19 +61,149 @@ struct Coordinate {
 
 impl<Db: Database> Default for Storage<Db> {
     fn default() -> Self {
+        Self::with_zalsa(Zalsa::new::<Db>())
+    }
+}
+
+impl<Db: Database> Storage<Db> {
+    fn with_zalsa(zalsa_impl: Zalsa) -> Self {
         Self {
-            zalsa_impl: Arc::new(Zalsa::new::<Db>()),
+            zalsa_local: ZalsaLocal::new(zalsa_impl.nonce()),
+            zalsa_impl: Arc::new(zalsa_impl),
             coordinate: CoordinateDrop(Arc::new(Coordinate {
                 clones: Mutex::new(1),
                 cvar: Default::default(),
             })),
-            zalsa_local: ZalsaLocal::new(),
+            batch: Default::default(),
             phantom: PhantomData,
         }
     }
-}
 
-impl<Db: Database> Storage<Db> {
+    /// Creates the storage of a new database with the ingredients of `old`, without its
+    /// memoized values: every tracked function executes again when it is next invoked.
+    ///
+    /// Interned values are copied with the same ids, so that handles to them (e.g., interned
+    /// symbols) can be used with both databases. So are the inputs whose fields all have
+    /// [`Durability::HIGH`](`crate::Durability::HIGH`) and implement `Clone`; the other
+    /// inputs are retired in the new database. Tracked structs are not copied, and external
+    /// ingredients start out empty.
+    ///
+    /// The databases do not share their interners: values interned after this call get
+    /// ids that are only valid in the database that interned them.
+    ///
+    /// The default LRU capacity is kept; other settings must be set again.
+    pub fn new_from(old: &Self) -> Self {
+        Self::with_zalsa(old.zalsa_impl.new_from::<Db>())
+    }
+
+    /// Sets the number of worker threads used for parallel revalidation.
+    ///
+    /// When a memo has to be deep-verified (typically after an input of high durability
+    /// changed), the inputs of that memo are queued to a bounded pool of workers that
+    /// verify them in parallel, while the current thread walks them in order as usual.
+    /// This spreads the cost of a "revalidation storm" over several threads.
+    /// Queued inputs are skipped once the current thread found an earlier input that changed.
+    ///
+    /// Workers run on forks of the database and are cancelled like any other handle
+    /// when an input is set. Setting `0` (the default) disables parallel revalidation.
+    pub fn set_revalidation_threads(&self, num_threads: usize) {
+        self.zalsa_impl.set_revalidation_threads(num_threads);
+    }
+
+    /// Sets the number of input changes retained in the [revision log](`Database::revision_log`).
+    ///
+    /// Once the log is full, the oldest changes are discarded.
+    /// Setting `0` (the default) disables the log.
+    pub fn set_revision_log_capacity(&self, capacity: usize) {
+        self.zalsa_impl.set_revision_log_capacity(capacity);
+    }
+
+    /// Sets every how many executions a tracked function is executed a second time to check
+    /// that it is deterministic, panicking with both values if they are not equal.
+    ///
+    /// Nondeterministic functions silently break backdating, so this is meant for tests.
+    /// Functions declared with `no_eq` or `volatile`, those that read untracked state and
+    /// those recovering from a cycle are not checked. Setting `0` (the default) disables the
+    /// check, so that enabling the feature alone does not change the behavior of a database.
+    #[cfg(feature = "check_determinism")]
+    pub fn set_determinism_check_interval(&self, interval: usize) {
+        self.zalsa_impl.set_determinism_check_interval(interval);
+    }
+
+    /// Sets the LRU capacity of tracked functions that do not set one with the `lru` option.
+    ///
+    /// This only applies to functions whose ingredients are created afterwards, so call it
+    /// before running any query. Setting `0` (the default) disables LRU eviction.
+    pub fn set_default_lru_capacity(&self, capacity: usize) {
+        self.zalsa_impl.set_default_lru_capacity(capacity);
+    }
+
+    /// Registers the ingredients of the [`JarGroup`][] `G` with this database,
+    /// so that their indices do not depend on the order in which queries run.
+    pub fn register<G: JarGroup>(&self) {
+        G::register(&Registry::new(&self.zalsa_impl));
+    }
+
+    /// Registers an [`Interceptor`][] invoked around the execution of every tracked function.
+    ///
+    /// Interceptors are shared by all handles to this database and cannot be removed.
+    pub fn add_interceptor(&self, interceptor: impl Interceptor) {
+        self.zalsa_impl.add_interceptor(Arc::new(interceptor));
+    }
+
+    /// Sets the [`MemoCacheBackend`][] looked up before executing tracked functions
+    /// declared with `cache_key`, replacing any previous backend.
+    ///
+    /// The backend is shared by all handles to this database.
+    pub fn set_memo_cache(&self, backend: impl MemoCacheBackend) {
+        self.zalsa_impl.set_memo_cache(Box::new(backend));
+    }
+
+    /// Sets the [`BlockingStrategy`][] invoked when a thread has to wait for a query
+    /// executing on another thread, replacing any previous strategy.
+    ///
+    /// The strategy is shared by all handles to this database.
+    pub fn set_blocking_strategy(&self, blocking_strategy: impl BlockingStrategy) {
+        self.zalsa_impl
+            .set_blocking_strategy(Box::new(blocking_strategy));
+    }
+
+    /// Sets how long a thread may wait for a query executing on another thread
+    /// before unwinding with a [`Deadlock`](`crate::Deadlock`) that describes the waits involved.
+    ///
+    /// Salsa cannot detect deadlocks involving locks (or other synchronization) of its users,
+    /// e.g., a thread holding a lock while waiting for a query whose execution needs that lock.
+    /// As queries may legitimately take a long time, this timeout is disabled (`None`) by default.
+    pub fn set_deadlock_timeout(&self, timeout: Option<Duration>) {
+        self.zalsa_impl.set_deadlock_timeout(timeout);
+    }
+
+    /// Sets how often a thread waiting for a query executing on another thread reports
+    /// an [`EventKind::StillBlockedOn`][] event, naming the query and the thread executing it.
+    ///
+    /// Unlike a [deadlock timeout](`Self::set_deadlock_timeout`), this does not interrupt
+    /// the wait, so it can be used to surface long waits in production. Disabled (`None`)
+    /// by default.
+    pub fn set_block_warning_interval(&self, interval: Option<Duration>) {
+        self.zalsa_impl.set_block_warning_interval(interval);
+    }
+
+    /// Enables or disables leak detection for [`ReadGuard`](`crate::ReadGuard`)s.
+    ///
+    /// When enabled, guards created with [`guard_read`](`crate::guard_read`) panic when they
+    /// are used after the database was modified, reporting the revision and the query in
+    /// which they were created. This is meant as a debugging aid: it adds a small cost to
+    /// every guard and is disabled by default.
+    pub fn set_leak_detection(&self, enabled: bool) {
+        self.zalsa_impl.set_leak_detection(enabled);
+    }
+
+    /// Creates an empty [`SideTable`][] whose entries are evicted as the memoized values
+    /// of this database are invalidated or discarded.
+    pub fn side_table<V: Send + Sync + 'static>(&self) -> SideTable<V> {
+        SideTable::new(&self.zalsa_impl)
+    }
+
     // ANCHOR: cancel_other_workers
     /// Sets cancellation flag and blocks until all other workers with access
     /// to this storage have completed.
@@ -67,6 +211,10 @@ impl<Db: Database> Storage<Db> {
     /// This could deadlock if there is a single worker with two handles to the
     /// same database!
     fn cancel_others(&self, db: &Db) {
+        zalsa_local::assert_no_executing_query(db.as_dyn_database(), &|| {
+            "mutate the database".to_string()
+        });
+
         self.zalsa_impl.set_cancellation_flag();
 
         db.salsa_event(&|| Event::new(EventKind::DidSetCancellationFlag));
@@ -85,12 +233,41 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
     }
 
     fn zalsa_mut(&mut self) -> &mut Zalsa {
+        if self.storage().batch.revision_started {
+            // Other handles were already cancelled by the first write of this batch.
+            return Arc::get_mut(&mut self.storage_mut().zalsa_impl).unwrap();
+        }
+
         self.storage().cancel_others(self);
 
         let storage = self.storage_mut();
         // The ref count on the `Arc` should now be 1
         let zalsa_mut = Arc::get_mut(&mut storage.zalsa_impl).unwrap();
         zalsa_mut.new_revision();
+
+        if storage.batch.depth > 0 {
+            // Keep the cancellation flag set until the batch ends, so that
+            // queries cannot observe (and memoize) a partially applied batch.
+            storage.batch.revision_started = true;
+            zalsa_mut.set_cancellation_flag();
+        }
+
+        zalsa_mut
+    }
+
+    fn zalsa_exclusive(&mut self) -> &mut Zalsa {
+        let revision_started = self.storage().batch.revision_started;
+        if !revision_started {
+            self.storage().cancel_others(self);
+        }
+
+        // The ref count on the `Arc` should now be 1
+        let zalsa_mut = Arc::get_mut(&mut self.storage_mut().zalsa_impl).unwrap();
+        if !revision_started {
+            // Nothing changes, so other handles may resume in the current revision.
+            zalsa_mut.reset_cancellation_flag();
+        }
+        zalsa_mut.invalidate_read_guards();
         zalsa_mut
     }
 
@@ -98,6 +275,20 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
         &self.storage().zalsa_local
     }
 
+    fn zalsa_begin_batch(&mut self) {
+        self.storage_mut().batch.depth += 1;
+    }
+
+    fn zalsa_end_batch(&mut self) {
+        let storage = self.storage_mut();
+        storage.batch.depth -= 1;
+        if storage.batch.depth == 0 && std::mem::take(&mut storage.batch.revision_started) {
+            Arc::get_mut(&mut storage.zalsa_impl)
+                .unwrap()
+                .reset_cancellation_flag();
+        }
+    }
+
     fn fork_db(&self) -> Box<dyn Database> {
         Box::new(self.clone())
     }
@@ -109,15 +300,25 @@ impl<Db: Database> Clone for Storage<Db> {
     fn clone(&self) -> Self {
         *self.coordinate.clones.lock() += 1;
 
+        let zalsa_local = ZalsaLocal::new(self.zalsa_impl.nonce());
+        zalsa_local.set_priority(self.zalsa_local.priority());
+
         Self {
             zalsa_impl: self.zalsa_impl.clone(),
             coordinate: CoordinateDrop(Arc::clone(&self.coordinate)),
-            zalsa_local: ZalsaLocal::new(),
+            zalsa_local,
+            batch: Default::default(),
             phantom: PhantomData,
         }
     }
 }
 
+impl<Db: Database> Drop for Storage<Db> {
+    fn drop(&mut self) {
+        self.zalsa_local.release_preempt_flag(&self.zalsa_impl);
+    }
+}
+
 struct CoordinateDrop(Arc<Coordinate>);
 
 impl std::ops::Deref for CoordinateDrop {
@@ -12,7 +12,7 @@ use memo::MemoTable;
 use parking_lot::Mutex;
 use sync::SyncTable;
 
-use crate::{zalsa::transmute_data_ptr, Id, IngredientIndex, Revision};
+use crate::{zalsa::transmute_data_ptr, Id, IngredientIndex, InvalidKeyReason, Revision};
 
 pub(crate) mod memo;
 pub(crate) mod sync;
@@ -30,6 +30,12 @@ pub(crate) struct Table {
 pub(crate) trait TablePage: Any + Send + Sync {
     fn hidden_type_name(&self) -> &'static str;
 
+    /// The ingredient for elements on this page.
+    fn ingredient_index(&self) -> IngredientIndex;
+
+    /// Number of slots of this page that are allocated.
+    fn allocated(&self) -> usize;
+
     /// Access the memos attached to `slot`.
     ///
     /// # Safety condition
@@ -43,11 +49,20 @@ pub(crate) trait TablePage: Any + Send + Sync {
     ///
     /// The `current_revision` MUST be the current revision of the database owning this table page.
     unsafe fn syncs(&self, slot: SlotIndex, current_revision: Revision) -> &SyncTable;
+
+    /// Drops the memos attached to every slot of this page.
+    fn clear_memos(&mut self);
+
+    /// Invokes `f` with the memos attached to every slot of this page.
+    fn for_each_memo_table(&mut self, f: &mut dyn FnMut(SlotIndex, &mut MemoTable));
+
+    /// Returns a page for the same ingredient holding copies of the slots of this page,
+    /// up to the first one that is not copied (see [`Slot::fork`]).
+    fn fork(&self) -> Box<dyn TablePage>;
 }
 
 pub(crate) struct Page<T: Slot> {
     /// The ingredient for elements on this page.
-    #[allow(dead_code)] // pretty sure we'll need this
     ingredient: IngredientIndex,
 
     /// Number of elements of `data` that are initialized.
@@ -81,6 +96,15 @@ pub(crate) trait Slot: Any + Send + Sync {
     ///
     /// The current revision MUST be the current revision of the database containing this slot.
     unsafe fn syncs(&self, current_revision: Revision) -> &SyncTable;
+
+    /// Mutable access to the [`MemoTable`][] for this slot.
+    fn memos_mut(&mut self) -> &mut MemoTable;
+
+    /// Returns a copy of this slot, without its memos, for a database created from the one
+    /// containing this slot; or `None` if the slot is not copied.
+    fn fork(&self) -> Option<Self>
+    where
+        Self: Sized;
 }
 
 unsafe impl<T: Slot> Send for Page<T> {}
@@ -180,6 +204,93 @@ impl Table {
         let (page, slot) = split_id(id);
         self.pages[page.0].syncs(slot, current_revision)
     }
+
+    /// Returns a table with the same pages as this one, holding copies of their slots.
+    /// Used to create a database from another, see [`Storage::new_from`](`crate::Storage::new_from`).
+    pub(crate) fn fork(&self) -> Self {
+        let table = Self::default();
+        for page in self.pages.iter() {
+            table.pages.push(page.fork());
+        }
+        table
+    }
+
+    /// Drops all memos stored in this table.
+    pub(crate) fn clear_memos(&mut self) {
+        for index in 0..self.pages.len() {
+            self.pages[index].clear_memos();
+        }
+    }
+
+    /// Invokes `f` with the memos attached to every entry of this table,
+    /// along with the entry's id and the ingredient it belongs to.
+    pub(crate) fn for_each_memo_table(
+        &mut self,
+        mut f: impl FnMut(IngredientIndex, Id, &mut MemoTable),
+    ) {
+        for index in 0..self.pages.len() {
+            let page = &mut self.pages[index];
+            let ingredient = page.ingredient_index();
+            page.for_each_memo_table(&mut |slot, memos| {
+                f(ingredient, make_id(PageIndex::new(index), slot), memos)
+            });
+        }
+    }
+
+    /// The ingredient that allocated `id`.
+    ///
+    /// # Panics
+    ///
+    /// If `id` is out of bounds.
+    pub(crate) fn ingredient_index(&self, id: Id) -> IngredientIndex {
+        let (page, _) = split_id(id);
+        self.pages[page.0].ingredient_index()
+    }
+
+    /// The ingredient that allocated `id`, or `None` if `id` was never allocated.
+    #[cfg(feature = "fallible_v1")]
+    pub(crate) fn try_ingredient_index(&self, id: Id) -> Option<IngredientIndex> {
+        let (page, slot) = split_id(id);
+        if page.0 >= self.pages.len() {
+            return None;
+        }
+        let page_ref = &self.pages[page.0];
+        (slot.0 < page_ref.allocated()).then(|| page_ref.ingredient_index())
+    }
+
+    /// The ids of all entries allocated for `ingredient`.
+    pub(crate) fn ids(&self, ingredient: IngredientIndex) -> impl Iterator<Item = Id> + '_ {
+        (0..self.pages.len())
+            .filter(move |&index| self.pages[index].ingredient_index() == ingredient)
+            .flat_map(move |index| {
+                (0..self.pages[index].allocated())
+                    .map(move |slot| make_id(PageIndex::new(index), SlotIndex::new(slot)))
+            })
+    }
+
+    /// Checks that `id` was allocated from this table for the given ingredient.
+    pub(crate) fn check_id(
+        &self,
+        id: Id,
+        ingredient: IngredientIndex,
+    ) -> Result<(), InvalidKeyReason> {
+        let (page, slot) = split_id(id);
+        if page.0 >= self.pages.len() {
+            return Err(InvalidKeyReason::UnknownPage);
+        }
+        let page_ref = &self.pages[page.0];
+        let found = page_ref.ingredient_index();
+        if found != ingredient {
+            return Err(InvalidKeyReason::WrongIngredient {
+                expected: ingredient,
+                found,
+            });
+        }
+        if slot.0 >= page_ref.allocated() {
+            return Err(InvalidKeyReason::Unallocated);
+        }
+        Ok(())
+    }
 }
 
 impl<T: Slot> Page<T> {
@@ -270,6 +381,14 @@ impl<T: Slot> TablePage for Page<T> {
         std::any::type_name::<Self>()
     }
 
+    fn ingredient_index(&self) -> IngredientIndex {
+        self.ingredient
+    }
+
+    fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Acquire)
+    }
+
     unsafe fn memos(&self, slot: SlotIndex, current_revision: Revision) -> &MemoTable {
         self.get(slot).memos(current_revision)
     }
@@ -277,6 +396,34 @@ impl<T: Slot> TablePage for Page<T> {
     unsafe fn syncs(&self, slot: SlotIndex, current_revision: Revision) -> &SyncTable {
         self.get(slot).syncs(current_revision)
     }
+
+    fn clear_memos(&mut self) {
+        let len = *self.allocated.get_mut();
+        for data in &mut self.data[..len] {
+            // SAFETY: `data` is initialized for slots up to `len`
+            let slot = unsafe { data.get_mut().assume_init_mut() };
+            std::mem::take(slot.memos_mut());
+        }
+    }
+
+    fn for_each_memo_table(&mut self, f: &mut dyn FnMut(SlotIndex, &mut MemoTable)) {
+        let len = *self.allocated.get_mut();
+        for (index, data) in self.data[..len].iter_mut().enumerate() {
+            // SAFETY: `data` is initialized for slots up to `len`
+            let slot = unsafe { data.get_mut().assume_init_mut() };
+            f(SlotIndex::new(index), slot.memos_mut());
+        }
+    }
+
+    fn fork(&self) -> Box<dyn TablePage> {
+        let mut page = Page::<T>::new(self.ingredient);
+        // Ids are positions in the page, so slots cannot be skipped.
+        for (index, slot) in self.slots().map_while(T::fork).enumerate() {
+            page.data[index].get_mut().write(slot);
+            *page.allocated.get_mut() = index + 1;
+        }
+        Box::new(page)
+    }
 }
 
 impl<T: Slot> Drop for Page<T> {
@@ -7,7 +7,7 @@ use std::{
 use arc_swap::ArcSwap;
 use parking_lot::RwLock;
 
-use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin};
+use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin, Revision};
 
 /// The "memo table" stores the memoized results of tracked function calls.
 /// Every tracked function must take a salsa struct as its first argument
@@ -20,6 +20,15 @@ pub(crate) struct MemoTable {
 pub(crate) trait Memo: Any + Send + Sync + Debug {
     /// Returns the `origin` of this memo
     fn origin(&self) -> &QueryOrigin;
+
+    /// Returns the last revision in which this memo was verified.
+    fn verified_at(&self) -> Revision;
+
+    /// Returns the most recent revision in which the value of this memo changed.
+    fn changed_at(&self) -> Revision;
+
+    /// Returns true if this memo holds a value, i.e. the value was not evicted.
+    fn has_value(&self) -> bool;
 }
 
 /// Wraps the data stored for a memoized entry.
@@ -194,6 +203,22 @@ impl MemoTable {
         Some(unsafe { Self::from_dummy::<M>(arc_swap.swap(Self::to_dummy(memo))) })
     }
 
+    /// Returns all memos of this table, along with the index of their ingredient.
+    pub(crate) fn memos(&mut self) -> Vec<(MemoIngredientIndex, Arc<dyn Memo>)> {
+        self.memos
+            .get_mut()
+            .iter()
+            .zip(0..)
+            .filter_map(|(memo, index)| {
+                let data = memo.data.as_ref()?;
+                Some((
+                    MemoIngredientIndex::from_usize(index),
+                    (data.to_dyn_fn)(data.arc_swap.load_full()),
+                ))
+            })
+            .collect()
+    }
+
     pub(crate) fn into_memos(self) -> impl Iterator<Item = (MemoIngredientIndex, Arc<dyn Memo>)> {
         self.memos
             .into_inner()
@@ -1,4 +1,6 @@
 use std::{
+    cell::Cell,
+    ptr::NonNull,
     sync::atomic::{AtomicBool, Ordering},
     thread::ThreadId,
 };
@@ -34,10 +36,10 @@ impl SyncTable {
     pub(crate) fn claim<'me>(
         &'me self,
         db: &'me dyn Database,
-        zalsa_local: &ZalsaLocal,
+        zalsa_local: &'me ZalsaLocal,
         database_key_index: DatabaseKeyIndex,
         memo_ingredient_index: MemoIngredientIndex,
-    ) -> Option<ClaimGuard<'me>> {
+    ) -> ClaimResult<'me> {
         let mut syncs = self.syncs.write();
         let zalsa = db.zalsa();
         let thread_id = std::thread::current().id();
@@ -50,11 +52,13 @@ impl SyncTable {
                     id: thread_id,
                     anyone_waiting: AtomicBool::new(false),
                 });
-                Some(ClaimGuard {
+                ClaimResult::Claimed(ClaimGuard {
                     database_key_index,
                     memo_ingredient_index,
                     zalsa,
+                    zalsa_local,
                     sync_table: self,
+                    shared_memo: Cell::new(None),
                 })
             }
             Some(SyncState {
@@ -68,13 +72,57 @@ impl SyncTable {
                 // boolean is to decide *whether* to acquire the lock,
                 // not to gate future atomic reads.
                 anyone_waiting.store(true, Ordering::Relaxed);
-                zalsa.block_on_or_unwind(db, zalsa_local, database_key_index, *other_id, syncs);
-                None
+                ClaimResult::Released(zalsa.block_on_or_unwind(
+                    db,
+                    zalsa_local,
+                    database_key_index,
+                    *other_id,
+                    syncs,
+                ))
             }
         }
     }
 }
 
+pub(crate) enum ClaimResult<'me> {
+    /// The current thread claimed the query.
+    Claimed(ClaimGuard<'me>),
+
+    /// Another thread held the claim and released it after completing the query,
+    /// possibly sharing the memo it left.
+    Released(Option<SharedMemo>),
+}
+
+/// A memo that the thread holding a claim verified in the current revision,
+/// shared with the threads blocked on the claim so that they need not look it up
+/// and verify it again. Only functions declared with `broadcast` share their memos.
+///
+/// The memo is type-erased: it is only ever read back by the ingredient that shared it.
+/// Reading it relies on the invariant that a memo replaced in the memo table lives until
+/// the next revision: the function ingredient moves it to its `deleted_entries`, which are
+/// only dropped by `reset_for_new_revision`, and a new revision cannot start while the
+/// threads that receive the memo hold their database.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SharedMemo(NonNull<()>);
+
+// SAFETY: the memo is only read by the ingredient that shared it, under the same
+// conditions as a memo read from the memo table.
+unsafe impl Send for SharedMemo {}
+
+impl SharedMemo {
+    pub(crate) fn new<M>(memo: &M) -> Self {
+        Self(NonNull::from(memo).cast())
+    }
+
+    /// # Safety
+    ///
+    /// `M` must be the type of the memo this was created from, and `'db` must not
+    /// outlive the revision in which it was shared.
+    pub(crate) unsafe fn as_ref<'db, M>(self) -> &'db M {
+        self.0.cast().as_ref()
+    }
+}
+
 /// Marks an active 'claim' in the synchronization map. The claim is
 /// released when this value is dropped.
 #[must_use]
@@ -82,10 +130,17 @@ pub(crate) struct ClaimGuard<'me> {
     database_key_index: DatabaseKeyIndex,
     memo_ingredient_index: MemoIngredientIndex,
     zalsa: &'me Zalsa,
+    zalsa_local: &'me ZalsaLocal,
     sync_table: &'me SyncTable,
+    shared_memo: Cell<Option<SharedMemo>>,
 }
 
 impl ClaimGuard<'_> {
+    /// Shares `memo`, verified in the current revision, with the threads blocked on this claim.
+    pub(crate) fn share(&self, memo: SharedMemo) {
+        self.shared_memo.set(Some(memo));
+    }
+
     fn remove_from_map_and_unblock_queries(&self, wait_result: WaitResult) {
         let mut syncs = self.sync_table.syncs.write();
 
@@ -104,9 +159,13 @@ impl ClaimGuard<'_> {
 impl Drop for ClaimGuard<'_> {
     fn drop(&mut self) {
         let wait_result = if std::thread::panicking() {
-            WaitResult::Panicked
+            if self.zalsa_local.is_preempted() {
+                WaitResult::Preempted
+            } else {
+                WaitResult::Panicked
+            }
         } else {
-            WaitResult::Completed
+            WaitResult::Completed(self.shared_memo.get())
         };
         self.remove_from_map_and_unblock_queries(wait_result)
     }
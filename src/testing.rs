@@ -0,0 +1,8 @@
+//! Utilities for testing the incremental behavior of salsa databases.
+//!
+//! These are meant for downstream projects as much as for salsa itself: incremental
+//! bugs tend to show up only for specific combinations of dependency shapes, durabilities
+//! and edits, which are easier to find by generating them than by writing them out.
+
+pub mod bench;
+pub mod graphgen;
@@ -0,0 +1,251 @@
+//! Recording which tracked functions execute in the steps of an incremental scenario.
+//!
+//! Tests of incremental behavior typically make the tracked functions log their
+//! executions and compare the logs after each edit. A [`Bench`] records this without
+//! changes to the tracked functions or the database: each [step](`Bench::step`) applies
+//! a mutation, runs queries, and reports the functions that were executed, validated
+//! (reused without executing) and backdated (re-executed without changing their value)
+//! in the process, along with the time it took.
+//!
+//! ```
+//! use salsa::testing::bench::Bench;
+//! use salsa::{Database, DatabaseImpl, Setter};
+//!
+//! #[salsa::input]
+//! struct File {
+//!     #[return_ref]
+//!     text: String,
+//! }
+//!
+//! #[salsa::tracked]
+//! fn line_count(db: &dyn Database, file: File) -> usize {
+//!     file.text(db).lines().count()
+//! }
+//!
+//! #[salsa::tracked]
+//! fn is_empty(db: &dyn Database, file: File) -> bool {
+//!     line_count(db, file) == 0
+//! }
+//!
+//! fn main() {
+//!     let mut db = DatabaseImpl::new();
+//!     let file = File::new(&db, "a\nb".to_string());
+//!     let mut bench = Bench::new(&db);
+//!
+//!     bench
+//!         .step("initial", &mut db, |_| {}, |db| assert!(!is_empty(db, file)))
+//!         .assert_executed(&["is_empty(Id(0))", "line_count(Id(0))"]);
+//!
+//!     let step = bench.step(
+//!         "edit a line",
+//!         &mut db,
+//!         |db| {
+//!             file.set_text(db).to("a\nc".to_string());
+//!         },
+//!         |db| assert!(!is_empty(db, file)),
+//!     );
+//!     step.assert_executed(&["line_count(Id(0))"]);
+//!     step.assert_backdated(&["line_count(Id(0))"]);
+//!
+//!     println!("{bench}");
+//! }
+//! ```
+
+use std::{
+    collections::BTreeSet,
+    fmt,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::{attach::attach, Database, DatabaseKeyIndex, Interceptor};
+
+/// Runs the steps of an incremental scenario on a database and records what each step did.
+///
+/// Creating a `Bench` registers an [`Interceptor`] with the database, which stops recording
+/// once the `Bench` is dropped. Queries executed outside of [`Bench::step`] are not recorded.
+pub struct Bench {
+    recording: Arc<Mutex<Recording>>,
+    reports: Vec<StepReport>,
+}
+
+/// What happened during a [step](`Bench::step`).
+///
+/// The functions are identified by the debug representation of their key,
+/// e.g. `parse(Id(0))`, listed in the order in which they were recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StepReport {
+    pub name: String,
+
+    /// Functions that executed.
+    pub executed: Vec<String>,
+
+    /// Functions whose memoized value was reused without executing them.
+    pub validated: Vec<String>,
+
+    /// Functions that executed and produced a value equal to their old one.
+    pub backdated: Vec<String>,
+
+    /// Time taken by the queries of the step, excluding its mutation.
+    pub elapsed: Duration,
+}
+
+#[derive(Default)]
+struct Recording {
+    active: bool,
+    executed: Vec<String>,
+    validated: Vec<String>,
+    backdated: Vec<String>,
+}
+
+struct Recorder {
+    recording: Weak<Mutex<Recording>>,
+}
+
+impl Bench {
+    /// Creates a bench recording the queries executed on `db` (and all other handles to its storage).
+    pub fn new<Db: ?Sized + Database>(db: &Db) -> Self {
+        let recording = Arc::new(Mutex::new(Recording::default()));
+        db.zalsa().add_interceptor(Arc::new(Recorder {
+            recording: Arc::downgrade(&recording),
+        }));
+        Self {
+            recording,
+            reports: vec![],
+        }
+    }
+
+    /// Runs a step of the scenario: applies `mutate` to the database (e.g., setting inputs),
+    /// then runs `query`, recording the functions executed, validated and backdated by it.
+    pub fn step<Db: Database>(
+        &mut self,
+        name: &str,
+        db: &mut Db,
+        mutate: impl FnOnce(&mut Db),
+        query: impl FnOnce(&Db),
+    ) -> &StepReport {
+        mutate(db);
+
+        self.recording.lock().unwrap().active = true;
+        let start = Instant::now();
+        query(db);
+        let elapsed = start.elapsed();
+
+        let mut recording = self.recording.lock().unwrap();
+        recording.active = false;
+        self.reports.push(StepReport {
+            name: name.to_string(),
+            executed: std::mem::take(&mut recording.executed),
+            validated: std::mem::take(&mut recording.validated),
+            backdated: std::mem::take(&mut recording.backdated),
+            elapsed,
+        });
+        self.reports.last().unwrap()
+    }
+
+    /// The reports of all steps run so far.
+    pub fn reports(&self) -> &[StepReport] {
+        &self.reports
+    }
+}
+
+impl StepReport {
+    /// Asserts that exactly the functions in `expected` executed, in any order.
+    #[track_caller]
+    pub fn assert_executed(&self, expected: &[&str]) {
+        self.assert_set("executed", &self.executed, expected);
+    }
+
+    /// Asserts that exactly the functions in `expected` were validated, in any order.
+    #[track_caller]
+    pub fn assert_validated(&self, expected: &[&str]) {
+        self.assert_set("validated", &self.validated, expected);
+    }
+
+    /// Asserts that exactly the functions in `expected` were backdated, in any order.
+    #[track_caller]
+    pub fn assert_backdated(&self, expected: &[&str]) {
+        self.assert_set("backdated", &self.backdated, expected);
+    }
+
+    #[track_caller]
+    fn assert_set(&self, what: &str, actual: &[String], expected: &[&str]) {
+        let actual: BTreeSet<&str> = actual.iter().map(|key| &key[..]).collect();
+        let expected: BTreeSet<&str> = expected.iter().copied().collect();
+        if actual != expected {
+            panic!(
+                "step `{name}`: expected {expected:?} to be {what}, but {actual:?} were\n\
+                missing: {missing:?}\n\
+                unexpected: {unexpected:?}",
+                name = self.name,
+                missing = expected.difference(&actual).collect::<Vec<_>>(),
+                unexpected = actual.difference(&expected).collect::<Vec<_>>(),
+            );
+        }
+    }
+}
+
+impl fmt::Display for StepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} executed, {} validated, {} backdated in {:?}",
+            self.name,
+            self.executed.len(),
+            self.validated.len(),
+            self.backdated.len(),
+            self.elapsed
+        )
+    }
+}
+
+/// Lists the report of each step on its own line.
+impl fmt::Display for Bench {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for report in &self.reports {
+            writeln!(f, "{report}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Bench {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bench")
+            .field("reports", &self.reports)
+            .finish()
+    }
+}
+
+impl Recorder {
+    fn record(
+        &self,
+        db: &dyn Database,
+        database_key: DatabaseKeyIndex,
+        list: impl FnOnce(&mut Recording) -> &mut Vec<String>,
+    ) {
+        let Some(recording) = self.recording.upgrade() else {
+            return;
+        };
+        if !recording.lock().unwrap().active {
+            return;
+        }
+        let key = attach(db, || format!("{database_key:?}"));
+        list(&mut recording.lock().unwrap()).push(key);
+    }
+}
+
+impl Interceptor for Recorder {
+    fn did_execute(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        self.record(db, database_key, |recording| &mut recording.executed);
+    }
+
+    fn did_validate(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        self.record(db, database_key, |recording| &mut recording.validated);
+    }
+
+    fn did_backdate(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        self.record(db, database_key, |recording| &mut recording.backdated);
+    }
+}
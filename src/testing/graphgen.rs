@@ -0,0 +1,427 @@
+//! Generation of random dependency graphs, and an oracle checking incremental results
+//! against recomputation from scratch.
+//!
+//! A [`GraphConfig`] generates a [`Scenario`]: a [`Graph`] of nodes, each with a value, a
+//! durability and edges to other nodes, followed by a sequence of [`Step`]s that mutate the
+//! graph and query some of its nodes. The graph does not prescribe any computation; a [`Model`]
+//! maps it onto the queries under test, typically with one salsa input per node and a tracked
+//! function per kind of result.
+//!
+//! [`check`] replays a scenario on a single, incrementally updated instance of the model and
+//! compares every result with the one computed by a fresh instance built from the graph as of
+//! that step. Any difference is a bug in the model or in salsa.
+//!
+//! ```
+//! use salsa::testing::graphgen::{self, Graph, GraphConfig, Model, Mutation};
+//! use salsa::Setter;
+//!
+//! #[salsa::input]
+//! struct Node {
+//!     value: u32,
+//!     #[return_ref]
+//!     edges: Vec<usize>,
+//! }
+//!
+//! #[salsa::input]
+//! struct Nodes {
+//!     #[return_ref]
+//!     nodes: Vec<Node>,
+//! }
+//!
+//! #[salsa::tracked]
+//! fn total<'db>(db: &'db dyn salsa::Database, nodes: Nodes, index: usize) -> u64 {
+//!     let node = nodes.nodes(db)[index];
+//!     let edges = node.edges(db).clone();
+//!     u64::from(node.value(db)) + edges.into_iter().map(|edge| total(db, nodes, edge)).sum::<u64>()
+//! }
+//!
+//! struct Totals {
+//!     db: salsa::DatabaseImpl,
+//!     nodes: Nodes,
+//! }
+//!
+//! impl Model for Totals {
+//!     type Output = u64;
+//!
+//!     fn new(graph: &Graph) -> Self {
+//!         let db = salsa::DatabaseImpl::new();
+//!         let nodes = graph
+//!             .nodes
+//!             .iter()
+//!             .map(|node| {
+//!                 Node::builder(node.value, node.edges.clone())
+//!                     .durability(node.durability)
+//!                     .new(&db)
+//!             })
+//!             .collect();
+//!         let nodes = Nodes::new(&db, nodes);
+//!         Totals { db, nodes }
+//!     }
+//!
+//!     fn apply(&mut self, graph: &Graph, mutation: &Mutation) {
+//!         let index = mutation.node();
+//!         let node = self.nodes.nodes(&self.db)[index];
+//!         let durability = graph.nodes[index].durability;
+//!         match mutation {
+//!             Mutation::SetValue { value, .. } => {
+//!                 node.set_value(&mut self.db).with_durability(durability).to(*value);
+//!             }
+//!             Mutation::SetEdges { edges, .. } => {
+//!                 node.set_edges(&mut self.db).with_durability(durability).to(edges.clone());
+//!             }
+//!         }
+//!     }
+//!
+//!     fn evaluate(&mut self, node: usize) -> u64 {
+//!         total(&self.db, self.nodes, node)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     // `total` does not recover from cycles, so only generate acyclic graphs.
+//!     let config = GraphConfig {
+//!         cycle_density: 0.0,
+//!         ..GraphConfig::default()
+//!     };
+//!     graphgen::check_seeds::<Totals>(&config, 0..10);
+//! }
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::Durability;
+
+/// Parameters for generating [`Scenario`]s.
+#[derive(Clone, Debug)]
+pub struct GraphConfig {
+    /// Number of nodes in the graph.
+    pub nodes: usize,
+
+    /// Maximum number of outgoing edges of a node.
+    pub max_edges: usize,
+
+    /// Probability, between 0 and 1, that an edge points to the node itself or to a node
+    /// with a lower index. Only such edges can close a cycle: with a density of 0, all
+    /// generated graphs are acyclic.
+    pub cycle_density: f64,
+
+    /// Durabilities assigned to nodes, picked uniformly. Must not be empty.
+    pub durabilities: Vec<Durability>,
+
+    /// Node values are picked from `0..=max_value`.
+    pub max_value: u32,
+
+    /// Number of steps following the creation of the graph.
+    pub steps: usize,
+
+    /// Maximum number of nodes queried in each step.
+    pub max_queries: usize,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 12,
+            max_edges: 3,
+            cycle_density: 0.1,
+            durabilities: vec![Durability::LOW, Durability::MEDIUM, Durability::HIGH],
+            max_value: 10,
+            steps: 20,
+            max_queries: 4,
+        }
+    }
+}
+
+/// A graph along with a sequence of steps to perform on it, generated by [`GraphConfig::generate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+    /// The seed the scenario was generated from.
+    pub seed: u64,
+
+    /// The graph before the first step.
+    pub graph: Graph,
+
+    /// The nodes queried before the first step.
+    pub queries: Vec<usize>,
+
+    pub steps: Vec<Step>,
+}
+
+/// A dependency graph. Nodes are identified by their index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub value: u32,
+
+    /// Indices of the nodes this node depends on, in order. May contain duplicates.
+    pub edges: Vec<usize>,
+
+    /// The durability to use when setting the node's value or edges.
+    pub durability: Durability,
+}
+
+/// A single mutation, followed by queries on some nodes of the graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub mutation: Mutation,
+    pub queries: Vec<usize>,
+}
+
+/// A change to a node of a [`Graph`]. Mutations never change the durability of a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    SetValue { node: usize, value: u32 },
+    SetEdges { node: usize, edges: Vec<usize> },
+}
+
+/// Computations on a [`Graph`] whose incremental results are checked by [`check`].
+pub trait Model: Sized {
+    /// The result of evaluating a node.
+    type Output: PartialEq + fmt::Debug;
+
+    /// Creates an instance of the model for `graph`.
+    fn new(graph: &Graph) -> Self;
+
+    /// Applies `mutation` to the model. `graph` is the graph after the mutation.
+    fn apply(&mut self, graph: &Graph, mutation: &Mutation);
+
+    /// Evaluates the node with index `node`.
+    fn evaluate(&mut self, node: usize) -> Self::Output;
+}
+
+/// A result that differs between the incrementally updated model and a fresh one, see [`check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<T> {
+    /// The seed of the scenario.
+    pub seed: u64,
+
+    /// The number of steps performed before the mismatch.
+    pub step: usize,
+
+    /// The node whose results differ.
+    pub node: usize,
+
+    pub incremental: T,
+    pub from_scratch: T,
+}
+
+impl GraphConfig {
+    /// Generates a scenario. The same configuration and seed always yield the same scenario.
+    pub fn generate(&self, seed: u64) -> Scenario {
+        assert!(
+            !self.durabilities.is_empty(),
+            "`GraphConfig::durabilities` must not be empty"
+        );
+        let mut rng = Rng::new(seed);
+        let nodes = (0..self.nodes)
+            .map(|node| Node {
+                value: self.value(&mut rng),
+                edges: self.edges(&mut rng, node),
+                durability: self.durabilities[rng.below(self.durabilities.len())],
+            })
+            .collect();
+        let queries = self.queries(&mut rng);
+        let steps = (0..self.steps)
+            .filter(|_| self.nodes > 0)
+            .map(|_| {
+                let node = rng.below(self.nodes);
+                let mutation = if rng.chance(0.5) {
+                    Mutation::SetValue {
+                        node,
+                        value: self.value(&mut rng),
+                    }
+                } else {
+                    Mutation::SetEdges {
+                        node,
+                        edges: self.edges(&mut rng, node),
+                    }
+                };
+                Step {
+                    mutation,
+                    queries: self.queries(&mut rng),
+                }
+            })
+            .collect();
+        Scenario {
+            seed,
+            graph: Graph { nodes },
+            queries,
+            steps,
+        }
+    }
+
+    fn value(&self, rng: &mut Rng) -> u32 {
+        rng.below(self.max_value as usize + 1) as u32
+    }
+
+    fn edges(&self, rng: &mut Rng, node: usize) -> Vec<usize> {
+        (0..rng.below(self.max_edges + 1))
+            .filter_map(|_| {
+                if rng.chance(self.cycle_density) {
+                    Some(rng.below(node + 1))
+                } else if node + 1 < self.nodes {
+                    Some(node + 1 + rng.below(self.nodes - node - 1))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn queries(&self, rng: &mut Rng) -> Vec<usize> {
+        if self.nodes == 0 {
+            return vec![];
+        }
+        (0..=rng.below(self.max_queries))
+            .map(|_| rng.below(self.nodes))
+            .collect()
+    }
+}
+
+impl Graph {
+    /// Applies `mutation` to the graph.
+    pub fn apply(&mut self, mutation: &Mutation) {
+        match mutation {
+            Mutation::SetValue { node, value } => self.nodes[*node].value = *value,
+            Mutation::SetEdges { node, edges } => self.nodes[*node].edges = edges.clone(),
+        }
+    }
+
+    /// Returns true if some node can reach itself.
+    pub fn has_cycle(&self) -> bool {
+        (0..self.nodes.len()).any(|node| self.reaches_cycle(node))
+    }
+
+    /// Returns true if a cycle can be reached from `node`, including when `node` is part of one.
+    pub fn reaches_cycle(&self, node: usize) -> bool {
+        #[derive(Copy, Clone, PartialEq)]
+        enum State {
+            Unvisited,
+            OnStack,
+            Done,
+        }
+
+        fn visit(graph: &Graph, states: &mut [State], node: usize) -> bool {
+            match states[node] {
+                State::OnStack => return true,
+                State::Done => return false,
+                State::Unvisited => {}
+            }
+            states[node] = State::OnStack;
+            for &edge in &graph.nodes[node].edges {
+                if visit(graph, states, edge) {
+                    return true;
+                }
+            }
+            states[node] = State::Done;
+            false
+        }
+
+        visit(self, &mut vec![State::Unvisited; self.nodes.len()], node)
+    }
+}
+
+impl Mutation {
+    /// The index of the mutated node.
+    pub fn node(&self) -> usize {
+        match self {
+            Mutation::SetValue { node, .. } | Mutation::SetEdges { node, .. } => *node,
+        }
+    }
+}
+
+/// Replays `scenario` on an instance of `M` and checks each result against a fresh instance
+/// of `M` created for the graph as of the same step, evaluating the same nodes in the same order.
+pub fn check<M: Model>(scenario: &Scenario) -> Result<(), Mismatch<M::Output>> {
+    let mut graph = scenario.graph.clone();
+    let mut incremental = M::new(&graph);
+
+    let queries =
+        std::iter::once(&scenario.queries).chain(scenario.steps.iter().map(|s| &s.queries));
+    for (step, queries) in queries.enumerate() {
+        if step > 0 {
+            let mutation = &scenario.steps[step - 1].mutation;
+            graph.apply(mutation);
+            incremental.apply(&graph, mutation);
+        }
+
+        let mut from_scratch = M::new(&graph);
+        for &node in queries {
+            let incremental = incremental.evaluate(node);
+            let from_scratch = from_scratch.evaluate(node);
+            if incremental != from_scratch {
+                return Err(Mismatch {
+                    seed: scenario.seed,
+                    step,
+                    node,
+                    incremental,
+                    from_scratch,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generates a scenario from `config` for each of `seeds` and [checks](`check`) it.
+///
+/// # Panics
+///
+/// On the first mismatch, with a message naming the seed to reproduce it with.
+#[track_caller]
+pub fn check_seeds<M: Model>(config: &GraphConfig, seeds: Range<u64>) {
+    for seed in seeds {
+        if let Err(mismatch) = check::<M>(&config.generate(seed)) {
+            panic!("{mismatch}");
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for Mismatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "incremental result differs from recomputation for node {} after step {} of seed {}: \
+            {:?} (incremental) vs {:?} (from scratch)",
+            self.node, self.step, self.seed, self.incremental, self.from_scratch
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for Mismatch<T> {}
+
+/// SplitMix64: small, fast, and good enough to explore graph shapes.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, or 0 if `n` is 0.
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+use crate::{Database, DatabaseKeyIndex, Revision};
+
+/// Emits a [`tracing`] event at the `INFO` level, like `tracing::info!`, with an additional
+/// `salsa` field recording the current revision and the tracked function being executed.
+///
+/// The first argument is the database; the remaining ones are passed on to `tracing::info!`:
+///
+/// ```ignore
+/// #[salsa::tracked]
+/// fn parse(db: &dyn Db, file: File) -> Ast {
+///     salsa::trace!(db, "parsing {} bytes", file.text(db).len());
+///     // ...
+/// }
+/// ```
+///
+/// logs `INFO salsa=R3 parse(Id(0)): parsing 42 bytes` (with the default formatter of
+/// `tracing-subscriber`), which keeps the logs of queries executing in parallel apart.
+#[macro_export]
+macro_rules! trace {
+    ($db:expr, $($arg:tt)+) => {
+        $crate::plumbing::tracing::info!(
+            salsa = %$crate::plumbing::TraceContext::new($db),
+            $($arg)+
+        )
+    };
+}
+
+/// The context attached to events emitted with [`trace!`](`crate::trace!`).
+#[derive(Copy, Clone, Debug)]
+pub struct TraceContext {
+    revision: Revision,
+    query: Option<DatabaseKeyIndex>,
+}
+
+impl TraceContext {
+    pub fn new<Db: ?Sized + Database>(db: &Db) -> Self {
+        Self {
+            revision: db.zalsa().current_revision(),
+            query: db.zalsa_local().active_query().map(|(key, _)| key),
+        }
+    }
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.revision)?;
+        if let Some(query) = self.query {
+            write!(f, " {query:?}")?;
+        }
+        Ok(())
+    }
+}
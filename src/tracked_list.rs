@@ -0,0 +1,220 @@
+use std::fmt;
+
+use crate::{
+    entries::{Entries, ProjectedEntries},
+    update::{update_fallback, Update},
+    Database,
+};
+
+/// A list returned by a tracked function whose elements are tracked per index.
+///
+/// This is the positional counterpart of [`Entries`]: each index is stamped with the
+/// revision in which its element last changed, and readers that access the list through
+/// [`TrackedList::project`] only depend on the indices they read. Reading `items[3]` thus
+/// does not invalidate when only `items[7]` changed:
+///
+/// ```ignore
+/// #[salsa::tracked(return_ref)]
+/// fn items(db: &dyn Db, file: File) -> salsa::TrackedList<Item> {
+///     let mut items = salsa::TrackedList::new(db);
+///     for item in parse(db, file) {
+///         items.push(db, item);
+///     }
+///     items
+/// }
+///
+/// #[salsa::tracked]
+/// fn item_name(db: &dyn Db, file: File, index: usize) -> Option<String> {
+///     let items = salsa::TrackedList::project(db, || items(db, file));
+///     items.get(db, index).map(|item| item.name.clone())
+/// }
+/// ```
+///
+/// Elements are compared with the element previously pushed at the same index, so the
+/// diff is order-aware: moving an element changes every index between its old and its
+/// new position. Reading the length only depends on which indices exist, not on their
+/// elements.
+///
+/// As with [`Entries`], each execution of a tracked function may create at most one
+/// `TrackedList<T>`.
+pub struct TrackedList<T> {
+    entries: Entries<Position, Element<T>>,
+    len: usize,
+}
+
+/// A view on a [`TrackedList`] obtained with [`TrackedList::project`] whose reads are tracked per index.
+pub struct ProjectedList<'db, T> {
+    entries: ProjectedEntries<'db, Position, Element<T>>,
+    list: &'db TrackedList<T>,
+}
+
+/// Each index of the list is stored as two entries: one recording that the index
+/// exists, which never changes while it does, and one holding the element.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Position {
+    Present(usize),
+    Element(usize),
+}
+
+#[derive(PartialEq, Eq)]
+enum Element<T> {
+    Present,
+    Value(T),
+}
+
+impl<T> TrackedList<T>
+where
+    T: Eq + Send + Sync + 'static,
+{
+    /// Creates an empty list owned by the tracked function currently executing on `db`.
+    ///
+    /// # Panics
+    ///
+    /// If no tracked function is executing.
+    pub fn new<Db: ?Sized + Database>(db: &Db) -> Self {
+        Self {
+            entries: Entries::new(db),
+            len: 0,
+        }
+    }
+
+    /// Appends `value`, stamping its index with the current revision if it differs
+    /// from the element at that index in the previous execution.
+    ///
+    /// # Panics
+    ///
+    /// If this is not invoked by the tracked function that created the list.
+    pub fn push<Db: ?Sized + Database>(&mut self, db: &Db, value: T) {
+        let index = self.len;
+        self.entries
+            .insert(db, Position::Present(index), Element::Present);
+        self.entries
+            .insert(db, Position::Element(index), Element::Value(value));
+        self.len += 1;
+    }
+
+    /// Returns the element at `index`, without recording a dependency on it.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        element(self.entries.get(&Position::Element(index)))
+    }
+
+    /// Iterates over the elements, without recording any dependency.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len).filter_map(|index| self.get(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Invokes `op`, typically a call to a tracked function returning a reference to a
+    /// `TrackedList`, without recording a dependency on its result. The list is then read
+    /// through the returned [`ProjectedList`], which records a dependency on each index read.
+    pub fn project<'db, Db: ?Sized + Database>(
+        db: &'db Db,
+        op: impl FnOnce() -> &'db Self,
+    ) -> ProjectedList<'db, T> {
+        let mut list = None;
+        let entries = Entries::project(db, || {
+            let inner: &'db Self = list.insert(op());
+            &inner.entries
+        });
+        ProjectedList {
+            entries,
+            list: list.unwrap(),
+        }
+    }
+}
+
+impl<'db, T> ProjectedList<'db, T>
+where
+    T: Eq + Send + Sync + 'static,
+{
+    /// Returns the element at `index`, recording a dependency on that index
+    /// (or on its absence).
+    pub fn get<Db: ?Sized + Database>(&self, db: &Db, index: usize) -> Option<&'db T> {
+        element(self.entries.get(db, &Position::Element(index)))
+    }
+
+    /// Returns the length of the list, recording a dependency on which indices exist.
+    pub fn len<Db: ?Sized + Database>(&self, db: &Db) -> usize {
+        let len = self.list.len;
+        if len > 0 {
+            self.entries.get(db, &Position::Present(len - 1));
+        }
+        self.entries.get(db, &Position::Present(len));
+        len
+    }
+
+    pub fn is_empty<Db: ?Sized + Database>(&self, db: &Db) -> bool {
+        self.len(db) == 0
+    }
+
+    /// Iterates over the elements, recording a dependency on the length and on each element.
+    pub fn iter<'a, Db: ?Sized + Database>(
+        &'a self,
+        db: &'a Db,
+    ) -> impl Iterator<Item = &'db T> + 'a {
+        (0..self.len(db)).filter_map(move |index| self.get(db, index))
+    }
+
+    /// The underlying list. Reading it does not record any dependency.
+    pub fn untracked(&self) -> &'db TrackedList<T> {
+        self.list
+    }
+}
+
+fn element<T>(element: Option<&Element<T>>) -> Option<&T> {
+    match element {
+        Some(Element::Value(value)) => Some(value),
+        Some(Element::Present) | None => None,
+    }
+}
+
+impl<T: PartialEq> PartialEq for TrackedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<T: Eq> Eq for TrackedList<T> {}
+
+impl<T> Clone for TrackedList<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrackedList<T>
+where
+    T: fmt::Debug + Eq + Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> fmt::Debug for ProjectedList<'_, T>
+where
+    T: fmt::Debug + Eq + Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProjectedList").field(&self.list).finish()
+    }
+}
+
+unsafe impl<T> Update for TrackedList<T>
+where
+    T: PartialEq + 'static,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+        unsafe { update_fallback(old_pointer, new_value) }
+    }
+}
@@ -1,4 +1,11 @@
-use std::{any::TypeId, fmt, hash::Hash, marker::PhantomData, ops::DerefMut};
+use std::{
+    any::TypeId,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    ops::DerefMut,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crossbeam::{atomic::AtomicCell, queue::SegQueue};
 use tracked_field::FieldIngredientImpl;
@@ -6,15 +13,16 @@ use tracked_field::FieldIngredientImpl;
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, Jar, JarAux, MaybeChangedAfter},
+    durability::Durabilities,
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, JarAux, MaybeChangedAfter},
     key::{DatabaseKeyIndex, InputDependencyIndex},
     plumbing::ZalsaLocal,
-    runtime::StampedValue,
+    runtime::{Runtime, StampedValue},
     salsa_struct::SalsaStructInDb,
     table::{memo::MemoTable, sync::SyncTable, Slot, Table},
     zalsa::{IngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Database, Durability, Event, EventKind, Id, Revision,
+    Database, Durability, Event, EventKind, Id, Revision, StaleHandle,
 };
 
 pub mod tracked_field;
@@ -33,6 +41,14 @@ pub trait Configuration: Sized + 'static {
     /// The absolute indices of any tracked fields.
     const TRACKED_FIELD_INDICES: &'static [usize];
 
+    /// True if the identity of the struct is computed by a user-supplied function
+    /// (`#[salsa::tracked(identity = ...)]`) rather than from all of its untracked fields.
+    ///
+    /// Untracked fields can then change while the struct is reused, so they get
+    /// field ingredients (following those of the tracked fields) and reading them
+    /// records a dependency, just like for tracked fields.
+    const CUSTOM_IDENTITY: bool;
+
     /// A (possibly empty) tuple of the fields for this struct.
     type Fields<'db>: Send + Sync;
 
@@ -55,6 +71,7 @@ pub trait Configuration: Sized + 'static {
     /// Deref the struct to yield the underlying id.
     fn deref_struct(s: Self::Struct<'_>) -> Id;
 
+    /// The value hashed to identify the struct, see [`Configuration::CUSTOM_IDENTITY`].
     fn untracked_fields(fields: &Self::Fields<'_>) -> impl Hash;
 
     /// Create a new value revision array where each element is set to `current_revision`.
@@ -94,7 +111,7 @@ pub struct JarImpl<C>
 where
     C: Configuration,
 {
-    phantom: PhantomData<C>,
+    phantom: PhantomData<fn() -> C>,
 }
 
 impl<C: Configuration> Default for JarImpl<C> {
@@ -122,14 +139,32 @@ impl<C: Configuration> Jar for JarImpl<C> {
             },
         );
 
+        let untracked_field_ingredients = (0..C::FIELD_DEBUG_NAMES.len())
+            .filter(|field_index| {
+                C::CUSTOM_IDENTITY && !C::TRACKED_FIELD_INDICES.contains(field_index)
+            })
+            .enumerate()
+            .map(|(relative_untracked_index, field_index)| {
+                Box::new(<FieldIngredientImpl<C>>::new(
+                    field_index,
+                    struct_index
+                        .successor(C::TRACKED_FIELD_INDICES.len() + relative_untracked_index),
+                )) as _
+            });
+
         std::iter::once(Box::new(struct_ingredient) as _)
             .chain(tracked_field_ingredients)
+            .chain(untracked_field_ingredients)
             .collect()
     }
 
     fn salsa_struct_type_id(&self) -> Option<TypeId> {
         Some(TypeId::of::<<C as Configuration>::Struct<'static>>())
     }
+
+    fn clone_jar(&self) -> Box<dyn Jar + Send + Sync> {
+        Box::new(Self::default())
+    }
 }
 
 pub trait TrackedStructInDb: SalsaStructInDb {
@@ -287,6 +322,11 @@ where
     /// leaked a reference across threads somehow.
     updated_at: AtomicCell<Option<Revision>>,
 
+    /// Set when the tracked struct is deleted because its creating query
+    /// no longer creates it. Reading its fields afterwards is a bug in user code
+    /// (e.g., a handle stashed in an input or thread-local) and panics.
+    deleted: AtomicBool,
+
     /// Fields of this tracked struct. They can change across revisions,
     /// but they do not change within a particular revision.
     fields: C::Fields<'static>,
@@ -315,6 +355,10 @@ pub(crate) struct DisambiguatorMap {
 }
 
 impl DisambiguatorMap {
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+    }
+
     pub(crate) fn disambiguate(&mut self, key: IdentityHash) -> Disambiguator {
         use hashbrown::hash_map::RawEntryMut;
 
@@ -422,6 +466,7 @@ where
     ) -> Id {
         let value = |_| Value {
             updated_at: AtomicCell::new(Some(current_revision)),
+            deleted: AtomicBool::new(false),
             durability: current_deps.durability,
             fields: unsafe { self.to_static(fields) },
             revisions: C::new_revisions(current_deps.changed_at),
@@ -558,6 +603,25 @@ where
         table.get_raw(id)
     }
 
+    /// Fetch the data for `id` in order to read its fields.
+    ///
+    /// # Panics
+    ///
+    /// If the tracked struct was deleted.
+    fn live_data(table: &Table, id: Id) -> &Value<C> {
+        let data = Self::data(table, id);
+        if data.is_deleted() {
+            // Its creating query no longer creates it, so the handle must not be
+            // kept across revisions (e.g., in an input or thread-local).
+            StaleHandle {
+                struct_name: C::DEBUG_NAME,
+                id,
+            }
+            .throw();
+        }
+        data
+    }
+
     /// Deletes the given entities. This is used after a query `Q` executes and we can compare
     /// the entities `E_now` that it produced in this revision vs the entities
     /// `E_prev` it produced in the last revision. Any missing entities `E_prev - E_new` can be
@@ -576,6 +640,7 @@ where
         });
 
         let zalsa = db.zalsa();
+        zalsa.evict_side_table_entries(self.database_key_index(id));
         let current_revision = zalsa.current_revision();
         let data = Self::data_raw(zalsa.table(), id);
 
@@ -600,6 +665,7 @@ where
                 }
             }
         }
+        data_ref.deleted.store(true, Ordering::Release);
 
         // Take the memo table. This is safe because we have modified `data_ref.updated_at` to `None`
         // and the code that references the memo-table has a read-lock.
@@ -614,6 +680,8 @@ where
             };
 
             db.salsa_event(&|| Event::new(EventKind::DidDiscard { key: executor }));
+            zalsa.evict_side_table_entries(executor);
+            zalsa.lookup_ingredient(ingredient_index).discard_key(id);
 
             for stale_output in memo.origin().outputs() {
                 stale_output.remove_stale_output(db, executor);
@@ -632,7 +700,7 @@ where
         s: C::Struct<'db>,
     ) -> &'db C::Fields<'db> {
         let id = C::deref_struct(s);
-        let value = Self::data(db.zalsa().table(), id);
+        let value = Self::live_data(db.zalsa().table(), id);
         unsafe { self.to_self_ref(&value.fields) }
     }
 
@@ -655,15 +723,17 @@ where
         let (zalsa, zalsa_local) = db.zalsas();
         let id = C::deref_struct(s);
         let field_ingredient_index = self.ingredient_index.successor(relative_tracked_index);
-        let data = Self::data(zalsa.table(), id);
+        let data = Self::live_data(zalsa.table(), id);
 
         data.read_lock(zalsa.current_revision());
 
         let field_changed_at = data.revisions[field_index];
 
         zalsa_local.report_tracked_read(
+            db,
             InputDependencyIndex::new(field_ingredient_index, id),
             data.durability,
+            Durabilities::at_least(data.durability),
             field_changed_at,
             InputAccumulatedValues::Empty,
         );
@@ -679,13 +749,30 @@ where
         &'db self,
         db: &'db dyn crate::Database,
         s: C::Struct<'db>,
+        field_index: usize,
+        relative_untracked_index: usize,
     ) -> &'db C::Fields<'db> {
-        let (zalsa, _) = db.zalsas();
+        let (zalsa, zalsa_local) = db.zalsas();
         let id = C::deref_struct(s);
-        let data = Self::data(zalsa.table(), id);
+        let data = Self::live_data(zalsa.table(), id);
 
         data.read_lock(zalsa.current_revision());
 
+        if C::CUSTOM_IDENTITY {
+            // The field may have changed while the struct was reused.
+            let field_ingredient_index = self
+                .ingredient_index
+                .successor(C::TRACKED_FIELD_INDICES.len() + relative_untracked_index);
+            zalsa_local.report_tracked_read(
+                db,
+                InputDependencyIndex::new(field_ingredient_index, id),
+                data.durability,
+                Durabilities::at_least(data.durability),
+                data.revisions[field_index],
+                InputAccumulatedValues::Empty,
+            );
+        }
+
         unsafe { self.to_self_ref(&data.fields) }
     }
 
@@ -712,6 +799,10 @@ where
         self.ingredient_index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedStruct
+    }
+
     fn maybe_changed_after(
         &self,
         _db: &dyn Database,
@@ -729,6 +820,10 @@ where
         None
     }
 
+    fn is_stale_handle(&self, db: &dyn Database, key_index: Id) -> bool {
+        Self::data(db.zalsa().table(), key_index).is_deleted()
+    }
+
     fn mark_validated_output<'db>(
         &'db self,
         _db: &'db dyn Database,
@@ -766,6 +861,20 @@ where
     }
 
     fn reset_for_new_revision(&mut self) {}
+
+    fn discard_entries(&mut self, runtime: &mut Runtime) {
+        for id in runtime.table().ids(self.ingredient_index) {
+            // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+            let data = unsafe { &mut *Self::data_raw(runtime.table(), id) };
+            if data.is_deleted() {
+                // Already on the free list.
+                continue;
+            }
+            data.updated_at.store(None);
+            data.deleted.store(true, Ordering::Release);
+            self.free_list.push(id);
+        }
+    }
 }
 
 impl<C> std::fmt::Debug for IngredientImpl<C>
@@ -801,9 +910,19 @@ where
         std::mem::take(&mut self.memos)
     }
 
+    fn is_deleted(&self) -> bool {
+        self.deleted.load(Ordering::Acquire)
+    }
+
     fn read_lock(&self, current_revision: Revision) {
         loop {
             match self.updated_at.load() {
+                None if self.is_deleted() => {
+                    panic!(
+                        "tracked struct `{}` was used after it was deleted",
+                        C::DEBUG_NAME
+                    );
+                }
                 None => {
                     panic!("access to field whilst the value is being initialized");
                 }
@@ -844,4 +963,13 @@ where
         self.read_lock(current_revision);
         &self.syncs
     }
+
+    fn memos_mut(&mut self) -> &mut crate::table::memo::MemoTable {
+        &mut self.memos
+    }
+
+    fn fork(&self) -> Option<Self> {
+        // Tracked structs are created again by the functions that create them.
+        None
+    }
 }
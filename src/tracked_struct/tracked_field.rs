@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    ingredient::{Ingredient, MaybeChangedAfter},
+    ingredient::{Ingredient, IngredientKind, MaybeChangedAfter},
     zalsa::IngredientIndex,
     Database, Id,
 };
@@ -51,6 +51,10 @@ where
         self.ingredient_index
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedField
+    }
+
     fn cycle_recovery_strategy(&self) -> crate::cycle::CycleRecoveryStrategy {
         crate::cycle::CycleRecoveryStrategy::Panic
     }
@@ -118,6 +122,10 @@ where
     fn debug_name(&self) -> &'static str {
         C::FIELD_DEBUG_NAMES[self.field_index]
     }
+
+    fn owner_debug_name(&self) -> Option<&'static str> {
+        Some(C::DEBUG_NAME)
+    }
 }
 
 impl<C> std::fmt::Debug for FieldIngredientImpl<C>
@@ -0,0 +1,161 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::Database;
+
+/// A value that can be moved from one database to another with [`transfer`].
+///
+/// Implemented by structs declared with `#[salsa::input(transfer)]` or
+/// `#[salsa::interned(transfer)]`, whose fields must implement `Transfer`, and by common types that cannot hold salsa handles, which are
+/// simply cloned. Containers transfer their elements, so that a `Vec` of interned
+/// structs is re-interned element-wise.
+///
+/// Salsa handles are only meaningful within the database that created them; copying
+/// their ids verbatim into another database would silently refer to unrelated data.
+/// Implement this trait for your own types by transferring each of their fields.
+pub trait Transfer {
+    /// The transferred value, which may borrow the target database for `'db`.
+    type Output<'db>;
+
+    /// Re-creates `self`, which belongs to `from`, in `to`.
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db>;
+}
+
+/// Re-creates `value`, which belongs to the database `from`, in the database `to`.
+///
+/// Interned structs are re-interned in `to` and inputs are re-created there, with the
+/// default durabilities of their fields; their fields are transferred in turn.
+/// As inputs have an identity, every transfer of an input creates a new input, even if
+/// the same input occurs twice within `value`.
+/// This is meant for test harnesses or multi-project workspaces moving small amounts of
+/// data between independent databases.
+///
+/// Reading the fields of an input records a dependency as usual if this is called from
+/// a tracked function of `from`. Creating inputs in `to` must not happen within a
+/// tracked function of `to`.
+pub fn transfer<'db, FromDb, ToDb, T>(from: &FromDb, to: &'db ToDb, value: T) -> T::Output<'db>
+where
+    FromDb: ?Sized + Database,
+    ToDb: ?Sized + Database,
+    T: Transfer,
+{
+    value.transfer(from.as_dyn_database(), to.as_dyn_database())
+}
+
+impl<T: Transfer> Transfer for &T {
+    type Output<'db> = T::Output<'db>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        T::transfer(self, from, to)
+    }
+}
+
+impl<T: Transfer> Transfer for Option<T> {
+    type Output<'db> = Option<T::Output<'db>>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        self.as_ref().map(|value| value.transfer(from, to))
+    }
+}
+
+impl<T: Transfer, E: Transfer> Transfer for Result<T, E> {
+    type Output<'db> = Result<T::Output<'db>, E::Output<'db>>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        match self {
+            Ok(value) => Ok(value.transfer(from, to)),
+            Err(error) => Err(error.transfer(from, to)),
+        }
+    }
+}
+
+impl<T: Transfer> Transfer for Vec<T> {
+    type Output<'db> = Vec<T::Output<'db>>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        self.iter().map(|value| value.transfer(from, to)).collect()
+    }
+}
+
+impl<T: Transfer> Transfer for Box<T> {
+    type Output<'db> = Box<T::Output<'db>>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        Box::new(T::transfer(self, from, to))
+    }
+}
+
+impl<T: Transfer> Transfer for Arc<T> {
+    type Output<'db> = Arc<T::Output<'db>>;
+
+    fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+        Arc::new(T::transfer(self, from, to))
+    }
+}
+
+macro_rules! clone_impl {
+    ($($t:ty,)*) => {
+        $(
+            impl Transfer for $t {
+                type Output<'db> = Self;
+
+                fn transfer(&self, _from: &dyn Database, _to: &dyn Database) -> Self {
+                    self.clone()
+                }
+            }
+        )*
+    }
+}
+
+clone_impl! {
+    String,
+    i128,
+    u128,
+    i64,
+    u64,
+    i32,
+    u32,
+    i16,
+    u16,
+    i8,
+    u8,
+    bool,
+    char,
+    f32,
+    f64,
+    usize,
+    isize,
+    PathBuf,
+    Box<str>,
+    Arc<str>,
+}
+
+macro_rules! tuple_impl {
+    ($($t:ident),*; $($u:ident),*) => {
+        impl<$($t),*> Transfer for ($($t,)*)
+        where
+            $($t: Transfer,)*
+        {
+            type Output<'db> = ($($t::Output<'db>,)*);
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn transfer<'db>(&self, from: &dyn Database, to: &'db dyn Database) -> Self::Output<'db> {
+                let ($($u,)*) = self;
+                ($($u.transfer(from, to),)*)
+            }
+        }
+    }
+}
+
+tuple_impl!(;);
+tuple_impl!(A; a);
+tuple_impl!(A, B; a, b);
+tuple_impl!(A, B, C; a, b, c);
+tuple_impl!(A, B, C, D; a, b, c, d);
+tuple_impl!(A, B, C, D, E; a, b, c, d, e);
+tuple_impl!(A, B, C, D, E, F; a, b, c, d, e, f);
+tuple_impl!(A, B, C, D, E, F, G; a, b, c, d, e, f, g);
+tuple_impl!(A, B, C, D, E, F, G, H; a, b, c, d, e, f, g, h);
+tuple_impl!(A, B, C, D, E, F, G, H, I; a, b, c, d, e, f, g, h, i);
+tuple_impl!(A, B, C, D, E, F, G, H, I, J; a, b, c, d, e, f, g, h, i, j);
+tuple_impl!(A, B, C, D, E, F, G, H, I, J, K; a, b, c, d, e, f, g, h, i, j, k);
+tuple_impl!(A, B, C, D, E, F, G, H, I, J, K, L; a, b, c, d, e, f, g, h, i, j, k, l);
@@ -1,10 +1,14 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     hash::{BuildHasher, Hash},
+    mem::ManuallyDrop,
     path::PathBuf,
     sync::Arc,
 };
 
+use indexmap::{IndexMap, IndexSet};
+use smallvec::SmallVec;
+
 use crate::Revision;
 
 /// This is used by the macro generated code.
@@ -186,6 +190,29 @@ where
     }
 }
 
+unsafe impl<A> Update for SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: Update,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_vec: Self) -> bool {
+        let old_vec: &mut SmallVec<A> = unsafe { &mut *old_pointer };
+
+        if old_vec.len() != new_vec.len() {
+            old_vec.clear();
+            old_vec.extend(new_vec);
+            return true;
+        }
+
+        let mut changed = false;
+        for (old_element, new_element) in old_vec.iter_mut().zip(new_vec) {
+            changed |= A::Item::maybe_update(old_element, new_element);
+        }
+
+        changed
+    }
+}
+
 macro_rules! maybe_update_set {
     ($old_pointer: expr, $new_set: expr) => {{
         let old_pointer = $old_pointer;
@@ -278,6 +305,50 @@ where
     }
 }
 
+unsafe impl<K, S> Update for IndexSet<K, S>
+where
+    K: Update + Eq + Hash,
+    S: BuildHasher,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_set: Self) -> bool {
+        let old_set: &mut Self = unsafe { &mut *old_pointer };
+
+        // Unlike for `HashSet`, the order of the elements is observable.
+        if old_set.len() == new_set.len() && old_set.iter().eq(new_set.iter()) {
+            false
+        } else {
+            old_set.clear();
+            old_set.extend(new_set);
+            true
+        }
+    }
+}
+
+unsafe impl<K, V, S> Update for IndexMap<K, V, S>
+where
+    K: Update + Eq + Hash,
+    V: Update,
+    S: BuildHasher,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_map: Self) -> bool {
+        let old_map: &mut Self = unsafe { &mut *old_pointer };
+
+        // Unlike for `HashMap`, the order of the keys is observable.
+        let same_keys = old_map.len() == new_map.len() && old_map.keys().eq(new_map.keys());
+        if !same_keys {
+            old_map.clear();
+            old_map.extend(new_map);
+            return true;
+        }
+
+        let mut changed = false;
+        for ((_, old_value), (_, new_value)) in old_map.iter_mut().zip(new_map) {
+            changed |= V::maybe_update(old_value, new_value);
+        }
+        changed
+    }
+}
+
 unsafe impl<T> Update for Box<T>
 where
     T: Update,
@@ -316,6 +387,62 @@ where
     }
 }
 
+unsafe impl<T> Update for Box<[T]>
+where
+    T: Update,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, new_box: Self) -> bool {
+        let old_box: &mut Box<[T]> = unsafe { &mut *old_pointer };
+
+        if old_box.len() != new_box.len() {
+            *old_box = new_box;
+            return true;
+        }
+
+        let mut changed = false;
+        for (old_element, new_element) in old_box.iter_mut().zip(new_box.into_vec()) {
+            changed |= T::maybe_update(old_element, new_element);
+        }
+        changed
+    }
+}
+
+unsafe impl<T> Update for Arc<[T]>
+where
+    T: Update,
+{
+    unsafe fn maybe_update(old_pointer: *mut Self, mut new_arc: Self) -> bool {
+        let old_arc: &mut Arc<[T]> = unsafe { &mut *old_pointer };
+
+        if Arc::ptr_eq(old_arc, &new_arc) {
+            return false;
+        }
+
+        // Elements can only be updated in place if we own both slices.
+        let same_len = old_arc.len() == new_arc.len();
+        if let (true, Some(old_slice), Some(_)) =
+            (same_len, Arc::get_mut(old_arc), Arc::get_mut(&mut new_arc))
+        {
+            // SAFETY: `ManuallyDrop<T>` has the same layout as `T`. Dropping the
+            // resulting `Arc` frees the allocation without dropping the elements,
+            // which are moved out below (or leaked, should `maybe_update` panic).
+            let new_arc: Arc<[ManuallyDrop<T>]> =
+                unsafe { Arc::from_raw(Arc::into_raw(new_arc) as *const [ManuallyDrop<T>]) };
+
+            let mut changed = false;
+            for (old_element, new_element) in old_slice.iter_mut().zip(new_arc.iter()) {
+                // SAFETY: Each element is read exactly once and never dropped in place.
+                let new_element = unsafe { std::ptr::read(&**new_element) };
+                changed |= T::maybe_update(old_element, new_element);
+            }
+            return changed;
+        }
+
+        *old_arc = new_arc;
+        true
+    }
+}
+
 unsafe impl<T, const N: usize> Update for [T; N]
 where
     T: Update,
@@ -376,6 +503,46 @@ fallback_impl! {
     usize,
     isize,
     PathBuf,
+    Box<str>,
+    Arc<str>,
+}
+
+/// Implements [`Update`] for the given `'static` types by comparing
+/// old and new values with their `PartialEq` impl.
+///
+/// This is mostly useful for trait objects, which the `Update` impls for
+/// containers (e.g., `Vec<T>`) otherwise cannot support:
+///
+/// ```
+/// trait Shape: Send + Sync {
+///     fn area(&self) -> u32;
+/// }
+///
+/// impl PartialEq for dyn Shape {
+///     fn eq(&self, other: &Self) -> bool {
+///         self.area() == other.area()
+///     }
+/// }
+///
+/// salsa::unsafe_update_via_eq!(Box<dyn Shape>);
+/// ```
+///
+/// # Safety
+///
+/// The `PartialEq` impl must return `false` whenever the new value differs from the old
+/// one in a way that readers can observe. Otherwise, readers keep using the old value
+/// (without any undefined behavior, as the types are `'static`).
+#[macro_export]
+macro_rules! unsafe_update_via_eq {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            unsafe impl $crate::Update for $ty {
+                unsafe fn maybe_update(old_pointer: *mut Self, new_value: Self) -> bool {
+                    unsafe { $crate::plumbing::update_fallback(old_pointer, new_value) }
+                }
+            }
+        )+
+    };
 }
 
 macro_rules! tuple_impl {
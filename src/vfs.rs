@@ -0,0 +1,273 @@
+//! A virtual file system: file inputs read from disk and kept up to date with its changes.
+//!
+//! Embedders watching files all need the same glue: map paths to inputs, re-read the files
+//! that changed, and set the inputs. [`Vfs`] provides it without the usual revision churn:
+//! changes are applied in a single revision, and only files whose text differs are set.
+//!
+//! With the `vfs_notify` feature, [`Vfs::watching`] creates a VFS that watches the files it
+//! reads with the [`notify`](https://docs.rs/notify) crate.
+
+use std::path::{Path, PathBuf};
+
+use dashmap::mapref::entry::Entry;
+
+use crate::files::normalize;
+use crate::hash::{FxDashMap, FxIndexMap};
+use crate::{Database, Durability, FileId, Setter};
+
+/// The text of a file read by a [`Vfs`], or `None` if it could not be read.
+#[crate::input]
+struct VfsFile {
+    #[return_ref]
+    text: Option<String>,
+}
+
+/// Owns the inputs holding the text of files on disk; see the [module documentation](`self`).
+///
+/// A VFS belongs to a single database (and its clones). Since applying changes requires
+/// `&mut` access to the database, a VFS stored in the database is typically held in an `Arc`.
+pub struct Vfs {
+    /// The input of each file read so far.
+    files: FxDashMap<FileId, VfsFile>,
+
+    /// The id of each file read so far, by its absolute normalized path,
+    /// to find the files affected by a change.
+    paths: FxDashMap<PathBuf, FileId>,
+
+    /// The durability of the inputs.
+    durability: Durability,
+
+    #[cfg(feature = "vfs_notify")]
+    watcher: Option<watcher::Watcher>,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vfs {
+    /// Creates a VFS that reads files with [`Durability::LOW`] and does not watch them.
+    pub fn new() -> Self {
+        Self {
+            files: Default::default(),
+            paths: Default::default(),
+            durability: Durability::LOW,
+            #[cfg(feature = "vfs_notify")]
+            watcher: None,
+        }
+    }
+
+    /// Sets the durability of the files read from now on, e.g. [`Durability::HIGH`]
+    /// for the sources of dependencies, which rarely change.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// The text of the file at `path`, or `None` if it does not exist or is not valid UTF-8.
+    ///
+    /// The file is read from disk when its text is first requested. Tracked functions calling
+    /// this depend on the text, so they are re-executed once [`apply_changes`](Self::apply_changes)
+    /// sets it to a new value.
+    pub fn text<'db>(&self, db: &'db dyn Database, path: FileId) -> Option<&'db str> {
+        self.file(db, path).text(db).as_deref()
+    }
+
+    /// The input of the file at `path`, creating it on first use.
+    fn file(&self, db: &dyn Database, path: FileId) -> VfsFile {
+        if let Some(file) = self.files.get(&path) {
+            return *file;
+        }
+
+        match self.files.entry(path) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let absolute = absolute(&path.path(db));
+                // Watch before reading, so that no change is missed in between.
+                #[cfg(feature = "vfs_notify")]
+                if let Some(watcher) = &self.watcher {
+                    watcher.watch(&absolute);
+                }
+                let file = VfsFile::builder(read(&absolute))
+                    .durability(self.durability)
+                    .new(db);
+                self.paths.insert(absolute, path);
+                *entry.insert(file)
+            }
+        }
+    }
+
+    /// Re-reads the files at the `changed` paths and sets the text of those that differ,
+    /// all in a single new revision. Returns the ids of these files.
+    ///
+    /// Paths of files that were not read yet are ignored. If no text changed, no revision
+    /// is started, so memoized values remain valid without even being verified.
+    pub fn apply_changes<Db: Database>(
+        &self,
+        db: &mut Db,
+        changed: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Vec<FileId> {
+        let mut changes = FxIndexMap::default();
+        for path in changed {
+            let path = absolute(path.as_ref());
+            let Some(id) = self.paths.get(&path).map(|id| *id) else {
+                continue;
+            };
+            if changes.contains_key(&id) {
+                continue;
+            }
+            let file = self.files.get(&id).map(|file| *file).unwrap();
+            let text = read(&path);
+            if *file.text(&*db) != text {
+                changes.insert(id, (file, text));
+            }
+        }
+
+        if !changes.is_empty() {
+            db.batch(|db| {
+                for (file, text) in changes.values_mut() {
+                    file.set_text(db).to(text.take());
+                }
+            });
+        }
+        changes.into_keys().collect()
+    }
+}
+
+/// The absolute, normalized form of `path`, by which changed paths are matched to files.
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        normalize(path)
+    } else {
+        normalize(&std::env::current_dir().unwrap_or_default().join(path))
+    }
+}
+
+fn read(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(feature = "vfs_notify")]
+mod watcher {
+    use std::path::{Path, PathBuf};
+
+    use crossbeam::channel::Receiver;
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+    use parking_lot::Mutex;
+
+    use super::Vfs;
+    use crate::hash::FxHashSet;
+    use crate::{Database, FileId};
+
+    pub(super) struct Watcher {
+        watcher: Mutex<RecommendedWatcher>,
+
+        /// The directories watched so far.
+        directories: Mutex<FxHashSet<PathBuf>>,
+
+        events: Receiver<notify::Result<notify::Event>>,
+    }
+
+    impl Watcher {
+        /// Watches the directory of the file at `path`, rather than the file itself, so
+        /// that the file is still watched after it is replaced (as editors do when saving)
+        /// and its creation is observed if it does not exist yet.
+        pub(super) fn watch(&self, path: &Path) {
+            let Some(directory) = path.parent() else {
+                return;
+            };
+            if !self.directories.lock().insert(directory.to_path_buf()) {
+                return;
+            }
+            if let Err(error) = self
+                .watcher
+                .lock()
+                .watch(directory, RecursiveMode::NonRecursive)
+            {
+                tracing::warn!("cannot watch {}: {error}", directory.display());
+            }
+        }
+    }
+
+    impl Vfs {
+        /// Creates a VFS that watches the files it reads. The changes are applied with
+        /// [`apply_pending_changes`](Self::apply_pending_changes) or
+        /// [`wait_for_changes`](Self::wait_for_changes).
+        pub fn watching() -> notify::Result<Self> {
+            let (sender, events) = crossbeam::channel::unbounded();
+            let watcher = notify::recommended_watcher(move |event| {
+                // The receiver is only dropped along with the watcher.
+                let _ = sender.send(event);
+            })?;
+            Ok(Self {
+                watcher: Some(Watcher {
+                    watcher: Mutex::new(watcher),
+                    directories: Default::default(),
+                    events,
+                }),
+                ..Self::new()
+            })
+        }
+
+        /// Applies the changes reported since the last call, without blocking,
+        /// as [`apply_changes`](Self::apply_changes) does.
+        ///
+        /// # Panics
+        ///
+        /// If the VFS was not created with [`Vfs::watching`].
+        pub fn apply_pending_changes<Db: Database>(&self, db: &mut Db) -> Vec<FileId> {
+            let changed = self.changed_paths(self.watcher().events.try_iter());
+            self.apply_changes(db, changed)
+        }
+
+        /// Blocks until a file that was read changes, then applies this change along with
+        /// all others reported so far. Returns the ids of the changed files, which is never empty.
+        ///
+        /// # Panics
+        ///
+        /// If the VFS was not created with [`Vfs::watching`].
+        pub fn wait_for_changes<Db: Database>(&self, db: &mut Db) -> Vec<FileId> {
+            let events = &self.watcher().events;
+            loop {
+                let first = events.recv().expect("the watcher lives as long as the VFS");
+                let changed = self.changed_paths(std::iter::once(first).chain(events.try_iter()));
+                let changed = self.apply_changes(db, changed);
+                if !changed.is_empty() {
+                    return changed;
+                }
+            }
+        }
+
+        fn watcher(&self) -> &Watcher {
+            self.watcher
+                .as_ref()
+                .expect("the VFS was not created with `Vfs::watching`")
+        }
+
+        /// The paths affected by `events`. If events may have been missed,
+        /// all files are considered changed, so that they are re-read.
+        fn changed_paths(
+            &self,
+            events: impl Iterator<Item = notify::Result<notify::Event>>,
+        ) -> Vec<PathBuf> {
+            let mut changed = vec![];
+            for event in events {
+                match event {
+                    Ok(event) if !event.need_rescan() => changed.extend(event.paths),
+                    Ok(_) => return self.all_paths(),
+                    Err(error) => {
+                        tracing::warn!("file watcher error, re-reading all files: {error}");
+                        return self.all_paths();
+                    }
+                }
+            }
+            changed
+        }
+
+        fn all_paths(&self) -> Vec<PathBuf> {
+            self.paths.iter().map(|entry| entry.key().clone()).collect()
+        }
+    }
+}
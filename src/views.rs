@@ -2,6 +2,8 @@ use crate::{zalsa::transmute_data_ptr, Database};
 use append_only_vec::AppendOnlyVec;
 use std::{
     any::{Any, TypeId},
+    fmt,
+    panic::{self, UnwindSafe},
     sync::Arc,
 };
 
@@ -138,6 +140,51 @@ impl Views {
     }
 }
 
+/// A panic payload indicating that the database was upcast to a view it was never
+/// registered for, e.g., by [`salsa::par_map`](`crate::par_map`) with a `dyn Db` for
+/// a `#[salsa::db]` trait none of whose tracked functions were called yet.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct MissingView {
+    /// Name of the view type.
+    pub view: &'static str,
+}
+
+impl MissingView {
+    pub(crate) fn throw(self) -> ! {
+        // We use resume and not panic here to avoid running the panic
+        // hook (that is, to avoid collecting and printing backtrace).
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// Runs `f`, and catches any missing view.
+    pub fn catch<F, T>(f: F) -> Result<T, MissingView>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(missing_view) => Err(*missing_view),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl fmt::Display for MissingView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the database has no view `{}`; it is registered when the first tracked function \
+            taking it is called",
+            self.view
+        )
+    }
+}
+
+impl std::error::Error for MissingView {}
+
 /// A generic downcaster for specific `Db` and `DbView` types.
 struct ViewCaster<Db, DbView: ?Sized>(fn(&Db) -> &DbView);
 
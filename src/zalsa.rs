@@ -1,20 +1,30 @@
 use append_only_vec::AppendOnlyVec;
+use arc_swap::{ArcSwap, ArcSwapOption, Guard};
 use parking_lot::{Mutex, RwLock};
 use rustc_hash::FxHashMap;
 use std::any::{Any, TypeId};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use std::thread::ThreadId;
+use std::time::Duration;
 
 use crate::cycle::CycleRecoveryStrategy;
+use crate::durability::Durabilities;
 use crate::ingredient::{Ingredient, Jar, JarAux};
+use crate::interceptor::Interceptor;
+use crate::memo_cache::MemoCacheBackend;
 use crate::nonce::{Nonce, NonceGenerator};
-use crate::runtime::{Runtime, WaitResult};
+use crate::revalidation::RevalidationPool;
+use crate::runtime::{BlockingStrategy, InputChange, Retire, Runtime, WaitResult};
+use crate::salsa_struct::SalsaStructInDb;
+use crate::side_table::Evict;
 use crate::table::memo::MemoTable;
-use crate::table::sync::SyncTable;
+use crate::table::sync::{SharedMemo, SyncTable};
 use crate::table::Table;
 use crate::views::Views;
 use crate::zalsa_local::ZalsaLocal;
-use crate::{Database, DatabaseKeyIndex, Durability, Id, Revision};
+use crate::{Database, DatabaseKeyIndex, Durability, Id, Priority, Revision};
 
 /// Internal plumbing trait.
 ///
@@ -47,10 +57,25 @@ pub unsafe trait ZalsaDatabase: Any {
     #[doc(hidden)]
     fn zalsa_mut(&mut self) -> &mut Zalsa;
 
+    /// Plumbing method: exclusive access to the internal salsa methods, without
+    /// starting a new revision.
+    ///
+    /// **WARNING:** Cancels other database handles. This can lead to deadlock!
+    #[doc(hidden)]
+    fn zalsa_exclusive(&mut self) -> &mut Zalsa;
+
     /// Access the thread-local state associated with this database
     #[doc(hidden)]
     fn zalsa_local(&self) -> &ZalsaLocal;
 
+    /// Plumbing method: start a batch of writes, see [`Database::batch`].
+    #[doc(hidden)]
+    fn zalsa_begin_batch(&mut self);
+
+    /// Plumbing method: end a batch of writes started with `zalsa_begin_batch`.
+    #[doc(hidden)]
+    fn zalsa_end_batch(&mut self);
+
     /// Clone the database.
     #[doc(hidden)]
     fn fork_db(&self) -> Box<dyn Database>;
@@ -141,12 +166,51 @@ pub struct Zalsa {
     /// Immutable unless the mutex on `ingredients_map` is held.
     ingredients_vec: AppendOnlyVec<Box<dyn Ingredient>>,
 
+    /// The jars whose ingredients were created, in order, so that databases created from
+    /// this one get the same ingredient indices. See [`Zalsa::new_from`][].
+    ///
+    /// Only pushed to while the mutex on `jar_map` is held.
+    jars: AppendOnlyVec<Box<dyn Jar + Send + Sync>>,
+
     /// Indices of ingredients that require reset when a new revision starts.
     ingredients_requiring_reset: AppendOnlyVec<IngredientIndex>,
 
     /// The runtime for this particular salsa database handle.
     /// Each handle gets its own runtime, but the runtimes have shared state between them.
     runtime: Runtime,
+
+    /// Worker pool used to verify memo inputs in parallel, if enabled.
+    /// See [`Zalsa::set_revalidation_threads`][].
+    revalidation_pool: ArcSwapOption<RevalidationPool>,
+
+    /// Interceptors invoked around the execution of tracked functions.
+    /// See [`Zalsa::add_interceptor`][].
+    interceptors: ArcSwap<Vec<Arc<dyn Interceptor>>>,
+
+    /// Backend looked up before executing tracked functions declared with `cache_key`.
+    /// See [`Zalsa::set_memo_cache`][].
+    memo_cache: ArcSwapOption<Box<dyn MemoCacheBackend>>,
+
+    /// Side tables whose entries are evicted along with memoized values.
+    /// See [`SideTable`](`crate::SideTable`).
+    side_tables: ArcSwap<Vec<Weak<dyn Evict>>>,
+
+    /// Generation of the memoized values, shared with [`ReadGuard`](`crate::ReadGuard`)s
+    /// if leak detection is enabled. See [`Zalsa::set_leak_detection`][].
+    read_generation: ArcSwapOption<AtomicUsize>,
+
+    /// LRU capacity of tracked functions that set none, applied when their ingredients are created.
+    /// See [`Zalsa::set_default_lru_capacity`][].
+    default_lru_capacity: AtomicUsize,
+
+    /// Every how many executions a tracked function is re-executed to check that it is
+    /// deterministic. See [`Zalsa::set_determinism_check_interval`][].
+    #[cfg(feature = "check_determinism")]
+    determinism_check_interval: AtomicUsize,
+
+    /// Number of executions of tracked functions, to select those to check.
+    #[cfg(feature = "check_determinism")]
+    executions: AtomicUsize,
 }
 
 impl Zalsa {
@@ -156,9 +220,20 @@ impl Zalsa {
             nonce: NONCE.nonce(),
             jar_map: Default::default(),
             ingredients_vec: AppendOnlyVec::new(),
+            jars: AppendOnlyVec::new(),
             ingredients_requiring_reset: AppendOnlyVec::new(),
             runtime: Runtime::default(),
             memo_ingredient_indices: Default::default(),
+            revalidation_pool: Default::default(),
+            interceptors: Default::default(),
+            memo_cache: Default::default(),
+            side_tables: Default::default(),
+            read_generation: Default::default(),
+            default_lru_capacity: AtomicUsize::new(0),
+            #[cfg(feature = "check_determinism")]
+            determinism_check_interval: AtomicUsize::new(0),
+            #[cfg(feature = "check_determinism")]
+            executions: AtomicUsize::new(0),
         }
     }
 
@@ -175,6 +250,11 @@ impl Zalsa {
         self.runtime.table()
     }
 
+    /// Mutable access to the [`Table`][], used to visit all memos while no other handles exist.
+    pub(crate) fn table_mut(&mut self) -> &mut Table {
+        self.runtime.table_mut()
+    }
+
     /// Returns the [`MemoTable`][] for the salsa struct with the given id
     pub(crate) fn memo_table_for(&self, id: Id) -> &MemoTable {
         // SAFETY: We are supply the correct current revision
@@ -204,6 +284,7 @@ impl Zalsa {
                 IngredientIndex::from(self.ingredients_vec.len())
             });
             if should_create {
+                self.jars.push(jar.clone_jar());
                 let aux = JarAuxImpl(self, &jar_map);
                 let ingredients = jar.create_ingredients(&aux, index);
                 for ingredient in ingredients {
@@ -229,10 +310,48 @@ impl Zalsa {
         }
     }
 
+    /// Creates the state of a new database with the same ingredients as this one,
+    /// see [`Storage::new_from`](`crate::Storage::new_from`).
+    pub(crate) fn new_from<Db: Database>(&self) -> Self {
+        let mut zalsa = Self::new::<Db>();
+        *zalsa.default_lru_capacity.get_mut() = self.default_lru_capacity.load(Ordering::Relaxed);
+
+        // Holding the lock prevents ingredients from being created meanwhile,
+        // so that all pages of the table belong to ingredients of the new database.
+        let _jar_map = self.jar_map.lock();
+        for jar in self.jars.iter() {
+            zalsa.add_or_lookup_jar_by_type(&**jar);
+        }
+        *zalsa.table_mut() = self.table().fork();
+        for index in 0..zalsa.ingredients_vec.len() {
+            let old = self.lookup_ingredient(IngredientIndex::from(index));
+            zalsa.ingredients_vec[index].copy_from(old, &zalsa.runtime);
+        }
+        zalsa
+    }
+
+    /// Returns the index of the ingredient for the salsa struct `S`,
+    /// or `None` if no such struct was ever created in this database.
+    pub(crate) fn lookup_salsa_struct<S: SalsaStructInDb>(&self) -> Option<IngredientIndex> {
+        let jar_map = self.jar_map.lock();
+        S::lookup_ingredient_index(&JarAuxImpl(self, &jar_map))
+    }
+
+    /// Returns the indices of the ingredients of the salsa structs whose ids `S` holds,
+    /// see [`SalsaStructInDb::lookup_ingredient_indices`].
+    pub(crate) fn lookup_salsa_struct_indices<S: SalsaStructInDb>(&self) -> Vec<IngredientIndex> {
+        let jar_map = self.jar_map.lock();
+        S::lookup_ingredient_indices(&JarAuxImpl(self, &jar_map))
+    }
+
     pub(crate) fn lookup_ingredient(&self, index: IngredientIndex) -> &dyn Ingredient {
         &*self.ingredients_vec[index.as_usize()]
     }
 
+    pub(crate) fn ingredients(&self) -> impl Iterator<Item = &dyn Ingredient> {
+        self.ingredients_vec.iter().map(|ingredient| &**ingredient)
+    }
+
     /// **NOT SEMVER STABLE**
     pub fn lookup_ingredient_mut(
         &mut self,
@@ -262,10 +381,41 @@ impl Zalsa {
         self.runtime.last_changed_revision(durability)
     }
 
+    pub(crate) fn last_changed_revision_of(&self, durabilities: Durabilities) -> Revision {
+        self.runtime.last_changed_revision_of(durabilities)
+    }
+
     pub(crate) fn set_cancellation_flag(&self) {
         self.runtime.set_cancellation_flag()
     }
 
+    pub(crate) fn reset_cancellation_flag(&mut self) {
+        self.runtime.reset_cancellation_flag()
+    }
+
+    pub(crate) fn begin_speculation(&self) {
+        self.runtime.begin_speculation()
+    }
+
+    /// Ends the active speculation, restoring the inputs it modified.
+    /// Returns the inputs it created, which the caller must retire.
+    pub(crate) fn end_speculation(&mut self) -> Vec<(Retire, Id)> {
+        let speculation = self.runtime.end_speculation();
+        for (index, undo) in speculation.undo.into_iter().rev() {
+            let (ingredient, runtime) = self.lookup_ingredient_mut(index);
+            undo(ingredient, runtime);
+        }
+        speculation.created
+    }
+
+    pub(crate) fn record_speculative_input(&self, index: IngredientIndex, id: Id, retire: Retire) {
+        self.runtime.record_speculative_input(index, id, retire)
+    }
+
+    pub(crate) fn is_speculative_revision(&self, revision: Revision) -> bool {
+        self.runtime.is_speculative_revision(revision)
+    }
+
     /// Triggers a new revision. Invoked automatically when you call `zalsa_mut`
     /// and so doesn't need to be called otherwise.
     pub(crate) fn new_revision(&mut self) -> Revision {
@@ -274,10 +424,171 @@ impl Zalsa {
         for index in self.ingredients_requiring_reset.iter() {
             self.ingredients_vec[index.as_usize()].reset_for_new_revision();
         }
+        self.invalidate_read_guards();
 
         new_revision
     }
 
+    /// Enables or disables the detection of [`ReadGuard`](`crate::ReadGuard`)s used
+    /// after the database was modified. Only affects guards created afterwards.
+    pub(crate) fn set_leak_detection(&self, enabled: bool) {
+        let generation = enabled.then(|| Arc::new(AtomicUsize::new(0)));
+        self.read_generation.store(generation);
+    }
+
+    /// The generation to tag new [`ReadGuard`](`crate::ReadGuard`)s with, if leak detection is enabled.
+    pub(crate) fn read_generation(&self) -> Option<Arc<AtomicUsize>> {
+        self.read_generation.load_full()
+    }
+
+    /// Marks all existing [`ReadGuard`](`crate::ReadGuard`)s as stale, as
+    /// the values they reference may be modified or freed.
+    pub(crate) fn invalidate_read_guards(&mut self) {
+        if let Some(generation) = &*self.read_generation.load() {
+            generation.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Sets the number of worker threads used to verify the inputs of memos in parallel
+    /// after they may have been invalidated. Setting `0` disables parallel revalidation.
+    pub(crate) fn set_revalidation_threads(&self, num_threads: usize) {
+        let pool = (num_threads > 0).then(|| Arc::new(RevalidationPool::new(num_threads)));
+        self.revalidation_pool.store(pool);
+    }
+
+    /// Returns the parallel revalidation pool, if one is enabled.
+    pub(crate) fn revalidation_pool(&self) -> Guard<Option<Arc<RevalidationPool>>> {
+        self.revalidation_pool.load()
+    }
+
+    /// Drops all memoized values, and deletes the tracked structs they created.
+    /// Inputs and interned values are kept.
+    pub(crate) fn discard_memos(&mut self) {
+        self.runtime.table_mut().clear_memos();
+        for index in 0..self.ingredients_vec.len() {
+            self.ingredients_vec[index].discard_entries(&mut self.runtime);
+        }
+        for side_table in self.side_tables.load().iter() {
+            if let Some(side_table) = side_table.upgrade() {
+                side_table.evict_all();
+            }
+        }
+    }
+
+    /// Sets the LRU capacity of the tracked functions without an `lru` option
+    /// whose ingredients are created afterwards. `0` disables LRU eviction.
+    pub(crate) fn set_default_lru_capacity(&self, capacity: usize) {
+        self.default_lru_capacity.store(capacity, Ordering::Relaxed);
+    }
+
+    /// Sets every how many executions a tracked function is re-executed to check that it is
+    /// deterministic. `0` disables the check.
+    #[cfg(feature = "check_determinism")]
+    pub(crate) fn set_determinism_check_interval(&self, interval: usize) {
+        self.determinism_check_interval
+            .store(interval, Ordering::Relaxed);
+    }
+
+    /// Whether the tracked function that just executed should be checked for determinism.
+    #[cfg(feature = "check_determinism")]
+    pub(crate) fn should_check_determinism(&self) -> bool {
+        let interval = self.determinism_check_interval.load(Ordering::Relaxed);
+        interval != 0 && self.executions.fetch_add(1, Ordering::Relaxed) % interval == 0
+    }
+
+    /// Sets the number of input changes retained in the revision log. `0` disables the log.
+    pub(crate) fn set_revision_log_capacity(&self, capacity: usize) {
+        self.runtime.set_revision_log_capacity(capacity)
+    }
+
+    /// Sets the strategy invoked when a thread has to wait for a query executing on another thread.
+    pub(crate) fn set_blocking_strategy(&self, blocking_strategy: Box<dyn BlockingStrategy>) {
+        self.runtime.set_blocking_strategy(blocking_strategy)
+    }
+
+    /// Sets how long a thread may wait for a query executing on another thread
+    /// before unwinding with a [`Deadlock`](`crate::Deadlock`).
+    pub(crate) fn set_deadlock_timeout(&self, timeout: Option<Duration>) {
+        self.runtime.set_deadlock_timeout(timeout)
+    }
+
+    /// Sets how often a thread waiting for a query executing on another thread
+    /// reports that it is still waiting.
+    pub(crate) fn set_block_warning_interval(&self, interval: Option<Duration>) {
+        self.runtime.set_block_warning_interval(interval)
+    }
+
+    /// See [`Runtime::has_waiters_above`][]
+    pub(crate) fn has_waiters_above(&self, id: ThreadId, priority: Priority) -> bool {
+        self.runtime.has_waiters_above(id, priority)
+    }
+
+    /// See [`Runtime::preempt_flag`][]
+    pub(crate) fn preempt_flag(&self, id: ThreadId) -> Arc<AtomicBool> {
+        self.runtime.preempt_flag(id)
+    }
+
+    /// See [`Runtime::release_preempt_flag`][]
+    pub(crate) fn release_preempt_flag(&self, id: ThreadId, flag: Arc<AtomicBool>) {
+        self.runtime.release_preempt_flag(id, flag)
+    }
+
+    /// See [`Runtime::take_preempt_request`][]
+    pub(crate) fn take_preempt_request(&self, id: ThreadId, flag: &AtomicBool) -> bool {
+        self.runtime.take_preempt_request(id, flag)
+    }
+
+    /// Returns the input changes retained in the revision log, oldest first.
+    pub(crate) fn revision_log(&self) -> Vec<InputChange> {
+        self.runtime.revision_log()
+    }
+
+    /// Registers an interceptor to be invoked around the execution of tracked functions.
+    pub(crate) fn add_interceptor(&self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors.rcu(|interceptors| {
+            let mut interceptors = Vec::clone(interceptors);
+            interceptors.push(interceptor.clone());
+            interceptors
+        });
+    }
+
+    /// Returns the registered interceptors, in registration order.
+    pub(crate) fn interceptors(&self) -> Guard<Arc<Vec<Arc<dyn Interceptor>>>> {
+        self.interceptors.load()
+    }
+
+    /// Registers a side table, dropping the registrations of side tables that no longer exist.
+    pub(crate) fn set_memo_cache(&self, backend: Box<dyn MemoCacheBackend>) {
+        self.memo_cache.store(Some(Arc::new(backend)));
+    }
+
+    /// Returns the memo cache backend, if one was set.
+    pub(crate) fn memo_cache(&self) -> Option<Arc<Box<dyn MemoCacheBackend>>> {
+        self.memo_cache.load_full()
+    }
+
+    pub(crate) fn add_side_table(&self, side_table: Weak<dyn Evict>) {
+        self.side_tables.rcu(|side_tables| {
+            let mut side_tables: Vec<_> = side_tables
+                .iter()
+                .filter(|side_table| side_table.strong_count() > 0)
+                .cloned()
+                .collect();
+            side_tables.push(side_table.clone());
+            side_tables
+        });
+    }
+
+    /// Evicts the entries for `key` from all side tables, as its memoized value
+    /// (or the struct it identifies) is about to be invalidated or discarded.
+    pub(crate) fn evict_side_table_entries(&self, key: DatabaseKeyIndex) {
+        for side_table in self.side_tables.load().iter() {
+            if let Some(side_table) = side_table.upgrade() {
+                side_table.evict(key);
+            }
+        }
+    }
+
     /// See [`Runtime::block_on_or_unwind`][]
     pub(crate) fn block_on_or_unwind<QueryMutexGuard>(
         &self,
@@ -286,7 +597,7 @@ impl Zalsa {
         database_key: DatabaseKeyIndex,
         other_id: ThreadId,
         query_mutex_guard: QueryMutexGuard,
-    ) {
+    ) -> Option<SharedMemo> {
         self.runtime
             .block_on_or_unwind(db, local_state, database_key, other_id, query_mutex_guard)
     }
@@ -311,6 +622,13 @@ impl Zalsa {
     }
 }
 
+impl Drop for Zalsa {
+    fn drop(&mut self) {
+        // Detached guards may outlive the database, whose values they reference.
+        self.invalidate_read_guards();
+    }
+}
+
 struct JarAuxImpl<'a>(&'a Zalsa, &'a FxHashMap<TypeId, IngredientIndex>);
 
 impl JarAux for JarAuxImpl<'_> {
@@ -335,6 +653,10 @@ impl JarAux for JarAuxImpl<'_> {
         memo_ingredients.push(ingredient_index);
         mi
     }
+
+    fn default_lru_capacity(&self) -> usize {
+        self.0.default_lru_capacity.load(Ordering::Relaxed)
+    }
 }
 
 /// Caches a pointer to an ingredient in a database.
@@ -399,6 +721,55 @@ where
     }
 }
 
+/// Caches pointers to the ingredients of the instances of a generic tracked function.
+///
+/// A `static` cannot be generic, so a single cache is shared by all instances,
+/// keyed by the type of their ingredient.
+pub struct GenericIngredientCache {
+    cached_data: std::sync::OnceLock<RwLock<FxHashMap<TypeId, CachedIndex>>>,
+}
+
+/// The index of an ingredient, and the nonce of the database it belongs to.
+type CachedIndex = (Nonce<StorageNonce>, IngredientIndex);
+
+impl Default for GenericIngredientCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenericIngredientCache {
+    /// Create a new cache
+    pub const fn new() -> Self {
+        Self {
+            cached_data: std::sync::OnceLock::new(),
+        }
+    }
+
+    /// Get a reference to the ingredient `I` in the database.
+    /// If the ingredient is not already in the cache, it will be created.
+    pub fn get_or_create<'s, I: Ingredient>(
+        &self,
+        db: &'s dyn Database,
+        create_index: impl Fn() -> IngredientIndex,
+    ) -> &'s I {
+        let zalsa = db.zalsa();
+        let cached_data = self.cached_data.get_or_init(Default::default);
+        let key = TypeId::of::<I>();
+
+        let cached = cached_data.read().get(&key).copied();
+        let index = match cached {
+            Some((nonce, index)) if nonce == zalsa.nonce() => index,
+            _ => {
+                let index = create_index();
+                cached_data.write().insert(key, (zalsa.nonce(), index));
+                index
+            }
+        };
+        zalsa.lookup_ingredient(index).assert_type::<I>()
+    }
+}
+
 /// Given a wide pointer `T`, extracts the data pointer (typed as `U`).
 ///
 /// # Safety requirement
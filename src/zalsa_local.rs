@@ -1,17 +1,20 @@
-use crossbeam::atomic::AtomicCell;
 use rustc_hash::FxHashMap;
 use tracing::debug;
 
 use crate::accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues};
 use crate::active_query::ActiveQuery;
-use crate::durability::Durability;
+use crate::durability::{Durabilities, Durability};
 use crate::key::{DatabaseKeyIndex, InputDependencyIndex, OutputDependencyIndex};
+use crate::nonce::Nonce;
 use crate::runtime::StampedValue;
+use crate::stats::Allocation;
+#[cfg(feature = "stats")]
+use crate::stats::AllocationStats;
 use crate::table::PageIndex;
 use crate::table::Slot;
 use crate::table::Table;
 use crate::tracked_struct::{Disambiguator, Identity, IdentityHash, IdentityMap};
-use crate::zalsa::IngredientIndex;
+use crate::zalsa::{IngredientIndex, StorageNonce, Zalsa};
 use crate::Accumulator;
 use crate::Cancelled;
 use crate::Cycle;
@@ -19,8 +22,66 @@ use crate::Database;
 use crate::Event;
 use crate::EventKind;
 use crate::Id;
+use crate::Priority;
 use crate::Revision;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::ThreadId;
+use std::time::Instant;
+
+mod edges;
+
+/// Frames whose edge buffer grew beyond this are not reused, so that a single
+/// query with many dependencies does not keep a large buffer alive.
+const MAX_RECYCLED_EDGES: usize = 1024;
+
+thread_local! {
+    /// The outermost query executing on this thread for each database handle that has one,
+    /// together with the nonce of the handle's storage; see [`assert_no_executing_query`].
+    static EXECUTING: RefCell<Vec<(Nonce<StorageNonce>, DatabaseKeyIndex)>> = const { RefCell::new(Vec::new()) };
+
+    /// Set when a query frame is dropped while unwinding, i.e., without its read being recorded
+    /// by its caller; see [`take_unwound_query`].
+    static UNWOUND_QUERY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Returns true if a query frame unwound on this thread since the last call, in which case
+/// the dependencies of the queries that caught the panic are incomplete.
+pub(crate) fn take_unwound_query() -> bool {
+    UNWOUND_QUERY.replace(false)
+}
+
+/// Panics if a query of `db` is executing on this thread, naming that query and `mutation`.
+///
+/// Mutating the database waits for all other handles to be dropped, so mutating it through
+/// one handle while a query executes on another handle of this thread would deadlock.
+pub fn assert_no_executing_query(db: &dyn Database, mutation: &dyn Fn() -> String) {
+    let (zalsa, zalsa_local) = db.zalsas();
+    let nonce = zalsa.nonce();
+    let executing = zalsa_local
+        .active_query()
+        .map(|(query, _)| query)
+        .or_else(|| {
+            EXECUTING.with_borrow(|executing| {
+                executing
+                    .iter()
+                    .rev()
+                    .find(|&&(storage, _)| storage == nonce)
+                    .map(|&(_, query)| query)
+            })
+        });
+
+    if let Some(query) = executing {
+        let query = crate::attach::with_attached_database(|_| format!("{query:?}"))
+            .unwrap_or_else(|| crate::attach::attach(db, || format!("{query:?}")));
+        panic!(
+            "cannot {mutation} while the query `{query}` is executing on this thread: \
+             the database can only be mutated once all queries completed",
+            mutation = mutation(),
+        );
+    }
+}
 
 /// State that is specific to a single execution thread.
 ///
@@ -29,6 +90,9 @@ use std::cell::RefCell;
 /// **Note also that all mutations to the database handle (and hence
 /// to the local-state) must be undone during unwinding.**
 pub struct ZalsaLocal {
+    /// Nonce of the storage this handle belongs to.
+    storage_nonce: Nonce<StorageNonce>,
+
     /// Vector of active queries.
     ///
     /// This is normally `Some`, but it is set to `None`
@@ -38,16 +102,61 @@ pub struct ZalsaLocal {
     /// during unwinding.
     query_stack: RefCell<Vec<ActiveQuery>>,
 
+    /// Frames of queries that completed on this thread. Their buffers are reused
+    /// by the next queries pushed onto `query_stack` instead of allocating new ones.
+    spare_queries: RefCell<Vec<ActiveQuery>>,
+
+    /// Counts how often `spare_queries` avoided allocating a new frame.
+    #[cfg(feature = "stats")]
+    allocation_stats: Cell<AllocationStats>,
+
     /// Stores the most recent page for a given ingredient.
     /// This is thread-local to avoid contention.
     most_recent_pages: RefCell<FxHashMap<IngredientIndex, PageIndex>>,
+
+    /// Number of [`no_cancel_scope`](`Self::no_cancel_scope`) calls currently
+    /// active on this thread. While non-zero, cancellation does not unwind.
+    no_cancel_depth: Cell<usize>,
+
+    /// Priority of the queries executed through this handle.
+    priority: Cell<Priority>,
+
+    /// Set while unwinding after being preempted, see [`Priority::Low`].
+    preempted: Cell<bool>,
+
+    /// The [preempt flag](`Zalsa::preempt_flag`) of the thread this handle last checked
+    /// for preemption on. The dependency graph is only consulted while it is set.
+    preempt_requested: RefCell<Option<(ThreadId, Arc<AtomicBool>)>>,
+
+    /// Number of queries this handle may still execute, set within
+    /// [`run_with_fuel`](`Self::run_with_fuel`).
+    fuel: Cell<Option<usize>>,
+
+    /// Instant after which queries unwind at their next cancellation checkpoint, set within
+    /// [`run_with_deadline`](`Self::run_with_deadline`).
+    deadline: Cell<Option<Instant>>,
+
+    /// Set on the handles of revalidation threads: memos whose inputs changed are reported
+    /// as changed instead of being re-executed, see [`RevalidationPool`](`crate::revalidation::RevalidationPool`).
+    verify_only: Cell<bool>,
 }
 
 impl ZalsaLocal {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(storage_nonce: Nonce<StorageNonce>) -> Self {
         ZalsaLocal {
+            storage_nonce,
             query_stack: RefCell::new(vec![]),
+            spare_queries: RefCell::new(vec![]),
+            #[cfg(feature = "stats")]
+            allocation_stats: Cell::new(AllocationStats::default()),
             most_recent_pages: RefCell::new(FxHashMap::default()),
+            no_cancel_depth: Cell::new(0),
+            priority: Cell::new(Priority::Normal),
+            preempted: Cell::new(false),
+            preempt_requested: RefCell::new(None),
+            fuel: Cell::new(None),
+            deadline: Cell::new(None),
+            verify_only: Cell::new(false),
         }
     }
 
@@ -87,7 +196,24 @@ impl ZalsaLocal {
     #[inline]
     pub(crate) fn push_query(&self, database_key_index: DatabaseKeyIndex) -> ActiveQueryGuard<'_> {
         let mut query_stack = self.query_stack.borrow_mut();
-        query_stack.push(ActiveQuery::new(database_key_index));
+        if query_stack.is_empty() {
+            self.preempted.set(false);
+            EXECUTING.with_borrow_mut(|executing| {
+                executing.push((self.storage_nonce, database_key_index));
+            });
+        }
+        let query = match self.spare_queries.borrow_mut().pop() {
+            Some(mut query) => {
+                query.reset(database_key_index);
+                self.record_allocation(Allocation::Reused);
+                query
+            }
+            None => {
+                self.record_allocation(Allocation::New);
+                ActiveQuery::new(database_key_index)
+            }
+        };
+        query_stack.push(query);
         ActiveQueryGuard {
             local_state: self,
             database_key_index,
@@ -95,11 +221,64 @@ impl ZalsaLocal {
         }
     }
 
+    /// Executes `op` within a frame of the query `database_key_index`, which executes on
+    /// another thread, returning the frame with the reads of `op`; see [`crate::join`].
+    pub(crate) fn record_reads_for<R>(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+        op: impl FnOnce() -> R,
+    ) -> (R, ActiveQuery) {
+        let active_query = self.push_query(database_key_index);
+        let result = op();
+        (result, active_query.complete())
+    }
+
+    /// Adds the reads recorded by [`Self::record_reads_for`] on another thread to the
+    /// active query.
+    pub(crate) fn add_reads_from(&self, frame: &ActiveQuery) {
+        self.with_query_stack(|stack| {
+            if let Some(top_query) = stack.last_mut() {
+                top_query.add_reads_from(frame);
+            }
+        })
+    }
+
+    /// Keeps the buffers of a completed frame for the next query, unless they grew large.
+    fn recycle_query(&self, query: ActiveQuery) {
+        if query.edge_capacity() <= MAX_RECYCLED_EDGES {
+            self.spare_queries.borrow_mut().push(query);
+        }
+    }
+
+    #[inline]
+    fn record_allocation(&self, allocation: Allocation) {
+        #[cfg(feature = "stats")]
+        {
+            let mut stats = self.allocation_stats.get();
+            stats.record(allocation);
+            self.allocation_stats.set(stats);
+        }
+        #[cfg(not(feature = "stats"))]
+        let _ = allocation;
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn allocation_stats(&self) -> AllocationStats {
+        self.allocation_stats.get()
+    }
+
     /// Executes a closure within the context of the current active query stacks.
     pub(crate) fn with_query_stack<R>(&self, c: impl FnOnce(&mut Vec<ActiveQuery>) -> R) -> R {
         c(self.query_stack.borrow_mut().as_mut())
     }
 
+    /// Returns the keys of the queries executing (or being verified) on this thread,
+    /// from the outermost to the innermost one.
+    #[cfg(feature = "debug")]
+    pub(crate) fn active_query_stack(&self) -> Vec<DatabaseKeyIndex> {
+        self.with_query_stack(|stack| stack.iter().map(|query| query.database_key_index).collect())
+    }
+
     /// Returns the index of the active query along with its *current* durability/changed-at
     /// information. As the query continues to execute, naturally, that information may change.
     pub(crate) fn active_query(&self) -> Option<(DatabaseKeyIndex, StampedValue<()>)> {
@@ -124,10 +303,11 @@ impl ZalsaLocal {
         &self,
         index: IngredientIndex,
         value: A,
+        revision: Revision,
     ) -> Result<(), ()> {
         self.with_query_stack(|stack| {
             if let Some(top_query) = stack.last_mut() {
-                top_query.accumulated.accumulate(index, value);
+                top_query.accumulated.accumulate(index, value, revision);
                 Ok(())
             } else {
                 Err(())
@@ -155,11 +335,14 @@ impl ZalsaLocal {
         })
     }
 
-    /// Register that currently active query reads the given input
+    /// Register that currently active query reads the given input, whose value
+    /// depends on inputs of the durabilities `durabilities`.
     pub(crate) fn report_tracked_read(
         &self,
+        db: &dyn Database,
         input: InputDependencyIndex,
         durability: Durability,
+        durabilities: Durabilities,
         changed_at: Revision,
         accumulated: InputAccumulatedValues,
     ) {
@@ -167,36 +350,105 @@ impl ZalsaLocal {
             "report_tracked_read(input={:?}, durability={:?}, changed_at={:?})",
             input, durability, changed_at
         );
-        self.with_query_stack(|stack| {
-            if let Some(top_query) = stack.last_mut() {
-                top_query.add_read(input, durability, changed_at, accumulated);
-
-                // We are a cycle participant:
-                //
-                //     C0 --> ... --> Ci --> Ci+1 -> ... -> Cn --> C0
-                //                        ^   ^
-                //                        :   |
-                //         This edge -----+   |
-                //                            |
-                //                            |
-                //                            N0
-                //
-                // In this case, the value we have just read from `Ci+1`
-                // is actually the cycle fallback value and not especially
-                // interesting. We unwind now with `CycleParticipant` to avoid
-                // executing the rest of our query function. This unwinding
-                // will be caught and our own fallback value will be used.
-                //
-                // Note that `Ci+1` may` have *other* callers who are not
-                // participants in the cycle (e.g., N0 in the graph above).
-                // They will not have the `cycle` marker set in their
-                // stack frames, so they will just read the fallback value
-                // from `Ci+1` and continue on their merry way.
-                if let Some(cycle) = &top_query.cycle {
-                    cycle.clone().throw()
+        let auto_cancel_check = self.with_query_stack(|stack| {
+            let Some(top_query) = stack.last_mut() else {
+                return false;
+            };
+            if !top_query.add_read(input, durability, durabilities, changed_at, accumulated) {
+                self.record_allocation(Allocation::DeduplicatedRead);
+            }
+
+            // We are a cycle participant:
+            //
+            //     C0 --> ... --> Ci --> Ci+1 -> ... -> Cn --> C0
+            //                        ^   ^
+            //                        :   |
+            //         This edge -----+   |
+            //                            |
+            //                            |
+            //                            N0
+            //
+            // In this case, the value we have just read from `Ci+1`
+            // is actually the cycle fallback value and not especially
+            // interesting. We unwind now with `CycleParticipant` to avoid
+            // executing the rest of our query function. This unwinding
+            // will be caught and our own fallback value will be used.
+            //
+            // Note that `Ci+1` may` have *other* callers who are not
+            // participants in the cycle (e.g., N0 in the graph above).
+            // They will not have the `cycle` marker set in their
+            // stack frames, so they will just read the fallback value
+            // from `Ci+1` and continue on their merry way.
+            if let Some(cycle) = &top_query.cycle {
+                cycle.clone().throw()
+            }
+            top_query.auto_cancel_check
+        });
+
+        if auto_cancel_check {
+            self.unwind_if_revision_cancelled(db);
+        }
+    }
+
+    /// Register that the currently active query reads each of the given inputs, along
+    /// with their durability and the revision in which they last changed.
+    ///
+    /// This is equivalent to calling [`report_tracked_read`](`Self::report_tracked_read`)
+    /// for each input, but borrows the query stack, checks for cycles and checks for
+    /// cancellation once for all of them, which dominates the cost of tight loops
+    /// reading many fields.
+    ///
+    /// `inputs` is consumed while the query stack is borrowed, so it must not call into
+    /// the database.
+    pub(crate) fn report_tracked_reads(
+        &self,
+        db: &dyn Database,
+        inputs: impl IntoIterator<Item = (InputDependencyIndex, Durability, Revision)>,
+    ) {
+        let auto_cancel_check = self.with_query_stack(|stack| {
+            let Some(top_query) = stack.last_mut() else {
+                return false;
+            };
+            for (input, durability, changed_at) in inputs {
+                debug!(
+                    "report_tracked_reads(input={:?}, durability={:?}, changed_at={:?})",
+                    input, durability, changed_at
+                );
+                let new = top_query.add_read(
+                    input,
+                    durability,
+                    Durabilities::at_least(durability),
+                    changed_at,
+                    InputAccumulatedValues::Empty,
+                );
+                if !new {
+                    self.record_allocation(Allocation::DeduplicatedRead);
                 }
             }
-        })
+
+            // See `report_tracked_read`.
+            if let Some(cycle) = &top_query.cycle {
+                cycle.clone().throw()
+            }
+            top_query.auto_cancel_check
+        });
+
+        if auto_cancel_check {
+            self.unwind_if_revision_cancelled(db);
+        }
+    }
+
+    /// Executes `op` without recording the reads it performs in the active query, if any.
+    ///
+    /// Outputs recorded by `op`, such as tracked structs it creates, are kept.
+    pub(crate) fn without_tracked_reads<R>(&self, op: impl FnOnce() -> R) -> R {
+        let snapshot =
+            self.with_query_stack(|stack| stack.last().map(|query| query.reads_snapshot()));
+        let result = op();
+        if let Some(snapshot) = snapshot {
+            self.with_query_stack(|stack| stack.last_mut().unwrap().restore_reads(snapshot));
+        }
+        result
     }
 
     /// Register that the current query read an untracked value
@@ -214,8 +466,6 @@ impl ZalsaLocal {
 
     /// Update the top query on the stack to act as though it read a value
     /// of durability `durability` which changed in `revision`.
-    // FIXME: Use or remove this.
-    #[allow(dead_code)]
     pub(crate) fn report_synthetic_read(&self, durability: Durability, revision: Revision) {
         self.with_query_stack(|stack| {
             if let Some(top_query) = stack.last_mut() {
@@ -291,9 +541,165 @@ impl ZalsaLocal {
     pub(crate) fn unwind_if_revision_cancelled(&self, db: &dyn Database) {
         db.salsa_event(&|| Event::new(EventKind::WillCheckCancellation));
         let zalsa = db.zalsa();
-        if zalsa.load_cancellation_flag() {
+        if self.no_cancel_depth.get() > 0 {
+            return;
+        }
+
+        if self.priority.get() == Priority::Low && self.is_preempt_requested(zalsa) {
+            self.unwind_preempted(zalsa.current_revision());
+        }
+
+        if self.has_pending_write(zalsa) {
             self.unwind_cancelled(zalsa.current_revision());
         }
+
+        if let Some(deadline) = self.deadline.get() {
+            if Instant::now() >= deadline {
+                self.unwind_deadline_exceeded(zalsa.current_revision());
+            }
+        }
+    }
+
+    /// True if a thread of higher priority waits for this (low-priority) thread.
+    fn is_preempt_requested(&self, zalsa: &Zalsa) -> bool {
+        let thread_id = std::thread::current().id();
+        let mut preempt_requested = self.preempt_requested.borrow_mut();
+        let flag = match &*preempt_requested {
+            Some((id, flag)) if *id == thread_id => flag,
+            _ => {
+                if let Some((id, flag)) = preempt_requested.take() {
+                    zalsa.release_preempt_flag(id, flag);
+                }
+                &preempt_requested
+                    .insert((thread_id, zalsa.preempt_flag(thread_id)))
+                    .1
+            }
+        };
+        flag.load(Ordering::Acquire) && zalsa.take_preempt_request(thread_id, flag)
+    }
+
+    /// Releases the preempt flag this handle holds, see [`Zalsa::release_preempt_flag`].
+    pub(crate) fn release_preempt_flag(&self, zalsa: &Zalsa) {
+        if let Some((id, flag)) = self.preempt_requested.take() {
+            zalsa.release_preempt_flag(id, flag);
+        }
+    }
+
+    /// Like [`Self::unwind_if_revision_cancelled`], but returns the cancellation
+    /// instead of unwinding. Preemption is only reported by unwinding: were the query
+    /// to return it as its value, that value would be handed to the preempting thread.
+    pub(crate) fn check_cancelled(&self, db: &dyn Database) -> Result<(), Cancelled> {
+        db.salsa_event(&|| Event::new(EventKind::WillCheckCancellation));
+        let zalsa = db.zalsa();
+        if self.no_cancel_depth.get() == 0 && self.has_pending_write(zalsa) {
+            self.report_untracked_read(zalsa.current_revision());
+            return Err(Cancelled::PendingWrite);
+        }
+        Ok(())
+    }
+
+    /// True if a write is pending and this thread does not defer cancellation
+    /// because a thread of higher priority waits for it.
+    fn has_pending_write(&self, zalsa: &Zalsa) -> bool {
+        zalsa.load_cancellation_flag()
+            && !zalsa.has_waiters_above(std::thread::current().id(), self.priority.get())
+    }
+
+    /// True if this handle is unwinding after being preempted.
+    pub(crate) fn is_preempted(&self) -> bool {
+        self.preempted.get()
+    }
+
+    pub(crate) fn is_verify_only(&self) -> bool {
+        self.verify_only.get()
+    }
+
+    pub(crate) fn set_verify_only(&self) {
+        self.verify_only.set(true);
+    }
+
+    pub(crate) fn priority(&self) -> Priority {
+        self.priority.get()
+    }
+
+    pub(crate) fn set_priority(&self, priority: Priority) {
+        self.priority.set(priority);
+    }
+
+    /// Executes `op` without unwinding on cancellation. If the revision was
+    /// cancelled in the meantime, unwinds as soon as the outermost scope exits.
+    pub(crate) fn no_cancel_scope<R>(&self, db: &dyn Database, op: impl FnOnce() -> R) -> R {
+        struct PopScope<'s>(&'s Cell<usize>);
+
+        impl Drop for PopScope<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() - 1);
+            }
+        }
+
+        self.no_cancel_depth.set(self.no_cancel_depth.get() + 1);
+        let result = {
+            let _guard = PopScope(&self.no_cancel_depth);
+            op()
+        };
+        self.unwind_if_revision_cancelled(db);
+        result
+    }
+
+    /// Executes `op` with `fuel` for at most that many query executions. An enclosing
+    /// budget is charged for the executions performed by `op`.
+    pub(crate) fn run_with_fuel<R>(&self, fuel: usize, op: impl FnOnce() -> R) -> R {
+        struct RestoreFuel<'s> {
+            fuel: &'s Cell<Option<usize>>,
+            budget: usize,
+            outer: Option<usize>,
+        }
+
+        impl Drop for RestoreFuel<'_> {
+            fn drop(&mut self) {
+                let consumed = self.budget - self.fuel.get().unwrap_or(0);
+                self.fuel
+                    .set(self.outer.map(|outer| outer.saturating_sub(consumed)));
+            }
+        }
+
+        let _guard = RestoreFuel {
+            fuel: &self.fuel,
+            budget: fuel,
+            outer: self.fuel.replace(Some(fuel)),
+        };
+        op()
+    }
+
+    /// Executes `op` with queries unwinding once `deadline` has passed. An enclosing
+    /// deadline still applies if it is earlier.
+    pub(crate) fn run_with_deadline<R>(&self, deadline: Instant, op: impl FnOnce() -> R) -> R {
+        struct RestoreDeadline<'s>(&'s Cell<Option<Instant>>, Option<Instant>);
+
+        impl Drop for RestoreDeadline<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.1);
+            }
+        }
+
+        let outer = self.deadline.get();
+        let _guard = RestoreDeadline(&self.deadline, outer);
+        self.deadline
+            .set(Some(outer.map_or(deadline, |outer| outer.min(deadline))));
+        op()
+    }
+
+    /// Consumes fuel for executing `database_key_index`, unwinding with
+    /// [`Cancelled::FuelExhausted`] if there is none left.
+    pub(crate) fn consume_fuel(&self, database_key_index: DatabaseKeyIndex) {
+        match self.fuel.get() {
+            None => {}
+            Some(0) => {
+                debug!("{database_key_index:?}: fuel exhausted");
+                Cancelled::FuelExhausted.throw();
+            }
+            Some(fuel) => self.fuel.set(Some(fuel - 1)),
+        }
     }
 
     #[cold]
@@ -301,6 +707,19 @@ impl ZalsaLocal {
         self.report_untracked_read(current_revision);
         Cancelled::PendingWrite.throw();
     }
+
+    #[cold]
+    fn unwind_deadline_exceeded(&self, current_revision: Revision) {
+        self.report_untracked_read(current_revision);
+        Cancelled::DeadlineExceeded.throw();
+    }
+
+    #[cold]
+    fn unwind_preempted(&self, current_revision: Revision) {
+        self.report_untracked_read(current_revision);
+        self.preempted.set(true);
+        Cancelled::Preempted.throw();
+    }
 }
 
 impl std::panic::RefUnwindSafe for ZalsaLocal {}
@@ -313,9 +732,6 @@ pub(crate) struct QueryRevisions {
     /// The most revision in which some input changed.
     pub(crate) changed_at: Revision,
 
-    /// Minimum durability of the inputs to this query.
-    pub(crate) durability: Durability,
-
     /// How was this query computed?
     pub(crate) origin: QueryOrigin,
 
@@ -339,38 +755,23 @@ pub(crate) struct QueryRevisions {
     pub(super) tracked_struct_ids: IdentityMap,
 
     pub(super) accumulated: Option<Box<AccumulatedMap>>,
-    /// [`InputAccumulatedValues::Empty`] if any input read during the query's execution
-    /// has any direct or indirect accumulated values.
-    pub(super) accumulated_inputs: AtomicCell<InputAccumulatedValues>,
 }
 
-impl QueryRevisions {
-    pub(crate) fn stamped_value<V>(&self, value: V) -> StampedValue<V> {
-        self.stamp_template().stamp(value)
-    }
+/// The [`QueryRevisions`] of a query that completed execution, along with the
+/// information that memos keep packed alongside their `verified_at` revision.
+#[derive(Debug)]
+pub(crate) struct CompletedQuery {
+    pub(crate) revisions: QueryRevisions,
 
-    pub(crate) fn stamp_template(&self) -> StampTemplate {
-        StampTemplate {
-            durability: self.durability,
-            changed_at: self.changed_at,
-        }
-    }
-}
+    /// Minimum durability of the inputs to this query.
+    pub(crate) durability: Durability,
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(crate) struct StampTemplate {
-    durability: Durability,
-    changed_at: Revision,
-}
+    /// Durabilities of the inputs to this query, including the minimum.
+    pub(crate) durabilities: Durabilities,
 
-impl StampTemplate {
-    pub(crate) fn stamp<V>(self, value: V) -> StampedValue<V> {
-        StampedValue {
-            value,
-            durability: self.durability,
-            changed_at: self.changed_at,
-        }
-    }
+    /// [`InputAccumulatedValues::Any`] if any input read during the query's execution
+    /// has any direct or indirect accumulated values.
+    pub(crate) accumulated_inputs: InputAccumulatedValues,
 }
 
 /// Tracks the way that a memoized value for a query was created.
@@ -396,8 +797,13 @@ pub enum QueryOrigin {
 }
 
 impl QueryOrigin {
+    /// True if the value was assigned using `specify`.
+    pub(crate) fn is_assigned(&self) -> bool {
+        matches!(self, QueryOrigin::Assigned(_))
+    }
+
     /// Indices for queries *read* by this query
-    pub(crate) fn inputs(&self) -> impl DoubleEndedIterator<Item = InputDependencyIndex> + '_ {
+    pub(crate) fn inputs(&self) -> impl Iterator<Item = InputDependencyIndex> + '_ {
         let opt_edges = match self {
             QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) => Some(edges),
             QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => None,
@@ -406,7 +812,7 @@ impl QueryOrigin {
     }
 
     /// Indices for queries *written* by this query (if any)
-    pub(crate) fn outputs(&self) -> impl DoubleEndedIterator<Item = OutputDependencyIndex> + '_ {
+    pub(crate) fn outputs(&self) -> impl Iterator<Item = OutputDependencyIndex> + '_ {
         let opt_edges = match self {
             QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) => Some(edges),
             QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => None,
@@ -420,7 +826,7 @@ impl QueryOrigin {
 /// e.g., when creating the memoized value for Q0 executed another function Q1)
 /// and output edges
 /// (e.g., when Q0 specified the value for another query Q2).
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct QueryEdges {
     /// The list of outgoing edges from this node.
     /// This list combines *both* inputs and outputs.
@@ -434,8 +840,9 @@ pub struct QueryEdges {
     /// Important:
     ///
     /// * The inputs must be in **execution order** for the red-green algorithm to work.
-    // pub input_outputs: ThinBox<[DependencyEdge]>, once that is a thing
-    pub input_outputs: Box<[QueryEdge]>,
+    ///
+    /// The edges are stored in a compact encoding, see the [`edges`] module.
+    input_outputs: edges::EncodedEdges,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -445,11 +852,16 @@ pub enum QueryEdge {
 }
 
 impl QueryEdges {
+    /// Returns the inputs and outputs, in execution order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = QueryEdge> + Clone + '_ {
+        edges::Decoder::new(&self.input_outputs)
+    }
+
     /// Returns the (tracked) inputs that were executed in computing this memoized value.
     ///
     /// These will always be in execution order.
-    pub(crate) fn inputs(&self) -> impl DoubleEndedIterator<Item = InputDependencyIndex> + '_ {
-        self.input_outputs.iter().filter_map(|&edge| match edge {
+    pub(crate) fn inputs(&self) -> impl Iterator<Item = InputDependencyIndex> + '_ {
+        self.iter().filter_map(|edge| match edge {
             QueryEdge::Input(dependency_index) => Some(dependency_index),
             QueryEdge::Output(_) => None,
         })
@@ -458,8 +870,8 @@ impl QueryEdges {
     /// Returns the (tracked) outputs that were executed in computing this memoized value.
     ///
     /// These will always be in execution order.
-    pub(crate) fn outputs(&self) -> impl DoubleEndedIterator<Item = OutputDependencyIndex> + '_ {
-        self.input_outputs.iter().filter_map(|&edge| match edge {
+    pub(crate) fn outputs(&self) -> impl Iterator<Item = OutputDependencyIndex> + '_ {
+        self.iter().filter_map(|edge| match edge {
             QueryEdge::Output(dependency_index) => Some(dependency_index),
             QueryEdge::Input(_) => None,
         })
@@ -468,11 +880,17 @@ impl QueryEdges {
     /// Creates a new `QueryEdges`; the values given for each field must meet struct invariants.
     pub(crate) fn new(input_outputs: impl IntoIterator<Item = QueryEdge>) -> Self {
         Self {
-            input_outputs: input_outputs.into_iter().collect(),
+            input_outputs: edges::encode(input_outputs),
         }
     }
 }
 
+impl std::fmt::Debug for QueryEdges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 /// When a query is pushed onto the `active_query` stack, this guard
 /// is returned to represent its slot. The guard can be used to pop
 /// the query from the stack -- in the case of unwinding, the guard's
@@ -492,7 +910,19 @@ impl ActiveQueryGuard<'_> {
                 stack.last().unwrap().database_key_index,
                 self.database_key_index
             );
-            stack.pop().unwrap()
+            let query = stack.pop().unwrap();
+            if stack.is_empty() {
+                EXECUTING.with_borrow_mut(|executing| executing.pop());
+            }
+            query
+        })
+    }
+
+    /// Check for cancellation after each read of the query.
+    pub(crate) fn enable_auto_cancel_check(&self) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            stack.last_mut().unwrap().auto_cancel_check = true;
         })
     }
 
@@ -513,18 +943,21 @@ impl ActiveQueryGuard<'_> {
         query
     }
 
-    /// Pops an active query from the stack. Returns the [`QueryRevisions`]
+    /// Pops an active query from the stack. Returns the [`CompletedQuery`]
     /// which summarizes the other queries that were accessed during this
     /// query's execution.
     #[inline]
-    pub(crate) fn pop(self) -> QueryRevisions {
+    pub(crate) fn pop(self) -> CompletedQuery {
         // Extract accumulated inputs.
-        let popped_query = self.complete();
+        let local_state = self.local_state;
+        let mut popped_query = self.complete();
 
         // If this frame were a cycle participant, it would have unwound.
         assert!(popped_query.cycle.is_none());
 
-        popped_query.into_revisions()
+        let completed = popped_query.take_completed();
+        local_state.recycle_query(popped_query);
+        completed
     }
 
     /// If the active query is registered as a cycle participant, remove and
@@ -537,6 +970,9 @@ impl ActiveQueryGuard<'_> {
 
 impl Drop for ActiveQueryGuard<'_> {
     fn drop(&mut self) {
+        if std::thread::panicking() {
+            UNWOUND_QUERY.set(true);
+        }
         self.pop_helper();
     }
 }
@@ -0,0 +1,139 @@
+//! The compact encoding of [`QueryEdges`](super::QueryEdges).
+//!
+//! Queries with many dependencies mostly read keys of a few ingredients, often in runs of
+//! nearby ids (e.g. the fields of tracked structs created one after the other). The edges
+//! are therefore grouped in runs of consecutive edges of the same kind and ingredient: a run
+//! starts with the ingredient index, followed by the id of each edge, encoded as the
+//! difference to the previous id of the run. All numbers are LEB128-encoded, so that most
+//! edges take one or two bytes instead of twelve.
+//!
+//! * The header of a run is `ingredient_index << 1 | is_output`.
+//! * Each id is `zigzag(id - previous_id) << 1 | has_next`, where `has_next` tells whether
+//!   the run continues and `previous_id` is 0 for the first edge of the run. Reads of
+//!   whole tables, which have no id, are encoded as id 0, the ids of keys starting at 1.
+
+use super::QueryEdge;
+use crate::key::{InputDependencyIndex, OutputDependencyIndex};
+use crate::zalsa::IngredientIndex;
+use crate::Id;
+
+/// The encoded edges. A boxed slice rather than a `Vec`, as memos are never extended:
+/// this saves a word in every memo.
+pub(super) type EncodedEdges = Box<[u8]>;
+
+pub(super) fn encode(edges: impl IntoIterator<Item = QueryEdge>) -> EncodedEdges {
+    let mut encoded = Vec::new();
+    let mut edges = edges.into_iter().peekable();
+    while let Some(edge) = edges.next() {
+        let (header, mut id) = split(edge);
+        write(&mut encoded, header);
+        let mut previous = 0;
+        loop {
+            let next = edges.next_if(|&edge| split(edge).0 == header);
+            let delta = zigzag(i64::from(id) - i64::from(previous));
+            write(&mut encoded, delta << 1 | u64::from(next.is_some()));
+            let Some(next) = next else {
+                break;
+            };
+            previous = id;
+            id = split(next).1;
+        }
+    }
+    encoded.into_boxed_slice()
+}
+
+/// Splits `edge` into the header of its run and its id.
+fn split(edge: QueryEdge) -> (u64, u32) {
+    let (ingredient_index, is_output, key_index) = match edge {
+        QueryEdge::Input(input) => (input.ingredient_index(), false, input.key_index()),
+        QueryEdge::Output(output) => (output.ingredient_index(), true, Some(output.key_index())),
+    };
+    let header = (ingredient_index.as_usize() as u64) << 1 | u64::from(is_output);
+    let id = key_index.map_or(0, |id| id.as_u32() + 1);
+    (header, id)
+}
+
+/// Iterates over the edges encoded in `encoded`, in order.
+#[derive(Clone)]
+pub(super) struct Decoder<'a> {
+    encoded: &'a [u8],
+
+    /// The header of the current run, if the next edge belongs to it.
+    run: Option<u64>,
+
+    /// The id of the previous edge of the current run.
+    previous: u32,
+}
+
+impl<'a> Decoder<'a> {
+    pub(super) fn new(encoded: &'a [u8]) -> Self {
+        Self {
+            encoded,
+            run: None,
+            previous: 0,
+        }
+    }
+
+    fn read(&mut self) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let (&byte, rest) = self.encoded.split_first().unwrap();
+            self.encoded = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = QueryEdge;
+
+    fn next(&mut self) -> Option<QueryEdge> {
+        let header = match self.run {
+            Some(header) => header,
+            None if self.encoded.is_empty() => return None,
+            None => {
+                self.previous = 0;
+                self.read()
+            }
+        };
+        let id = self.read();
+        self.run = (id & 1 != 0).then_some(header);
+        let id = (i64::from(self.previous) + unzigzag(id >> 1)) as u32;
+        self.previous = id;
+
+        let ingredient_index = IngredientIndex::from((header >> 1) as usize);
+        let key_index = id.checked_sub(1).map(Id::from_u32);
+        Some(if header & 1 == 0 {
+            QueryEdge::Input(match key_index {
+                Some(key_index) => InputDependencyIndex::new(ingredient_index, key_index),
+                None => InputDependencyIndex::for_table(ingredient_index),
+            })
+        } else {
+            QueryEdge::Output(OutputDependencyIndex::new(
+                ingredient_index,
+                key_index.expect("outputs have an id"),
+            ))
+        })
+    }
+}
+
+fn write(encoded: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        encoded.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
@@ -0,0 +1,51 @@
+//! Test that the `getter_prefix` and `setter_prefix` options and `#[get(name = "..")]`
+//! change the names of the generated getters and setters.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input(getter_prefix = "get_", setter_prefix = "put_")]
+struct SourceFile {
+    text: String,
+    #[get(name = "source_text")]
+    #[set(name = "replace_source")]
+    source: String,
+}
+
+impl SourceFile {
+    // Would collide with the getter of `text` without `getter_prefix`.
+    fn text(self, db: &dyn Database) -> String {
+        format!("{}: {}", self.get_text(db), self.source_text(db))
+    }
+}
+
+#[salsa::tracked(getter_prefix = "get_")]
+struct Item<'db> {
+    name: String,
+}
+
+#[salsa::interned(getter_prefix = "get_")]
+struct Name<'db> {
+    #[get(name = "as_string")]
+    text: String,
+}
+
+#[salsa::tracked]
+fn item_name(db: &dyn Database, file: SourceFile) -> String {
+    Item::new(db, file.get_text(db)).get_name(db)
+}
+
+#[test]
+fn execute() {
+    let mut db = DatabaseImpl::new();
+    let file = SourceFile::new(&db, "a".to_string(), "b".to_string());
+    assert_eq!(file.text(&db), "a: b");
+    assert_eq!(item_name(&db, file), "a");
+
+    file.put_text(&mut db).to("c".to_string());
+    file.replace_source(&mut db).to("d".to_string());
+    assert_eq!(file.text(&db), "c: d");
+    assert_eq!(item_name(&db, file), "c");
+
+    assert_eq!(Name::new(&db, "e".to_string()).as_string(&db), "e");
+}
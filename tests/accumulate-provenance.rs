@@ -0,0 +1,86 @@
+//! Test `accumulated_with_provenance`, which reports the query that accumulated
+//! each value and the revision in which it did.
+
+use salsa::plumbing::AsId;
+use salsa::{Accumulator, Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Project {
+    files: Vec<File>,
+}
+
+#[salsa::accumulator]
+struct Diagnostic(String);
+
+#[salsa::tracked]
+fn check_file(db: &dyn Database, file: File) {
+    for line in file.text(db).lines() {
+        if let Some(message) = line.strip_prefix("error: ") {
+            Diagnostic(message.to_string()).accumulate(db);
+        }
+    }
+}
+
+#[salsa::tracked]
+fn check_project(db: &dyn Database, project: Project) {
+    for file in project.files(db) {
+        check_file(db, file);
+    }
+}
+
+/// Maps each diagnostic to the file it was reported for and the revision it was reported in.
+fn diagnostics(db: &DatabaseImpl, project: Project) -> Vec<(String, File, salsa::Revision)> {
+    check_project::accumulated_with_provenance::<Diagnostic>(db, project)
+        .into_iter()
+        .map(|diagnostic| {
+            let file = project
+                .files(db)
+                .into_iter()
+                .find(|file| file.as_id() == diagnostic.query.key_index())
+                .unwrap();
+            (diagnostic.value.0, file, diagnostic.accumulated_at)
+        })
+        .collect()
+}
+
+#[test]
+fn provenance() {
+    let mut db = DatabaseImpl::new();
+    let a = File::new(&db, "error: a1\nok\nerror: a2".to_string());
+    let b = File::new(&db, "error: b1".to_string());
+    let project = Project::new(&db, vec![a, b]);
+
+    let first = salsa::plumbing::current_revision(&db);
+    assert_eq!(
+        diagnostics(&db, project),
+        [
+            ("a1".to_string(), a, first),
+            ("a2".to_string(), a, first),
+            ("b1".to_string(), b, first),
+        ]
+    );
+    assert_eq!(
+        check_project::accumulated_with_provenance::<Diagnostic>(&db, project)[0]
+            .query
+            .to_stable_string(&db),
+        format!("check_file({:?})", a.as_id())
+    );
+
+    // `check_file(a)` is validated without re-executing, and keeps its revision.
+    b.set_text(&mut db).to("error: b2".to_string());
+    let second = salsa::plumbing::current_revision(&db);
+    assert_eq!(
+        diagnostics(&db, project),
+        [
+            ("a1".to_string(), a, first),
+            ("a2".to_string(), a, first),
+            ("b2".to_string(), b, second),
+        ]
+    );
+}
@@ -0,0 +1,43 @@
+//! Test `Database::active_query_stack`.
+
+use salsa::{Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn check(db: &dyn Database, file: File) -> Vec<String> {
+    if file.text(db).is_empty() {
+        vec![format!(
+            "empty file, while computing {:?}",
+            db.active_query_stack()
+        )]
+    } else {
+        vec![]
+    }
+}
+
+#[salsa::tracked]
+fn check_all(db: &dyn Database, file: File) -> Vec<String> {
+    check(db, file)
+}
+
+#[test]
+fn outside_of_queries() {
+    let db = DatabaseImpl::new();
+    assert!(db.active_query_stack().is_empty());
+}
+
+#[test]
+fn within_queries() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, String::new());
+    assert_eq!(
+        check_all(&db, file),
+        ["empty file, while computing [check_all(Id(0)), check(Id(0))]"]
+    );
+    assert!(db.active_query_stack().is_empty());
+}
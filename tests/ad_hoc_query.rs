@@ -0,0 +1,107 @@
+//! Test ad-hoc queries created with `Database::memo`.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use std::sync::Mutex;
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct Script {
+    #[return_ref]
+    text: String,
+}
+
+/// Executions of the closures of `word_count` and `line_count`.
+static EXECUTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn word_count(db: &common::LoggerDatabase, script: Script) -> usize {
+    db.memo(("word_count", script), move |db| {
+        EXECUTIONS
+            .lock()
+            .unwrap()
+            .push(format!("word_count({:?})", script.text(db)));
+        script.text(db).split_whitespace().count()
+    })
+}
+
+fn line_count(db: &common::LoggerDatabase, script: Script) -> usize {
+    db.memo(("line_count", script), move |db| {
+        EXECUTIONS
+            .lock()
+            .unwrap()
+            .push(format!("line_count({:?})", script.text(db)));
+        script.text(db).lines().count()
+    })
+}
+
+fn take_executions() -> Vec<String> {
+    std::mem::take(&mut *EXECUTIONS.lock().unwrap())
+}
+
+#[test]
+fn memoized_until_inputs_change() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Script::new(&db, "one two".to_string());
+    let b = Script::new(&db, "three".to_string());
+
+    assert_eq!(word_count(&db, a), 2);
+    assert_eq!(word_count(&db, b), 1);
+    assert_eq!(word_count(&db, a), 2);
+    assert_eq!(line_count(&db, a), 1);
+    assert_eq!(
+        take_executions(),
+        [
+            "word_count(\"one two\")",
+            "word_count(\"three\")",
+            "line_count(\"one two\")",
+        ]
+    );
+
+    a.set_text(&mut db).to("one\ntwo".to_string());
+    assert_eq!(word_count(&db, a), 2);
+    assert_eq!(word_count(&db, b), 1);
+    assert_eq!(line_count(&db, a), 2);
+    assert_eq!(
+        take_executions(),
+        ["word_count(\"one\\ntwo\")", "line_count(\"one\\ntwo\")"]
+    );
+}
+
+#[salsa::tracked]
+fn total_words(db: &dyn LogDatabase, a: Script, b: Script) -> usize {
+    db.push_log("total_words".to_string());
+    let count = |script: Script| {
+        salsa::memo(db, script, move |db| {
+            script.text(db).split_whitespace().count()
+        })
+    };
+    count(a) + count(b)
+}
+
+#[test]
+fn readers_depend_on_ad_hoc_queries() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Script::new(&db, "one two".to_string());
+    let b = Script::new(&db, "three".to_string());
+
+    assert_eq!(total_words(&db, a, b), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "total_words",
+        ]"#]]);
+
+    // The word count of `a` did not change, so `total_words` is not re-executed.
+    a.set_text(&mut db).to("two one".to_string());
+    assert_eq!(total_words(&db, a, b), 3);
+    db.assert_logs(expect!["[]"]);
+
+    b.set_text(&mut db).to("three four".to_string());
+    assert_eq!(total_words(&db, a, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "total_words",
+        ]"#]]);
+}
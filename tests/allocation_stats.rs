@@ -0,0 +1,61 @@
+//! Test that queries reuse the frames of completed queries,
+//! as reported by `Database::allocation_stats`.
+
+use salsa::{AllocationStats, Database, DatabaseImpl};
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    words(db, file).len()
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> Vec<String> {
+    text(db, file)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+#[salsa::tracked]
+fn text(db: &dyn Database, file: File) -> String {
+    file.text(db)
+}
+
+fn counters(stats: AllocationStats) -> [u64; 2] {
+    [stats.frames_allocated, stats.frames_reused]
+}
+
+#[test]
+fn frames_are_reused() {
+    let db = DatabaseImpl::new();
+    let first = File::new(&db, "a b".to_string());
+    let second = File::new(&db, "c d e".to_string());
+
+    // The three queries are nested, so each needs a frame of its own.
+    assert_eq!(word_count(&db, first), 2);
+    assert_eq!(counters(db.allocation_stats()), [3, 0]);
+
+    assert_eq!(word_count(&db, second), 3);
+    assert_eq!(counters(db.allocation_stats()), [3, 3]);
+}
+
+#[salsa::tracked]
+fn repeated_length(db: &dyn Database, file: File, times: usize) -> usize {
+    (0..times).map(|_| text(db, file).len()).sum()
+}
+
+#[test]
+fn repeated_reads_are_deduplicated() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, "a b".to_string());
+
+    // The first read of `text` is recorded, the others are not.
+    assert_eq!(repeated_length(&db, file, 10), 30);
+    assert_eq!(db.allocation_stats().deduplicated_reads, 9);
+    assert_eq!(repeated_length::dependencies(&db, file, 10).len(), 1);
+}
@@ -0,0 +1,75 @@
+//! Test that `Database::audit_consistency` finds no violations
+//! in databases maintained by salsa itself.
+
+mod common;
+
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked(db: &dyn Database, input: MyInput) -> Vec<MyTracked<'_>> {
+    let tracked: Vec<_> = (0..input.field(db))
+        .map(|i| MyTracked::new(db, i))
+        .collect();
+    if let Some(first) = tracked.first() {
+        specified::specify(db, *first, 100);
+    }
+    tracked
+}
+
+#[salsa::tracked(specify)]
+fn specified<'db>(db: &'db dyn Database, tracked: MyTracked<'db>) -> u32 {
+    tracked.field(db)
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Database, input: MyInput) -> u32 {
+    create_tracked(db, input)
+        .iter()
+        .map(|t| specified(db, *t))
+        .sum()
+}
+
+#[test]
+fn no_violations() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let input = MyInput::new(&db, 3);
+
+    assert_eq!(sum(&db, input), 103);
+    assert_eq!(db.audit_consistency(), vec![]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(sum(&db, input), 101);
+    assert_eq!(db.audit_consistency(), vec![]);
+
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(sum(&db, input), 101);
+    assert_eq!(db.audit_consistency(), vec![]);
+}
+
+#[test]
+fn does_not_start_a_new_revision() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    assert_eq!(sum(&db, input), 100);
+    db.assert_logs_len(2);
+
+    assert_eq!(db.audit_consistency(), vec![]);
+
+    // Memos are still up to date: no validation is needed.
+    assert_eq!(sum(&db, input), 100);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,101 @@
+//! Test that writes performed within `Database::batch`
+//! are applied in a single new revision.
+
+mod common;
+
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use expect_test::expect;
+use salsa::plumbing::ZalsaDatabase;
+use salsa::{Cancelled, Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    contents: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, file: File) -> u32 {
+    file.contents(db) * 2
+}
+
+#[test]
+fn batch_writes_share_a_revision() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    let b = File::new(&db, 2);
+    assert_eq!(double(&db, a), 2);
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(WillExecute { database_key: double(Id(1)) })",
+        ]"#]]);
+
+    let before = db.zalsa().current_revision();
+    let c = db.batch(|db| {
+        a.set_contents(db).to(10);
+        b.set_contents(db).to(20);
+        File::new(db, 30)
+    });
+    let after = db.zalsa().current_revision();
+
+    // The batch started exactly one revision.
+    assert_eq!(format!("{before:?}"), "R1");
+    assert_eq!(format!("{after:?}"), "R2");
+
+    assert_eq!(double(&db, a), 20);
+    assert_eq!(double(&db, b), 40);
+    assert_eq!(double(&db, c), 60);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(WillExecute { database_key: double(Id(1)) })",
+            "salsa_event(WillExecute { database_key: double(Id(2)) })",
+        ]"#]]);
+
+    // A plain write afterwards invalidates only what it touches.
+    a.set_contents(&mut db).to(11);
+    assert_eq!(double(&db, a), 22);
+    assert_eq!(double(&db, b), 40);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn tracked_fn_within_batch_is_cancelled() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let a = File::new(&db, 1);
+    assert_eq!(double(&db, a), 2);
+
+    let result = db.batch(|db| {
+        a.set_contents(db).to(10);
+        Cancelled::catch(|| double(db, a))
+    });
+    assert!(matches!(result, Err(Cancelled::PendingWrite { .. })));
+
+    // Once the batch has ended, the new values are observed.
+    assert_eq!(double(&db, a), 20);
+}
+
+#[test]
+fn nested_batches() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let a = File::new(&db, 1);
+    let b = File::new(&db, 2);
+
+    db.batch(|db| {
+        a.set_contents(db).to(10);
+        db.batch(|db| b.set_contents(db).to(20));
+        a.set_contents(db).to(100);
+    });
+
+    assert_eq!(double(&db, a), 200);
+    assert_eq!(double(&db, b), 40);
+}
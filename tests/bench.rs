@@ -0,0 +1,102 @@
+//! Test the recording of `salsa::testing::bench::Bench`.
+
+use salsa::testing::bench::Bench;
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Database, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked]
+fn is_long(db: &dyn Database, file: File) -> bool {
+    line_count(db, file) > 2
+}
+
+#[salsa::tracked]
+fn first_line(db: &dyn Database, file: File) -> String {
+    file.text(db).lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn records_steps() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a\nb".to_string());
+    let mut bench = Bench::new(&db);
+
+    // Queries outside of steps are not recorded.
+    assert_eq!(first_line(&db, file), "a");
+
+    let step = bench.step(
+        "initial",
+        &mut db,
+        |_| {},
+        |db| {
+            assert!(!is_long(db, file));
+            assert_eq!(first_line(db, file), "a");
+        },
+    );
+    step.assert_executed(&["is_long(Id(0))", "line_count(Id(0))"]);
+    step.assert_validated(&[]);
+    step.assert_backdated(&[]);
+
+    let step = bench.step(
+        "edit second line",
+        &mut db,
+        |db| {
+            file.set_text(db).to("a\nc".to_string());
+        },
+        |db| {
+            assert!(!is_long(db, file));
+            assert_eq!(first_line(db, file), "a");
+        },
+    );
+    step.assert_executed(&["line_count(Id(0))", "first_line(Id(0))"]);
+    step.assert_validated(&["is_long(Id(0))"]);
+    step.assert_backdated(&["line_count(Id(0))", "first_line(Id(0))"]);
+
+    let step = bench.step(
+        "add a line",
+        &mut db,
+        |db| {
+            file.set_text(db).to("a\nc\nd".to_string());
+        },
+        |db| assert!(is_long(db, file)),
+    );
+    step.assert_executed(&["line_count(Id(0))", "is_long(Id(0))"]);
+    step.assert_backdated(&[]);
+
+    assert_eq!(
+        bench
+            .reports()
+            .iter()
+            .map(|report| (report.name.as_str(), report.executed.len()))
+            .collect::<Vec<_>>(),
+        [("initial", 2), ("edit second line", 2), ("add a line", 2)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "step `initial`: expected {\"line_count(Id(0))\"} to be executed")]
+fn mismatch() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a".to_string());
+    let mut bench = Bench::new(&db);
+
+    bench
+        .step(
+            "initial",
+            &mut db,
+            |_| {},
+            |db| {
+                is_long(db, file);
+            },
+        )
+        .assert_executed(&["line_count(Id(0))"]);
+}
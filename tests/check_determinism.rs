@@ -0,0 +1,96 @@
+//! Test that the `check_determinism` feature detects nondeterministic tracked functions.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked]
+fn deterministic(db: &dyn Database, input: MyInput) -> u32 {
+    let tracked = MyTracked::new(db, input.field(db));
+    tracked_field(db, tracked) * 2
+}
+
+#[salsa::tracked]
+fn tracked_field<'db>(db: &'db dyn Database, tracked: MyTracked<'db>) -> u32 {
+    tracked.field(db)
+}
+
+#[salsa::tracked]
+fn nondeterministic(db: &dyn Database, input: MyInput) -> usize {
+    input.field(db) as usize + COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+#[salsa::tracked(no_eq)]
+fn nondeterministic_no_eq(db: &dyn Database, input: MyInput) -> usize {
+    input.field(db) as usize + COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+#[salsa::tracked]
+fn untracked(db: &dyn Database, input: MyInput) -> usize {
+    db.report_untracked_read();
+    input.field(db) as usize + COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+#[salsa::tracked(volatile(ttl_revisions = 1))]
+fn volatile(db: &dyn Database, input: MyInput) -> usize {
+    input.field(db) as usize + COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+fn checked_db() -> DatabaseImpl {
+    DatabaseImpl::builder()
+        .determinism_check_interval(1)
+        .build()
+}
+
+#[test]
+fn deterministic_functions_pass() {
+    let mut db = checked_db();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(deterministic(&db, input), 2);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(deterministic(&db, input), 4);
+}
+
+#[test]
+#[should_panic(expected = "`nondeterministic(Id(0))` is not deterministic")]
+fn nondeterministic_functions_panic() {
+    let db = checked_db();
+    let input = MyInput::new(&db, 1);
+    nondeterministic(&db, input);
+}
+
+#[test]
+fn no_eq_functions_are_not_checked() {
+    let db = checked_db();
+    let input = MyInput::new(&db, 1);
+    nondeterministic_no_eq(&db, input);
+}
+
+#[test]
+fn untracked_and_volatile_functions_are_not_checked() {
+    let db = checked_db();
+    let input = MyInput::new(&db, 1);
+    untracked(&db, input);
+    volatile(&db, input);
+}
+
+#[test]
+fn disabled_by_default() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    nondeterministic(&db, input);
+}
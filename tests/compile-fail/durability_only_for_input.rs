@@ -0,0 +1,17 @@
+//! Compile durability test:
+//!
+//! `#[durability(..)]` is only allowed on the fields of input structs, and must name a durability level.
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    #[durability(high)]
+    field: u32,
+}
+
+#[salsa::input]
+struct MyInput {
+    #[durability(forever)]
+    field: u32,
+}
+
+fn main() {}
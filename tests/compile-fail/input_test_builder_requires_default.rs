@@ -0,0 +1,12 @@
+#[salsa::input]
+struct MyInput {
+    field: NoDefault,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NoDefault(u32);
+
+fn main() {
+    let db = salsa::DatabaseImpl::new();
+    let _ = MyInput::test_builder().new(&db);
+}
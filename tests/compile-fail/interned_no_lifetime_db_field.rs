@@ -0,0 +1,16 @@
+//! Compile `no_lifetime` test:
+//!
+//! Interned structs declared with `no_lifetime` are `'static` handles,
+//! so their fields may not reference the `'db` lifetime.
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::interned(no_lifetime)]
+struct MyInterned<'db> {
+    tracked: MyTracked<'db>,
+}
+
+fn main() {}
@@ -68,4 +68,9 @@ fn tracked_fn_with_multiple_lts<'db1, 'db2>(db: &'db1 dyn Db, interned: MyIntern
     interned.field(db) * 2
 }
 
+#[salsa::tracked]
+fn tracked_fn_with_mut_db(db: &mut dyn Db, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
 fn main() {}
@@ -0,0 +1,11 @@
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(validate_args)]
+fn tracked_fn(db: &dyn salsa::Database, input: MyInput, extra: u32) -> u32 {
+    input.field(db) + extra
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+//! Test that `cycle_fallback` is accepted as another name for `recovery_fn`.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    cyclic: bool,
+}
+
+#[salsa::tracked(cycle_fallback = recover)]
+fn query_a(db: &dyn Database, input: MyInput) -> u32 {
+    if input.cyclic(db) {
+        query_b(db, input) + 1
+    } else {
+        0
+    }
+}
+
+#[salsa::tracked]
+fn query_b(db: &dyn Database, input: MyInput) -> u32 {
+    query_a(db, input) + 1
+}
+
+fn recover(_db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    100
+}
+
+#[test]
+fn cycle_fallback_is_used() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, false);
+    assert_eq!(query_b(&db, input), 1);
+
+    input.set_cyclic(&mut db).to(true);
+    assert_eq!(query_a(&db, input), 100);
+    assert_eq!(query_b(&db, input), 101);
+}
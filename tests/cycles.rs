@@ -374,6 +374,53 @@ fn cycle_deterministic_order() {
     expected.assert_debug_eq(&(a.unwrap_err().cycle, b.unwrap_err().cycle));
 }
 
+#[salsa::input]
+struct Link {
+    next: Option<Link>,
+}
+
+#[salsa::tracked(recovery_fn=recover_chain)]
+fn chain(db: &dyn Db, link: Link) -> Vec<String> {
+    match link.next(db) {
+        Some(next) => chain(db, next),
+        None => vec![],
+    }
+}
+
+fn recover_chain(db: &dyn Db, cycle: &salsa::Cycle, link: Link) -> Vec<String> {
+    cycle.participant_keys().map(|k| format!("{k:?}")).collect()
+}
+
+#[test]
+fn cycle_deterministic_order_same_function() {
+    // The participants are instances of the same function, so they are ordered by id,
+    // no matter whether we start from the first or the second link:
+    let f = || {
+        let mut db = salsa::DatabaseImpl::new();
+        let first = Link::new(&db, None);
+        let second = Link::new(&db, Some(first));
+        first.set_next(&mut db).to(Some(second));
+        (db, first, second)
+    };
+    let (db, first, _) = f();
+    let from_first = chain(&db, first);
+    let (db, _, second) = f();
+    let from_second = chain(&db, second);
+    let expected = expect![[r#"
+        (
+            [
+                "chain(Id(0))",
+                "chain(Id(1))",
+            ],
+            [
+                "chain(Id(0))",
+                "chain(Id(1))",
+            ],
+        )
+    "#]];
+    expected.assert_debug_eq(&(from_first, from_second));
+}
+
 #[test]
 fn cycle_multiple() {
     // No matter whether we start from A or B, we get the same set of participants:
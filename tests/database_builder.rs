@@ -0,0 +1,67 @@
+//! Test `DatabaseImpl::builder`.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, DatabaseImpl, EventKind};
+use test_log::test;
+
+#[salsa::input]
+struct Number {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, number: Number) -> u32 {
+    number.value(db) * 2
+}
+
+#[salsa::tracked(lru = 8)]
+fn triple(db: &dyn Database, number: Number) -> u32 {
+    number.value(db) * 3
+}
+
+/// Returns a database recording the names of the executed functions, and the record.
+fn build(default_lru_capacity: usize) -> (DatabaseImpl, Arc<Mutex<Vec<String>>>) {
+    let executed = Arc::new(Mutex::new(Vec::new()));
+    let db = DatabaseImpl::builder()
+        .default_lru_capacity(default_lru_capacity)
+        .event_sink({
+            let executed = executed.clone();
+            move |event| {
+                if let EventKind::WillExecute { database_key } = event.kind {
+                    executed.lock().unwrap().push(format!("{database_key:?}"));
+                }
+            }
+        })
+        .build();
+    (db, executed)
+}
+
+#[test]
+fn default_lru_capacity() {
+    let (db, executed) = build(2);
+    let numbers: Vec<_> = (0..3).map(|value| Number::new(&db, value)).collect();
+    for &number in &numbers {
+        assert_eq!(double(&db, number), number.value(&db) * 2);
+        assert_eq!(triple(&db, number), number.value(&db) * 3);
+    }
+    assert_eq!(executed.lock().unwrap().len(), 6);
+
+    // The value of `double` for the first number was evicted, the one of `triple` was not.
+    assert_eq!(double(&db, numbers[0]), 0);
+    assert_eq!(triple(&db, numbers[0]), 0);
+    let executed = executed.lock().unwrap();
+    assert_eq!(executed.len(), 7);
+    assert!(executed[6].starts_with("double"), "{executed:?}");
+}
+
+#[test]
+fn no_default_lru_capacity() {
+    let (db, executed) = build(0);
+    let numbers: Vec<_> = (0..3).map(|value| Number::new(&db, value)).collect();
+    for &number in &numbers {
+        double(&db, number);
+    }
+    double(&db, numbers[0]);
+    assert_eq!(executed.lock().unwrap().len(), 3);
+}
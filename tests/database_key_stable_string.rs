@@ -0,0 +1,86 @@
+//! Test the textual encoding of `DatabaseKeyIndex`.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::plumbing::AsId;
+use salsa::{Database, DatabaseImpl, DatabaseKeyIndex, EventKind, ParseKeyError};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.text(db).len()
+}
+
+mod a {
+    #[salsa::tracked]
+    pub(crate) fn lines(db: &dyn salsa::Database, file: super::File) -> usize {
+        file.text(db).lines().count()
+    }
+}
+
+mod b {
+    #[salsa::tracked]
+    pub(crate) fn lines(db: &dyn salsa::Database, file: super::File) -> usize {
+        file.text(db).lines().count()
+    }
+}
+
+/// A database recording the keys of the executed functions.
+fn database() -> (DatabaseImpl, Arc<Mutex<Vec<DatabaseKeyIndex>>>) {
+    let executed = Arc::new(Mutex::new(vec![]));
+    let db = DatabaseImpl::builder()
+        .event_sink({
+            let executed = executed.clone();
+            move |event| {
+                if let EventKind::WillExecute { database_key } = event.kind {
+                    executed.lock().unwrap().push(database_key);
+                }
+            }
+        })
+        .build();
+    (db, executed)
+}
+
+#[test]
+fn round_trip() {
+    let (db, executed) = database();
+    File::new(&db, String::new());
+    let file = File::new(&db, "text".to_string());
+    assert_eq!(length(&db, file), 4);
+
+    let key = executed.lock().unwrap()[0];
+    let text = key.to_stable_string(&db);
+    assert_eq!(text, "length(Id(1))");
+    assert_eq!(DatabaseKeyIndex::parse(&db, &text), Ok(key));
+
+    // Fields are qualified by the name of their struct.
+    let field = DatabaseKeyIndex::parse(&db, "File.text(Id(1))").unwrap();
+    assert_eq!(field.key_index(), file.as_id());
+    assert_eq!(field.to_stable_string(&db), "File.text(Id(1))");
+}
+
+#[test]
+fn parse_errors() {
+    let (db, _) = database();
+    let file = File::new(&db, "text".to_string());
+    a::lines(&db, file);
+    b::lines(&db, file);
+
+    assert_eq!(
+        DatabaseKeyIndex::parse(&db, "length(0)"),
+        Err(ParseKeyError::Malformed("length(0)".to_string()))
+    );
+    assert_eq!(
+        DatabaseKeyIndex::parse(&db, "length(Id(0))"),
+        Err(ParseKeyError::UnknownIngredient("length".to_string()))
+    );
+    assert_eq!(
+        DatabaseKeyIndex::parse(&db, "lines(Id(0))"),
+        Err(ParseKeyError::AmbiguousIngredient("lines".to_string()))
+    );
+}
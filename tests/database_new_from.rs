@@ -0,0 +1,128 @@
+//! Test that `DatabaseImpl::new_from` creates a database with the interned values and
+//! high-durability inputs of another, but none of its memoized values.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use salsa::{DatabaseImpl, Durability, EventKind, Setter, StaleHandle};
+use test_log::test;
+
+#[salsa::input(speculate)]
+struct SourceRoot {
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct NotClone;
+
+#[salsa::input]
+struct Handle {
+    #[return_ref]
+    value: NotClone,
+}
+
+#[salsa::interned(no_lifetime)]
+struct Symbol {
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::tracked]
+fn root_symbol(db: &dyn salsa::Database, root: SourceRoot) -> Symbol {
+    Symbol::new(db, root.name(db).to_uppercase())
+}
+
+/// Returns a database counting the executions of tracked functions.
+fn counting_db() -> (DatabaseImpl, Arc<AtomicUsize>) {
+    let executions = Arc::new(AtomicUsize::new(0));
+    let counter = executions.clone();
+    let db = DatabaseImpl::builder()
+        .event_sink(move |event| {
+            if let EventKind::WillExecute { .. } = event.kind {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .build();
+    (db, executions)
+}
+
+#[test]
+fn keeps_interned_values_and_high_durability_inputs() {
+    let (mut old, executions) = counting_db();
+    let root = SourceRoot::builder("std".to_string())
+        .durability(Durability::HIGH)
+        .new(&old);
+    let symbol = root_symbol(&old, root);
+    assert_eq!(executions.load(Ordering::SeqCst), 1);
+
+    let new = DatabaseImpl::new_from(&old);
+    assert_eq!(root.name(&new), "std");
+    assert_eq!(symbol.name(&new), "STD");
+
+    // The function executes again, and finds the same symbol.
+    assert_eq!(root_symbol(&new, root), symbol);
+    assert_eq!(executions.load(Ordering::SeqCst), 2);
+
+    // The databases are independent.
+    root.set_name(&mut old)
+        .with_durability(Durability::HIGH)
+        .to("core".to_string());
+    assert_eq!(root.name(&new), "std");
+    assert_eq!(root_symbol(&old, root).name(&old), "CORE");
+}
+
+#[test]
+fn retires_low_durability_inputs() {
+    let old = DatabaseImpl::new();
+    let file = File::new(&old, "fn main() {}".to_string());
+
+    let new = DatabaseImpl::new_from(&old);
+    let err = StaleHandle::catch(|| file.text(&new).clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`File(Id(0))` was used after it was retired or deleted"
+    );
+}
+
+#[test]
+fn does_not_reuse_the_ids_of_retired_inputs() {
+    let old = DatabaseImpl::new();
+    let file = File::new(&old, "a".to_string());
+
+    let new = DatabaseImpl::new_from(&old);
+    let other = File::new(&new, "b".to_string());
+    assert_ne!(other, file);
+    assert_eq!(other.text(&new), "b");
+}
+
+#[test]
+fn copies_high_durability_inputs_without_speculate() {
+    let old = DatabaseImpl::new();
+    let file = File::builder("a".to_string())
+        .durability(Durability::HIGH)
+        .new(&old);
+
+    let new = DatabaseImpl::new_from(&old);
+    assert_eq!(file.text(&new), "a");
+}
+
+#[test]
+fn retires_high_durability_inputs_that_cannot_be_cloned() {
+    let old = DatabaseImpl::new();
+    let handle = Handle::builder(NotClone)
+        .durability(Durability::HIGH)
+        .new(&old);
+
+    let new = DatabaseImpl::new_from(&old);
+    StaleHandle::catch(|| {
+        handle.value(&new);
+    })
+    .unwrap_err();
+}
@@ -0,0 +1,49 @@
+//! Test the `debug(db)` method generated by the `debug_with_db` option.
+
+use expect_test::expect;
+use salsa::{Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::input(debug_with_db)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned(debug_with_db)]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::tracked(debug_with_db)]
+struct MyTracked<'db> {
+    name: Name<'db>,
+    input: MyInput,
+}
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn Database, input: MyInput) -> String {
+    let tracked = MyTracked::new(db, Name::new(db, "a".to_string()), input);
+    format!("{:?}", tracked.debug(db))
+}
+
+#[test]
+fn debug_outside_of_queries() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+    expect!["MyInput { [salsa id]: Id(0), field: 22 }"]
+        .assert_eq(&format!("{:?}", input.debug(&db)));
+
+    // Without a database, the fields cannot be printed.
+    expect!["MyInput { [salsa id]: Id(0) }"].assert_eq(&format!("{input:?}"));
+
+    let name = Name::new(&db, "b".to_string());
+    expect![[r#"Name { text: "b" }"#]].assert_eq(&format!("{:?}", name.debug(&db)));
+}
+
+#[test]
+fn debug_nested_structs() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+    expect![[r#"MyTracked { [salsa id]: Id(800), name: Name { text: "a" }, input: MyInput { [salsa id]: Id(0), field: 22 } }"#]]
+        .assert_eq(&tracked_fn(&db, input));
+}
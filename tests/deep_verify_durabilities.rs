@@ -0,0 +1,95 @@
+//! Test that deep verification skips checking the inputs of a memo when only inputs
+//! of durabilities it did not read have changed.
+
+mod common;
+
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Config {
+    tab_width: usize,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.text(db).len()
+}
+
+#[salsa::tracked]
+fn is_long(db: &dyn Database, file: File) -> bool {
+    length(db, file) > 3
+}
+
+#[salsa::tracked]
+fn width(db: &dyn Database, file: File, config: Config) -> usize {
+    length(db, file) * config.tab_width(db)
+}
+
+#[test]
+fn skips_inputs_of_other_durabilities() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let file = File::new(&db, "abc".to_string());
+    let config = Config::builder(4).durability(Durability::HIGH).new(&db);
+    assert!(!is_long(&db, file));
+    assert_eq!(width(&db, file, config), 12);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: is_long(Id(0)) })",
+            "salsa_event(WillExecute { database_key: length(Id(0)) })",
+            "salsa_event(WillExecute { database_key: width(Id(800)) })",
+        ]"#]]);
+
+    // `is_long` only read low durability inputs: it is validated without validating `length`.
+    config.set_tab_width(&mut db).to(8);
+    assert!(!is_long(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: is_long(Id(0)) })",
+        ]"#]]);
+
+    // `width` read the high durability input, so its inputs are checked one by one.
+    assert_eq!(width(&db, file, config), 24);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: length(Id(0)) })",
+            "salsa_event(WillExecute { database_key: width(Id(800)) })",
+        ]"#]]);
+}
+
+#[test]
+fn checks_inputs_of_own_durabilities() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let file = File::new(&db, "abc".to_string());
+    let other = File::new(&db, "def".to_string());
+    assert!(!is_long(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: is_long(Id(0)) })",
+            "salsa_event(WillExecute { database_key: length(Id(0)) })",
+        ]"#]]);
+
+    // A low durability input changed: `length` has to be checked, and is unchanged.
+    other.set_text(&mut db).to("defg".to_string());
+    assert!(!is_long(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: length(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: is_long(Id(0)) })",
+        ]"#]]);
+
+    file.set_text(&mut db).to("abcd".to_string());
+    assert!(is_long(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: length(Id(0)) })",
+            "salsa_event(WillExecute { database_key: is_long(Id(0)) })",
+        ]"#]]);
+}
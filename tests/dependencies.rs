@@ -0,0 +1,127 @@
+//! Test the `dependencies` function generated for tracked functions.
+
+use expect_test::expect;
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+    verbose: bool,
+}
+
+#[salsa::interned]
+struct Word<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> usize {
+    let words: Vec<Word<'_>> = file
+        .text(db)
+        .split_whitespace()
+        .map(|text| Word::new(db, text.to_string()))
+        .collect();
+    words.len()
+}
+
+#[salsa::tracked]
+fn summary(db: &dyn Database, file: File) -> String {
+    if file.verbose(db) {
+        format!("{} words: {}", words(db, file), file.text(db))
+    } else {
+        format!("{} words", words(db, file))
+    }
+}
+
+#[test]
+fn lists_direct_dependencies() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a b".to_string(), false);
+    assert!(summary::dependencies(&db, file).is_empty());
+
+    summary(&db, file);
+    expect![[r#"
+        [
+            DependencyInfo {
+                ingredient: "verbose",
+                key: Some(
+                    Id(0),
+                ),
+                name: "verbose(Id(0))",
+            },
+            DependencyInfo {
+                ingredient: "words",
+                key: Some(
+                    Id(0),
+                ),
+                name: "words(Id(0))",
+            },
+        ]
+    "#]]
+    .assert_debug_eq(&summary::dependencies(&db, file));
+
+    file.set_verbose(&mut db).to(true);
+    summary(&db, file);
+    let names: Vec<_> = summary::dependencies(&db, file)
+        .into_iter()
+        .map(|dependency| dependency.name)
+        .collect();
+    assert_eq!(names, ["verbose(Id(0))", "words(Id(0))", "text(Id(0))"]);
+
+    let names: Vec<_> = words::dependencies(&db, file)
+        .into_iter()
+        .map(|dependency| dependency.name)
+        .collect();
+    assert_eq!(names, ["text(Id(0))", "Word()"]);
+}
+
+#[salsa::tracked]
+fn total_length(db: &dyn Database, files: Files) -> usize {
+    files
+        .order(db)
+        .iter()
+        .map(|&index| {
+            let file = files.files(db)[index];
+            Word::new(db, "separator".to_string());
+            file.text(db).len() + words(db, file)
+        })
+        .sum()
+}
+
+#[salsa::input]
+struct Files {
+    #[return_ref]
+    files: Vec<File>,
+    #[return_ref]
+    order: Vec<usize>,
+}
+
+#[test]
+fn lists_many_dependencies_in_order() {
+    let db = DatabaseImpl::new();
+    let files: Vec<File> = (0..1000)
+        .map(|i| File::new(&db, format!("file {i}"), false))
+        .collect();
+    // Read the files forwards, backwards and out of order.
+    let order: Vec<usize> = (0..300)
+        .chain((300..600).rev())
+        .chain((0..400).map(|i| 600 + i * 7 % 400))
+        .collect();
+    let files = Files::new(&db, files, order.clone());
+    total_length(&db, files);
+
+    let mut expected = vec!["order(Id(400))".to_string(), "files(Id(400))".to_string()];
+    for (i, index) in order.iter().enumerate() {
+        if i == 0 {
+            expected.push("Word()".to_string());
+        }
+        expected.push(format!("text(Id({index:x}))"));
+        expected.push(format!("words(Id({index:x}))"));
+    }
+    let names: Vec<_> = total_length::dependencies(&db, files)
+        .into_iter()
+        .map(|dependency| dependency.name)
+        .collect();
+    assert_eq!(names, expected);
+}
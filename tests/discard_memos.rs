@@ -0,0 +1,92 @@
+//! Test that `Database::discard_memos` forces tracked functions
+//! to execute again while keeping inputs and interned values.
+
+mod common;
+
+use common::{LogDatabase, LoggerDatabase};
+use expect_test::expect;
+use salsa::plumbing::{AsId, FromId};
+use salsa::{Database, StaleHandle};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned(no_lifetime)]
+struct Symbol {
+    #[return_ref]
+    name: String,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    name: Symbol,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn LogDatabase, file: File) -> Vec<Item<'_>> {
+    db.push_log(format!("items({})", file.text(db)));
+    file.text(db)
+        .split_whitespace()
+        .map(|word| Item::new(db, Symbol::new(db, word)))
+        .collect()
+}
+
+#[salsa::tracked]
+fn item_names(db: &dyn LogDatabase, file: File) -> Vec<Symbol> {
+    items(db, file).iter().map(|item| item.name(db)).collect()
+}
+
+#[test]
+fn discard_memos() {
+    let mut db = LoggerDatabase::default();
+
+    let file = File::new(&db, "a b".to_string());
+    let a = Symbol::new(&db, "a");
+    assert_eq!(item_names(&db, file)[0], a);
+    db.assert_logs(expect![[r#"
+        [
+            "items(a b)",
+        ]"#]]);
+
+    assert_eq!(item_names(&db, file).len(), 2);
+    db.assert_logs(expect!["[]"]);
+
+    db.discard_memos();
+
+    // Symbols survive, and functions execute again.
+    assert_eq!(a.name(&db), "a");
+    assert_eq!(item_names(&db, file)[0], a);
+    db.assert_logs(expect![[r#"
+        [
+            "items(a b)",
+        ]"#]]);
+}
+
+#[test]
+fn discard_memos_deletes_tracked_structs() {
+    let mut db = LoggerDatabase::default();
+
+    let file = File::new(&db, "a".to_string());
+    let item = items(&db, file)[0].as_id();
+
+    db.discard_memos();
+
+    StaleHandle::catch(|| Item::from_id(item).name(&db)).unwrap_err();
+}
+
+#[test]
+fn discard_memos_reuses_tracked_struct_ids() {
+    let mut db = LoggerDatabase::default();
+
+    let file = File::new(&db, "a b".to_string());
+    let before: Vec<_> = items(&db, file).iter().map(AsId::as_id).collect();
+
+    db.discard_memos();
+
+    let after: Vec<_> = items(&db, file).iter().map(AsId::as_id).collect();
+    assert_eq!(before, after);
+}
@@ -0,0 +1,192 @@
+//! Test that readers of `Entries` only depend on the entries they look up.
+
+mod common;
+
+use std::cell::Cell;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Database, Durability, Entries, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Items {
+    #[return_ref]
+    items: Vec<(String, u32)>,
+}
+
+#[salsa::tracked(return_ref)]
+fn table(db: &dyn LogDatabase, items: Items) -> Entries<String, u32> {
+    db.push_log("table".to_string());
+    let mut table = Entries::new(db);
+    for (name, value) in items.items(db) {
+        table.insert(db, name.clone(), *value);
+    }
+    table
+}
+
+#[salsa::tracked]
+fn lookup(db: &dyn LogDatabase, items: Items, name: String) -> Option<u32> {
+    db.push_log(format!("lookup({name})"));
+    let table = Entries::project(db, || table(db, items));
+    table.get(db, &name).copied()
+}
+
+thread_local! {
+    static LIVE_VALUES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A value that counts how many instances are alive on this thread.
+#[derive(Debug, PartialEq, Eq)]
+struct Counted(u32);
+
+impl Counted {
+    fn new(value: u32) -> Self {
+        LIVE_VALUES.with(|live| live.set(live.get() + 1));
+        Self(value)
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        LIVE_VALUES.with(|live| live.set(live.get() - 1));
+    }
+}
+
+#[salsa::tracked(return_ref)]
+fn counted_table(db: &dyn LogDatabase, items: Items) -> Entries<String, Counted> {
+    let mut table = Entries::new(db);
+    for (name, value) in items.items(db) {
+        table.insert(db, name.clone(), Counted::new(*value));
+    }
+    table
+}
+
+#[salsa::tracked]
+fn counted_lookup(db: &dyn LogDatabase, items: Items, name: String) -> Option<u32> {
+    let table = Entries::project(db, || counted_table(db, items));
+    table.get(db, &name).map(|value| value.0)
+}
+
+fn items(entries: &[(&str, u32)]) -> Vec<(String, u32)> {
+    entries
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect()
+}
+
+#[test]
+fn unrelated_entries() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Items::new(&db, items(&[("a", 1), ("b", 2)]));
+
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(1));
+    assert_eq!(lookup(&db, input, "b".to_string()), Some(2));
+    assert_eq!(lookup(&db, input, "c".to_string()), None);
+    db.assert_logs(expect![[r#"
+        [
+            "lookup(a)",
+            "table",
+            "lookup(b)",
+            "lookup(c)",
+        ]"#]]);
+
+    // Only `b` changed.
+    input.set_items(&mut db).to(items(&[("a", 1), ("b", 3)]));
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(1));
+    assert_eq!(lookup(&db, input, "b".to_string()), Some(3));
+    assert_eq!(lookup(&db, input, "c".to_string()), None);
+    db.assert_logs(expect![[r#"
+        [
+            "table",
+            "lookup(b)",
+        ]"#]]);
+
+    // `c` is added, `a` is removed.
+    input.set_items(&mut db).to(items(&[("b", 3), ("c", 4)]));
+    assert_eq!(lookup(&db, input, "a".to_string()), None);
+    assert_eq!(lookup(&db, input, "b".to_string()), Some(3));
+    assert_eq!(lookup(&db, input, "c".to_string()), Some(4));
+    db.assert_logs(expect![[r#"
+        [
+            "table",
+            "lookup(a)",
+            "lookup(c)",
+        ]"#]]);
+
+    // `a` is added back.
+    input
+        .set_items(&mut db)
+        .to(items(&[("a", 1), ("b", 3), ("c", 4)]));
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(1));
+    assert_eq!(lookup(&db, input, "b".to_string()), Some(3));
+    db.assert_logs(expect![[r#"
+        [
+            "table",
+            "lookup(a)",
+        ]"#]]);
+}
+
+#[test]
+fn entries_become_less_durable() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Items::builder(items(&[("a", 1)]))
+        .durability(Durability::HIGH)
+        .new(&db);
+
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(1));
+    db.assert_logs(expect![[r#"
+        [
+            "lookup(a)",
+            "table",
+        ]"#]]);
+
+    input
+        .set_items(&mut db)
+        .with_durability(Durability::LOW)
+        .to(items(&[("a", 1)]));
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(1));
+    db.assert_logs(expect![[r#"
+        [
+            "table",
+            "lookup(a)",
+        ]"#]]);
+
+    // `lookup` is not verified by durability alone anymore.
+    input.set_items(&mut db).to(items(&[("a", 2)]));
+    assert_eq!(lookup(&db, input, "a".to_string()), Some(2));
+    db.assert_logs(expect![[r#"
+        [
+            "table",
+            "lookup(a)",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "inserted twice")]
+fn duplicate_key() {
+    let db = common::LoggerDatabase::default();
+    let input = Items::new(&db, items(&[("a", 1), ("a", 2)]));
+    table(&db, input);
+}
+
+#[test]
+fn entries_are_reclaimed_with_their_owner() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Items::new(&db, items(&[("a", 1), ("b", 2)]));
+
+    assert_eq!(counted_lookup(&db, input, "a".to_string()), Some(1));
+    assert_eq!(counted_lookup(&db, input, "c".to_string()), None);
+    assert_eq!(LIVE_VALUES.with(Cell::get), 2);
+
+    // Retiring the input deletes the memo of `counted_table`, which owns the entries.
+    input.retire(&mut db);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(LIVE_VALUES.with(Cell::get), 0);
+
+    // The slots are reused by the entries of another owner.
+    let other = Items::new(&db, items(&[("a", 3)]));
+    assert_eq!(counted_lookup(&db, other, "a".to_string()), Some(3));
+    assert_eq!(counted_lookup(&db, other, "c".to_string()), None);
+    assert_eq!(LIVE_VALUES.with(Cell::get), 1);
+}
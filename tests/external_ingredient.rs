@@ -0,0 +1,137 @@
+//! Test an ingredient implemented outside of salsa with `salsa::plumbing::external`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::plumbing::external::{self, ExternalIngredient};
+use salsa::{Database, DatabaseImpl, Durability, Id, IngredientIndex, Revision};
+use test_log::test;
+
+/// A store of numbered cells, each remembering the revision in which it last changed.
+#[derive(Debug)]
+struct Cells {
+    index: IngredientIndex,
+    cells: Vec<(u32, Revision)>,
+}
+
+impl ExternalIngredient for Cells {
+    fn new(index: IngredientIndex) -> Self {
+        Self {
+            index,
+            cells: Vec::new(),
+        }
+    }
+
+    fn debug_name(&self) -> &'static str {
+        "Cells"
+    }
+
+    fn maybe_changed_after(&self, _db: &dyn Database, id: Id, revision: Revision) -> bool {
+        self.cells[id.as_u32() as usize].1 > revision
+    }
+}
+
+fn get_cell(db: &dyn Database, cell: u32) -> u32 {
+    let cells = external::ingredient::<Cells, _>(db);
+    let (value, changed_at) = cells.cells[cell as usize];
+    external::report_read(
+        db,
+        cells.index,
+        Id::from_u32(cell),
+        Durability::LOW,
+        changed_at,
+    );
+    value
+}
+
+fn set_cell(db: &mut dyn Database, cell: u32, value: u32) {
+    let (cells, revision) = external::ingredient_mut::<Cells, _>(db, Durability::LOW);
+    let cell = cell as usize;
+    if cells.cells.len() <= cell {
+        cells.cells.resize(cell + 1, (0, revision));
+    }
+    cells.cells[cell] = (value, revision);
+}
+
+#[salsa::input]
+struct CellRef {
+    cell: u32,
+}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked]
+fn double(db: &dyn Database, cell: CellRef) -> u32 {
+    EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    get_cell(db, cell.cell(db)) * 2
+}
+
+#[test]
+fn execute() {
+    let mut db = DatabaseImpl::new();
+    set_cell(&mut db, 0, 1);
+    set_cell(&mut db, 1, 10);
+    let first = CellRef::new(&db, 0);
+    let second = CellRef::new(&db, 1);
+
+    assert_eq!(double(&db, first), 2);
+    assert_eq!(double(&db, second), 20);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+
+    // Only the function reading the modified cell re-executes.
+    set_cell(&mut db, 0, 3);
+    assert_eq!(double(&db, first), 6);
+    assert_eq!(double(&db, second), 20);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 3);
+}
+
+static SUM_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sums the first `len` cells, reporting the reads at once.
+#[salsa::tracked]
+fn sum(db: &dyn Database, len: CellRef) -> u32 {
+    SUM_EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    let cells = external::ingredient::<Cells, _>(db);
+    let cells = &cells.cells[..len.cell(db) as usize];
+    let reads: Vec<_> = (0..cells.len() as u32)
+        .map(|cell| (Id::from_u32(cell), Durability::LOW, cells[cell as usize].1))
+        .collect();
+    external::report_reads(db, external::ingredient_index::<Cells, _>(db), &reads);
+    cells.iter().map(|&(value, _)| value).sum()
+}
+
+#[test]
+fn report_many_reads() {
+    let mut db = DatabaseImpl::new();
+    for cell in 0..4 {
+        set_cell(&mut db, cell, cell);
+    }
+    let len = CellRef::new(&db, 3);
+
+    assert_eq!(sum(&db, len), 3);
+    assert_eq!(SUM_EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    // A cell that was not read does not re-execute the function.
+    set_cell(&mut db, 3, 10);
+    assert_eq!(sum(&db, len), 3);
+    assert_eq!(SUM_EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    // Each of the cells that were read is a dependency.
+    set_cell(&mut db, 2, 10);
+    assert_eq!(sum(&db, len), 11);
+    assert_eq!(SUM_EXECUTIONS.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn register_ahead_of_use() {
+    struct CellsGroup;
+
+    impl salsa::JarGroup for CellsGroup {
+        fn register(registry: &salsa::Registry<'_>) {
+            registry.add_external::<Cells>();
+        }
+    }
+
+    let db = DatabaseImpl::new().with_jar::<CellsGroup>();
+    let index = external::ingredient_index::<Cells, _>(&db);
+    assert_eq!(external::ingredient::<Cells, _>(&db).index, index);
+}
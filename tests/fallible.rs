@@ -0,0 +1,156 @@
+//! Test that cancellation, unexpected cycles and stale handles can be
+//! observed as a `salsa::Error` instead of unwinding.
+
+use salsa::{Database, DatabaseImpl, Error, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn cycle_a(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_b(db, input)
+}
+
+#[salsa::tracked]
+fn cycle_b(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_a(db, input)
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn cycle_as_error() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let result = Error::catch(|| cycle_a(&db, input));
+    assert!(matches!(result, Err(Error::Cycle(_))), "{result:?}");
+}
+
+#[test]
+fn cancellation_as_error() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let result = db.batch(|db| {
+        input.set_field(db).to(2);
+        Error::catch(|| double(db, input))
+    });
+    assert!(matches!(result, Err(Error::Cancelled(_))), "{result:?}");
+
+    assert_eq!(Error::catch(|| double(&db, input)).unwrap(), 4);
+}
+
+#[test]
+#[should_panic(expected = "user panic")]
+fn other_panics_propagate() {
+    let _ = Error::catch(|| panic!("user panic"));
+}
+
+#[salsa::tracked(validate_args)]
+fn validated(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[test]
+fn invalid_key_as_error() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let other_db = DatabaseImpl::new();
+    let result = Error::catch(|| validated(&other_db, input));
+    assert!(matches!(result, Err(Error::InvalidKey(_))), "{result:?}");
+}
+
+#[test]
+fn try_fetch() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    assert_eq!(double::try_fetch(&db, input).unwrap(), 2);
+
+    let result = cycle_a::try_fetch(&db, input);
+    assert!(matches!(result, Err(Error::Cycle(_))), "{result:?}");
+}
+
+#[test]
+fn try_fetch_retired_input() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    input.retire(&mut db);
+
+    let result = double::try_fetch(&db, input);
+    assert!(
+        matches!(&result, Err(Error::StaleHandle(stale)) if stale.id == input.0),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn stale_handle_as_error() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    input.retire(&mut db);
+
+    let result = Error::catch(|| input.field(&db));
+    assert!(matches!(result, Err(Error::StaleHandle(_))), "{result:?}");
+}
+
+#[test]
+fn error_is_reported_once() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    input.retire(&mut db);
+
+    let err = double::try_fetch(&db, input).unwrap_err();
+    assert!(std::error::Error::source(&err).is_none());
+}
+
+#[test]
+fn try_set() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    assert_eq!(input.set_field(&mut db).try_to(2).unwrap(), 1);
+    assert_eq!(double(&db, input), 4);
+
+    input.retire(&mut db);
+    let result = input.set_field(&mut db).try_to(3);
+    assert!(matches!(result, Err(Error::StaleHandle(_))), "{result:?}");
+}
+
+#[salsa::interned]
+struct MyInterned<'db> {
+    name: String,
+}
+
+#[test]
+fn try_new() {
+    let db = DatabaseImpl::new();
+
+    let input = MyInput::try_new(&db, 1).unwrap();
+    assert_eq!(input.field(&db), 1);
+
+    let interned = MyInterned::try_new(&db, "a").unwrap();
+    assert_eq!(interned, MyInterned::new(&db, "a"));
+}
+
+#[salsa::db]
+trait OtherDb: Database {}
+
+#[test]
+fn missing_view_as_error() {
+    let db = DatabaseImpl::new();
+
+    let result = Error::catch(|| {
+        let db: &dyn Database = &db;
+        db.as_view::<dyn OtherDb>();
+    });
+    assert!(matches!(result, Err(Error::MissingView(_))), "{result:?}");
+}
@@ -0,0 +1,63 @@
+//! Test the built-in interned path type `salsa::FilePath` and the `salsa::File` input.
+
+use std::path::Path;
+
+use salsa::{Database, DatabaseImpl, File, FilePath, Setter};
+use test_log::test;
+
+#[salsa::tracked]
+fn line_count<'db>(db: &'db dyn Database, path: FilePath<'db>) -> usize {
+    path.file(db)
+        .map_or(0, |file| file.contents(db).lines().count())
+}
+
+#[test]
+fn normalization() {
+    let db = DatabaseImpl::new();
+    let path = FilePath::new(&db, "src/./lib/../main.rs");
+    assert_eq!(path, FilePath::new(&db, "src/main.rs"));
+    assert_eq!(path.as_path(), Path::new("src/main.rs"));
+    assert_eq!(format!("{path:?}"), "\"src/main.rs\"");
+
+    assert_eq!(&*FilePath::new(&db, "../a/../../b"), Path::new("../../b"));
+    assert_eq!(&*FilePath::new(&db, "/.."), Path::new("/"));
+    assert_eq!(&*FilePath::new(&db, "a/"), Path::new("a"));
+}
+
+#[test]
+fn prefixes() {
+    let db = DatabaseImpl::new();
+    let file = FilePath::new(&db, "/project/src/main.rs");
+    let src = FilePath::new(&db, "/project/src");
+    let root = FilePath::new(&db, "/");
+
+    assert_eq!(file.parent(&db), Some(src));
+    assert_eq!(root.parent(&db), None);
+    assert_eq!(FilePath::new(&db, "a").parent(&db), None);
+    assert_eq!(
+        file.ancestors(&db)
+            .map(|path| path.as_path())
+            .collect::<Vec<_>>(),
+        ["/project/src/main.rs", "/project/src", "/project", "/"].map(Path::new),
+    );
+
+    assert!(file.starts_with(&db, src));
+    assert!(file.starts_with(&db, file));
+    assert!(file.starts_with(&db, root));
+    assert!(!src.starts_with(&db, file));
+    assert!(!file.starts_with(&db, FilePath::new(&db, "/project/s")));
+}
+
+#[test]
+fn files() {
+    let mut db = DatabaseImpl::new();
+    let path = FilePath::new(&db, "a/b.txt").file_id();
+    let file = File::new(&db, path, "one\ntwo".to_string());
+    assert_eq!(file.path(&db).path(&db), FilePath::new(&db, "a/./b.txt"));
+    assert_eq!(FilePath::new(&db, "a/c/../b.txt").file(&db), Some(file));
+    assert_eq!(line_count(&db, path.path(&db)), 2);
+    assert_eq!(line_count(&db, FilePath::new(&db, "a")), 0);
+
+    file.set_contents(&mut db).to("one".to_string());
+    assert_eq!(line_count(&db, path.path(&db)), 1);
+}
@@ -0,0 +1,75 @@
+//! Test that tracked functions declared with `fingerprint` backdate their values
+//! by comparing fingerprints.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::{Database, DatabaseImpl, Fingerprint, Fingerprinter, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct SourceFile {
+    text: String,
+}
+
+/// Does not implement `Eq`.
+#[derive(Debug, salsa::Update)]
+struct Lines(Vec<String>);
+
+impl Fingerprint for Lines {
+    fn fingerprint(&self, fingerprinter: &mut Fingerprinter) {
+        self.0.fingerprint(fingerprinter);
+    }
+}
+
+#[salsa::tracked(return_ref, fingerprint)]
+fn lines(db: &dyn Database, file: SourceFile) -> Lines {
+    Lines(
+        file.text(db)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect(),
+    )
+}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked]
+fn line_count(db: &dyn Database, file: SourceFile) -> usize {
+    EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    lines(db, file).0.len()
+}
+
+#[test]
+fn backdate_by_fingerprint() {
+    let mut db = DatabaseImpl::new();
+    let file = SourceFile::new(&db, "a \nb".to_string());
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    // The lines are the same once trimmed, so `line_count` is not re-executed.
+    file.set_text(&mut db).to("a\nb ".to_string());
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    file.set_text(&mut db).to("a\nb\nc".to_string());
+    assert_eq!(line_count(&db, file), 3);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+
+    let expected = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    assert_eq!(
+        lines::fingerprint(&db, file),
+        salsa::fingerprint_of(&expected)
+    );
+}
+
+#[test]
+fn fingerprints_are_stable() {
+    assert_eq!(
+        salsa::fingerprint_of("abc"),
+        0xe26a08d6c03843d82945a821b0f9ee7a
+    );
+    assert_ne!(
+        salsa::fingerprint_of(&("ab", "c")),
+        salsa::fingerprint_of(&("a", "bc"))
+    );
+}
@@ -0,0 +1,103 @@
+//! Test the counters reported by `Database::ingredient_stats`.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use salsa::{Database, DatabaseImpl, Durability, IngredientStats, Setter};
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(lru = 1)]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.text(db).len()
+}
+
+#[salsa::tracked]
+fn is_long(db: &dyn Database, file: File) -> bool {
+    length(db, file) > 3
+}
+
+fn stats(db: &dyn Database, name: &str) -> IngredientStats {
+    db.ingredient_stats()
+        .into_iter()
+        .find(|stats| stats.debug_name == name)
+        .unwrap()
+}
+
+fn counters(stats: &IngredientStats) -> [u64; 5] {
+    [
+        stats.executions,
+        stats.shallow_verify_hits,
+        stats.deep_verify_hits,
+        stats.backdates,
+        stats.evictions,
+    ]
+}
+
+#[test]
+fn counters_of_tracked_functions() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "abc".to_string());
+
+    assert!(!is_long(&db, file));
+    assert!(!is_long(&db, file));
+    assert_eq!(counters(&stats(&db, "length")), [1, 0, 0, 0, 0]);
+    assert_eq!(counters(&stats(&db, "is_long")), [1, 1, 0, 0, 0]);
+
+    // `length` changes, `is_long` is backdated.
+    file.set_text(&mut db).to("abcd".to_string());
+    assert!(is_long(&db, file));
+    file.set_text(&mut db).to("abcde".to_string());
+    assert!(is_long(&db, file));
+    assert_eq!(counters(&stats(&db, "length")), [3, 2, 0, 0, 0]);
+    assert_eq!(counters(&stats(&db, "is_long")), [3, 1, 0, 1, 0]);
+
+    // Both are reused after checking their dependencies.
+    db.synthetic_write(Durability::LOW);
+    assert!(is_long(&db, file));
+    assert_eq!(counters(&stats(&db, "length")), [3, 2, 1, 0, 0]);
+    assert_eq!(counters(&stats(&db, "is_long")), [3, 1, 1, 1, 0]);
+
+    // Computing the length of another file evicts the first one.
+    let other = File::new(&db, "a".to_string());
+    assert!(!is_long(&db, other));
+    assert_eq!(counters(&stats(&db, "length")), [4, 2, 1, 0, 1]);
+    assert_eq!(stats(&db, "length").blocked, Duration::ZERO);
+}
+
+#[salsa::input]
+struct Signal {
+    sender: mpsc::SyncSender<()>,
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn Database, signal: Signal) -> u32 {
+    signal.sender(db).send(()).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    1
+}
+
+#[test]
+fn time_blocked_on_other_threads() {
+    let db = DatabaseImpl::new();
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let signal = Signal::new(&db, sender);
+
+    let other = std::thread::spawn({
+        let db = db.clone();
+        move || slow(&db, signal)
+    });
+
+    // `slow` is executing on the other thread, so this thread blocks until it completes.
+    receiver.recv().unwrap();
+    assert_eq!(slow(&db, signal), 1);
+    assert_eq!(other.join().unwrap(), 1);
+
+    let stats = stats(&db, "slow");
+    assert_eq!(stats.executions, 1);
+    assert!(stats.blocked > Duration::ZERO);
+}
@@ -0,0 +1,65 @@
+//! Test that `Database::ingredients` describes the registered ingredients.
+
+use salsa::{Database, DatabaseImpl, IngredientKind};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Word<'db> {
+    #[tracked]
+    text: String,
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> Vec<Word<'_>> {
+    file.text(db)
+        .split_whitespace()
+        .map(|text| Word::new(db, text.to_string()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn length<'db>(db: &'db dyn Database, word: Word<'db>) -> usize {
+    word.text(db).len()
+}
+
+#[test]
+fn describes_ingredients() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, "a bb".to_string());
+    let _ = File::new(&db, "ccc".to_string());
+    for word in words(&db, file) {
+        length(&db, word);
+    }
+
+    // Ingredients are listed in the order they were registered.
+    let ingredients: Vec<_> = db
+        .ingredients()
+        .into_iter()
+        .map(|info| {
+            (
+                info.owner_debug_name,
+                info.debug_name,
+                info.kind,
+                info.memos,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ingredients,
+        [
+            (None, "File", IngredientKind::Input, None),
+            (Some("File"), "text", IngredientKind::InputField, None),
+            (None, "words", IngredientKind::TrackedFunction, Some(1)),
+            (None, "Word", IngredientKind::TrackedStruct, None),
+            (Some("Word"), "text", IngredientKind::TrackedField, None),
+            (None, "length", IngredientKind::TrackedFunction, Some(2)),
+        ]
+    );
+    let words = &db.ingredients()[2];
+    assert_eq!(db.ingredient_debug_name(words.ingredient_index), "words");
+}
@@ -1,4 +1,5 @@
-//! Tests that fields attributed with `#[default]` are initialized with `Default::default()`.
+//! Tests that fields attributed with `#[default]` are initialized with `Default::default()`,
+//! and fields attributed with `#[default(expr)]` with `expr`.
 
 use salsa::Durability;
 use test_log::test;
@@ -41,3 +42,25 @@ fn builder_default_optional_value() {
     assert!(input.required(&db));
     assert_eq!(input.optional(&db), 0);
 }
+
+#[salsa::input]
+struct Config {
+    name: String,
+    #[default(4)]
+    jobs: usize,
+    #[default(vec!["lib".to_string()])]
+    targets: Vec<String>,
+}
+
+#[test]
+fn default_expression() {
+    let db = salsa::DatabaseImpl::new();
+
+    let config = Config::new(&db, "a".to_string());
+    assert_eq!(config.jobs(&db), 4);
+    assert_eq!(config.targets(&db), ["lib"]);
+
+    let config = Config::builder("b".to_string()).jobs(1).new(&db);
+    assert_eq!(config.jobs(&db), 1);
+    assert_eq!(config.targets(&db), ["lib"]);
+}
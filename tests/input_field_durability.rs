@@ -1,4 +1,5 @@
-//! Tests that code using the builder's durability methods compiles.
+//! Tests that code using the builder's durability methods compiles
+//! and that `#[durability(..)]` sets the default durability of a field.
 
 use salsa::Durability;
 use test_log::test;
@@ -35,3 +36,55 @@ fn optional_field_durability() {
     assert!(input.required_field(&db));
     assert_eq!(input.optional_field(&db), 20);
 }
+
+#[salsa::input]
+struct MyInputWithDurability {
+    #[durability(high)]
+    high_field: bool,
+
+    low_field: bool,
+}
+
+#[test]
+fn field_durability_attribute() {
+    use salsa::plumbing::ZalsaDatabase;
+    use salsa::Setter;
+
+    let mut db = salsa::DatabaseImpl::new();
+
+    let input = MyInputWithDurability::new(&db, true, true);
+
+    // Setting the low-durability field leaves high durability values untouched...
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+    input.set_low_field(&mut db).to(false);
+    assert_eq!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+
+    // ...while the field declared with `#[durability(high)]` is high durability by default.
+    input.set_high_field(&mut db).to(false);
+    assert_ne!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+}
+
+#[test]
+fn field_durability_attribute_overridden_by_builder() {
+    use salsa::plumbing::ZalsaDatabase;
+    use salsa::Setter;
+
+    let mut db = salsa::DatabaseImpl::new();
+
+    let input = MyInputWithDurability::builder(true, true)
+        .high_field_durability(Durability::LOW)
+        .new(&db);
+
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+    input.set_high_field(&mut db).to(false);
+    assert_eq!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+}
@@ -0,0 +1,95 @@
+//! Test enumerating inputs with `all` and looking them up by an `#[index]` field.
+
+use std::path::PathBuf;
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct File {
+    #[index]
+    path: PathBuf,
+    contents: String,
+}
+
+#[salsa::input]
+struct Config {
+    #[index]
+    name: String,
+    #[index]
+    id: u32,
+}
+
+#[salsa::input]
+struct Open {
+    path: PathBuf,
+}
+
+#[salsa::tracked]
+fn contents_of(db: &dyn Database, open: Open) -> Option<String> {
+    File::find_by_path(db, &open.path(db)).map(|file| file.contents(db))
+}
+
+#[test]
+fn find_by_field() {
+    let db = DatabaseImpl::new();
+    let a = File::new(&db, PathBuf::from("a.rs"), "a".to_string());
+    let b = File::new(&db, PathBuf::from("b.rs"), "b".to_string());
+
+    assert_eq!(File::find_by_path(&db, &PathBuf::from("a.rs")), Some(a));
+    assert_eq!(File::find_by_path(&db, &PathBuf::from("b.rs")), Some(b));
+    assert_eq!(File::find_by_path(&db, &PathBuf::from("c.rs")), None);
+}
+
+#[test]
+fn find_by_multiple_fields() {
+    let db = DatabaseImpl::new();
+    let config = Config::new(&db, "debug".to_string(), 1);
+
+    assert_eq!(
+        Config::find_by_name(&db, &"debug".to_string()),
+        Some(config)
+    );
+    assert_eq!(Config::find_by_id(&db, &1), Some(config));
+    assert_eq!(Config::find_by_id(&db, &2), None);
+}
+
+#[test]
+fn index_follows_setter() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, PathBuf::from("old.rs"), String::new());
+
+    file.set_path(&mut db).to(PathBuf::from("new.rs"));
+
+    assert_eq!(File::find_by_path(&db, &PathBuf::from("old.rs")), None);
+    assert_eq!(
+        File::find_by_path(&db, &PathBuf::from("new.rs")),
+        Some(file)
+    );
+}
+
+#[test]
+fn find_in_tracked_fn_sees_new_inputs() {
+    let mut db = DatabaseImpl::new();
+    let open = Open::new(&db, PathBuf::from("lib.rs"));
+    assert_eq!(contents_of(&db, open), None);
+
+    let file = File::new(&db, PathBuf::from("lib.rs"), "fn main() {}".to_string());
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(contents_of(&db, open), Some("fn main() {}".to_string()));
+
+    file.set_contents(&mut db).to("fn lib() {}".to_string());
+    assert_eq!(contents_of(&db, open), Some("fn lib() {}".to_string()));
+}
+
+#[test]
+fn all_returns_created_inputs() {
+    let db = DatabaseImpl::new();
+    assert_eq!(File::all(&db).count(), 0);
+
+    let a = File::new(&db, PathBuf::from("a.rs"), String::new());
+    let b = File::new(&db, PathBuf::from("b.rs"), String::new());
+
+    let mut files: Vec<_> = File::all(&db).collect();
+    files.sort();
+    assert_eq!(files, vec![a, b]);
+}
@@ -0,0 +1,101 @@
+//! Test `derive(InputGroup)`, which mirrors a plain struct into a salsa input
+//! and only sets the fields that changed when syncing.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Durability;
+
+#[derive(Clone, salsa::InputGroup)]
+struct Config {
+    #[durability(high)]
+    opt_level: u32,
+    #[return_ref]
+    target: String,
+    verbose: bool,
+}
+
+#[salsa::tracked]
+fn describe_target(db: &dyn LogDatabase, config: ConfigInput) -> String {
+    db.push_log("describe_target".to_string());
+    format!("{} at -O{}", config.target(db), config.opt_level(db))
+}
+
+#[salsa::tracked]
+fn is_verbose(db: &dyn LogDatabase, config: ConfigInput) -> bool {
+    db.push_log("is_verbose".to_string());
+    config.verbose(db)
+}
+
+#[test]
+fn sync_sets_changed_fields() {
+    let mut db = common::LoggerDatabase::default();
+    let mut config = Config {
+        opt_level: 2,
+        target: "x86_64".to_string(),
+        verbose: false,
+    };
+    let input = ConfigInput::new_from(&db, &config);
+
+    assert_eq!(describe_target(&db, input), "x86_64 at -O2");
+    assert!(!is_verbose(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "describe_target",
+            "is_verbose",
+        ]"#]]);
+
+    config.verbose = true;
+    input.sync_from(&mut db, &config);
+    assert_eq!(describe_target(&db, input), "x86_64 at -O2");
+    assert!(is_verbose(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "is_verbose",
+        ]"#]]);
+
+    // Syncing an unchanged value starts no new revision.
+    let revision = salsa::plumbing::current_revision(&db);
+    input.sync_from(&mut db, &config);
+    assert_eq!(salsa::plumbing::current_revision(&db), revision);
+
+    config.target = "aarch64".to_string();
+    input.sync_from(&mut db, &config);
+    assert_eq!(describe_target(&db, input), "aarch64 at -O2");
+    assert!(is_verbose(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "describe_target",
+        ]"#]]);
+}
+
+#[test]
+fn durabilities_are_kept() {
+    use salsa::plumbing::ZalsaDatabase;
+
+    let mut db = common::LoggerDatabase::default();
+    let mut config = Config {
+        opt_level: 2,
+        target: "x86_64".to_string(),
+        verbose: false,
+    };
+    let input = ConfigInput::new_from(&db, &config);
+
+    // Syncing a low-durability field leaves high durability values untouched...
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+    config.verbose = true;
+    input.sync_from(&mut db, &config);
+    assert_eq!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+
+    // ...while the field declared with `#[durability(high)]` is synced with high durability.
+    config.opt_level = 3;
+    input.sync_from(&mut db, &config);
+    assert_ne!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+}
@@ -0,0 +1,90 @@
+//! Test `#[salsa::input(lazy)]`, whose `#[lazy]` fields are loaded when first read.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use salsa::{Database, DatabaseImpl, Durability, Setter};
+
+#[salsa::input(lazy)]
+struct File {
+    path: PathBuf,
+    #[lazy]
+    text: String,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Database, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+/// Registers a loader reading the files from `disk`, and returns the number of loads.
+fn register_loader(db: &mut DatabaseImpl, disk: HashMap<PathBuf, String>) -> Arc<AtomicUsize> {
+    let loads = Arc::new(AtomicUsize::new(0));
+    let counter = loads.clone();
+    File::register_text_loader(db, move |db, file| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        (disk[&file.path(db)].clone(), Durability::HIGH)
+    });
+    loads
+}
+
+#[test]
+fn loads_on_first_read() {
+    let mut db = DatabaseImpl::new();
+    let loads = register_loader(
+        &mut db,
+        HashMap::from([(PathBuf::from("a.rs"), "a\nb".to_string())]),
+    );
+    let file = File::new(&db, PathBuf::from("a.rs"));
+    assert_eq!(loads.load(Ordering::SeqCst), 0);
+
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(file.text(&db), "a\nb");
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn loaded_field_is_an_input() {
+    let mut db = DatabaseImpl::new();
+    let loads = register_loader(
+        &mut db,
+        HashMap::from([(PathBuf::from("a.rs"), "a".to_string())]),
+    );
+    let file = File::new(&db, PathBuf::from("a.rs"));
+    assert_eq!(line_count(&db, file), 1);
+
+    // A low-durability change does not invalidate the high-durability loaded text.
+    let other = File::builder(PathBuf::from("b.rs"))
+        .text(String::new())
+        .new(&db);
+    other.set_text(&mut db).to("b".to_string());
+    assert_eq!(line_count(&db, file), 1);
+
+    // Setting the field replaces the loaded value, keeping its durability.
+    let old = file.set_text(&mut db).to("a\nb\nc".to_string());
+    assert_eq!(old, "a");
+    assert_eq!(line_count(&db, file), 3);
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn given_value_is_not_loaded() {
+    let mut db = DatabaseImpl::new();
+    let loads = register_loader(&mut db, HashMap::new());
+    let file = File::builder(PathBuf::from("a.rs"))
+        .text("a".to_string())
+        .new(&db);
+
+    assert_eq!(file.text(&db), "a");
+    assert_eq!(loads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+#[should_panic(expected = "no loader registered for the lazy field `File::text`")]
+fn missing_loader() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, PathBuf::from("a.rs"));
+    file.text(&db);
+}
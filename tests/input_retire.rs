@@ -0,0 +1,137 @@
+//! Test retiring inputs:
+//!
+//! * the tracked functions that read a retired input are invalidated,
+//! * the memos keyed on it and the tracked structs they created are discarded,
+//! * its id is not reused for new inputs, so handles to it keep panicking.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::plumbing::AsId;
+use salsa::{Database, DatabaseImpl, Setter, StaleHandle};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    #[index]
+    path: String,
+    contents: String,
+}
+
+#[salsa::input]
+struct Workspace {
+    files: Vec<File>,
+}
+
+#[salsa::tracked]
+struct Line<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn total_length(db: &dyn LogDatabase, workspace: Workspace) -> usize {
+    db.push_log(format!("total_length({:?})", workspace.files(db).len()));
+    workspace
+        .files(db)
+        .into_iter()
+        .map(|file| file.contents(db).len())
+        .sum()
+}
+
+#[salsa::tracked]
+fn lines(db: &dyn LogDatabase, file: File) -> Vec<Line<'_>> {
+    file.contents(db)
+        .lines()
+        .map(|line| Line::new(db, line.to_string()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn first_contents(db: &dyn Database, workspace: Workspace) -> String {
+    workspace.files(db)[0].contents(db)
+}
+
+#[test]
+fn retire_invalidates_dependents() {
+    let mut db = common::DiscardLoggerDatabase::default();
+    let a = File::new(&db, "a".to_string(), "aaa".to_string());
+    let b = File::new(&db, "b".to_string(), "bb".to_string());
+    let workspace = Workspace::new(&db, vec![a, b]);
+    assert_eq!(total_length(&db, workspace), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "total_length(2)",
+        ]"#]]);
+
+    // The caller updates the workspace before retiring the file,
+    // so that no function reads it anymore.
+    workspace.set_files(&mut db).to(vec![a]);
+    b.retire(&mut db);
+    assert_eq!(total_length(&db, workspace), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "total_length(1)",
+        ]"#]]);
+}
+
+#[test]
+fn retire_discards_memos_and_outputs() {
+    let mut db = common::DiscardLoggerDatabase::default();
+    let file = File::new(&db, "a".to_string(), "one\ntwo".to_string());
+    assert_eq!(lines(&db, file).len(), 2);
+    db.assert_logs(expect![[r#"
+        []"#]]);
+
+    file.retire(&mut db);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidDiscard { key: DatabaseKeyIndex(IngredientIndex(3), Id(0)) })",
+            "salsa_event(DidDiscard { key: DatabaseKeyIndex(IngredientIndex(4), Id(400)) })",
+            "salsa_event(DidDiscard { key: DatabaseKeyIndex(IngredientIndex(4), Id(401)) })",
+        ]"#]]);
+}
+
+#[test]
+fn retired_input_is_not_listed() {
+    let mut db = DatabaseImpl::new();
+    let a = File::new(&db, "a".to_string(), String::new());
+    let b = File::new(&db, "b".to_string(), String::new());
+
+    a.retire(&mut db);
+
+    assert_eq!(File::all(&db).collect::<Vec<_>>(), vec![b]);
+    assert_eq!(File::find_by_path(&db, &"a".to_string()), None);
+    assert_eq!(File::find_by_path(&db, &"b".to_string()), Some(b));
+    db.attach(|_| {
+        assert_eq!(
+            format!("{a:?}"),
+            "File { [salsa id]: Id(0), [retired]: true }"
+        );
+    });
+}
+
+#[test]
+fn retired_id_is_not_reused() {
+    let mut db = DatabaseImpl::new();
+    let old = File::new(&db, "old".to_string(), "old".to_string());
+    let workspace = Workspace::new(&db, vec![old]);
+    assert_eq!(first_contents(&db, workspace), "old");
+
+    old.retire(&mut db);
+    let new = File::new(&db, "new".to_string(), "new".to_string());
+    assert_ne!(new, old);
+    assert_eq!(File::find_by_path(&db, &"new".to_string()), Some(new));
+
+    // The function still reads the retired input.
+    StaleHandle::catch(|| first_contents(&db, workspace)).unwrap_err();
+}
+
+#[test]
+fn read_retired_input() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a".to_string(), String::new());
+    file.retire(&mut db);
+    let err = StaleHandle::catch(|| file.contents(&db)).unwrap_err();
+    assert_eq!(err.id, file.as_id());
+}
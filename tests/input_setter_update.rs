@@ -0,0 +1,75 @@
+//! Test that `Setter::update` modifies an input field in place
+//! and invalidates the functions that read it.
+
+mod common;
+
+use common::{LogDatabase, LoggerDatabase};
+use expect_test::expect;
+use salsa::{Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    items: Vec<u32>,
+
+    other: u32,
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("sum({:?})", input.items(db)));
+    input.items(db).iter().sum()
+}
+
+#[test]
+fn update_in_place() {
+    let mut db = LoggerDatabase::default();
+
+    let input = MyInput::new(&db, vec![1, 2], 0);
+    assert_eq!(sum(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "sum([1, 2])",
+        ]"#]]);
+
+    let len = input.set_items(&mut db).update(|items| {
+        items.push(3);
+        items.len()
+    });
+    assert_eq!(len, 3);
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "sum([1, 2, 3])",
+        ]"#]]);
+
+    // Updating another field does not invalidate `sum`.
+    input.set_other(&mut db).update(|other| *other += 1);
+    assert_eq!(input.other(&db), 1);
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn update_with_durability() {
+    use salsa::plumbing::ZalsaDatabase;
+
+    let mut db = LoggerDatabase::default();
+
+    let input = MyInput::new(&db, vec![], 0);
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+
+    input
+        .set_items(&mut db)
+        .with_durability(Durability::HIGH)
+        .update(|items| items.push(1));
+    assert_eq!(input.items(&db), &[1]);
+
+    // The field is now of high durability, so updating it again bumps the high durability revision.
+    input.set_items(&mut db).update(|items| items.push(2));
+    assert_ne!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+}
@@ -0,0 +1,67 @@
+//! Tests that `test_builder` initializes all fields with `Default::default()`
+//! and that selected fields can be overridden.
+
+use salsa::Durability;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    name: String,
+    count: usize,
+    #[default]
+    enabled: bool,
+}
+
+#[salsa::input]
+struct NotDefault {
+    field: NoDefault,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NoDefault(u32);
+
+#[test]
+fn all_defaults() {
+    let db = salsa::DatabaseImpl::new();
+
+    let input = MyInput::test_builder().new(&db);
+
+    assert_eq!(input.name(&db), "");
+    assert_eq!(input.count(&db), 0);
+    assert!(!input.enabled(&db));
+}
+
+#[test]
+fn override_fields() {
+    let db = salsa::DatabaseImpl::new();
+
+    let input = MyInput::test_builder()
+        .count(3)
+        .enabled(true)
+        .durability(Durability::HIGH)
+        .new(&db);
+
+    assert_eq!(input.name(&db), "");
+    assert_eq!(input.count(&db), 3);
+    assert!(input.enabled(&db));
+}
+
+#[test]
+fn override_required_field_of_builder() {
+    let db = salsa::DatabaseImpl::new();
+
+    let input = MyInput::builder("a".to_string(), 1)
+        .name("b".to_string())
+        .new(&db);
+
+    assert_eq!(input.name(&db), "b");
+}
+
+#[test]
+fn inputs_without_default_still_have_builder() {
+    let db = salsa::DatabaseImpl::new();
+
+    let input = NotDefault::builder(NoDefault(1)).new(&db);
+
+    assert_eq!(input.field(&db), NoDefault(1));
+}
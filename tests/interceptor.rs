@@ -0,0 +1,120 @@
+//! Test that interceptors are invoked around the execution
+//! of tracked functions and can veto it.
+
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{Cancelled, Database, DatabaseImpl, DatabaseKeyIndex, Intercept, Interceptor, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn forbidden(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.log.lock().unwrap())
+    }
+}
+
+impl Interceptor for Recorder {
+    fn will_execute(&self, db: &dyn Database, database_key: DatabaseKeyIndex) -> Intercept {
+        let name = db.ingredient_debug_name(database_key.ingredient_index());
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("will_execute({name})"));
+        if name == "forbidden" {
+            Intercept::Veto
+        } else {
+            Intercept::Proceed
+        }
+    }
+
+    fn did_execute(&self, db: &dyn Database, database_key: DatabaseKeyIndex) {
+        let name = db.ingredient_debug_name(database_key.ingredient_index());
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("did_execute({name})"));
+    }
+}
+
+#[test]
+fn interceptors_wrap_execution() {
+    let mut db = DatabaseImpl::new();
+    let recorder = Recorder::default();
+    db.storage().add_interceptor(recorder.clone());
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(outer(&db, input), 3);
+    expect![[r#"
+        [
+            "will_execute(outer)",
+            "will_execute(inner)",
+            "did_execute(inner)",
+            "did_execute(outer)",
+        ]
+    "#]]
+    .assert_debug_eq(&recorder.take());
+
+    // Memoized values are reused without invoking the interceptors.
+    assert_eq!(outer(&db, input), 3);
+    assert!(recorder.take().is_empty());
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 5);
+    expect![[r#"
+        [
+            "will_execute(inner)",
+            "did_execute(inner)",
+            "will_execute(outer)",
+            "did_execute(outer)",
+        ]
+    "#]]
+    .assert_debug_eq(&recorder.take());
+}
+
+#[test]
+fn interceptor_veto() {
+    let db = DatabaseImpl::new();
+    let recorder = Recorder::default();
+    db.storage().add_interceptor(recorder.clone());
+
+    let input = MyInput::new(&db, 1);
+    let result = Cancelled::catch(|| forbidden(&db, input));
+    assert!(
+        matches!(result, Err(Cancelled::Vetoed { .. })),
+        "{result:?}"
+    );
+    expect![[r#"
+        [
+            "will_execute(forbidden)",
+        ]
+    "#]]
+    .assert_debug_eq(&recorder.take());
+
+    // Other functions are unaffected.
+    assert_eq!(inner(&db, input), 2);
+}
@@ -3,6 +3,7 @@
 
 use expect_test::expect;
 use salsa::plumbing::{AsId, FromId};
+use salsa::Setter;
 use std::path::{Path, PathBuf};
 use test_log::test;
 
@@ -197,3 +198,33 @@ fn interning_with_custom_ids_and_no_lifetime() {
     assert_eq!(s1, s1_2);
     assert_eq!(s2, s2_2);
 }
+
+#[salsa::input]
+struct InputWithSymbol {
+    symbol: InternedStringNoLifetime,
+}
+
+#[salsa::tracked]
+fn symbol_text(db: &dyn salsa::Database, input: InputWithSymbol) -> String {
+    input.symbol(db).data(db)
+}
+
+#[test]
+fn interning_without_lifetimes_across_revisions() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let hello = InternedStringNoLifetime::new(&db, "Hello");
+    let input = InputWithSymbol::new(&db, hello);
+    assert_eq!(symbol_text(&db, input), "Hello");
+
+    // Symbols created in an earlier revision remain valid and stable.
+    let world = InternedStringNoLifetime::new(&db, "World");
+    input.set_symbol(&mut db).to(world);
+    assert_eq!(symbol_text(&db, input), "World");
+    assert_eq!(hello.data(&db), "Hello");
+    assert_eq!(InternedStringNoLifetime::new(&db, "Hello"), hello);
+
+    // ...and can be shared with other threads.
+    let handle = std::thread::spawn(move || hello);
+    assert_eq!(handle.join().unwrap().data(&db), "Hello");
+}
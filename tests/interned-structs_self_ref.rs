@@ -70,6 +70,7 @@ const _: () = {
         const DEBUG_NAME: &'static str = "InternedString";
         type Fields<'a> = StructData<'a>;
         type Struct<'a> = InternedString<'a>;
+        type Hasher = zalsa_::interned::DefaultHasher;
         fn struct_from_id<'db>(id: salsa::Id) -> Self::Struct<'db> {
             InternedString(id, std::marker::PhantomData)
         }
@@ -0,0 +1,71 @@
+//! Test enumerating the values of an interned struct with `all` and `all_tracked`.
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::interned]
+struct Word<'db> {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned(no_lifetime)]
+struct Number {
+    value: u64,
+}
+
+#[salsa::input]
+struct Input {
+    count: u64,
+}
+
+#[salsa::tracked]
+fn intern_numbers(db: &dyn Database, input: Input) -> u64 {
+    for value in 0..input.count(db) {
+        Number::new(db, value);
+    }
+    input.count(db)
+}
+
+#[salsa::tracked]
+fn count_untracked(db: &dyn Database) -> usize {
+    Number::all(db).count()
+}
+
+#[salsa::tracked]
+fn count_tracked(db: &dyn Database) -> usize {
+    Number::all_tracked(db).count()
+}
+
+#[test]
+fn all_returns_interned_values() {
+    let db = DatabaseImpl::new();
+    let hello = Word::new(&db, "hello".to_string());
+    let world = Word::new(&db, "world".to_string());
+    Word::new(&db, "hello".to_string());
+
+    let mut words: Vec<_> = Word::all(&db).collect();
+    words.sort_by_key(|word| word.text(&db).clone());
+    assert_eq!(words, vec![hello, world]);
+}
+
+#[test]
+fn all_is_empty_without_values() {
+    let db = DatabaseImpl::new();
+    assert_eq!(Number::all(&db).count(), 0);
+}
+
+#[test]
+fn all_tracked_observes_new_values() {
+    let mut db = DatabaseImpl::new();
+    let input = Input::new(&db, 2);
+    intern_numbers(&db, input);
+    assert_eq!(count_untracked(&db), 2);
+    assert_eq!(count_tracked(&db), 2);
+
+    input.set_count(&mut db).to(3);
+    intern_numbers(&db, input);
+
+    // The untracked read is not re-executed, the tracked one is.
+    assert_eq!(count_untracked(&db), 2);
+    assert_eq!(count_tracked(&db), 3);
+}
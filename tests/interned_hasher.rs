@@ -0,0 +1,59 @@
+//! Test the `hasher` option of interned structs.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, DefaultHasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::{Database, DatabaseImpl};
+
+static HASHERS_BUILT: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the hashers it builds, to check that it is used.
+#[derive(Clone, Default)]
+struct CountingHasher;
+
+impl BuildHasher for CountingHasher {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        HASHERS_BUILT.fetch_add(1, Ordering::Relaxed);
+        DefaultHasher::new()
+    }
+}
+
+#[salsa::interned(hasher = CountingHasher)]
+struct Word<'db> {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned(no_lifetime, hasher = RandomState)]
+struct Number {
+    value: u64,
+}
+
+#[salsa::tracked]
+fn length<'db>(db: &'db dyn Database, word: Word<'db>) -> usize {
+    word.text(db).len()
+}
+
+#[test]
+fn custom_hasher() {
+    let db = DatabaseImpl::new();
+
+    let before = HASHERS_BUILT.load(Ordering::Relaxed);
+    let w1 = Word::new(&db, "hello");
+    let w2 = Word::new(&db, "world".to_string());
+    let w3 = Word::new(&db, "hello".to_string());
+    assert!(HASHERS_BUILT.load(Ordering::Relaxed) > before);
+
+    assert_eq!(w1, w3);
+    assert_ne!(w1, w2);
+    assert_eq!(length(&db, w1), 5);
+
+    let n1 = Number::new(&db, 1);
+    let n2 = Number::new(&db, 2);
+    assert_eq!(n1, Number::new(&db, 1));
+    assert_ne!(n1, n2);
+    assert_eq!(n2.value(&db), 2);
+}
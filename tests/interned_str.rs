@@ -0,0 +1,78 @@
+//! Test the built-in interned string type `salsa::Str`.
+
+use salsa::{Database, DatabaseImpl, Setter, Str};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn identifiers(db: &dyn Database, file: File) -> Vec<Str<'_>> {
+    file.text(db)
+        .split_whitespace()
+        .map(|text| Str::new(db, text))
+        .collect()
+}
+
+#[salsa::tracked]
+fn is_keyword<'db>(db: &'db dyn Database, name: Str<'db>) -> bool {
+    let _ = db;
+    matches!(name.as_str(), "fn" | "let")
+}
+
+#[salsa::tracked]
+struct Binding<'db> {
+    name: Str<'db>,
+}
+
+#[test]
+fn interning() {
+    let db = DatabaseImpl::new();
+    let a = Str::new(&db, "a");
+    let b = Str::new(&db, "b");
+    assert_eq!(a, Str::new(&db, "a"));
+    assert_ne!(a, b);
+    assert!(a < b, "ordered by interning order");
+    assert_eq!(&*a, "a");
+    assert_eq!(format!("{a:?} {b}"), "\"a\" b");
+
+    let c = Str::new_prehashed(&db, "c", Str::prehash("c"));
+    assert_eq!(c, Str::new(&db, "c"));
+}
+
+#[test]
+fn in_tracked_functions() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "fn f".to_string());
+    let keywords: Vec<_> = identifiers(&db, file)
+        .into_iter()
+        .map(|name| is_keyword(&db, name))
+        .collect();
+    assert_eq!(keywords, [true, false]);
+
+    file.set_text(&mut db).to("let fn".to_string());
+    let names = identifiers(&db, file);
+    assert_eq!(names, [Str::new(&db, "let"), Str::new(&db, "fn")]);
+    assert!(is_keyword(&db, names[0]));
+}
+
+#[salsa::tracked]
+fn bind(db: &dyn Database, file: File) -> Vec<Binding<'_>> {
+    identifiers(db, file)
+        .into_iter()
+        .map(|name| Binding::new(db, name))
+        .collect()
+}
+
+#[test]
+fn as_field() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, "x y".to_string());
+    let names: Vec<_> = bind(&db, file)
+        .into_iter()
+        .map(|binding| binding.name(&db).to_string())
+        .collect();
+    assert_eq!(names, ["x", "y"]);
+}
@@ -0,0 +1,138 @@
+//! Test `invalidate`, which forces a tracked function to re-execute for one key,
+//! e.g. because it read some state external to salsa.
+
+mod common;
+use common::LogDatabase;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use expect_test::expect;
+use salsa::Durability;
+use test_log::test;
+
+/// State that salsa does not know about. Each test uses different sensors,
+/// as tests run concurrently.
+static EXTERNAL: [AtomicU32; 6] = [const { AtomicU32::new(0) }; 6];
+
+#[salsa::input]
+struct Sensor {
+    index: usize,
+}
+
+#[salsa::tracked]
+fn read_sensor(db: &dyn LogDatabase, sensor: Sensor) -> u32 {
+    db.push_log(format!("read_sensor({})", sensor.index(db)));
+    EXTERNAL[sensor.index(db)].load(Ordering::SeqCst) / 10
+}
+
+#[salsa::tracked]
+fn report(db: &dyn LogDatabase, sensor: Sensor) -> String {
+    db.push_log(format!("report({})", sensor.index(db)));
+    format!("sensor {}: {}", sensor.index(db), read_sensor(db, sensor))
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn LogDatabase, a: Sensor, b: Sensor) -> u32 {
+    db.push_log("sum".to_string());
+    read_sensor(db, a) + read_sensor(db, b)
+}
+
+#[test]
+fn invalidate_one_key() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Sensor::new(&db, 0);
+    let b = Sensor::new(&db, 1);
+    EXTERNAL[0].store(10, Ordering::SeqCst);
+
+    assert_eq!(report(&db, a), "sensor 0: 1");
+    assert_eq!(report(&db, b), "sensor 1: 0");
+    db.assert_logs(expect![[r#"
+        [
+            "report(0)",
+            "read_sensor(0)",
+            "report(1)",
+            "read_sensor(1)",
+        ]"#]]);
+
+    // Without invalidation, the change is not observed.
+    EXTERNAL[0].store(20, Ordering::SeqCst);
+    assert_eq!(report(&db, a), "sensor 0: 1");
+    db.assert_logs(expect!["[]"]);
+
+    // Only `a` is re-executed, along with the function depending on it.
+    read_sensor::invalidate(&mut db, a);
+    assert_eq!(report(&db, a), "sensor 0: 2");
+    assert_eq!(report(&db, b), "sensor 1: 0");
+    db.assert_logs(expect![[r#"
+        [
+            "read_sensor(0)",
+            "report(0)",
+        ]"#]]);
+
+    // Only re-executed once.
+    assert_eq!(report(&db, a), "sensor 0: 2");
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn invalidate_backdates() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Sensor::new(&db, 2);
+    let b = Sensor::new(&db, 3);
+    EXTERNAL[3].store(30, Ordering::SeqCst);
+
+    assert_eq!(sum(&db, a, b), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "sum",
+            "read_sensor(2)",
+            "read_sensor(3)",
+        ]"#]]);
+
+    // The value read changes, but not the result: `sum` is not re-executed.
+    EXTERNAL[3].store(31, Ordering::SeqCst);
+    read_sensor::invalidate(&mut db, b);
+    assert_eq!(sum(&db, a, b), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "read_sensor(3)",
+        ]"#]]);
+
+    // Also works for functions with multiple arguments.
+    sum::invalidate(&mut db, a, b);
+    assert_eq!(sum(&db, a, b), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "sum",
+        ]"#]]);
+}
+
+#[test]
+fn invalidate_high_durability() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Sensor::builder(4).durability(Durability::HIGH).new(&db);
+    EXTERNAL[4].store(40, Ordering::SeqCst);
+    assert_eq!(report(&db, a), "sensor 4: 4");
+    db.assert_logs_len(2);
+
+    EXTERNAL[4].store(50, Ordering::SeqCst);
+    read_sensor::invalidate(&mut db, a);
+    assert_eq!(report(&db, a), "sensor 4: 5");
+    db.assert_logs(expect![[r#"
+        [
+            "read_sensor(4)",
+            "report(4)",
+        ]"#]]);
+}
+
+#[test]
+fn invalidate_before_execution() {
+    let mut db = common::LoggerDatabase::default();
+    let a = Sensor::new(&db, 5);
+    read_sensor::invalidate(&mut db, a);
+    read_sensor(&db, a);
+    db.assert_logs(expect![[r#"
+        [
+            "read_sensor(5)",
+        ]"#]]);
+}
@@ -0,0 +1,74 @@
+//! Test that functions declared with `keep_stale_on_panic` keep their
+//! previous value when re-executing them panics.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, DatabaseImpl, Durability, EventKind, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked(keep_stale_on_panic)]
+fn parse(db: &dyn Database, file: File) -> Vec<String> {
+    let text = file.text(db);
+    if text.contains("crash") {
+        panic!("cannot parse {text:?}");
+    }
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    parse(db, file).len()
+}
+
+/// A database recording the messages of the `DidKeepStaleValue` events.
+fn database() -> (DatabaseImpl, Arc<Mutex<Vec<Option<String>>>>) {
+    let messages = Arc::new(Mutex::new(vec![]));
+    let db = DatabaseImpl::builder()
+        .event_sink({
+            let messages = messages.clone();
+            move |event| {
+                if let EventKind::DidKeepStaleValue { message, .. } = event.kind {
+                    messages.lock().unwrap().push(message);
+                }
+            }
+        })
+        .build();
+    (db, messages)
+}
+
+#[test]
+fn keeps_previous_value() {
+    let (mut db, messages) = database();
+    let file = File::new(&db, "a b".to_string());
+    assert_eq!(word_count(&db, file), 2);
+
+    file.set_text(&mut db).to("a b crash".to_string());
+    assert_eq!(word_count(&db, file), 2);
+    assert_eq!(parse(&db, file), vec!["a", "b"]);
+    assert_eq!(
+        *messages.lock().unwrap(),
+        vec![Some("cannot parse \"a b crash\"".to_string())]
+    );
+
+    // The function is retried in the next revision.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(word_count(&db, file), 2);
+    assert_eq!(messages.lock().unwrap().len(), 2);
+
+    file.set_text(&mut db).to("a b c".to_string());
+    assert_eq!(word_count(&db, file), 3);
+    assert_eq!(messages.lock().unwrap().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "cannot parse \"crash\"")]
+fn panics_without_previous_value() {
+    let (db, _) = database();
+    let file = File::new(&db, "crash".to_string());
+    parse(&db, file);
+}
@@ -0,0 +1,110 @@
+//! Test that `ReadGuard`s detect being used after the database was modified.
+
+use std::cell::RefCell;
+use std::panic::AssertUnwindSafe;
+
+use salsa::{Database, DatabaseImpl, ReadGuard, Setter};
+
+#[salsa::input]
+struct MyInput {
+    #[return_ref]
+    text: String,
+}
+
+thread_local! {
+    static STASH: RefCell<Option<ReadGuard<'static, str>>> = const { RefCell::new(None) };
+}
+
+#[salsa::tracked]
+fn stash_text(db: &dyn Database, input: MyInput) -> usize {
+    let guard = salsa::guard_read(db, input.text(db).as_str());
+    let len = guard.len();
+    // SAFETY: Leak detection is enabled.
+    STASH.with(|stash| *stash.borrow_mut() = Some(unsafe { guard.detach() }));
+    len
+}
+
+fn stashed_len() -> usize {
+    STASH.with(|stash| stash.borrow().as_ref().unwrap().len())
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn leaked_read_is_detected() {
+    let mut db = DatabaseImpl::new();
+    db.storage().set_leak_detection(true);
+
+    let input = MyInput::new(&db, "hello".to_string());
+    assert_eq!(stash_text(&db, input), 5);
+    assert_eq!(stashed_len(), 5);
+
+    input.set_text(&mut db).to("goodbye".to_string());
+
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(stashed_len)).unwrap_err();
+    let message = panic_message(payload);
+    assert!(message.starts_with("leaked read"), "{message}");
+    assert!(message.contains("R1"), "{message}");
+    assert!(message.contains("stash_text(Id(0))"), "{message}");
+
+    STASH.with(|stash| assert!(stash.borrow().as_ref().unwrap().is_stale()));
+}
+
+#[test]
+fn guard_outside_of_tracked_fn() {
+    let mut db = DatabaseImpl::new();
+    db.storage().set_leak_detection(true);
+
+    let input = MyInput::new(&db, "hello".to_string());
+    let guard = salsa::guard_read(&db, input.text(&db).as_str());
+    assert_eq!(&*guard, "hello");
+    assert!(!guard.is_stale());
+
+    // SAFETY: Leak detection is enabled.
+    let guard = unsafe { guard.detach() };
+    input.set_text(&mut db).to("goodbye".to_string());
+
+    assert!(guard.is_stale());
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(|| guard.len())).unwrap_err();
+    let message = panic_message(payload);
+    assert!(message.starts_with("leaked read"), "{message}");
+    assert!(!message.contains(" by `"), "{message}");
+}
+
+#[test]
+fn discard_memos_invalidates_guards() {
+    let mut db = DatabaseImpl::new();
+    db.storage().set_leak_detection(true);
+
+    let input = MyInput::new(&db, "hello".to_string());
+    stash_text(&db, input);
+    db.discard_memos();
+
+    STASH.with(|stash| assert!(stash.borrow().as_ref().unwrap().is_stale()));
+}
+
+#[test]
+fn dropping_the_database_invalidates_guards() {
+    let db = DatabaseImpl::new();
+    db.storage().set_leak_detection(true);
+
+    let input = MyInput::new(&db, "hello".to_string());
+    stash_text(&db, input);
+    drop(db);
+
+    STASH.with(|stash| assert!(stash.borrow().as_ref().unwrap().is_stale()));
+}
+
+#[test]
+fn disabled_by_default() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, "hello".to_string());
+    let guard = salsa::guard_read(&db, input.text(&db).as_str());
+    assert!(!guard.is_stale());
+    assert_eq!(format!("{guard:?}"), "\"hello\"");
+}
@@ -0,0 +1,72 @@
+//! Test that tracked functions declared with `cache_key` share their values
+//! across databases through a `MemoCacheBackend`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, DatabaseImpl, MemoCacheBackend, MemoCacheKey, Setter};
+use test_log::test;
+
+#[derive(Clone, Default)]
+struct SharedCache(Arc<Mutex<HashMap<MemoCacheKey, Vec<u8>>>>);
+
+impl MemoCacheBackend for SharedCache {
+    fn load(&self, key: &MemoCacheKey) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn store(&self, key: &MemoCacheKey, value: Vec<u8>) {
+        self.0.lock().unwrap().insert(key.clone(), value);
+    }
+}
+
+#[salsa::input]
+struct SourceFile {
+    text: String,
+}
+
+/// FNV-1a, which does not depend on the build like `DefaultHasher` may.
+fn text_fingerprint(db: &dyn Database, file: SourceFile) -> u128 {
+    file.text(db)
+        .bytes()
+        .fold(0x6c62272e07bb014262b821756295c58d, |hash, byte| {
+            (hash ^ u128::from(byte)).wrapping_mul(0x0000000001000000000000000000013b)
+        })
+}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked(cache_key = text_fingerprint)]
+fn word_count(db: &dyn Database, file: SourceFile) -> u32 {
+    EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    file.text(db).split_whitespace().count() as u32
+}
+
+#[test]
+fn execute() {
+    let cache = SharedCache::default();
+
+    let db1 = DatabaseImpl::builder().memo_cache(cache.clone()).build();
+    let file = SourceFile::new(&db1, "a b c".to_string());
+    assert_eq!(word_count(&db1, file), 3);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    // Another database with the same text loads the value instead of executing.
+    let mut db2 = DatabaseImpl::builder().memo_cache(cache.clone()).build();
+    let file = SourceFile::new(&db2, "a b c".to_string());
+    assert_eq!(word_count(&db2, file), 3);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+
+    // The fingerprint depends on the text, so changing it executes the function again.
+    file.set_text(&mut db2).to("a b".to_string());
+    assert_eq!(word_count(&db2, file), 2);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+
+    // Which stored the new value.
+    let db3 = DatabaseImpl::builder().memo_cache(cache.clone()).build();
+    let file = SourceFile::new(&db3, "a b".to_string());
+    assert_eq!(word_count(&db3, file), 2);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+    assert_eq!(cache.0.lock().unwrap().len(), 2);
+}
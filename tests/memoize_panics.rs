@@ -0,0 +1,115 @@
+//! Test that functions declared with `memoize_panics` raise their panic
+//! again, without re-executing, until their inputs change.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, DatabaseImpl, EventKind, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked(memoize_panics)]
+fn parse(db: &dyn Database, file: File) -> Vec<String> {
+    let text = file.text(db);
+    if text.contains("crash") {
+        panic!("cannot parse {text:?}");
+    }
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    parse(db, file).len()
+}
+
+#[salsa::tracked(memoize_panics)]
+fn checked_word_count(db: &dyn Database, file: File) -> usize {
+    unchecked_word_count(db, file)
+}
+
+#[salsa::tracked]
+fn unchecked_word_count(db: &dyn Database, file: File) -> usize {
+    let text = file.text(db);
+    assert!(!text.contains("crash"), "cannot count {text:?}");
+    text.split_whitespace().count()
+}
+
+/// A database recording the functions that are executed.
+fn database() -> (DatabaseImpl, Arc<Mutex<Vec<String>>>) {
+    let executed = Arc::new(Mutex::new(vec![]));
+    let db = DatabaseImpl::builder()
+        .event_sink({
+            let executed = executed.clone();
+            move |event| {
+                if let EventKind::WillExecute { database_key } = event.kind {
+                    executed.lock().unwrap().push(format!("{database_key:?}"));
+                }
+            }
+        })
+        .build();
+    (db, executed)
+}
+
+/// Returns the message of the panic raised by `f`.
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = catch_unwind(AssertUnwindSafe(f)).expect_err("expected a panic");
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn panic_is_memoized() {
+    let (mut db, executed) = database();
+    let file = File::new(&db, "a b crash".to_string());
+
+    for _ in 0..2 {
+        assert_eq!(
+            panic_message(|| {
+                word_count(&db, file);
+            }),
+            "cannot parse \"a b crash\""
+        );
+    }
+    assert_eq!(
+        std::mem::take(&mut *executed.lock().unwrap()),
+        ["word_count(Id(0))", "parse(Id(0))", "word_count(Id(0))"]
+    );
+
+    // The panic is memoized until the input changes.
+    file.set_text(&mut db).to("a b".to_string());
+    assert_eq!(word_count(&db, file), 2);
+    assert_eq!(
+        std::mem::take(&mut *executed.lock().unwrap()),
+        ["word_count(Id(0))", "parse(Id(0))"]
+    );
+}
+
+#[test]
+fn panic_through_other_query_is_not_memoized() {
+    let (db, executed) = database();
+    let file = File::new(&db, "crash".to_string());
+
+    for _ in 0..2 {
+        assert_eq!(
+            panic_message(|| {
+                checked_word_count(&db, file);
+            }),
+            "cannot count \"crash\""
+        );
+    }
+    assert_eq!(
+        std::mem::take(&mut *executed.lock().unwrap()),
+        [
+            "checked_word_count(Id(0))",
+            "unchecked_word_count(Id(0))",
+            "checked_word_count(Id(0))",
+            "unchecked_word_count(Id(0))",
+        ]
+    );
+}
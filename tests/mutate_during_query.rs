@@ -0,0 +1,104 @@
+//! Test that mutating the database from within a query panics with a message naming
+//! the query, rather than deadlocking while waiting for the query's handle to be dropped.
+
+use std::cell::RefCell;
+
+use salsa::{Database, DatabaseImpl, Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+thread_local! {
+    /// Another handle on the database under test, which the queries below mutate.
+    static HANDLE: RefCell<Option<DatabaseImpl>> = const { RefCell::new(None) };
+}
+
+#[salsa::tracked]
+fn set_field(db: &dyn Database, input: MyInput) -> u32 {
+    HANDLE.with_borrow_mut(|handle| input.set_field(handle.as_mut().unwrap()).to(1));
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn synthetic_write(db: &dyn Database, input: MyInput) -> u32 {
+    HANDLE.with_borrow_mut(|handle| handle.as_mut().unwrap().synthetic_write(Durability::LOW));
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    set_field(db, input)
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot call `MyInput::set_field` while the query `set_field(Id(0))` \
+                           is executing on this thread"
+)]
+fn set_field_in_query() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    HANDLE.set(Some(db.clone()));
+    set_field(&db, input);
+}
+
+/// Only the outermost query executing through another handle is known.
+#[test]
+#[should_panic(
+    expected = "cannot call `MyInput::set_field` while the query `outer(Id(0))` \
+                           is executing on this thread"
+)]
+fn set_field_in_nested_query() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    HANDLE.set(Some(db.clone()));
+    outer(&db, input);
+}
+
+#[test]
+#[should_panic(
+    expected = "cannot mutate the database while the query `synthetic_write(Id(0))` \
+                           is executing on this thread"
+)]
+fn synthetic_write_in_query() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    HANDLE.set(Some(db.clone()));
+    synthetic_write(&db, input);
+}
+
+#[salsa::tracked]
+fn set_other_field(db: &dyn Database, input: MyInput, other: MyInput) -> u32 {
+    HANDLE.with_borrow_mut(|handle| other.set_field(handle.as_mut().unwrap()).to(1));
+    input.field(db)
+}
+
+#[test]
+fn set_field_of_other_database_in_query() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+
+    let other_db = DatabaseImpl::new();
+    let other = MyInput::new(&other_db, 0);
+    HANDLE.set(Some(other_db));
+    assert_eq!(set_other_field(&db, input, other), 0);
+    HANDLE.with_borrow(|handle| assert_eq!(other.field(handle.as_ref().unwrap()), 1));
+}
+
+#[test]
+fn set_field_after_query() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    assert_eq!(read_field(&db, input), 0);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(read_field(&db, input), 2);
+}
+
+#[salsa::tracked]
+fn read_field(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db)
+}
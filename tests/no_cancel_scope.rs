@@ -0,0 +1,23 @@
+//! Test that `Database::no_cancel_scope` is transparent when no write is pending.
+
+use salsa::{Database, DatabaseImpl};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    salsa::no_cancel_scope(db, || input.field(db) * 2)
+}
+
+#[test]
+fn scope_returns_result() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 21);
+    assert_eq!(db.no_cancel_scope(|| double(&db, input)), 42);
+
+    let nested = db.no_cancel_scope(|| db.no_cancel_scope(|| input.field(&db)));
+    assert_eq!(nested, 21);
+}
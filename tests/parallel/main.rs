@@ -1,9 +1,18 @@
 mod setup;
 
+mod parallel_block_warning;
+mod parallel_blocking_strategy;
 mod parallel_cancellation;
+mod parallel_cancellation_checks;
 mod parallel_cycle_all_recover;
 mod parallel_cycle_mid_recover;
 mod parallel_cycle_none_recover;
 mod parallel_cycle_one_recover;
+mod parallel_deadlock;
+mod parallel_join;
 mod parallel_map;
+mod parallel_no_cancel_scope;
+mod parallel_preemption;
+mod parallel_priority;
+mod parallel_shared_memo;
 mod signal;
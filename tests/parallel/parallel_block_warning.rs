@@ -0,0 +1,79 @@
+//! Test that a thread waiting for a query executing on another thread periodically
+//! reports that it is still waiting, without giving up.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use salsa::{Database, EventKind};
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn Database, input: MyInput) -> i32 {
+    STARTED.store(true, Ordering::SeqCst);
+    while !RELEASED.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    input.field(db)
+}
+
+#[test]
+fn execute() {
+    let waits = Arc::new(Mutex::new(vec![]));
+    let db = salsa::DatabaseImpl::builder()
+        .block_warning_interval(Some(Duration::from_millis(10)))
+        .event_sink({
+            let waits = waits.clone();
+            move |event| {
+                if let EventKind::StillBlockedOn {
+                    other_thread_id,
+                    database_key,
+                    waited,
+                } = event.kind
+                {
+                    waits
+                        .lock()
+                        .unwrap()
+                        .push((other_thread_id, database_key, waited));
+                }
+            }
+        })
+        .build();
+    let input = MyInput::new(&db, 22);
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || slow(&db, input)
+    });
+    while !STARTED.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || slow(&db, input)
+    });
+    while waits.lock().unwrap().len() < 3 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    RELEASED.store(true, Ordering::SeqCst);
+
+    let thread_b_id = thread_b.thread().id();
+    assert_eq!(thread_a.join().unwrap(), 22);
+    assert_eq!(thread_b.join().unwrap(), 22);
+
+    let waits = waits.lock().unwrap();
+    assert!(waits.iter().all(|&(other_thread_id, database_key, _)| {
+        other_thread_id == thread_b_id
+            && db.ingredient_debug_name(database_key.ingredient_index()) == "slow"
+    }));
+    assert!(waits.windows(2).all(|w| w[0].2 < w[1].2));
+    assert!(waits[0].2 >= Duration::from_millis(10));
+}
@@ -0,0 +1,123 @@
+//! Test that a `BlockingStrategy` is invoked instead of parking the thread
+//! when a query blocks on another thread.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use salsa::plumbing::HasStorage;
+use salsa::{BlockingStrategy, Database, DatabaseKeyIndex, Waiter};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    input.field(db) * 2
+}
+
+/// Records the queries blocked on and polls the waiter until they complete.
+#[derive(Clone, Default)]
+struct Polling {
+    blocked_on: Arc<Mutex<Vec<DatabaseKeyIndex>>>,
+}
+
+impl BlockingStrategy for Polling {
+    fn block(&self, waiter: &mut Waiter<'_>) {
+        self.blocked_on.lock().unwrap().push(waiter.database_key());
+        while !waiter.is_done() {
+            // A strategy could back off here.
+            waiter.wait_timeout(Duration::from_millis(1));
+        }
+    }
+}
+
+#[salsa::tracked]
+fn constant(_db: &dyn salsa::Database) -> i32 {
+    1
+}
+
+/// Invokes a tracked function while waiting, as a task run by an executor might.
+struct Reentrant;
+
+impl BlockingStrategy for Reentrant {
+    fn block(&self, _waiter: &mut Waiter<'_>) {
+        salsa::with_attached_database(constant);
+    }
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1
+// signal stage 1             wait for stage 1 (blocks)
+// wait for stage 2 (blocks)  (unblocked)
+// |                          a1, blocks on thread A: signals stage 2
+// (unblocked)                strategy polls
+// a1 completes               |
+//                            (unblocked), reads result of a1
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+    let strategy = Polling::default();
+    db.storage().set_blocking_strategy(strategy.clone());
+
+    let input = MyInput::new(&db, 21);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            a1(&db, input)
+        }
+    });
+
+    assert_eq!(thread_a.join().unwrap(), 42);
+    assert_eq!(thread_b.join().unwrap(), 42);
+
+    let blocked_on = strategy.blocked_on.lock().unwrap();
+    assert_eq!(blocked_on.len(), 1);
+    assert_eq!(
+        db.ingredient_debug_name(blocked_on[0].ingredient_index()),
+        "a1"
+    );
+}
+
+#[test]
+fn tracked_function_within_block() {
+    let db = Knobs::default();
+    db.storage().set_blocking_strategy(Reentrant);
+
+    let input = MyInput::new(&db, 21);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            a1(&db, input)
+        }
+    });
+
+    assert_eq!(thread_a.join().unwrap(), 42);
+    let panic = thread_b.join().unwrap_err();
+    assert!(panic.downcast_ref::<&str>().is_some_and(|message| message
+        .starts_with("cannot invoke tracked functions within `BlockingStrategy::block`")));
+}
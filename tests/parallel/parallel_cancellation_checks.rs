@@ -0,0 +1,85 @@
+//! Test `Database::check_cancelled` and tracked functions declared
+//! with `auto_cancel_check`.
+
+use std::panic::AssertUnwindSafe;
+
+use salsa::{Cancelled, Setter};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn check_cancelled(db: &dyn KnobsDatabase, input: MyInput) -> bool {
+    db.signal(1);
+    db.wait_for(2);
+    let _ = input.field(db);
+    db.check_cancelled().is_err()
+}
+
+#[salsa::tracked]
+fn manual(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+
+    // Reading an input does not check for cancellation.
+    input.field(db)
+}
+
+#[salsa::tracked(auto_cancel_check)]
+fn automatic(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+
+    // Reading an input checks for cancellation: this unwinds.
+    input.field(db)
+}
+
+// Thread A                   Main thread
+// --------                   -----------
+// query                      wait for stage 1 (blocks)
+// signal stage 1             (unblocked)
+// wait for stage 2 (blocks)  set input, triggers cancellation
+// |                          triggering cancellation sends stage 2
+// (unblocked)                (blocks until thread A drops its handle)
+// reads input
+fn run<T: Send + 'static>(query: impl FnOnce(&Knobs, MyInput) -> T + Send + 'static) -> T {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || query(&db, input)
+    });
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(2);
+
+    thread_a.join().unwrap()
+}
+
+#[test]
+fn returns_pending_write() {
+    assert!(run(|db, input| check_cancelled(db, input)));
+}
+
+#[test]
+fn reads_are_not_checked() {
+    assert_eq!(run(|db, input| manual(db, input)), 1);
+}
+
+#[test]
+fn reads_are_checked() {
+    let result = run(|db, input| Cancelled::catch(AssertUnwindSafe(|| automatic(db, input))));
+    expect_test::expect![[r#"
+        Err(
+            PendingWrite,
+        )
+    "#]]
+    .assert_debug_eq(&result);
+}
@@ -0,0 +1,87 @@
+//! Test that a thread waiting for a query past the deadlock timeout unwinds
+//! with a `Deadlock`, breaking a deadlock that involves a user lock.
+
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use salsa::plumbing::HasStorage;
+use salsa::{Database, Deadlock};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    let _guard = LOCK.lock();
+    input.field(db)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+//                            a1
+// wait for stage 1 (blocks)  signal stage 1
+// (unblocked)                wait for stage 2 (blocks)
+// acquire LOCK               |
+// a1, blocks on thread B:    |
+//   signals stage 2          (unblocked)
+// |                          acquire LOCK (blocks)
+// times out, unwinds with    |
+//   `Deadlock`, releasing    |
+//   LOCK                     (unblocked)
+//                            a1 completes
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+    db.storage()
+        .set_deadlock_timeout(Some(Duration::from_millis(100)));
+
+    let input = MyInput::new(&db, 22);
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            Deadlock::catch(AssertUnwindSafe(|| {
+                let _guard = LOCK.lock();
+                a1(&db, input)
+            }))
+        }
+    });
+
+    let thread_a_id = thread_a.thread().id();
+    let thread_b_id = thread_b.thread().id();
+
+    let deadlock = thread_a.join().unwrap().unwrap_err();
+    assert_eq!(thread_b.join().unwrap(), 22);
+
+    assert_eq!(deadlock.timeout, Duration::from_millis(100));
+    assert_eq!(deadlock.waits.len(), 1);
+    let wait = deadlock.waits[0];
+    assert_eq!(wait.thread_id, thread_a_id);
+    assert_eq!(wait.other_thread_id, thread_b_id);
+    assert_eq!(
+        db.ingredient_debug_name(wait.database_key.ingredient_index()),
+        "a1"
+    );
+    assert!(deadlock.to_string().starts_with("possible deadlock"));
+
+    // Once the deadlock is broken, the result of `a1` is available to everyone.
+    assert_eq!(a1(&db, input), 22);
+}
@@ -0,0 +1,81 @@
+//! Test `salsa::join`: the reads of both closures are dependencies of the calling query.
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct Pair {
+    left: String,
+    right: String,
+}
+
+#[salsa::tracked]
+struct Word<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn left_len(db: &dyn Database, pair: Pair) -> usize {
+    pair.left(db).len()
+}
+
+#[salsa::tracked]
+fn right_len(db: &dyn Database, pair: Pair) -> usize {
+    pair.right(db).len()
+}
+
+#[salsa::tracked]
+fn lens(db: &dyn Database, pair: Pair) -> (usize, usize) {
+    salsa::join(db, |db| left_len(db, pair), |db| right_len(db, pair))
+}
+
+#[salsa::tracked]
+fn right_field(db: &dyn Database, pair: Pair) -> (usize, String) {
+    salsa::join(db, |_| 0, |db| pair.right(db))
+}
+
+#[salsa::tracked]
+fn create_word(db: &dyn Database, pair: Pair) -> usize {
+    let (_, len) = salsa::join(
+        db,
+        |_| (),
+        |db| Word::new(db, pair.right(db)).text(db).len(),
+    );
+    len
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn reads_of_both_closures_are_dependencies() {
+    let mut db = salsa::DatabaseImpl::new();
+    let pair = Pair::new(&db, "a".to_string(), "bc".to_string());
+    assert_eq!(lens(&db, pair), (1, 2));
+    assert_eq!(right_field(&db, pair), (0, "bc".to_string()));
+
+    pair.set_right(&mut db).to("def".to_string());
+    assert_eq!(lens(&db, pair), (1, 3));
+    assert_eq!(right_field(&db, pair), (0, "def".to_string()));
+
+    pair.set_left(&mut db).to("ghij".to_string());
+    assert_eq!(lens(&db, pair), (4, 3));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn outside_of_tracked_function() {
+    let db = salsa::DatabaseImpl::new();
+    let pair = Pair::new(&db, "a".to_string(), "bc".to_string());
+    let db: &dyn Database = &db;
+    assert_eq!(
+        salsa::join(db, |db| left_len(db, pair), |db| right_len(db, pair)),
+        (1, 2)
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[should_panic(expected = "`salsa::join` closures can only read the database")]
+fn tracked_struct_in_closure() {
+    let db = salsa::DatabaseImpl::new();
+    let pair = Pair::new(&db, "a".to_string(), "bc".to_string());
+    create_word(&db, pair);
+}
@@ -0,0 +1,77 @@
+//! Test that cancellation is deferred until a `no_cancel_scope` exits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use salsa::Cancelled;
+use salsa::Setter;
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+static SCOPE_COMPLETED: AtomicBool = AtomicBool::new(false);
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    let value = salsa::no_cancel_scope(db, || {
+        db.signal(1);
+        db.wait_for(2);
+
+        // The write is pending, but we are not cancelled (yet).
+        let value = b1(db, input);
+        SCOPE_COMPLETED.store(true, Ordering::SeqCst);
+        value
+    });
+    panic!("should have been cancelled after the scope, got {value}")
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db) * 2
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1
+// enter no_cancel_scope      wait for stage 1 (blocks)
+// signal stage 1             (unblocked)
+//                            set input, triggers cancellation
+// wait for stage 2 (blocks)  triggering cancellation sends stage 2
+// |                          (blocks until thread A drops its handle)
+// (unblocked)
+// b1, not cancelled
+// exit scope, cancelled
+
+#[test]
+fn execute() {
+    let mut db = Knobs::default();
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(2);
+
+    let cancelled = thread_a
+        .join()
+        .unwrap_err()
+        .downcast::<Cancelled>()
+        .unwrap();
+
+    expect_test::expect![[r#"
+        PendingWrite
+    "#]]
+    .assert_debug_eq(&cancelled);
+    assert!(SCOPE_COMPLETED.load(Ordering::SeqCst));
+
+    assert_eq!(b1(&db, input), 4);
+}
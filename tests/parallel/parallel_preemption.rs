@@ -0,0 +1,75 @@
+//! Test that a low-priority thread is preempted when a thread
+//! of higher priority waits for the query it executes.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::{Cancelled, Database, Priority};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    db.signal(1);
+    db.wait_for(2);
+
+    // Checks for cancellation: unwinds if preempted.
+    b1(db, input)
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db) * 2
+}
+
+// Thread B (low)             Thread A (high)
+// --------------             ---------------
+// a1                         wait for stage 1 (blocks)
+// signal stage 1             (unblocked)
+// wait for stage 2 (blocks)  a1, blocks on thread B: signals stage 2
+// (unblocked)                |
+// b1, preempted              (unblocked)
+//                            a1, executes it
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.set_query_priority(Priority::Low);
+            Cancelled::catch(AssertUnwindSafe(|| a1(&db, input)))
+        }
+    });
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.set_query_priority(Priority::High);
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            a1(&db, input)
+        }
+    });
+
+    assert_eq!(thread_a.join().unwrap(), 2);
+    expect_test::expect![[r#"
+        Err(
+            Preempted,
+        )
+    "#]]
+    .assert_debug_eq(&thread_b.join().unwrap());
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,73 @@
+//! Test that a thread defers cancellation while a thread of
+//! higher priority waits for the query it executes.
+
+use salsa::{Database, Priority, Setter};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(3);
+
+    // The write is pending, but a high-priority thread waits for us.
+    b1(db, input)
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db) * 2
+}
+
+// Thread B (normal)          Thread A (high)            Main thread
+// --------------             ---------------            -----------
+// a1                         wait for stage 1 (blocks)  wait for stage 2 (blocks)
+// signal stage 1             (unblocked)                |
+// wait for stage 3 (blocks)  a1, blocks on thread B:    |
+// |                            signals stage 2          (unblocked)
+// |                          |                          set input, triggers cancellation
+// |                          |                          triggering cancellation sends stage 3
+// (unblocked)                |                          (blocks until threads drop their handles)
+// b1, not cancelled          |                          |
+// a1 completes               (unblocked)                |
+//                            reads result of a1         (unblocked)
+
+#[test]
+fn execute() {
+    let mut db = Knobs::default();
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.set_query_priority(Priority::Normal);
+            a1(&db, input)
+        }
+    });
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.set_query_priority(Priority::High);
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            a1(&db, input)
+        }
+    });
+
+    db.wait_for(2);
+    db.signal_on_did_cancel.store(3);
+    input.set_field(&mut db).to(2);
+
+    assert_eq!(thread_a.join().unwrap(), 2);
+    assert_eq!(thread_b.join().unwrap(), 2);
+
+    assert_eq!(a1(&db, input), 4);
+}
@@ -0,0 +1,56 @@
+//! Test that a thread blocked on a query receives the value computed
+//! by the thread that held the claim.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(return_ref, broadcast)]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> Vec<i32> {
+    EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    db.signal(1);
+    db.wait_for(2);
+    vec![input.field(db); 3]
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1
+// signal stage 1             wait for stage 1 (blocks)
+// wait for stage 2 (blocks)  (unblocked)
+// |                          a1, blocks on thread A: signals stage 2
+// (unblocked)                |
+// a1 completes, shares memo  (unblocked), receives the memo
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+
+    let input = MyInput::new(&db, 7);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input).clone()
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.knobs().signal_on_will_block.store(2);
+            a1(&db, input).clone()
+        }
+    });
+
+    assert_eq!(thread_a.join().unwrap(), vec![7, 7, 7]);
+    assert_eq!(thread_b.join().unwrap(), vec![7, 7, 7]);
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 1);
+}
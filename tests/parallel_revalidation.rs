@@ -0,0 +1,147 @@
+//! Test that memos are revalidated correctly when parallel revalidation is enabled.
+
+mod common;
+
+use common::{LogDatabase, LoggerDatabase};
+use expect_test::expect;
+use salsa::plumbing::HasStorage;
+use salsa::{Database, Durability, Setter};
+
+#[salsa::input]
+struct Item {
+    value: u32,
+}
+
+#[salsa::input]
+struct Items {
+    #[return_ref]
+    items: Vec<Item>,
+}
+
+#[salsa::tracked]
+fn leaf(db: &dyn LogDatabase, item: Item) -> u32 {
+    db.push_log(format!("leaf({})", item.value(db)));
+    item.value(db) * 2
+}
+
+#[salsa::tracked]
+fn total(db: &dyn LogDatabase, items: Items) -> u32 {
+    items.items(db).iter().map(|&item| leaf(db, item)).sum()
+}
+
+fn setup(db: &LoggerDatabase) -> Items {
+    let items = (0..64)
+        .map(|value| Item::builder(value).durability(Durability::HIGH).new(db))
+        .collect();
+    Items::builder(items).durability(Durability::HIGH).new(db)
+}
+
+#[test]
+fn revalidate_after_high_durability_write() {
+    let mut db = LoggerDatabase::default();
+    db.storage().set_revalidation_threads(4);
+
+    let items = setup(&db);
+    assert_eq!(total(&db, items), 4032);
+    db.assert_logs_len(64);
+
+    // Everything must be deep-verified, but nothing re-executes.
+    db.synthetic_write(Durability::HIGH);
+    assert_eq!(total(&db, items), 4032);
+    db.assert_logs_len(0);
+
+    // Only the leaf that changed re-executes.
+    let item = items.items(&db)[3];
+    item.set_value(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(100);
+    assert_eq!(total(&db, items), 4226);
+    db.assert_logs(expect![[r#"
+        [
+            "leaf(100)",
+        ]"#]]);
+}
+
+#[test]
+fn disable_revalidation_threads() {
+    let mut db = LoggerDatabase::default();
+    db.storage().set_revalidation_threads(2);
+
+    let items = setup(&db);
+    assert_eq!(total(&db, items), 4032);
+    db.assert_logs_len(64);
+
+    db.storage().set_revalidation_threads(0);
+    db.synthetic_write(Durability::HIGH);
+    assert_eq!(total(&db, items), 4032);
+    db.assert_logs_len(0);
+}
+
+#[test]
+fn revalidate_after_first_input_changed() {
+    let mut db = LoggerDatabase::default();
+    db.storage().set_revalidation_threads(4);
+
+    let items = setup(&db);
+    assert_eq!(total(&db, items), 4032);
+    db.assert_logs_len(64);
+
+    // `total` first reads the list, which changed: the leaves queued for
+    // prefetching are abandoned, and the dropped item's leaf is not needed.
+    let mut list = items.items(&db).clone();
+    let dropped = list.remove(3);
+    items
+        .set_items(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(list);
+    dropped
+        .set_value(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(100);
+    assert_eq!(total(&db, items), 4026);
+}
+
+#[salsa::input]
+struct Gate {
+    open: bool,
+}
+
+/// Takes a while to execute, giving revalidation threads time to verify the leaves.
+#[salsa::tracked]
+fn is_open(db: &dyn LogDatabase, gate: Gate) -> bool {
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    gate.open(db)
+}
+
+#[salsa::tracked]
+fn gated_total(db: &dyn LogDatabase, gate: Gate, items: Items) -> u32 {
+    if !is_open(db, gate) {
+        return 0;
+    }
+    items.items(db).iter().map(|&item| leaf(db, item)).sum()
+}
+
+#[test]
+fn revalidation_threads_do_not_execute_queries() {
+    let mut db = LoggerDatabase::default();
+    db.storage().set_revalidation_threads(4);
+
+    let gate = Gate::builder(true).durability(Durability::HIGH).new(&db);
+    let items = setup(&db);
+    assert_eq!(gated_total(&db, gate, items), 4032);
+    db.assert_logs_len(64);
+
+    // The leaves changed, but are not read once the gate is closed: while the gate
+    // is verified, revalidation threads find them changed without executing them.
+    for &item in items.items(&db).clone().iter() {
+        let value = item.value(&db);
+        item.set_value(&mut db)
+            .with_durability(Durability::HIGH)
+            .to(value + 1);
+    }
+    gate.set_open(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(false);
+    assert_eq!(gated_total(&db, gate, items), 0);
+    db.assert_logs_len(0);
+}
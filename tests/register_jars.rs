@@ -0,0 +1,84 @@
+//! Test registering the ingredients of jar groups ahead of their first use.
+
+use salsa::plumbing::Ingredient;
+use salsa::{Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Config {
+    verbose: bool,
+}
+
+#[salsa::interned]
+struct Word<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    let words: Vec<Word<'_>> = file
+        .text(db)
+        .split_whitespace()
+        .map(|text| Word::new(db, text.to_string()))
+        .collect();
+    words.len()
+}
+
+/// A group as a plugin would define it.
+struct WordCountPlugin;
+
+impl salsa::JarGroup for WordCountPlugin {
+    fn register(registry: &salsa::Registry<'_>) {
+        // Registering `word_count` also registers `File`.
+        registry.add::<word_count>().add::<Word<'static>>();
+    }
+}
+
+#[test]
+fn indices_follow_registration_order() {
+    let db1 = DatabaseImpl::new()
+        .with_jar::<Config>()
+        .with_jar::<WordCountPlugin>();
+    let db2 = DatabaseImpl::new()
+        .with_jar::<Config>()
+        .with_jar::<WordCountPlugin>();
+
+    // Use the items in different orders.
+    let file = File::new(&db1, "a b c".to_string());
+    assert_eq!(word_count(&db1, file), 3);
+    Config::new(&db1, true);
+
+    Config::new(&db2, false);
+    Word::new(&db2, "a".to_string());
+
+    assert_eq!(
+        File::ingredient(&db1).ingredient_index(),
+        File::ingredient(&db2).ingredient_index(),
+    );
+    assert_eq!(
+        Config::ingredient(&db1).ingredient_index(),
+        Config::ingredient(&db2).ingredient_index(),
+    );
+    assert!(
+        Config::ingredient(&db1).ingredient_index() < File::ingredient(&db1).ingredient_index()
+    );
+}
+
+#[test]
+fn registering_twice_has_no_effect() {
+    let db = DatabaseImpl::new()
+        .with_jar::<WordCountPlugin>()
+        .with_jar::<File>()
+        .with_jar::<WordCountPlugin>();
+    let index = File::ingredient(&db).ingredient_index();
+    db.storage().register::<File>();
+    assert_eq!(File::ingredient(&db).ingredient_index(), index);
+
+    let file = File::new(&db, "hello world".to_string());
+    assert_eq!(word_count(&db, file), 2);
+}
@@ -0,0 +1,65 @@
+//! Test that input changes are recorded in the revision log.
+
+use expect_test::expect;
+use salsa::plumbing::ZalsaDatabase;
+use salsa::{Database, DatabaseImpl, Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+    other: u32,
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1, 2);
+    input.set_field(&mut db).to(3);
+    assert!(db.revision_log().is_empty());
+}
+
+#[test]
+fn records_changes() {
+    let mut db = DatabaseImpl::new();
+    db.storage().set_revision_log_capacity(16);
+
+    let a = MyInput::new(&db, 1, 2);
+    let b = MyInput::new(&db, 1, 2);
+    a.set_field(&mut db).to(3);
+    let r1 = db.zalsa().current_revision();
+    b.set_other(&mut db).with_durability(Durability::HIGH).to(4);
+    a.set_other(&mut db).to(5);
+
+    db.attach(|db| {
+        let changes: Vec<_> = db
+            .revision_log()
+            .into_iter()
+            .filter(|change| change.revision > r1)
+            .map(|change| format!("{:?} ({:?})", change.key, change.durability))
+            .collect();
+        expect![[r#"
+            [
+                "other(Id(1)) (Durability(2))",
+                "other(Id(0)) (Durability(0))",
+            ]
+        "#]]
+        .assert_debug_eq(&changes);
+    });
+}
+
+#[test]
+fn bounded_capacity() {
+    let mut db = DatabaseImpl::new();
+    db.storage().set_revision_log_capacity(2);
+
+    let input = MyInput::new(&db, 0, 0);
+    for i in 1..=5 {
+        input.set_field(&mut db).to(i);
+    }
+
+    let log = db.revision_log();
+    assert_eq!(log.len(), 2);
+    assert!(log[0].revision < log[1].revision);
+    assert_eq!(log[1].revision, db.zalsa().current_revision());
+}
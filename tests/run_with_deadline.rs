@@ -0,0 +1,66 @@
+//! Test that `run_with_deadline` unwinds with `Cancelled::DeadlineExceeded`
+//! at the first cancellation checkpoint after the deadline.
+
+use std::time::{Duration, Instant};
+
+use salsa::{Cancelled, Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn Database, input: MyInput) -> u32 {
+    loop {
+        db.unwind_if_revision_cancelled();
+        std::thread::sleep(Duration::from_millis(1));
+        if input.field(db) == 0 {
+            return 0;
+        }
+    }
+}
+
+#[salsa::tracked]
+fn fast(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) + 1
+}
+
+#[test]
+fn exceeded() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let deadline = Instant::now() + Duration::from_millis(20);
+    let result = Cancelled::catch(|| db.run_with_deadline(deadline, || slow(&db, input)));
+    assert!(matches!(result, Err(Cancelled::DeadlineExceeded { .. })));
+    assert!(Instant::now() >= deadline);
+
+    // The deadline no longer applies once the call returned.
+    assert_eq!(fast(&db, input), 2);
+}
+
+#[test]
+fn met() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    assert_eq!(db.run_with_deadline(deadline, || fast(&db, input)), 2);
+}
+
+#[test]
+fn nested_earliest_applies() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let result = Cancelled::catch(|| {
+        db.run_with_deadline(Instant::now(), || {
+            db.run_with_deadline(Instant::now() + Duration::from_secs(60), || {
+                fast(&db, input)
+            })
+        })
+    });
+    assert!(matches!(result, Err(Cancelled::DeadlineExceeded { .. })));
+}
@@ -0,0 +1,66 @@
+//! Test that `run_with_fuel` bounds the number of executed queries
+//! and unwinds with `Cancelled::FuelExhausted` beyond the budget.
+
+use salsa::{Cancelled, Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn exhausted() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let result = Cancelled::catch(|| db.run_with_fuel(1, || outer(&db, input)));
+    assert!(matches!(result, Err(Cancelled::FuelExhausted { .. })));
+
+    // `inner` was not executed, so executing both needs two units of fuel.
+    assert_eq!(db.run_with_fuel(2, || outer(&db, input)), 3);
+}
+
+#[test]
+fn validation_consumes_no_fuel() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(outer(&db, input), 3);
+
+    // Both memos are still valid in the new revision.
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(db.run_with_fuel(0, || outer(&db, input)), 3);
+
+    input.set_field(&mut db).to(2);
+    let result = Cancelled::catch(|| db.run_with_fuel(1, || outer(&db, input)));
+    assert!(matches!(result, Err(Cancelled::FuelExhausted { .. })));
+
+    // Without a budget, the queries execute as usual.
+    assert_eq!(outer(&db, input), 5);
+}
+
+#[test]
+fn nested() {
+    let db = DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+
+    let result = Cancelled::catch(|| {
+        db.run_with_fuel(3, || {
+            assert_eq!(db.run_with_fuel(2, || outer(&db, a)), 3);
+            // Only one unit of fuel is left for the two queries.
+            outer(&db, b)
+        })
+    });
+    assert!(matches!(result, Err(Cancelled::FuelExhausted { .. })));
+}
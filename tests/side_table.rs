@@ -0,0 +1,184 @@
+//! Test that entries of a `SideTable` are evicted along with
+//! the memoized values of the queries they are keyed on.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database as _, DatabaseKeyIndex, Durability, Event, EventKind, Setter, SideTable};
+
+#[salsa::db]
+trait Db: salsa::Database {
+    fn notes(&self) -> &SideTable<String>;
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    notes: SideTable<String>,
+    executed: Arc<Mutex<Vec<DatabaseKeyIndex>>>,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let storage = salsa::Storage::default();
+        let notes = storage.side_table();
+        Self {
+            storage,
+            notes,
+            executed: Default::default(),
+        }
+    }
+}
+
+impl Database {
+    fn take_executed(&self) -> Vec<DatabaseKeyIndex> {
+        std::mem::take(&mut *self.executed.lock().unwrap())
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: &dyn Fn() -> Event) {
+        if let EventKind::WillExecute { database_key } = event().kind {
+            self.executed.lock().unwrap().push(database_key);
+        }
+    }
+}
+
+#[salsa::db]
+impl Db for Database {
+    fn notes(&self) -> &SideTable<String> {
+        &self.notes
+    }
+}
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn check(db: &dyn Db, file: File) -> usize {
+    let text = file.text(db);
+    db.notes()
+        .insert_for_active_query(db, format!("checked {text:?}"));
+    text.len()
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    index: usize,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Db, file: File) -> Vec<Item<'_>> {
+    (0..file.text(db).len())
+        .map(|index| Item::new(db, index))
+        .collect()
+}
+
+#[salsa::tracked]
+fn check_item<'db>(db: &'db dyn Db, item: Item<'db>) -> usize {
+    db.notes().insert_for_active_query(db, "item".to_string());
+    item.index(db)
+}
+
+#[salsa::tracked]
+fn check_items(db: &dyn Db, file: File) -> usize {
+    items(db, file)
+        .into_iter()
+        .map(|item| check_item(db, item))
+        .sum()
+}
+
+#[salsa::tracked(lru = 1)]
+fn check_lru(db: &dyn Db, file: File) -> usize {
+    db.notes().insert_for_active_query(db, "lru".to_string());
+    file.text(db).len()
+}
+
+#[test]
+fn evicted_on_reexecution() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+
+    check(&db, a);
+    check(&db, b);
+    let [check_a, check_b] = db.take_executed()[..] else {
+        panic!("expected two executions")
+    };
+    assert_eq!(db.notes.len(), 2);
+    assert_eq!(db.notes.get(check_a).as_deref(), Some("checked \"a\""));
+
+    // A value inserted from outside of the query is evicted once it re-executes.
+    db.notes.insert(check_b, "stale".to_string());
+    a.set_text(&mut db).to("aa".to_string());
+    b.set_text(&mut db).to("bb".to_string());
+    assert_eq!(db.notes.get(check_b).as_deref(), Some("stale"));
+
+    check(&db, a);
+    check(&db, b);
+    assert_eq!(db.take_executed(), [check_a, check_b]);
+    assert_eq!(db.notes.get(check_a).as_deref(), Some("checked \"aa\""));
+    assert_eq!(db.notes.get(check_b).as_deref(), Some("checked \"bb\""));
+}
+
+#[test]
+fn kept_while_memo_is_valid() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a".to_string());
+
+    check(&db, a);
+    let [check_a] = db.take_executed()[..] else {
+        panic!("expected one execution")
+    };
+
+    db.synthetic_write(Durability::LOW);
+    check(&db, a);
+    assert!(db.take_executed().is_empty());
+    assert_eq!(db.notes.get(check_a).as_deref(), Some("checked \"a\""));
+}
+
+#[test]
+fn evicted_with_lru() {
+    let db = Database::default();
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+
+    check_lru(&db, a);
+    check_lru(&db, b);
+    let [check_a, check_b] = db.take_executed()[..] else {
+        panic!("expected two executions")
+    };
+
+    // Using `b` pushed the value of `a` out of the LRU.
+    assert!(!db.notes.contains_key(check_a));
+    assert!(db.notes.contains_key(check_b));
+}
+
+#[test]
+fn evicted_with_deleted_struct() {
+    let mut db = Database::default();
+    let a = File::new(&db, "abc".to_string());
+
+    assert_eq!(check_items(&db, a), 3);
+    assert_eq!(db.notes.len(), 3);
+
+    // The last item is no longer created, deleting it and its memos.
+    a.set_text(&mut db).to("ab".to_string());
+    assert_eq!(check_items(&db, a), 1);
+    assert_eq!(db.notes.len(), 2);
+}
+
+#[test]
+fn evicted_with_discard_memos() {
+    let mut db = Database::default();
+    let a = File::new(&db, "a".to_string());
+
+    check(&db, a);
+    assert_eq!(db.notes.len(), 1);
+
+    db.discard_memos();
+    assert!(db.notes.is_empty());
+}
@@ -47,3 +47,15 @@ fn debug() {
         expected.assert_eq(&actual);
     });
 }
+
+#[test]
+fn set() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input1 = MyInput::set(&mut db, 3, 4);
+    assert_eq!(MyInput::get(&db), input1);
+
+    let input2 = MyInput::set(&mut db, 5, 6);
+    assert_eq!(input1, input2);
+    assert_eq!(input1.field(&db), 5);
+    assert_eq!(input1.id_field(&db), 6);
+}
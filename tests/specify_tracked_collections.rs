@@ -0,0 +1,173 @@
+//! Test specifying the value of tracked functions that return
+//! collections of tracked structs, and that the structs are
+//! discarded once they are no longer part of any value.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked(specify)]
+fn children<'db>(_db: &'db dyn LogDatabase, _parent: MyTracked<'db>) -> Vec<MyTracked<'db>> {
+    panic!("never specified")
+}
+
+#[salsa::tracked(specify)]
+fn first_child<'db>(_db: &'db dyn LogDatabase, _parent: MyTracked<'db>) -> Option<MyTracked<'db>> {
+    panic!("never specified")
+}
+
+#[salsa::tracked(specify)]
+fn grouped<'db>(
+    _db: &'db dyn LogDatabase,
+    _parent: MyTracked<'db>,
+) -> Vec<Option<Vec<MyTracked<'db>>>> {
+    panic!("never specified")
+}
+
+#[salsa::tracked]
+fn build(db: &dyn LogDatabase, input: MyInput) -> MyTracked<'_> {
+    let parent = MyTracked::new(db, 0);
+    let kids: Vec<_> = (1..=input.field(db))
+        .map(|i| MyTracked::new(db, i))
+        .collect();
+    children::specify(db, parent, kids.clone());
+    first_child::specify(db, parent, kids.first().copied());
+    grouped::specify(db, parent, vec![Some(kids.clone()), None]);
+    parent
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    let parent = build(db, input);
+    let children: u32 = children(db, parent).iter().map(|t| t.field(db)).sum();
+    let first = first_child(db, parent).map_or(0, |t| t.field(db));
+    let grouped: u32 = grouped(db, parent)
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|t| t.field(db))
+        .sum();
+    db.push_log(format!("sum: {children} {first} {grouped}"));
+    children + first + grouped
+}
+
+#[test]
+fn specify_collections() {
+    let mut db = common::DiscardLoggerDatabase::default();
+    let input = MyInput::new(&db, 3);
+    assert_eq!(sum(&db, input), 6 + 1 + 6);
+    db.assert_logs(expect![[r#"
+        [
+            "sum: 6 1 6",
+        ]"#]]);
+
+    // Creates only 1 of the kids, so the other 2 are discarded.
+    input.set_field(&mut db).to(1);
+    assert_eq!(sum(&db, input), 1 + 1 + 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillDiscardStaleOutput { execute_key: build(Id(0)), output_key: MyTracked(Id(402)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
+            "salsa_event(WillDiscardStaleOutput { execute_key: build(Id(0)), output_key: MyTracked(Id(403)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(403)) })",
+            "sum: 1 1 1",
+        ]"#]]);
+
+    input.set_field(&mut db).to(0);
+    assert_eq!(sum(&db, input), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillDiscardStaleOutput { execute_key: build(Id(0)), output_key: MyTracked(Id(401)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(401)) })",
+            "sum: 0 0 0",
+        ]"#]]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(sum(&db, input), 3 + 1 + 3);
+    db.assert_logs(expect![[r#"
+        [
+            "sum: 3 1 3",
+        ]"#]]);
+
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(sum(&db, input), 3 + 1 + 3);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[salsa::tracked(specify)]
+fn defaulted<'db>(db: &'db dyn LogDatabase, parent: MyTracked<'db>) -> Option<Vec<MyTracked<'db>>> {
+    Some(vec![MyTracked::new(db, parent.field(db) * 10)])
+}
+
+#[salsa::tracked]
+fn build_maybe(db: &dyn LogDatabase, input: MyInput) -> MyTracked<'_> {
+    let parent = MyTracked::new(db, 1);
+    if input.field(db) > 0 {
+        let kids = (1..=input.field(db))
+            .map(|i| MyTracked::new(db, i))
+            .collect();
+        defaulted::specify(db, parent, Some(kids));
+    }
+    parent
+}
+
+#[salsa::tracked]
+fn sum_maybe(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    let parent = build_maybe(db, input);
+    defaulted(db, parent)
+        .iter()
+        .flatten()
+        .map(|t| t.field(db))
+        .sum()
+}
+
+#[test]
+fn specify_after_execute() {
+    let mut db = common::DiscardLoggerDatabase::default();
+    let input = MyInput::new(&db, 0);
+    assert_eq!(sum_maybe(&db, input), 10);
+    db.assert_logs(expect!["[]"]);
+
+    // `defaulted` is now specified, discarding the struct its execution created.
+    input.set_field(&mut db).to(2);
+    assert_eq!(sum_maybe(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillDiscardStaleOutput { execute_key: defaulted(Id(400)), output_key: MyTracked(Id(401)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(401)) })",
+        ]"#]]);
+
+    // `defaulted` is no longer specified, so it has to execute again,
+    // even though none of the inputs of its previous execution changed.
+    input.set_field(&mut db).to(0);
+    assert_eq!(sum_maybe(&db, input), 10);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillDiscardStaleOutput { execute_key: build_maybe(Id(0)), output_key: MyTracked(Id(402)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
+            "salsa_event(WillDiscardStaleOutput { execute_key: build_maybe(Id(0)), output_key: MyTracked(Id(403)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(403)) })",
+            "salsa_event(WillDiscardStaleOutput { execute_key: build_maybe(Id(0)), output_key: defaulted(Id(400)) })",
+        ]"#]]);
+
+    input.set_field(&mut db).to(1);
+    assert_eq!(sum_maybe(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillDiscardStaleOutput { execute_key: defaulted(Id(400)), output_key: MyTracked(Id(401)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(401)) })",
+        ]"#]]);
+}
@@ -0,0 +1,147 @@
+//! Test that `Database::speculate` rolls back the changes to inputs
+//! and the values memoized against them.
+
+mod common;
+
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+#[salsa::input(speculate)]
+struct File {
+    #[index]
+    path: String,
+    contents: u32,
+}
+
+#[salsa::input]
+struct Config {
+    factor: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, file: File) -> u32 {
+    file.contents(db) * 2
+}
+
+#[salsa::tracked]
+fn scaled(db: &dyn Database, file: File, config: Config) -> u32 {
+    file.contents(db) * config.factor(db)
+}
+
+#[test]
+fn changes_are_rolled_back() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+
+    let a = File::new(&db, "a".to_string(), 1);
+    let b = File::new(&db, "b".to_string(), 2);
+    assert_eq!(double(&db, a), 2);
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(WillExecute { database_key: double(Id(1)) })",
+        ]"#]]);
+
+    let result = db.speculate(|db| {
+        a.set_contents(db).to(10);
+        a.set_path(db).to("c".to_string());
+        assert_eq!(File::find_by_path(db, &"c".to_string()), Some(a));
+        double(db, a)
+    });
+    assert_eq!(result, 20);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+        ]"#]]);
+
+    // The memo of `b` was not read within the speculation and remains valid,
+    // while the one of `a` is recomputed against the restored input.
+    assert_eq!(a.contents(&db), 1);
+    assert_eq!(File::find_by_path(&db, &"a".to_string()), Some(a));
+    assert_eq!(File::find_by_path(&db, &"c".to_string()), None);
+    assert_eq!(double(&db, a), 2);
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn created_inputs_are_retired() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let a = File::new(&db, "a".to_string(), 1);
+    let created = db.speculate(|db| {
+        let created = File::new(db, "b".to_string(), 2);
+        created.set_contents(db).to(3);
+        assert_eq!(double(db, created), 6);
+        created
+    });
+
+    assert_eq!(File::find_by_path(&db, &"b".to_string()), None);
+    assert_eq!(
+        File::all(&db).collect::<Vec<_>>(),
+        vec![a],
+        "{created:?} should be retired"
+    );
+}
+
+#[test]
+fn memos_read_within_the_speculation_are_reverified() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let a = File::new(&db, "a".to_string(), 1);
+    let config = Config::new(&db, 3);
+    assert_eq!(scaled(&db, a, config), 3);
+
+    db.speculate(|db| {
+        a.set_contents(db).to(10);
+        assert_eq!(scaled(db, a, config), 30);
+    });
+    assert_eq!(scaled(&db, a, config), 3);
+
+    config.set_factor(&mut db).to(4);
+    assert_eq!(scaled(&db, a, config), 4);
+}
+
+#[test]
+fn changes_are_rolled_back_on_panic() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let a = File::new(&db, "a".to_string(), 1);
+    assert_eq!(double(&db, a), 2);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        db.speculate(|db| {
+            a.set_contents(db).to(10);
+            assert_eq!(double(db, a), 20);
+            panic!("oops");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(double(&db, a), 2);
+
+    // A new speculation can begin.
+    assert_eq!(db.speculate(|db| double(db, a)), 2);
+}
+
+#[test]
+#[should_panic(expected = "the input must be declared with `#[salsa::input(speculate)]`")]
+fn input_without_speculate() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    let config = Config::new(&db, 3);
+    db.speculate(|db| config.set_factor(db).to(4));
+}
+
+#[test]
+#[should_panic(expected = "`Database::speculate` cannot be called within another speculation")]
+fn nested_speculation() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    db.speculate(|db| db.speculate(|_| ()));
+}
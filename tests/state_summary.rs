@@ -0,0 +1,104 @@
+//! Test that a summary exported by one database tells another database,
+//! with the same inputs created again, which memos are still valid.
+
+use salsa::{Database, DatabaseImpl, StateSummary};
+use test_log::test;
+
+#[salsa::input(fingerprint)]
+struct SourceFile {
+    text: String,
+}
+
+#[salsa::input]
+struct Config {
+    verbose: bool,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: SourceFile) -> usize {
+    file.text(db).len()
+}
+
+#[salsa::tracked]
+fn total_length(db: &dyn Database, a: SourceFile, b: SourceFile) -> usize {
+    length(db, a) + length(db, b)
+}
+
+#[salsa::tracked]
+fn verbose(db: &dyn Database, config: Config) -> bool {
+    config.verbose(db)
+}
+
+fn first_run() -> StateSummary {
+    let mut db = DatabaseImpl::new();
+    let a = SourceFile::new(&db, "a".to_string());
+    let b = SourceFile::new(&db, "bb".to_string());
+    let config = Config::new(&db, true);
+    assert_eq!(total_length(&db, a, b), 3);
+    assert!(verbose(&db, config));
+    db.export_state_summary()
+}
+
+#[test]
+fn unchanged_inputs() {
+    let summary = first_run();
+    let memos: Vec<_> = summary.memos().collect();
+    assert_eq!(
+        memos.len(),
+        4,
+        "expected `length` twice, `total_length` and `verbose`: {memos:?}"
+    );
+
+    let db = DatabaseImpl::new();
+    let a = SourceFile::new(&db, "a".to_string());
+    let b = SourceFile::new(&db, "bb".to_string());
+    // Registers the ingredients, so that the keys of the summary are found.
+    assert_eq!(total_length(&db, a, b), 3);
+
+    let diff = db.import_and_diff(&summary);
+    assert!(diff.changed_inputs.is_empty());
+    assert_eq!(diff.clean.len(), 3);
+    // `verbose` read an input that is not fingerprinted.
+    assert_eq!(diff.dirty.len(), 1);
+    assert!(diff.dirty[0].starts_with("verbose("), "{:?}", diff.dirty);
+}
+
+#[test]
+fn changed_input() {
+    let summary = first_run();
+
+    let db = DatabaseImpl::new();
+    let a = SourceFile::new(&db, "a".to_string());
+    let b = SourceFile::new(&db, "ccc".to_string());
+    assert_eq!(total_length(&db, a, b), 4);
+
+    let diff = db.import_and_diff(&summary);
+    assert_eq!(diff.changed_inputs, ["SourceFile.text(Id(1))"]);
+    assert_eq!(diff.clean, ["length(Id(0))"]);
+    assert_eq!(diff.dirty.len(), 3);
+    assert!(diff.dirty.contains(&"length(Id(1))".to_string()));
+}
+
+#[test]
+fn missing_input() {
+    let summary = first_run();
+
+    let db = DatabaseImpl::new();
+    let a = SourceFile::new(&db, "a".to_string());
+    assert_eq!(length(&db, a), 1);
+
+    let diff = db.import_and_diff(&summary);
+    assert_eq!(diff.changed_inputs, ["SourceFile.text(Id(1))"]);
+    assert_eq!(diff.clean, ["length(Id(0))"]);
+}
+
+#[test]
+fn text_round_trip() {
+    let summary = first_run();
+    let text = summary.to_string();
+    assert!(text.contains("input\tSourceFile.text(Id(0))\t"), "{text}");
+    assert_eq!(text.parse::<StateSummary>(), Ok(summary));
+
+    let error = "memo".parse::<StateSummary>().unwrap_err();
+    assert_eq!(error.to_string(), "malformed state summary at line 1");
+}
@@ -0,0 +1,153 @@
+//! Test that enums deriving `Supertype` can be used as keys of tracked functions.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Word<'db> {
+    #[tracked]
+    text: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, salsa::Supertype)]
+enum Item<'db> {
+    File(File),
+    Name(Name<'db>),
+    Word(Word<'db>),
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> Vec<Word<'_>> {
+    file.text(db)
+        .split_whitespace()
+        .map(|text| Word::new(db, text.to_string()))
+        .collect()
+}
+
+#[salsa::tracked]
+fn length<'db>(db: &'db dyn Database, item: Item<'db>) -> usize {
+    match item {
+        Item::File(file) => file.text(db).len(),
+        Item::Name(name) => name.text(db).len(),
+        Item::Word(word) => word.text(db).len(),
+    }
+}
+
+#[test]
+fn enum_as_key() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a bb".to_string());
+    let name = Name::new(&db, "ccc".to_string());
+
+    assert_eq!(length(&db, Item::from(file)), 4);
+    assert_eq!(length(&db, Item::from(name)), 3);
+    let lengths: Vec<_> = words(&db, file)
+        .into_iter()
+        .map(|word| length(&db, word.into()))
+        .collect();
+    assert_eq!(lengths, [1, 2]);
+
+    file.set_text(&mut db).to("ddd".to_string());
+    assert_eq!(length(&db, file.into()), 3);
+    let name = Name::new(&db, "ccc".to_string());
+    assert_eq!(length(&db, name.into()), 3);
+}
+
+#[test]
+fn conversions() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, String::new());
+    let item = Item::from(file);
+
+    assert_eq!(item.downcast::<File>(), Some(file));
+    assert_eq!(item.downcast::<Name>(), None);
+    assert_eq!(File::try_from(item), Ok(file));
+    assert_eq!(Name::try_from(item), Err(item));
+}
+
+#[salsa::tracked]
+struct Function<'db> {
+    name: String,
+}
+
+#[salsa::tracked]
+struct Class<'db> {
+    name: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, salsa::Supertype, salsa::Update)]
+enum Definition<'db> {
+    Function(Function<'db>),
+    Class(Class<'db>),
+}
+
+#[salsa::tracked(specify)]
+fn kind<'db>(db: &'db dyn Database, definition: Definition<'db>) -> String {
+    match definition {
+        Definition::Function(_) => "function".to_string(),
+        Definition::Class(class) => format!("class {}", class.name(db)),
+    }
+}
+
+#[salsa::tracked]
+fn definitions(db: &dyn Database, file: File) -> Vec<Definition<'_>> {
+    file.text(db)
+        .split_whitespace()
+        .map(|name| match name.strip_prefix("fn:") {
+            Some(name) => {
+                let function = Function::new(db, name.to_string());
+                kind::specify(db, function.into(), format!("fn {name}"));
+                function.into()
+            }
+            None => Class::new(db, name.to_string()).into(),
+        })
+        .collect()
+}
+
+#[test]
+fn specify_enum_key() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, "fn:f C".to_string());
+    let kinds: Vec<_> = definitions(&db, file)
+        .into_iter()
+        .map(|definition| kind(&db, definition))
+        .collect();
+    assert_eq!(kinds, ["fn f", "class C"]);
+}
+
+#[salsa::input]
+struct Unrelated {}
+
+#[salsa::tracked(validate_args)]
+fn validated<'db>(db: &'db dyn Database, item: Item<'db>) -> usize {
+    length(db, item)
+}
+
+#[test]
+fn validate_enum_key() {
+    let db = DatabaseImpl::new();
+    let file = File::new(&db, "a".to_string());
+    let name = Name::new(&db, "bb".to_string());
+    assert_eq!(validated(&db, file.into()), 1);
+    assert_eq!(validated(&db, name.into()), 2);
+
+    // An id that is not an entry of any variant in this database is rejected.
+    let other = DatabaseImpl::new();
+    let _ = File::new(&other, String::new());
+    let _ = Unrelated::new(&other);
+    let foreign = Name::new(&other, "c".to_string());
+    let result = salsa::InvalidKey::catch(std::panic::AssertUnwindSafe(|| {
+        validated(&db, foreign.into())
+    }));
+    assert!(result.is_err());
+}
@@ -0,0 +1,66 @@
+//! Test `report_synthetic_read`: a query depending on external state of a given
+//! durability is re-executed only when an input of that durability changes.
+
+mod common;
+use common::LogDatabase;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+use test_log::test;
+
+/// A configuration flag that salsa does not know about.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+#[salsa::tracked]
+fn verbose(db: &dyn LogDatabase) -> bool {
+    db.push_log("verbose".to_string());
+    db.report_synthetic_read(Durability::HIGH);
+    VERBOSE.load(Ordering::SeqCst)
+}
+
+#[salsa::tracked]
+fn render(db: &dyn LogDatabase, file: File) -> String {
+    db.push_log("render".to_string());
+    if verbose(db) {
+        format!("{:?}", file.contents(db))
+    } else {
+        file.contents(db)
+    }
+}
+
+#[test]
+fn reexecuted_on_write_of_durability() {
+    let mut db = common::LoggerDatabase::default();
+    let file = File::new(&db, "hello".to_string());
+    assert_eq!(render(&db, file), "hello");
+    db.assert_logs(expect![[r#"
+        [
+            "render",
+            "verbose",
+        ]"#]]);
+
+    // A low-durability change does not re-execute `verbose`.
+    VERBOSE.store(true, Ordering::SeqCst);
+    file.set_contents(&mut db).to("world".to_string());
+    assert_eq!(render(&db, file), "world");
+    db.assert_logs(expect![[r#"
+        [
+            "render",
+        ]"#]]);
+
+    // A high-durability change does.
+    db.synthetic_write(Durability::HIGH);
+    assert_eq!(render(&db, file), "\"world\"");
+    db.assert_logs(expect![[r#"
+        [
+            "verbose",
+            "render",
+        ]"#]]);
+}
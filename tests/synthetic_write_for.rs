@@ -0,0 +1,85 @@
+//! Test that `Database::synthetic_write_for` only invalidates
+//! the readers of fields of the given input.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Config {
+    tab_width: usize,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Database, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked]
+fn indent(db: &dyn Database, config: Config) -> String {
+    " ".repeat(config.tab_width(db))
+}
+
+#[test]
+fn invalidates_readers_of_one_input() {
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let file = File::new(&db, "a\nb".to_string());
+    let config = Config::new(&db, 4);
+
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(indent(&db, config), "    ");
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: line_count(Id(0)) })",
+            "salsa_event(WillExecute { database_key: indent(Id(400)) })",
+        ]"#]]);
+
+    db.synthetic_write_for::<File>(Durability::LOW);
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(indent(&db, config), "    ");
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: line_count(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: indent(Id(400)) })",
+        ]"#]]);
+}
+
+#[test]
+fn respects_durability() {
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let file = File::builder("a".to_string())
+        .durability(Durability::HIGH)
+        .new(&db);
+
+    assert_eq!(line_count(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: line_count(Id(0)) })",
+        ]"#]]);
+
+    // The field has a higher durability than the synthetic write.
+    db.synthetic_write_for::<File>(Durability::LOW);
+    assert_eq!(line_count(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: line_count(Id(0)) })",
+        ]"#]]);
+
+    db.synthetic_write_for::<File>(Durability::HIGH);
+    assert_eq!(line_count(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: line_count(Id(0)) })",
+        ]"#]]);
+
+    // Setting the field afterwards still works as usual.
+    file.set_text(&mut db).to("a\nb".to_string());
+    assert_eq!(line_count(&db, file), 2);
+}
@@ -0,0 +1,93 @@
+//! Test that `salsa::trace!` attaches the revision and the active query to events.
+
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use salsa::plumbing::tracing::{
+    self,
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+use salsa::{Database, DatabaseImpl, Setter};
+
+/// Records the fields of all events emitted by `salsa::trace!`.
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        if event.metadata().target() != module_path!() {
+            return;
+        }
+
+        struct Fields(String);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if !self.0.is_empty() {
+                    self.0.push(' ');
+                }
+                write!(self.0, "{}={:?}", field.name(), value).unwrap();
+            }
+        }
+
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    salsa::trace!(db, "doubling {}", input.field(db));
+    input.field(db) * 2
+}
+
+#[test]
+fn trace() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    tracing::subscriber::with_default(recorder, || {
+        let mut db = DatabaseImpl::new();
+        let input = MyInput::new(&db, 1);
+
+        salsa::trace!(&db, "before");
+        assert_eq!(double(&db, input), 2);
+
+        input.set_field(&mut db).to(2);
+        assert_eq!(double(&db, input), 4);
+    });
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "message=before salsa=R1",
+            "message=doubling 1 salsa=R1 double(Id(0))",
+            "message=doubling 2 salsa=R2 double(Id(0))",
+        ]
+    );
+}
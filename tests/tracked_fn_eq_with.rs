@@ -0,0 +1,76 @@
+//! Test that `#[salsa::tracked(eq_with = ...)]` backdates values
+//! that are equal according to the given function.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Setter;
+
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+/// A function `param => body`, where `body` is a single variable.
+#[derive(Clone, Debug, salsa::Update)]
+struct Lambda {
+    param: String,
+    body: String,
+}
+
+/// Compares lambdas up to renaming of their parameter.
+fn alpha_equivalent(old: &Lambda, new: &Lambda) -> bool {
+    match (old.body == old.param, new.body == new.param) {
+        (true, true) => true,
+        (false, false) => old.body == new.body,
+        _ => false,
+    }
+}
+
+#[salsa::tracked(eq_with = alpha_equivalent)]
+fn parse(db: &dyn LogDatabase, source: Source) -> Lambda {
+    db.push_log("parse".to_string());
+    let (param, body) = source.text(db).split_once("=>").unwrap();
+    Lambda {
+        param: param.trim().to_string(),
+        body: body.trim().to_string(),
+    }
+}
+
+#[salsa::tracked]
+fn is_identity(db: &dyn LogDatabase, source: Source) -> bool {
+    db.push_log("is_identity".to_string());
+    let lambda = parse(db, source);
+    lambda.param == lambda.body
+}
+
+#[test]
+fn backdate_alpha_equivalent() {
+    let mut db = common::LoggerDatabase::default();
+    let source = Source::new(&db, "x => x".to_string());
+
+    assert!(is_identity(&db, source));
+    db.assert_logs(expect![[r#"
+        [
+            "is_identity",
+            "parse",
+        ]"#]]);
+
+    // Renaming the parameter does not change the lambda.
+    source.set_text(&mut db).to("y => y".to_string());
+    assert!(is_identity(&db, source));
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+        ]"#]]);
+
+    source.set_text(&mut db).to("y => z".to_string());
+    assert!(!is_identity(&db, source));
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+            "is_identity",
+        ]"#]]);
+}
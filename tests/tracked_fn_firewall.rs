@@ -0,0 +1,112 @@
+//! Test that `#[salsa::tracked(firewall)]` functions backdate their values,
+//! including after untracked reads, and report the changes they absorb.
+
+mod common;
+
+use common::{HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{Database, EventKind, Setter, Storage};
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct FirewallDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for FirewallDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        let event = event();
+        match event.kind {
+            EventKind::WillExecute { .. } | EventKind::DidAbsorbChange { .. } => {
+                self.push_log(format!("{:?}", event.kind));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl HasLogger for FirewallDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::tracked(firewall)]
+fn line_count(db: &dyn Database, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked(firewall)]
+fn untracked_line_count(db: &dyn Database, file: File) -> usize {
+    db.report_untracked_read();
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked]
+fn summary(db: &dyn Database, file: File) -> String {
+    format!("{} lines", line_count(db, file))
+}
+
+#[salsa::tracked]
+fn untracked_summary(db: &dyn Database, file: File) -> String {
+    format!("{} lines", untracked_line_count(db, file))
+}
+
+#[test]
+fn absorbs_change() {
+    let mut db = FirewallDatabase::default();
+    let file = File::new(&db, "a\nb".to_string());
+
+    assert_eq!(summary(&db, file), "2 lines");
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: summary(Id(0)) }",
+            "WillExecute { database_key: line_count(Id(0)) }",
+        ]"#]]);
+
+    file.set_text(&mut db).to("c\nd".to_string());
+    assert_eq!(summary(&db, file), "2 lines");
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: line_count(Id(0)) }",
+            "DidAbsorbChange { database_key: line_count(Id(0)) }",
+        ]"#]]);
+
+    file.set_text(&mut db).to("c".to_string());
+    assert_eq!(summary(&db, file), "1 lines");
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: line_count(Id(0)) }",
+            "WillExecute { database_key: summary(Id(0)) }",
+        ]"#]]);
+}
+
+#[test]
+fn absorbs_untracked_read() {
+    let mut db = FirewallDatabase::default();
+    let file = File::new(&db, "a\nb".to_string());
+
+    assert_eq!(untracked_summary(&db, file), "2 lines");
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: untracked_summary(Id(0)) }",
+            "WillExecute { database_key: untracked_line_count(Id(0)) }",
+        ]"#]]);
+
+    // The firewall re-executes in every revision, but its readers do not.
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(untracked_summary(&db, file), "2 lines");
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: untracked_line_count(Id(0)) }",
+            "DidAbsorbChange { database_key: untracked_line_count(Id(0)) }",
+        ]"#]]);
+}
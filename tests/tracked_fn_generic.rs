@@ -0,0 +1,77 @@
+//! Test tracked functions with type parameters, each instance of which
+//! has its own ingredient.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Problem {
+    numbers: Vec<u32>,
+}
+
+trait Domain: 'static {
+    type Result: Clone + Eq + std::fmt::Debug + Send + Sync + salsa::Update;
+
+    fn solve(numbers: &[u32]) -> Self::Result;
+}
+
+struct Sum;
+
+impl Domain for Sum {
+    type Result = u32;
+
+    fn solve(numbers: &[u32]) -> u32 {
+        numbers.iter().sum()
+    }
+}
+
+struct Largest;
+
+impl Domain for Largest {
+    type Result = Option<u32>;
+
+    fn solve(numbers: &[u32]) -> Option<u32> {
+        numbers.iter().copied().max()
+    }
+}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[salsa::tracked]
+fn solve<T: Domain>(db: &dyn Database, problem: Problem) -> T::Result {
+    EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    T::solve(&problem.numbers(db))
+}
+
+#[salsa::tracked]
+fn describe<'db, T>(db: &'db dyn Database, problem: Problem) -> String
+where
+    T: Domain,
+{
+    format!("{:?}", solve::<T>(db, problem))
+}
+
+#[test]
+fn instances_are_memoized_separately() {
+    let mut db = DatabaseImpl::new();
+    let problem = Problem::new(&db, vec![1, 5, 2]);
+
+    assert_eq!(solve::<Sum>(&db, problem), 8);
+    assert_eq!(solve::<Largest>(&db, problem), Some(5));
+    assert_eq!(solve::<Sum>(&db, problem), 8);
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+
+    let instances = db
+        .ingredients()
+        .into_iter()
+        .filter(|info| info.debug_name == "solve")
+        .count();
+    assert_eq!(instances, 2);
+
+    assert_eq!(describe::<Largest>(&db, problem), "Some(5)");
+    problem.set_numbers(&mut db).to(vec![]);
+    assert_eq!(describe::<Largest>(&db, problem), "None");
+    assert_eq!(describe::<Sum>(&db, problem), "0");
+}
@@ -0,0 +1,48 @@
+//! Test that `get_ref` returns references into the memoized values of tracked functions.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> Vec<String> {
+    file.text(db)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+#[salsa::tracked(return_ref)]
+fn upper(db: &dyn Database, file: File, suffix: char) -> String {
+    let mut text = file.text(db).to_uppercase();
+    text.push(suffix);
+    text
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    words::get_ref(db, file).len()
+}
+
+#[test]
+fn get_ref() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a b".to_string());
+
+    let words_ref: &[String] = words::get_ref(&db, file);
+    assert_eq!(words_ref, ["a", "b"]);
+    assert!(std::ptr::eq(words_ref, words::get_ref(&db, file)));
+    assert_eq!(word_count(&db, file), 2);
+
+    let upper_ref: &str = upper::get_ref(&db, file, '!');
+    assert_eq!(upper_ref, "A B!");
+
+    // Functions reading the value through `get_ref` depend on it like any other reader.
+    file.set_text(&mut db).to("a b c".to_string());
+    assert_eq!(word_count(&db, file), 3);
+    assert_eq!(upper::get_ref(&db, file, '?'), "A B C?");
+}
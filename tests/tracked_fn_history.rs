@@ -0,0 +1,104 @@
+//! Test that functions declared with `history = N` can compare their
+//! value in a past revision with a newer one.
+
+use salsa::plumbing::ZalsaDatabase;
+use salsa::{Database, OutputDiff, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    contents: String,
+    version: u32,
+}
+
+#[salsa::tracked(history = 2)]
+fn line_count(db: &dyn Database, file: File) -> usize {
+    file.contents(db).lines().count()
+}
+
+#[salsa::tracked(history = 1, lru = 1)]
+fn word_count(db: &dyn Database, file: File) -> usize {
+    file.contents(db).split_whitespace().count()
+}
+
+#[test]
+fn diff_between_revisions() {
+    let mut db = salsa::DatabaseImpl::new();
+    let file = File::new(&db, "a".to_string(), 0);
+
+    assert_eq!(line_count(&db, file), 1);
+    let r1 = db.zalsa().current_revision();
+
+    file.set_version(&mut db).to(1);
+    assert_eq!(line_count(&db, file), 1);
+    let r2 = db.zalsa().current_revision();
+
+    file.set_contents(&mut db).to("a\nb".to_string());
+    assert_eq!(line_count(&db, file), 2);
+    let r3 = db.zalsa().current_revision();
+
+    file.set_contents(&mut db).to("a\nb\nc".to_string());
+    assert_eq!(line_count(&db, file), 3);
+    let r4 = db.zalsa().current_revision();
+
+    assert_eq!(
+        line_count::diff(&db, file, r3, r4),
+        OutputDiff::Changed { old: &2, new: &3 }
+    );
+    assert_eq!(line_count::diff(&db, file, r4, r4), OutputDiff::Unchanged);
+
+    // The value computed in `r1` was backdated in `r2`, so they are the same value.
+    assert_eq!(line_count::diff(&db, file, r1, r2), OutputDiff::Unchanged);
+    assert_eq!(
+        line_count::diff(&db, file, r2, r4),
+        OutputDiff::Changed { old: &1, new: &3 }
+    );
+
+    // Only the two previous values are retained.
+    file.set_contents(&mut db).to(String::new());
+    assert_eq!(line_count(&db, file), 0);
+    let r5 = db.zalsa().current_revision();
+    assert_eq!(
+        line_count::diff(&db, file, r3, r5),
+        OutputDiff::Changed { old: &2, new: &0 }
+    );
+    assert_eq!(line_count::diff(&db, file, r2, r5), OutputDiff::Unknown);
+}
+
+#[test]
+fn diff_unknown_without_execution() {
+    let mut db = salsa::DatabaseImpl::new();
+    let file = File::new(&db, "a".to_string(), 0);
+
+    assert_eq!(line_count(&db, file), 1);
+    let r1 = db.zalsa().current_revision();
+
+    // The function is not invoked in `r2`, so its value there is unknown.
+    file.set_contents(&mut db).to("a\nb".to_string());
+    let r2 = db.zalsa().current_revision();
+    assert_eq!(line_count::diff(&db, file, r1, r2), OutputDiff::Unknown);
+}
+
+#[test]
+fn evicted_values_are_retained() {
+    let mut db = salsa::DatabaseImpl::new();
+    let a = File::new(&db, "a b".to_string(), 0);
+    let b = File::new(&db, "c".to_string(), 0);
+
+    assert_eq!(word_count(&db, a), 2);
+    let r1 = db.zalsa().current_revision();
+
+    // Evicts the value of `a`, which remains retained in its history.
+    b.set_version(&mut db).to(1);
+    assert_eq!(word_count(&db, b), 1);
+    a.set_version(&mut db).to(1);
+    assert_eq!(word_count(&db, b), 1);
+
+    a.set_contents(&mut db).to("a b c".to_string());
+    assert_eq!(word_count(&db, a), 3);
+    let r2 = db.zalsa().current_revision();
+    assert_eq!(
+        word_count::diff(&db, a, r1, r2),
+        OutputDiff::Changed { old: &2, new: &3 }
+    );
+}
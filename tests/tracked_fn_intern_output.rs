@@ -0,0 +1,51 @@
+//! Test that outputs of a tracked function declared with `intern_output`
+//! share their allocation when they compare equal.
+
+use std::sync::Arc;
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Item {
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, salsa::Update)]
+struct Signature {
+    len: usize,
+}
+
+#[salsa::tracked(intern_output)]
+fn signature(db: &dyn Database, item: Item) -> Arc<Signature> {
+    Arc::new(Signature {
+        len: item.name(db).len(),
+    })
+}
+
+#[salsa::tracked]
+fn signature_not_interned(db: &dyn Database, item: Item) -> Arc<Signature> {
+    Arc::new(Signature {
+        len: item.name(db).len(),
+    })
+}
+
+#[test]
+fn equal_outputs_are_shared() {
+    let mut db = DatabaseImpl::new();
+
+    let a = Item::new(&db, "abc".to_string());
+    let b = Item::new(&db, "xyz".to_string());
+    let c = Item::new(&db, "abcd".to_string());
+
+    assert!(Arc::ptr_eq(&signature(&db, a), &signature(&db, b)));
+    assert!(!Arc::ptr_eq(&signature(&db, a), &signature(&db, c)));
+    assert!(!Arc::ptr_eq(
+        &signature_not_interned(&db, a),
+        &signature_not_interned(&db, b)
+    ));
+
+    // Recomputed values are shared with the existing ones, too.
+    c.set_name(&mut db).to("def".to_string());
+    assert!(Arc::ptr_eq(&signature(&db, a), &signature(&db, c)));
+}
@@ -3,6 +3,7 @@ mod common;
 use common::LogDatabase;
 use expect_test::expect;
 use salsa::Setter as _;
+use std::sync::Arc;
 
 #[salsa::input]
 struct Input {
@@ -47,3 +48,42 @@ fn invoke() {
             "derived",
         ]"#]]);
 }
+
+/// A graph that does not implement `PartialEq`: with `no_eq`, deriving `Update` suffices.
+#[derive(Clone, Debug, salsa::Update)]
+struct Graph {
+    nodes: Arc<Vec<i16>>,
+}
+
+#[salsa::tracked(no_eq)]
+fn graph(db: &dyn LogDatabase, input: Input) -> Graph {
+    db.push_log("graph".to_string());
+    Graph {
+        nodes: Arc::new(vec![input.number(db).abs()]),
+    }
+}
+
+#[salsa::tracked]
+fn node_count(db: &dyn LogDatabase, input: Input) -> usize {
+    db.push_log("node_count".to_string());
+    graph(db, input).nodes.len()
+}
+
+#[test]
+fn output_without_eq() {
+    let mut db = common::LoggerDatabase::default();
+
+    let input = Input::new(&db, 5);
+    assert_eq!(node_count(&db, input), 1);
+
+    input.set_number(&mut db).to(-5);
+    assert_eq!(node_count(&db, input), 1);
+
+    db.assert_logs(expect![[r#"
+        [
+            "node_count",
+            "graph",
+            "graph",
+            "node_count",
+        ]"#]]);
+}
@@ -0,0 +1,82 @@
+//! Test that a `tracked` fn taking a salsa struct plus plain data
+//! memoizes each combination of arguments separately.
+
+mod common;
+
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use expect_test::expect;
+use salsa::Setter;
+
+#[salsa::input]
+struct Source {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Function<'db> {
+    name: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Mode {
+    Fast,
+    Precise,
+}
+
+#[salsa::tracked]
+fn parse(db: &dyn salsa::Database, source: Source) -> Function<'_> {
+    Function::new(db, source.text(db))
+}
+
+#[salsa::tracked]
+fn infer<'db>(db: &'db dyn salsa::Database, function: Function<'db>, mode: Mode) -> usize {
+    match mode {
+        Mode::Fast => function.name(db).len(),
+        Mode::Precise => function.name(db).len() * 2,
+    }
+}
+
+#[salsa::tracked]
+fn infer_source(db: &dyn salsa::Database, source: Source, mode: Mode, extra: String) -> usize {
+    infer(db, parse(db, source), mode) + extra.len()
+}
+
+#[salsa::tracked]
+impl Source {
+    #[salsa::tracked]
+    fn infer(self, db: &dyn salsa::Database, mode: Mode) -> usize {
+        infer(db, parse(db, self), mode)
+    }
+}
+
+#[test]
+fn execute() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let source = Source::new(&db, "foo".to_string());
+
+    assert_eq!(source.infer(&db, Mode::Fast), 3);
+    assert_eq!(source.infer(&db, Mode::Precise), 6);
+    assert_eq!(source.infer(&db, Mode::Fast), 3);
+    assert_eq!(infer_source(&db, source, Mode::Fast, "!".to_string()), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: inner_fn_name_(Id(400)) })",
+            "salsa_event(WillExecute { database_key: parse(Id(0)) })",
+            "salsa_event(WillExecute { database_key: infer(Id(c00)) })",
+            "salsa_event(WillExecute { database_key: inner_fn_name_(Id(401)) })",
+            "salsa_event(WillExecute { database_key: infer(Id(c01)) })",
+            "salsa_event(WillExecute { database_key: infer_source(Id(1000)) })",
+        ]"#]]);
+
+    source.set_text(&mut db).to("food".to_string());
+    assert_eq!(source.infer(&db, Mode::Precise), 8);
+    assert_eq!(infer_source(&db, source, Mode::Fast, "!".to_string()), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: parse(Id(0)) })",
+            "salsa_event(WillExecute { database_key: inner_fn_name_(Id(401)) })",
+            "salsa_event(WillExecute { database_key: infer(Id(c02)) })",
+            "salsa_event(WillExecute { database_key: infer_source(Id(1000)) })",
+            "salsa_event(WillExecute { database_key: infer(Id(c03)) })",
+        ]"#]]);
+}
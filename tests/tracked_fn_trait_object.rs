@@ -0,0 +1,99 @@
+//! Test tracked functions returning trait objects, compared with `eq_with`
+//! and updated with `unsafe(update_with = ...)`.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Setter;
+
+#[salsa::input]
+struct Source {
+    #[return_ref]
+    text: String,
+}
+
+trait Shape: std::fmt::Debug + Send + Sync {
+    fn area(&self) -> u32;
+}
+
+#[derive(Debug)]
+struct Square(u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+#[derive(Debug)]
+struct Rectangle(u32, u32);
+
+impl Shape for Rectangle {
+    fn area(&self) -> u32 {
+        self.0 * self.1
+    }
+}
+
+#[allow(clippy::borrowed_box)] // `eq_with` takes references to the output type.
+fn same_area(old: &Box<dyn Shape>, new: &Box<dyn Shape>) -> bool {
+    old.area() == new.area()
+}
+
+/// # Safety
+///
+/// Shapes hold no references into the database, so the old value can be replaced.
+unsafe fn replace_shape(old_pointer: *mut Box<dyn Shape>, new_value: Box<dyn Shape>) -> bool {
+    unsafe { *old_pointer = new_value };
+    true
+}
+
+#[salsa::tracked(return_ref, eq_with = same_area, unsafe(update_with = replace_shape))]
+fn parse(db: &dyn LogDatabase, source: Source) -> Box<dyn Shape> {
+    db.push_log("parse".to_string());
+    let sides: Vec<u32> = source
+        .text(db)
+        .split('x')
+        .map(|side| side.trim().parse().unwrap())
+        .collect();
+    match sides[..] {
+        [side] => Box::new(Square(side)),
+        [width, height] => Box::new(Rectangle(width, height)),
+        _ => panic!("not a shape"),
+    }
+}
+
+#[salsa::tracked]
+fn area(db: &dyn LogDatabase, source: Source) -> u32 {
+    db.push_log("area".to_string());
+    parse(db, source).area()
+}
+
+#[test]
+fn backdate_same_area() {
+    let mut db = common::LoggerDatabase::default();
+    let source = Source::new(&db, "2".to_string());
+
+    assert_eq!(area(&db, source), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "area",
+            "parse",
+        ]"#]]);
+
+    // A different shape with the same area.
+    source.set_text(&mut db).to("1 x 4".to_string());
+    assert_eq!(area(&db, source), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+        ]"#]]);
+
+    source.set_text(&mut db).to("3".to_string());
+    assert_eq!(area(&db, source), 9);
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+            "area",
+        ]"#]]);
+}
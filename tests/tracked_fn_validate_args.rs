@@ -0,0 +1,108 @@
+//! Test that a `tracked` fn with the `validate_args` option
+//! rejects keys that do not belong to the database.
+
+use salsa::{DatabaseImpl, InvalidKey, InvalidKeyReason};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::input]
+struct OtherInput {
+    field: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, salsa::Supertype)]
+enum Either {
+    My(MyInput),
+    Other(OtherInput),
+}
+
+#[salsa::tracked(validate_args)]
+fn either_field(db: &dyn salsa::Database, either: Either) -> u32 {
+    match either {
+        Either::My(input) => input.field(db),
+        Either::Other(input) => input.field(db),
+    }
+}
+
+#[salsa::tracked(validate_args)]
+fn double(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn valid_key() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(double(&db, input), 44);
+}
+
+#[test]
+fn struct_never_created() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+
+    let other_db = DatabaseImpl::new();
+    let err = InvalidKey::catch(|| double(&other_db, input)).unwrap_err();
+    assert_eq!(err.reason, InvalidKeyReason::UnknownPage);
+    assert_eq!(
+        err.to_string(),
+        "`double` invoked with invalid key `Id(0)`: it was not allocated in this database"
+    );
+}
+
+#[test]
+fn unknown_page() {
+    let db = DatabaseImpl::new();
+    OtherInput::new(&db, 1);
+    let input = MyInput::new(&db, 2);
+
+    let other_db = DatabaseImpl::new();
+    MyInput::new(&other_db, 1);
+    let err = InvalidKey::catch(|| double(&other_db, input)).unwrap_err();
+    assert_eq!(err.reason, InvalidKeyReason::UnknownPage);
+}
+
+#[test]
+fn unallocated() {
+    let db = DatabaseImpl::new();
+    MyInput::new(&db, 1);
+    let input = MyInput::new(&db, 2);
+
+    let other_db = DatabaseImpl::new();
+    MyInput::new(&other_db, 1);
+    let err = InvalidKey::catch(|| double(&other_db, input)).unwrap_err();
+    assert_eq!(err.reason, InvalidKeyReason::Unallocated);
+}
+
+#[test]
+fn wrong_ingredient() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    let other_db = DatabaseImpl::new();
+    OtherInput::new(&other_db, 1);
+    MyInput::new(&other_db, 2);
+    let err = InvalidKey::catch(|| double(&other_db, input)).unwrap_err();
+    assert!(
+        matches!(err.reason, InvalidKeyReason::WrongIngredient { .. }),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn supertype_variants() {
+    let db = DatabaseImpl::new();
+    OtherInput::new(&db, 1);
+    let input = OtherInput::new(&db, 2);
+    assert_eq!(either_field(&db, Either::Other(input)), 2);
+
+    // The key is an entry of the ingredient of a later variant, but not allocated.
+    let other_db = DatabaseImpl::new();
+    OtherInput::new(&other_db, 1);
+    let err = InvalidKey::catch(|| either_field(&other_db, Either::Other(input))).unwrap_err();
+    assert_eq!(err.reason, InvalidKeyReason::Unallocated);
+}
@@ -0,0 +1,133 @@
+//! Test that readers of a `TrackedList` only depend on the indices they read.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Setter, TrackedList};
+use test_log::test;
+
+#[salsa::input]
+struct Items {
+    #[return_ref]
+    items: Vec<u32>,
+}
+
+#[salsa::tracked(return_ref)]
+fn list(db: &dyn LogDatabase, items: Items) -> TrackedList<u32> {
+    db.push_log("list".to_string());
+    let mut list = TrackedList::new(db);
+    for item in items.items(db) {
+        list.push(db, *item);
+    }
+    list
+}
+
+#[salsa::tracked]
+fn element(db: &dyn LogDatabase, items: Items, index: usize) -> Option<u32> {
+    db.push_log(format!("element({index})"));
+    let list = TrackedList::project(db, || list(db, items));
+    list.get(db, index).copied()
+}
+
+#[salsa::tracked]
+fn len(db: &dyn LogDatabase, items: Items) -> usize {
+    db.push_log("len".to_string());
+    let list = TrackedList::project(db, || list(db, items));
+    list.len(db)
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn LogDatabase, items: Items) -> u32 {
+    db.push_log("sum".to_string());
+    let list = TrackedList::project(db, || list(db, items));
+    list.iter(db).sum()
+}
+
+#[test]
+fn unrelated_indices() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Items::new(&db, vec![1, 2, 3]);
+
+    assert_eq!(element(&db, input, 0), Some(1));
+    assert_eq!(element(&db, input, 2), Some(3));
+    assert_eq!(element(&db, input, 3), None);
+    assert_eq!(len(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "element(0)",
+            "list",
+            "element(2)",
+            "element(3)",
+            "len",
+        ]"#]]);
+
+    // Only index 2 changed.
+    input.set_items(&mut db).to(vec![1, 2, 4]);
+    assert_eq!(element(&db, input, 0), Some(1));
+    assert_eq!(element(&db, input, 2), Some(4));
+    assert_eq!(element(&db, input, 3), None);
+    assert_eq!(len(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "list",
+            "element(2)",
+        ]"#]]);
+
+    // Index 3 is added.
+    input.set_items(&mut db).to(vec![1, 2, 4, 5]);
+    assert_eq!(element(&db, input, 0), Some(1));
+    assert_eq!(element(&db, input, 2), Some(4));
+    assert_eq!(element(&db, input, 3), Some(5));
+    assert_eq!(len(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "list",
+            "element(3)",
+            "len",
+        ]"#]]);
+
+    // Index 3 is removed again.
+    input.set_items(&mut db).to(vec![1, 2, 4]);
+    assert_eq!(element(&db, input, 3), None);
+    assert_eq!(len(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "list",
+            "element(3)",
+            "len",
+        ]"#]]);
+}
+
+#[test]
+fn order_aware() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Items::new(&db, vec![1, 2, 3]);
+
+    assert_eq!(element(&db, input, 0), Some(1));
+    assert_eq!(element(&db, input, 1), Some(2));
+    assert_eq!(element(&db, input, 2), Some(3));
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "element(0)",
+            "list",
+            "element(1)",
+            "element(2)",
+            "sum",
+        ]"#]]);
+
+    // Swapping the first two elements changes their indices, but not the last one.
+    input.set_items(&mut db).to(vec![2, 1, 3]);
+    assert_eq!(element(&db, input, 0), Some(2));
+    assert_eq!(element(&db, input, 1), Some(1));
+    assert_eq!(element(&db, input, 2), Some(3));
+    assert_eq!(sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "list",
+            "element(0)",
+            "element(1)",
+            "sum",
+        ]"#]]);
+}
@@ -0,0 +1,133 @@
+//! Test that `#[salsa::tracked(identity = ...)]` structs are reused when
+//! untracked fields that are not part of their identity change.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::plumbing::AsId;
+use salsa::{Id, Setter};
+
+#[salsa::input]
+struct Source {
+    /// Names of the items along with the line they start on.
+    #[return_ref]
+    items: Vec<(String, u32)>,
+}
+
+/// An item, identified by its name only: the line it starts on is noise.
+#[salsa::tracked(identity = item_identity)]
+struct Item<'db> {
+    name: String,
+    line: u32,
+}
+
+fn item_identity<'a>(name: &'a String, _line: &u32) -> &'a String {
+    name
+}
+
+/// Same as `Item`, but identified by all of its fields.
+#[salsa::tracked]
+struct PlainItem<'db> {
+    name: String,
+    line: u32,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn LogDatabase, source: Source) -> Vec<Item<'_>> {
+    source
+        .items(db)
+        .iter()
+        .map(|(name, line)| Item::new(db, name.clone(), *line))
+        .collect()
+}
+
+#[salsa::tracked]
+fn plain_items(db: &dyn LogDatabase, source: Source) -> Vec<PlainItem<'_>> {
+    source
+        .items(db)
+        .iter()
+        .map(|(name, line)| PlainItem::new(db, name.clone(), *line))
+        .collect()
+}
+
+#[salsa::tracked]
+fn describe<'db>(db: &'db dyn LogDatabase, item: Item<'db>) -> String {
+    db.push_log(format!("describe({})", item.name(db)));
+    format!("fn {}", item.name(db))
+}
+
+#[salsa::tracked]
+fn location<'db>(db: &'db dyn LogDatabase, item: Item<'db>) -> u32 {
+    db.push_log(format!("location({})", item.name(db)));
+    item.line(db)
+}
+
+fn ids(structs: &[impl AsId]) -> Vec<Id> {
+    structs.iter().map(AsId::as_id).collect()
+}
+
+#[test]
+fn reused_when_noise_changes() {
+    let mut db = common::LoggerDatabase::default();
+    let source = Source::new(&db, vec![("a".to_string(), 1), ("b".to_string(), 3)]);
+
+    let old_items = ids(&items(&db, source));
+    let old_plain_items = ids(&plain_items(&db, source));
+    for &item in &items(&db, source) {
+        describe(&db, item);
+    }
+    db.assert_logs(expect![[r#"
+        [
+            "describe(a)",
+            "describe(b)",
+        ]"#]]);
+
+    // Shift both items down by one line.
+    source
+        .set_items(&mut db)
+        .to(vec![("a".to_string(), 2), ("b".to_string(), 4)]);
+
+    assert_eq!(ids(&items(&db, source)), old_items);
+    assert_ne!(ids(&plain_items(&db, source)), old_plain_items);
+    for &item in &items(&db, source) {
+        describe(&db, item);
+    }
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn readers_of_noise_reexecute() {
+    let mut db = common::LoggerDatabase::default();
+    let source = Source::new(&db, vec![("a".to_string(), 1), ("b".to_string(), 3)]);
+
+    let item_a = items(&db, source)[0];
+    let id_a = item_a.as_id();
+    assert_eq!(location(&db, item_a), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "location(a)",
+        ]"#]]);
+
+    source
+        .set_items(&mut db)
+        .to(vec![("a".to_string(), 2), ("b".to_string(), 4)]);
+
+    let item_a = items(&db, source)[0];
+    assert_eq!(item_a.as_id(), id_a);
+    assert_eq!(location(&db, item_a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "location(a)",
+        ]"#]]);
+
+    // Unchanged noise does not invalidate readers.
+    source
+        .set_items(&mut db)
+        .to(vec![("a".to_string(), 2), ("c".to_string(), 4)]);
+
+    let item_a = items(&db, source)[0];
+    assert_eq!(item_a.as_id(), id_a);
+    assert_eq!(location(&db, item_a), 2);
+    db.assert_logs(expect!["[]"]);
+}
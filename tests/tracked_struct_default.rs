@@ -0,0 +1,37 @@
+//! Tests `#[default]` fields of tracked structs and their builder.
+
+use salsa::{Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct Options<'db> {
+    level: u32,
+    #[default]
+    verbose: bool,
+    #[default(Some("out".to_string()))]
+    output: Option<String>,
+}
+
+#[salsa::tracked]
+fn options(db: &dyn Database, input: MyInput) -> (u32, bool, Option<String>) {
+    let defaults = Options::new(db, input.field(db));
+    let custom = Options::builder(input.field(db) + 1)
+        .verbose(true)
+        .output(None)
+        .new(db);
+    assert!(!defaults.verbose(db));
+    assert_eq!(defaults.output(db), Some("out".to_string()));
+    (custom.level(db), custom.verbose(db), custom.output(db))
+}
+
+#[test]
+fn defaults_and_builder() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(options(&db, input), (2, true, None));
+}
@@ -0,0 +1,42 @@
+//! Test that reading the fields of a tracked struct after its creating query
+//! stopped creating it unwinds with `StaleHandle` instead of returning stale data.
+
+use salsa::plumbing::{AsId, FromId};
+use salsa::{Database, DatabaseImpl, Setter, StaleHandle};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    let field = input.field(db);
+    (field > 0).then(|| MyTracked::new(db, field))
+}
+
+#[test]
+fn read_after_delete() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let tracked = create_tracked(&db, input).unwrap();
+    assert_eq!(tracked.field(&db), 1);
+
+    // Stash the handle as if it were kept in an input or thread-local.
+    let stashed = tracked.as_id();
+
+    input.set_field(&mut db).to(0);
+    assert!(create_tracked(&db, input).is_none());
+
+    let err = StaleHandle::catch(|| MyTracked::from_id(stashed).field(&db)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`MyTracked(Id(400))` was used after it was retired or deleted"
+    );
+}
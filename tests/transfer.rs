@@ -0,0 +1,84 @@
+//! Test `salsa::transfer`: moving interned and input handles between databases.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::interned(transfer)]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::interned(transfer)]
+struct Path<'db> {
+    segments: Vec<Name<'db>>,
+}
+
+#[salsa::input(transfer)]
+struct File {
+    path: Vec<String>,
+    #[default]
+    version: u32,
+}
+
+#[salsa::input(transfer)]
+struct Project {
+    files: Vec<File>,
+    main: Option<File>,
+}
+
+#[salsa::tracked]
+fn file_len(db: &dyn Database, file: File) -> usize {
+    file.path(db).len()
+}
+
+#[test]
+fn interned() {
+    let db1 = DatabaseImpl::new();
+    let db2 = DatabaseImpl::new();
+
+    // Occupy the first id of `db2`, so that ids differ between the databases.
+    let other = Name::new(&db2, "other".to_string());
+
+    let a = Name::new(&db1, "a".to_string());
+    let b = Name::new(&db1, "b".to_string());
+    let path = Path::new(&db1, vec![a, b]);
+
+    let path2 = salsa::transfer(&db1, &db2, path);
+    let segments = path2.segments(&db2);
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].text(&db2), "a");
+    assert_eq!(segments[1].text(&db2), "b");
+    assert_ne!(segments[0], other);
+
+    // Transferring again yields the same interned value.
+    assert_eq!(salsa::transfer(&db1, &db2, path), path2);
+    assert_eq!(salsa::transfer(&db1, &db2, b), segments[1]);
+}
+
+#[test]
+fn inputs() {
+    let mut db1 = DatabaseImpl::new();
+    let mut db2 = DatabaseImpl::new();
+
+    let lib = File::new(&db1, vec!["src".to_string(), "lib.rs".to_string()]);
+    let main = File::new(&db1, vec!["main.rs".to_string()]);
+    lib.set_version(&mut db1).to(3);
+    let project = Project::new(&db1, vec![lib, main], Some(main));
+
+    let project2 = salsa::transfer(&db1, &db2, project);
+    let files = project2.files(&db2);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path(&db2), ["src", "lib.rs"]);
+    assert_eq!(files[0].version(&db2), 3);
+
+    // Every occurrence of an input is transferred to a distinct input.
+    let main2 = project2.main(&db2).unwrap();
+    assert_ne!(main2, files[1]);
+    assert_eq!(main2.path(&db2), ["main.rs"]);
+    assert_eq!(file_len(&db2, files[1]), 1);
+
+    // The transferred inputs are independent of the original ones.
+    files[1].set_path(&mut db2).to(vec![]);
+    assert_eq!(file_len(&db2, files[1]), 0);
+    assert_eq!(file_len(&db1, main), 1);
+}
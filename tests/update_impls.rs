@@ -0,0 +1,143 @@
+//! Test the `Update` impls of containers holding values that refer to the database,
+//! as used by the fields of tracked structs.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::LogDatabase;
+use expect_test::expect;
+use indexmap::IndexMap;
+use salsa::Setter;
+use smallvec::SmallVec;
+use test_log::test;
+
+trait Shape: Send + Sync {
+    fn area(&self) -> u32;
+}
+
+struct Square(u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+impl PartialEq for dyn Shape {
+    fn eq(&self, other: &Self) -> bool {
+        self.area() == other.area()
+    }
+}
+
+impl std::fmt::Debug for dyn Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Shape({})", self.area())
+    }
+}
+
+salsa::unsafe_update_via_eq!(Box<dyn Shape>);
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct Leaf<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+struct Holder<'db> {
+    #[tracked]
+    by_name: IndexMap<String, Leaf<'db>>,
+
+    #[tracked]
+    small: SmallVec<[Leaf<'db>; 2]>,
+
+    #[tracked]
+    shared: Arc<[Leaf<'db>]>,
+
+    #[tracked]
+    boxed: Box<[Leaf<'db>]>,
+
+    #[tracked]
+    #[return_ref]
+    shapes: Vec<Box<dyn Shape>>,
+}
+
+#[salsa::tracked]
+fn make_holder(db: &dyn LogDatabase, input: MyInput) -> Holder<'_> {
+    let leaf = Leaf::new(db, input.field(db) / 2);
+    Holder::new(
+        db,
+        IndexMap::from([("leaf".to_string(), leaf)]),
+        SmallVec::from_iter([leaf]),
+        Arc::from([leaf]),
+        Box::from([leaf]),
+        vec![Box::new(Square(input.field(db)))],
+    )
+}
+
+macro_rules! reader {
+    ($name:ident, $read:expr) => {
+        #[salsa::tracked]
+        fn $name<'db>(db: &'db dyn LogDatabase, holder: Holder<'db>) -> u32 {
+            db.push_log(stringify!($name).to_string());
+            let read: fn(&'db dyn LogDatabase, &Holder<'db>) -> u32 = $read;
+            read(db, &holder)
+        }
+    };
+}
+
+reader!(read_by_name, |db, h| h.by_name(db)["leaf"].value(db));
+reader!(read_small, |db, h| h.small(db)[0].value(db));
+reader!(read_shared, |db, h| h.shared(db)[0].value(db));
+reader!(read_boxed, |db, h| h.boxed(db)[0].value(db));
+reader!(read_shapes, |db, h| h.shapes(db)[0].area());
+
+#[salsa::tracked]
+fn sum(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    let holder = make_holder(db, input);
+    read_by_name(db, holder)
+        + read_small(db, holder)
+        + read_shared(db, holder)
+        + read_boxed(db, holder)
+        + read_shapes(db, holder)
+}
+
+#[test]
+fn unchanged_fields_are_not_updated() {
+    let mut db = common::LoggerDatabase::default();
+    let input = MyInput::new(&db, 4);
+    assert_eq!(sum(&db, input), 4 * 2 + 16);
+    db.assert_logs(expect![[r#"
+        [
+            "read_by_name",
+            "read_small",
+            "read_shared",
+            "read_boxed",
+            "read_shapes",
+        ]"#]]);
+
+    // The leaf is the same, so only `shapes` changed.
+    input.set_field(&mut db).to(5);
+    assert_eq!(sum(&db, input), 4 * 2 + 25);
+    db.assert_logs(expect![[r#"
+        [
+            "read_shapes",
+        ]"#]]);
+
+    // The leaf is different, so all fields changed.
+    input.set_field(&mut db).to(6);
+    assert_eq!(sum(&db, input), 4 * 3 + 36);
+    db.assert_logs(expect![[r#"
+        [
+            "read_by_name",
+            "read_small",
+            "read_shared",
+            "read_boxed",
+            "read_shapes",
+        ]"#]]);
+}
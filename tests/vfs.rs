@@ -0,0 +1,144 @@
+//! Test `salsa::vfs::Vfs`, which reads file inputs from disk and applies changes to them.
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::{HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::vfs::Vfs;
+use salsa::{FileId, FilePath, Storage};
+use test_log::test;
+
+#[salsa::db]
+trait Db: LogDatabase {
+    fn vfs(&self) -> &Vfs;
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct VfsDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+    vfs: Arc<Vfs>,
+}
+
+#[salsa::db]
+impl salsa::Database for VfsDatabase {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::db]
+impl Db for VfsDatabase {
+    fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+}
+
+impl HasLogger for VfsDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct Source {
+    path: FileId,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn Db, source: Source) -> Option<usize> {
+    let text = db.vfs().text(db.as_dyn_database(), source.path(db));
+    db.push_log(format!("line_count({:?})", text));
+    text.map(|text| text.lines().count())
+}
+
+/// A fresh directory for the files of the test `name`.
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("salsa-vfs-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+fn reads_files_on_demand() {
+    let directory = directory("reads_files_on_demand");
+    std::fs::write(directory.join("a.txt"), "a\nb").unwrap();
+
+    let db = VfsDatabase::default();
+    let a = Source::new(&db, FilePath::new(&db, directory.join("a.txt")).file_id());
+    let missing = Source::new(
+        &db,
+        FilePath::new(&db, directory.join("missing.txt")).file_id(),
+    );
+
+    assert_eq!(line_count(&db, a), Some(2));
+    assert_eq!(line_count(&db, missing), None);
+}
+
+#[test]
+fn applies_changes_in_one_revision() {
+    let directory = directory("applies_changes_in_one_revision");
+    std::fs::write(directory.join("a.txt"), "a").unwrap();
+    std::fs::write(directory.join("b.txt"), "b").unwrap();
+
+    let mut db = VfsDatabase::default();
+    let a = FilePath::new(&db, directory.join("a.txt")).file_id();
+    let b = FilePath::new(&db, directory.join("b.txt")).file_id();
+    let a_source = Source::new(&db, a);
+    let b_source = Source::new(&db, b);
+    assert_eq!(line_count(&db, a_source), Some(1));
+    assert_eq!(line_count(&db, b_source), Some(1));
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(Some(\"a\"))",
+            "line_count(Some(\"b\"))",
+        ]"#]]);
+
+    std::fs::write(directory.join("a.txt"), "a\na").unwrap();
+    std::fs::write(directory.join("b.txt"), "b\nb").unwrap();
+    let vfs = db.vfs.clone();
+    let changed = vfs.apply_changes(
+        &mut db,
+        [
+            directory.join("a.txt"),
+            directory.join("./b.txt"),
+            directory.join("a.txt"),
+            directory.join("unrelated.txt"),
+        ],
+    );
+    assert_eq!(changed, [a, b]);
+
+    assert_eq!(line_count(&db, a_source), Some(2));
+    assert_eq!(line_count(&db, b_source), Some(2));
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(Some(\"a\\na\"))",
+            "line_count(Some(\"b\\nb\"))",
+        ]"#]]);
+}
+
+#[test]
+fn unchanged_files_start_no_revision() {
+    let directory = directory("unchanged_files_start_no_revision");
+    std::fs::write(directory.join("a.txt"), "a").unwrap();
+
+    let mut db = VfsDatabase::default();
+    let a = Source::new(&db, FilePath::new(&db, directory.join("a.txt")).file_id());
+    assert_eq!(line_count(&db, a), Some(1));
+
+    let vfs = db.vfs.clone();
+    let revision = salsa::plumbing::current_revision(&db);
+    assert_eq!(vfs.apply_changes(&mut db, [directory.join("a.txt")]), []);
+    assert_eq!(salsa::plumbing::current_revision(&db), revision);
+
+    // Deleting the file is a change.
+    std::fs::remove_file(directory.join("a.txt")).unwrap();
+    assert_eq!(
+        vfs.apply_changes(&mut db, [directory.join("a.txt")]).len(),
+        1
+    );
+    assert_eq!(line_count(&db, a), None);
+}
@@ -0,0 +1,31 @@
+//! Test `salsa::vfs::Vfs::watching`, which watches the files it reads for changes.
+
+use std::time::{Duration, Instant};
+
+use salsa::vfs::Vfs;
+use salsa::{DatabaseImpl, FilePath};
+use test_log::test;
+
+#[test]
+fn applies_watched_changes() {
+    let directory = std::env::temp_dir().join(format!("salsa-vfs-notify-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("a.txt"), "old").unwrap();
+
+    let mut db = DatabaseImpl::new();
+    let vfs = Vfs::watching().unwrap();
+    let a = FilePath::new(&db, directory.join("a.txt")).file_id();
+    assert_eq!(vfs.text(&db, a), Some("old"));
+
+    // Replace the file, as editors do when saving.
+    std::fs::write(directory.join("a.tmp"), "new").unwrap();
+    std::fs::rename(directory.join("a.tmp"), directory.join("a.txt")).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while vfs.apply_pending_changes(&mut db).is_empty() {
+        assert!(Instant::now() < deadline, "no change was observed");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(vfs.text(&db, a), Some("new"));
+}
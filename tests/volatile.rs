@@ -0,0 +1,96 @@
+//! Test `volatile(ttl_revisions = N)`: the function is re-executed once its memo
+//! was computed `N` revisions ago, even if its inputs did not change.
+
+mod common;
+use common::LogDatabase;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+use test_log::test;
+
+/// State that salsa does not know about. Each test uses a different clock,
+/// as tests run concurrently.
+static CLOCKS: [AtomicU32; 2] = [const { AtomicU32::new(0) }; 2];
+
+#[salsa::input]
+struct Clock {
+    index: usize,
+}
+
+#[salsa::input]
+struct Unrelated {
+    value: u32,
+}
+
+#[salsa::tracked(volatile(ttl_revisions = 2))]
+fn minutes(db: &dyn LogDatabase, clock: Clock) -> u32 {
+    db.push_log("minutes".to_string());
+    CLOCKS[clock.index(db)].load(Ordering::SeqCst) / 60
+}
+
+#[salsa::tracked]
+fn display(db: &dyn LogDatabase, clock: Clock) -> String {
+    db.push_log("display".to_string());
+    format!("{} min", minutes(db, clock))
+}
+
+#[test]
+fn reexecuted_after_ttl() {
+    let mut db = common::LoggerDatabase::default();
+    let clock = Clock::new(&db, 0);
+    let unrelated = Unrelated::new(&db, 0);
+    CLOCKS[0].store(60, Ordering::SeqCst);
+
+    assert_eq!(display(&db, clock), "1 min");
+    db.assert_logs(expect![[r#"
+        [
+            "display",
+            "minutes",
+        ]"#]]);
+
+    // Within the time-to-live, the memo is reused.
+    CLOCKS[0].store(120, Ordering::SeqCst);
+    unrelated.set_value(&mut db).to(1);
+    assert_eq!(display(&db, clock), "1 min");
+    db.assert_logs(expect!["[]"]);
+
+    // Two revisions after it was computed, the memo expires.
+    unrelated.set_value(&mut db).to(2);
+    assert_eq!(display(&db, clock), "2 min");
+    db.assert_logs(expect![[r#"
+        [
+            "minutes",
+            "display",
+        ]"#]]);
+
+    // Readers are not re-executed if the value did not change.
+    CLOCKS[0].store(150, Ordering::SeqCst);
+    unrelated.set_value(&mut db).to(3);
+    unrelated.set_value(&mut db).to(4);
+    assert_eq!(display(&db, clock), "2 min");
+    db.assert_logs(expect![[r#"
+        [
+            "minutes",
+        ]"#]]);
+}
+
+#[test]
+fn expires_despite_high_durability_inputs() {
+    let mut db = common::LoggerDatabase::default();
+    let clock = Clock::builder(1).durability(Durability::HIGH).new(&db);
+    CLOCKS[1].store(60, Ordering::SeqCst);
+    assert_eq!(display(&db, clock), "1 min");
+    db.assert_logs_len(2);
+
+    CLOCKS[1].store(180, Ordering::SeqCst);
+    db.synthetic_write(Durability::LOW);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(display(&db, clock), "3 min");
+    db.assert_logs(expect![[r#"
+        [
+            "minutes",
+            "display",
+        ]"#]]);
+}
@@ -0,0 +1,88 @@
+//! Test `with_no_dependencies`: reads performed within the scope do not
+//! become dependencies of the current query.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    contents: String,
+    #[default]
+    reads: u32,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log(format!("length({:?})", file.reads(db)));
+    file.contents(db).len()
+}
+
+#[salsa::tracked]
+fn render(db: &dyn LogDatabase, file: File) -> String {
+    let reads = salsa::with_no_dependencies(db, || file.reads(db));
+    db.push_log(format!("render({reads})"));
+    file.contents(db)
+}
+
+#[salsa::tracked]
+fn render_length(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log("render_length".to_string());
+    salsa::with_no_dependencies(db, || length(db, file))
+}
+
+#[test]
+fn reads_in_scope_are_not_dependencies() {
+    let mut db = common::LoggerDatabase::default();
+    let file = File::new(&db, "hello".to_string());
+    assert_eq!(render(&db, file), "hello");
+    db.assert_logs(expect![[r#"
+        [
+            "render(0)",
+        ]"#]]);
+
+    // The counter was read within the scope: no re-execution.
+    file.set_reads(&mut db).to(1);
+    assert_eq!(render(&db, file), "hello");
+    db.assert_logs(expect!["[]"]);
+
+    // The contents were read outside the scope.
+    file.set_contents(&mut db).to("world".to_string());
+    assert_eq!(render(&db, file), "world");
+    db.assert_logs(expect![[r#"
+        [
+            "render(1)",
+        ]"#]]);
+}
+
+#[test]
+fn nested_queries_keep_their_dependencies() {
+    let mut db = common::LoggerDatabase::default();
+    let file = File::new(&db, "hello".to_string());
+    assert_eq!(render_length(&db, file), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "render_length",
+            "length(0)",
+        ]"#]]);
+
+    // `length` depends on the contents, `render_length` does not depend on `length`.
+    file.set_contents(&mut db).to("hi".to_string());
+    assert_eq!(render_length(&db, file), 5);
+    assert_eq!(length(&db, file), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "length(0)",
+        ]"#]]);
+}
+
+#[test]
+fn outside_of_a_query() {
+    let db = common::LoggerDatabase::default();
+    let file = File::new(&db, "hello".to_string());
+    let contents = salsa::Database::with_no_dependencies(&db, || file.contents(&db));
+    assert_eq!(contents, "hello");
+}